@@ -0,0 +1,60 @@
+//! Makes sure a set of permanent channels exists on the server.
+//!
+//! Configured using the environment variables `TS3_ADDR`, `TS3_USERNAME`, `TS3_PASSWORD`,
+//! `TS3_SID` and `TS3_CHANNELS` (a comma separated list of channel names).
+
+use std::env;
+
+use ts3::request::RequestBuilder;
+use ts3::response::Response;
+use ts3::Client;
+
+/// Returns the environment variable `key` or `default` if it is not set.
+fn env_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_owned())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = Client::connect(env_or("TS3_ADDR", "127.0.0.1:10011")).await?;
+
+    client
+        .login(
+            &env_or("TS3_USERNAME", "serveradmin"),
+            &env::var("TS3_PASSWORD")?,
+        )
+        .await?;
+    client
+        .use_sid(env_or("TS3_SID", "1").parse::<u64>()?)
+        .await?;
+
+    let channels: Response = client.send(RequestBuilder::new("channellist")).await?;
+    let existing: Vec<String> = channels
+        .iter()
+        .filter_map(|entry| entry.get("channel_name").ok())
+        .collect();
+
+    let wanted = env_or("TS3_CHANNELS", "Lobby,AFK");
+    let missing: Vec<&str> = wanted
+        .split(',')
+        .filter(|name| !existing.iter().any(|c| c == name))
+        .collect();
+
+    let mut batch = client.batch();
+    for name in &missing {
+        batch = batch.push(
+            RequestBuilder::new("channelcreate")
+                .arg("channel_name", *name)
+                .arg("channel_flag_permanent", true),
+        );
+    }
+
+    for (name, result) in missing.iter().zip(batch.execute().await) {
+        match result {
+            Ok(_) => println!("Created channel {}", name),
+            Err(err) => println!("Failed to create channel {}: {}", name, err),
+        }
+    }
+
+    Ok(())
+}
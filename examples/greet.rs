@@ -1,18 +1,31 @@
+use std::env;
+
 use ts3::event::{ClientEnterView, EventHandler};
-use ts3::request::{ServerNotifyRegister, TextMessageTarget};
+use ts3::greeter::Greeter;
+use ts3::request::ServerNotifyRegister;
 use ts3::{async_trait, Client};
 
-const USERNAME: &str = "serveradmin";
-const PASSWORD: &str = "password";
+/// Returns the environment variable `key` or `default` if it is not set.
+fn env_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_owned())
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::connect("127.0.0.1:10011").await?;
+    let client = Client::connect(env_or("TS3_ADDR", "127.0.0.1:10011")).await?;
 
-    client.set_event_handler(Handler);
+    // Greet every joined client, at most once every 5 minutes.
+    client.set_event_handler(Greeter::new(Handler).template("Hello World, %nickname%!"));
 
-    client.login(USERNAME, PASSWORD).await?;
-    client.use_sid(1).await?;
+    client
+        .login(
+            &env_or("TS3_USERNAME", "serveradmin"),
+            &env::var("TS3_PASSWORD")?,
+        )
+        .await?;
+    client
+        .use_sid(env_or("TS3_SID", "1").parse::<u64>()?)
+        .await?;
 
     client
         .servernotifyregister(ServerNotifyRegister::Server)
@@ -26,15 +39,7 @@ struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
-    async fn cliententerview(&self, client: Client, event: ClientEnterView) {
+    async fn cliententerview(&self, _client: Client, event: ClientEnterView) {
         println!("User joined: {}", event.client_nickname);
-
-        // Greet the joined client with a "Hello World!".
-        if let Err(err) = client
-            .sendtextmessage(TextMessageTarget::Client(event.clid), "Hello World!")
-            .await
-        {
-            println!("Error greeting user: {}", err);
-        }
     }
 }
@@ -0,0 +1,59 @@
+//! A moderation bot banning clients with too many complaints and moving idle clients into
+//! an AFK channel.
+//!
+//! Configured using the environment variables `TS3_ADDR`, `TS3_USERNAME`, `TS3_PASSWORD`,
+//! `TS3_SID`, `TS3_COMPLAINTS` (the number of complaints before a ban) and
+//! `TS3_AFK_CHANNEL` (optional).
+
+use std::env;
+use std::time::Duration;
+
+use ts3::afk::AfkMover;
+use ts3::complain::ComplaintModerator;
+use ts3::shared::ChannelId;
+use ts3::Client;
+
+/// Returns the environment variable `key` or `default` if it is not set.
+fn env_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_owned())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = Client::connect(env_or("TS3_ADDR", "127.0.0.1:10011")).await?;
+
+    client
+        .login(
+            &env_or("TS3_USERNAME", "serveradmin"),
+            &env::var("TS3_PASSWORD")?,
+        )
+        .await?;
+    client
+        .use_sid(env_or("TS3_SID", "1").parse::<u64>()?)
+        .await?;
+
+    let threshold = env_or("TS3_COMPLAINTS", "3").parse()?;
+    let (_moderator, mut complaints) = ComplaintModerator::new(threshold)
+        .ban(Some(Duration::from_secs(60 * 60)), "Too many complaints")
+        .spawn(client.clone());
+
+    let _afk = match env::var("TS3_AFK_CHANNEL") {
+        Ok(cid) => {
+            let (handle, mut events) = AfkMover::new(ChannelId(cid.parse()?)).spawn(client);
+            tokio::spawn(async move {
+                while let Some(event) = events.recv().await {
+                    println!("{:?}", event);
+                }
+            });
+
+            Some(handle)
+        }
+        Err(_) => None,
+    };
+
+    while let Some(event) = complaints.recv().await {
+        println!("{:?}", event);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Periodically prints server statistics in the Prometheus text format.
+//!
+//! Configured using the environment variables `TS3_ADDR`, `TS3_USERNAME`, `TS3_PASSWORD`,
+//! `TS3_SID` and `TS3_INTERVAL` (in seconds).
+
+use std::env;
+use std::time::Duration;
+
+use ts3::stats::Sampler;
+use ts3::Client;
+
+/// Returns the environment variable `key` or `default` if it is not set.
+fn env_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_owned())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let client = Client::connect(env_or("TS3_ADDR", "127.0.0.1:10011")).await?;
+
+    client
+        .login(
+            &env_or("TS3_USERNAME", "serveradmin"),
+            &env::var("TS3_PASSWORD")?,
+        )
+        .await?;
+    client
+        .use_sid(env_or("TS3_SID", "1").parse::<u64>()?)
+        .await?;
+
+    let interval = Duration::from_secs(env_or("TS3_INTERVAL", "15").parse()?);
+    let (_sampler, mut samples) = Sampler::new(interval).spawn(client);
+
+    while let Some(sample) = samples.recv().await {
+        match sample {
+            Ok(sample) => {
+                println!("ts3_clients_online {}", sample.clients_online);
+                println!("ts3_queryclients_online {}", sample.queryclients_online);
+                println!("ts3_channels_online {}", sample.channels_online);
+                println!("ts3_bandwidth_sent_bytes {}", sample.bandwidth_sent);
+                println!("ts3_bandwidth_received_bytes {}", sample.bandwidth_received);
+                println!("ts3_packetloss_ratio {}", sample.packetloss);
+                println!("ts3_ping_milliseconds {}", sample.ping);
+            }
+            Err(err) => eprintln!("failed to sample: {}", err),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ts3::response::{ClientDbInfo, Response};
+use ts3::shared::list::Pipe;
+use ts3::shared::List;
+use ts3::Decode;
+
+/// Builds a `clientdblist` response with `n` entries.
+fn clientdblist(n: usize) -> Vec<u8> {
+    let entries: Vec<String> = (0..n)
+        .map(|i| {
+            format!(
+                "cldbid={i} client_unique_identifier=uid{i}/+abcdefghijklmnopqrs= \
+                 client_nickname=Client\\s{i} client_created=1600000000 \
+                 client_lastconnected=1700000000 client_totalconnections={i} \
+                 client_description client_lastip=127.0.0.1"
+            )
+        })
+        .collect();
+
+    entries.join("|").into_bytes()
+}
+
+fn decode(c: &mut Criterion) {
+    let buf = clientdblist(5000);
+
+    c.bench_function("clientdblist 5k typed", |b| {
+        b.iter(|| List::<ClientDbInfo, Pipe>::decode(black_box(&buf)).unwrap())
+    });
+
+    c.bench_function("clientdblist 5k raw", |b| {
+        b.iter(|| Response::decode(black_box(&buf)).unwrap())
+    });
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,124 @@
+//! Helpers for administrating server instances.
+//!
+//! # Examples
+//!
+//! Clone a virtual server to another instance:
+//!
+//! ```no_run
+//! use ts3::admin::{self, CloneOptions};
+//! use ts3::Client;
+//!
+//! # async fn run() -> Result<(), ts3::Error> {
+//! let src = Client::connect("old.example.com:10011").await?;
+//! src.login("serveradmin", "password").await?;
+//! src.use_sid(1).await?;
+//!
+//! let dst = Client::connect("new.example.com:10011").await?;
+//! dst.login("serveradmin", "password").await?;
+//!
+//! let server = admin::clone_server(&src, &dst, CloneOptions::new().port(9988)).await?;
+//! println!("cloned to server {:?} on port {}", server.sid, server.port);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::client::Result;
+use crate::request::VirtualServerProperties;
+use crate::shared::{ChannelId, ServerId};
+use crate::{Client, Error, ErrorKind};
+
+/// Options for [`clone_server`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CloneOptions {
+    keepfiles: bool,
+    port: Option<u16>,
+}
+
+impl CloneOptions {
+    /// Creates new `CloneOptions` using the defaults of the server.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the files of channels existing on the target server. Defaults to `false`.
+    pub fn keepfiles(mut self, keepfiles: bool) -> Self {
+        self.keepfiles = keepfiles;
+        self
+    }
+
+    /// Sets the voice port of the cloned server. By default the port chosen by the target
+    /// server is used.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+}
+
+/// A virtual server created by [`clone_server`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ClonedServer {
+    /// The id of the new server.
+    pub sid: ServerId,
+    /// The voice port of the new server.
+    pub port: u16,
+    /// The ids of all channels on the source server and on the new server, in this order.
+    pub channels: Vec<(ChannelId, ChannelId)>,
+}
+
+/// Clones the virtual server selected on `src` into a new virtual server on `dst`.
+///
+/// `dst` must not have a virtual server selected, the selected server would be replaced by
+/// the snapshot instead. Cloning fails before anything is sent if a server is selected on
+/// `dst`. The query interface cannot deselect a server, if the port is changed the new
+/// server stays selected on `dst` afterwards.
+///
+/// If setting the port fails the new server is not deleted.
+pub async fn clone_server(
+    src: &Client,
+    dst: &Client,
+    options: CloneOptions,
+) -> Result<ClonedServer> {
+    if dst.selected_server().is_some() {
+        return Err(invalid(
+            "a virtual server is selected on the target, it would be replaced",
+        ));
+    }
+
+    let snapshot = src.serversnapshotcreate().await?;
+    let entries = dst
+        .serversnapshotdeploy(&snapshot, options.keepfiles, true)
+        .await?;
+
+    // Only the first entry contains the server, an empty response decodes into a single
+    // entry with all fields unset.
+    let Some(first) = entries.first().filter(|entry| entry.sid.0 != 0) else {
+        return Err(invalid("the target server returned no deployed server"));
+    };
+    let mut server = ClonedServer {
+        sid: first.sid,
+        port: first.virtualserver_port,
+        channels: entries
+            .iter()
+            .map(|entry| (entry.ochannel_id, entry.nchannel_id))
+            .collect(),
+    };
+
+    if let Some(port) = options.port {
+        if port != server.port {
+            dst.use_sid(server.sid).await?;
+            dst.serveredit(VirtualServerProperties::new().port(port))
+                .await?;
+            server.port = port;
+        }
+    }
+
+    Ok(server)
+}
+
+fn invalid(reason: &str) -> Error {
+    Error(ErrorKind::InvalidRequest {
+        command: String::from("serversnapshotdeploy"),
+        reason: reason.to_owned(),
+    })
+}
@@ -0,0 +1,238 @@
+//! Automatically move idle clients into an AFK channel.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::afk::AfkMover;
+//! use ts3::shared::ChannelId;
+//! use ts3::Client;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let (_mover, mut events) = AfkMover::new(ChannelId(5))
+//!         .idle_threshold(Duration::from_secs(15 * 60))
+//!         .spawn(client);
+//!
+//!     while let Some(event) = events.recv().await {
+//!         println!("{:?}", event);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::request::ClientListOptions;
+use crate::response::ClientListEntry;
+use crate::shared::{ChannelId, ClientId, ServerGroupId};
+use crate::{Client, Error};
+
+/// An event emitted by an [`AfkMover`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AfkEvent {
+    /// A client was moved into the AFK channel.
+    Moved {
+        clid: ClientId,
+        nickname: String,
+        /// The channel the client was in before being moved.
+        from: ChannelId,
+        /// The time the client was idle.
+        idle: Duration,
+    },
+    /// An error occured while polling or moving clients.
+    Error(Error),
+}
+
+/// Periodically polls the client list and moves clients that are idle for longer than a
+/// threshold into an AFK channel.
+#[derive(Clone, Debug)]
+pub struct AfkMover {
+    afk_channel: ChannelId,
+    idle_threshold: Duration,
+    interval: Duration,
+    excluded_groups: Vec<ServerGroupId>,
+    excluded_channels: Vec<ChannelId>,
+}
+
+impl AfkMover {
+    /// Creates a new `AfkMover` moving idle clients into `afk_channel`.
+    pub fn new(afk_channel: ChannelId) -> Self {
+        Self {
+            afk_channel,
+            idle_threshold: Duration::from_secs(30 * 60),
+            interval: Duration::from_secs(60),
+            excluded_groups: Vec::new(),
+            excluded_channels: Vec::new(),
+        }
+    }
+
+    /// Sets the time a client must be idle before it is moved. Defaults to 30 minutes.
+    pub fn idle_threshold(mut self, idle_threshold: Duration) -> Self {
+        self.idle_threshold = idle_threshold;
+        self
+    }
+
+    /// Sets the interval in which the client list is polled. Defaults to 60 seconds.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Never moves clients in the server group `sgid`.
+    pub fn exclude_group(mut self, sgid: ServerGroupId) -> Self {
+        self.excluded_groups.push(sgid);
+        self
+    }
+
+    /// Never moves clients out of the channel `cid`.
+    pub fn exclude_channel(mut self, cid: ChannelId) -> Self {
+        self.excluded_channels.push(cid);
+        self
+    }
+
+    /// Returns the idle time of `client` if it should be moved into the AFK channel.
+    fn check(&self, client: &ClientListEntry) -> Option<Duration> {
+        let idle = client.idle_duration();
+
+        // Skip ServerQuery clients.
+        if client.client_type != 0
+            || idle < self.idle_threshold
+            || client.cid == self.afk_channel
+            || self.excluded_channels.contains(&client.cid)
+            || client
+                .client_servergroups
+                .iter()
+                .any(|sgid| self.excluded_groups.contains(sgid))
+        {
+            return None;
+        }
+
+        Some(idle)
+    }
+
+    async fn poll(&self, client: &Client, events: &mpsc::Sender<AfkEvent>) -> Result<(), Error> {
+        let clients = client
+            .clientlist(ClientListOptions {
+                times: true,
+                groups: true,
+                ..Default::default()
+            })
+            .await?;
+
+        let idle: Vec<(&ClientListEntry, Duration)> = clients
+            .iter()
+            .filter_map(|c| self.check(c).map(|idle| (c, idle)))
+            .collect();
+
+        if idle.is_empty() {
+            return Ok(());
+        }
+
+        let clids: Vec<ClientId> = idle.iter().map(|(c, _)| c.clid).collect();
+        client.clientmove(&clids, self.afk_channel, None).await?;
+
+        for (c, idle) in idle {
+            let _ = events.try_send(AfkEvent::Moved {
+                clid: c.clid,
+                nickname: c.client_nickname.clone(),
+                from: c.cid,
+                idle,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Spawns a new task running the `AfkMover` using `client`. Returns a handle to stop the
+    /// task and a receiver for all emitted [`AfkEvent`]s. Events are dropped if the receiver
+    /// is not drained.
+    pub fn spawn(self, client: Client) -> (AfkMoverHandle, mpsc::Receiver<AfkEvent>) {
+        let (tx, rx) = mpsc::channel(32);
+
+        let handle = tokio::task::spawn(async move {
+            loop {
+                if let Err(err) = self.poll(&client, &tx).await {
+                    let _ = tx.try_send(AfkEvent::Error(err));
+                }
+
+                sleep(self.interval).await;
+            }
+        });
+
+        (AfkMoverHandle { handle }, rx)
+    }
+}
+
+/// A handle to a running [`AfkMover`]. The `AfkMover` is stopped when the handle is
+/// dropped.
+#[derive(Debug)]
+pub struct AfkMoverHandle {
+    handle: JoinHandle<()>,
+}
+
+impl AfkMoverHandle {
+    /// Stops the `AfkMover`.
+    #[inline]
+    pub fn stop(self) {}
+}
+
+impl Drop for AfkMoverHandle {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::AfkMover;
+    use crate::response::ClientListEntry;
+    use crate::shared::{ChannelId, ServerGroupId};
+    use crate::Decode;
+
+    fn client(line: &str) -> ClientListEntry {
+        ClientListEntry::decode(line.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_afk_check() {
+        let mover = AfkMover::new(ChannelId(5))
+            .idle_threshold(Duration::from_secs(60))
+            .exclude_group(ServerGroupId(9))
+            .exclude_channel(ChannelId(3));
+
+        let idle = "clid=1 cid=1 client_type=0 client_idle_time=120000 client_servergroups=6";
+        assert_eq!(mover.check(&client(idle)), Some(Duration::from_secs(120)));
+
+        // Negative icon ids sent by some servers do not fail the client list.
+        let line = format!("{} client_icon_id=-1234", idle);
+        assert_eq!(mover.check(&client(&line)), Some(Duration::from_secs(120)));
+
+        for line in [
+            // Not idle for long enough.
+            "clid=1 cid=1 client_type=0 client_idle_time=59999 client_servergroups=6",
+            // ServerQuery client.
+            "clid=1 cid=1 client_type=1 client_idle_time=120000 client_servergroups=6",
+            // Already in the AFK channel.
+            "clid=1 cid=5 client_type=0 client_idle_time=120000 client_servergroups=6",
+            // Excluded channel.
+            "clid=1 cid=3 client_type=0 client_idle_time=120000 client_servergroups=6",
+            // Excluded group.
+            "clid=1 cid=1 client_type=0 client_idle_time=120000 client_servergroups=6,9",
+        ] {
+            assert_eq!(mover.check(&client(line)), None, "{}", line);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Structured join and leave records for auditing.
+//!
+//! An [`Auditor`] wraps an [`EventHandler`] and produces an [`AuditRecord`] for every
+//! `cliententerview` and `clientleftview` event, which can be persisted by the application.
+//! All events are forwarded to the wrapped handler.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ts3::audit::Auditor;
+//! use ts3::request::ServerNotifyRegister;
+//! use ts3::Client;
+//!
+//! struct Handler;
+//!
+//! impl ts3::event::EventHandler for Handler {}
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let (auditor, mut records) = Auditor::new(Handler);
+//!     client.set_event_handler(auditor);
+//!     client.servernotifyregister(ServerNotifyRegister::Server).await?;
+//!
+//!     while let Some(record) = records.recv().await {
+//!         println!("{:?}", record);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+
+use crate::event::{ClientEnterView, ClientLeftView, Event, EventHandler, ReasonId};
+use crate::shared::{ClientDatabaseId, ClientId};
+use crate::{Client, Error};
+
+/// A record of a client joining or leaving the server.
+#[derive(Clone, Debug)]
+pub enum AuditRecord {
+    Join(JoinRecord),
+    Leave(LeaveRecord),
+}
+
+/// A record of a client joining the server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct JoinRecord {
+    pub clid: ClientId,
+    pub cldbid: ClientDatabaseId,
+    pub uid: String,
+    pub nickname: String,
+    /// The IP address of the client. `None` if the query client lacks the permissions to
+    /// view it.
+    pub ip: Option<String>,
+    pub time: SystemTime,
+}
+
+/// A record of a client leaving the server.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct LeaveRecord {
+    pub clid: ClientId,
+    pub cldbid: ClientDatabaseId,
+    pub uid: String,
+    pub nickname: String,
+    pub reason: ReasonId,
+    pub time: SystemTime,
+    /// The time the client was connected. `None` if the client joined before the `Auditor`
+    /// was installed.
+    pub duration: Option<Duration>,
+}
+
+/// An [`EventHandler`] producing [`AuditRecord`]s. See the [module level documentation]
+/// for more details.
+///
+/// [module level documentation]: crate::audit
+pub struct Auditor<H> {
+    inner: H,
+    tx: mpsc::UnboundedSender<AuditRecord>,
+    joined: Mutex<HashMap<ClientId, JoinRecord>>,
+}
+
+impl<H> Auditor<H>
+where
+    H: EventHandler,
+{
+    /// Creates a new `Auditor` wrapping `inner`. Returns the `Auditor` and a receiver for all
+    /// produced records.
+    pub fn new(inner: H) -> (Self, mpsc::UnboundedReceiver<AuditRecord>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        (
+            Self {
+                inner,
+                tx,
+                joined: Mutex::new(HashMap::new()),
+            },
+            rx,
+        )
+    }
+}
+
+impl<H> Auditor<H>
+where
+    H: EventHandler,
+{
+    async fn record_join(&self, client: &Client, event: &ClientEnterView) {
+        // ServerQuery clients have no database entry with connection information.
+        if event.client_type != 0 {
+            return;
+        }
+
+        let mut record = JoinRecord {
+            clid: event.clid,
+            cldbid: event.client_database_id,
+            uid: event.client_unique_identifier.clone(),
+            nickname: event.client_nickname.clone(),
+            ip: None,
+            time: SystemTime::now(),
+        };
+
+        // Track the client before the lookup, the client might leave before it finishes.
+        self.joined
+            .lock()
+            .unwrap()
+            .insert(event.clid, record.clone());
+
+        record.ip = client
+            .clientdbinfo(event.client_database_id)
+            .await
+            .ok()
+            .map(|info| info.client_lastip)
+            .filter(|ip| !ip.is_empty());
+
+        let _ = self.tx.send(AuditRecord::Join(record));
+    }
+
+    fn record_leave(&self, event: &ClientLeftView) {
+        let Some(joined) = self.joined.lock().unwrap().remove(&event.clid) else {
+            return;
+        };
+
+        let time = SystemTime::now();
+        let _ = self.tx.send(AuditRecord::Leave(LeaveRecord {
+            clid: event.clid,
+            cldbid: joined.cldbid,
+            uid: joined.uid,
+            nickname: joined.nickname,
+            reason: event.reasonid,
+            time,
+            duration: time.duration_since(joined.time).ok(),
+        }));
+    }
+}
+
+#[async_trait]
+impl<H> EventHandler for Auditor<H>
+where
+    H: EventHandler,
+{
+    async fn handle(&self, client: Client, event: Event) {
+        match &event {
+            Event::ClientEnterView(event) => self.record_join(&client, event).await,
+            Event::ClientLeftView(event) => self.record_leave(event),
+            _ => (),
+        }
+
+        self.inner.handle(client, event).await
+    }
+
+    fn error(&self, client: Client, error: Error) {
+        self.inner.error(client, error)
+    }
+}
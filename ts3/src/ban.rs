@@ -0,0 +1,349 @@
+//! Helpers for sharing ban rules between servers.
+//!
+//! A [`BanSet`] can be exported from one virtual server using [`export`] and applied to
+//! another one using [`apply`]. With the `serde` feature enabled, a [`BanSet`] can be
+//! serialized to share it across communities.
+//!
+//! [`matching`] answers which ban rules match a client, e.g. to find out why a client is
+//! banned.
+
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+use crate::client::Result;
+use crate::regex::Regex;
+use crate::response::{BanEntry, ClientDbInfo, ClientListEntry};
+use crate::Client;
+
+/// A single ban rule. At least one of `ip`, `name`, `uid` and `mytsid` should be set.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BanRule {
+    pub ip: Option<String>,
+    pub name: Option<String>,
+    pub uid: Option<String>,
+    pub mytsid: Option<String>,
+    /// The duration of the ban in seconds. `0` means the ban is permanent.
+    pub duration: u64,
+    pub reason: Option<String>,
+    pub lastnickname: Option<String>,
+}
+
+impl BanRule {
+    /// Returns `true` if `self` and `other` match the same clients, ignoring duration, reason
+    /// and last nickname.
+    pub fn same_target(&self, other: &Self) -> bool {
+        self.ip == other.ip
+            && self.name == other.name
+            && self.uid == other.uid
+            && self.mytsid == other.mytsid
+    }
+}
+
+impl BanRule {
+    /// Returns `true` if the rule matches `target`. See [`matching`] for the semantics.
+    pub fn matches(&self, target: &BanTarget<'_>) -> bool {
+        rule_matches(
+            self.ip.as_deref(),
+            self.name.as_deref(),
+            self.uid.as_deref(),
+            self.mytsid.as_deref(),
+            target,
+        )
+    }
+}
+
+impl From<BanEntry> for BanRule {
+    fn from(value: BanEntry) -> Self {
+        fn non_empty(s: String) -> Option<String> {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s)
+            }
+        }
+
+        Self {
+            ip: non_empty(value.ip),
+            name: non_empty(value.name),
+            uid: non_empty(value.uid),
+            mytsid: non_empty(value.mytsid),
+            duration: value.duration,
+            reason: non_empty(value.reason),
+            lastnickname: non_empty(value.lastnickname),
+        }
+    }
+}
+
+/// A list of [`BanRule`]s.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct BanSet {
+    rules: Vec<BanRule>,
+}
+
+impl BanSet {
+    /// Creates a new `BanSet` from a list of rules.
+    #[inline]
+    pub fn new(rules: Vec<BanRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Returns the rules in this `BanSet`.
+    #[inline]
+    pub fn rules(&self) -> &[BanRule] {
+        &self.rules
+    }
+
+    /// Consumes this `BanSet`, returning the contained rules.
+    #[inline]
+    pub fn into_inner(self) -> Vec<BanRule> {
+        self.rules
+    }
+}
+
+impl From<Vec<BanRule>> for BanSet {
+    #[inline]
+    fn from(value: Vec<BanRule>) -> Self {
+        Self::new(value)
+    }
+}
+
+/// The properties of a client checked against ban rules. Properties which are `None` are
+/// unknown and never match.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BanTarget<'a> {
+    pub ip: Option<&'a str>,
+    pub name: Option<&'a str>,
+    pub uid: Option<&'a str>,
+    pub mytsid: Option<&'a str>,
+}
+
+impl<'a> From<&'a ClientDbInfo> for BanTarget<'a> {
+    fn from(value: &'a ClientDbInfo) -> Self {
+        Self {
+            ip: non_empty(&value.client_lastip),
+            name: non_empty(&value.client_nickname),
+            uid: non_empty(&value.client_unique_identifier),
+            mytsid: None,
+        }
+    }
+}
+
+/// The ip and uid are only included if the client list was requested with the `ip` and `uid`
+/// options.
+impl<'a> From<&'a ClientListEntry> for BanTarget<'a> {
+    fn from(value: &'a ClientListEntry) -> Self {
+        Self {
+            ip: non_empty(&value.client_connection_client_ip),
+            name: non_empty(&value.client_nickname),
+            uid: non_empty(&value.client_unique_identifier),
+            mytsid: None,
+        }
+    }
+}
+
+/// Returns the bans in `bans` matching `target`.
+///
+/// The `ip` and `name` of a ban are regular expressions which must match the whole value,
+/// names are matched case-insensitively. The `uid` and `mytsid` must be equal. A ban matches
+/// if all of its set properties match, rules with invalid expressions never match.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ts3::ban::{self, BanTarget};
+/// use ts3::shared::ClientDatabaseId;
+/// use ts3::Client;
+///
+/// # async fn run(client: Client) -> Result<(), ts3::Error> {
+/// let info = client.clientdbinfo(ClientDatabaseId(5)).await?;
+/// let bans = client.banlist().await?;
+///
+/// for ban in ban::matching(&bans, &BanTarget::from(&info)) {
+///     println!("banned by {}: {}", ban.invokername, ban.reason);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn matching<'a>(bans: &'a [BanEntry], target: &BanTarget<'_>) -> Vec<&'a BanEntry> {
+    bans.iter()
+        .filter(|ban| {
+            rule_matches(
+                non_empty(&ban.ip),
+                non_empty(&ban.name),
+                non_empty(&ban.uid),
+                non_empty(&ban.mytsid),
+                target,
+            )
+        })
+        .collect()
+}
+
+fn rule_matches(
+    ip: Option<&str>,
+    name: Option<&str>,
+    uid: Option<&str>,
+    mytsid: Option<&str>,
+    target: &BanTarget<'_>,
+) -> bool {
+    let checks = [
+        (ip, target.ip, Compare::Regex),
+        (name, target.name, Compare::RegexNoCase),
+        (uid, target.uid, Compare::Exact),
+        (mytsid, target.mytsid, Compare::Exact),
+    ];
+
+    let mut any = false;
+    for (pattern, value, compare) in checks {
+        let Some(pattern) = pattern.filter(|p| !p.is_empty()) else {
+            continue;
+        };
+
+        match value {
+            Some(value) if compare.matches(pattern, value) => any = true,
+            _ => return false,
+        }
+    }
+
+    any
+}
+
+/// How a property of a ban is compared with the property of a client.
+#[derive(Copy, Clone, Debug)]
+enum Compare {
+    Regex,
+    RegexNoCase,
+    Exact,
+}
+
+impl Compare {
+    fn matches(self, pattern: &str, value: &str) -> bool {
+        match self {
+            Self::Regex => Regex::new(pattern, false).is_some_and(|r| r.is_match(value)),
+            Self::RegexNoCase => Regex::new(pattern, true).is_some_and(|r| r.is_match(value)),
+            Self::Exact => pattern == value,
+        }
+    }
+}
+
+fn non_empty(s: &str) -> Option<&str> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// Exports all ban rules of the selected virtual server into a [`BanSet`].
+///
+/// Note that the exported duration is the original duration of the ban, not the time left.
+pub async fn export(client: &Client) -> Result<BanSet> {
+    match client.banlist().await {
+        Ok(list) => Ok(BanSet::new(
+            list.into_inner().into_iter().map(BanRule::from).collect(),
+        )),
+        Err(err) if err.is_empty_result() => Ok(BanSet::default()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Adds all rules of `set` to the selected virtual server, skipping rules that already exist
+/// on the server. `delay` is waited between two `banadd` commands to avoid hitting the flood
+/// protection of the server.
+///
+/// Returns the rules that were added.
+pub async fn apply(client: &Client, set: BanSet, delay: Duration) -> Result<Vec<BanRule>> {
+    let existing = export(client).await?;
+
+    let mut added: Vec<BanRule> = Vec::new();
+    for rule in set.into_inner() {
+        if existing.rules().iter().any(|r| r.same_target(&rule))
+            || added.iter().any(|r| r.same_target(&rule))
+        {
+            continue;
+        }
+
+        if !added.is_empty() {
+            sleep(delay).await;
+        }
+
+        client
+            .banadd(
+                rule.ip.as_deref(),
+                rule.name.as_deref(),
+                rule.uid.as_deref(),
+                rule.mytsid.as_deref(),
+                Some(rule.duration),
+                rule.reason.as_deref(),
+                rule.lastnickname.as_deref(),
+            )
+            .await?;
+
+        added.push(rule);
+    }
+
+    Ok(added)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{matching, BanRule, BanTarget};
+    use crate::response::BanEntry;
+
+    #[test]
+    fn test_ban_rule_matches() {
+        let target = BanTarget {
+            ip: Some("10.0.0.12"),
+            name: Some("SpamBot"),
+            uid: Some("abc="),
+            mytsid: None,
+        };
+
+        let rule = |ip: Option<&str>, name: Option<&str>, uid: Option<&str>| BanRule {
+            ip: ip.map(str::to_owned),
+            name: name.map(str::to_owned),
+            uid: uid.map(str::to_owned),
+            ..Default::default()
+        };
+
+        assert!(rule(Some("10\\.0\\.0\\..*"), None, None).matches(&target));
+        assert!(!rule(Some("10\\.0\\.0\\.1"), None, None).matches(&target));
+        assert!(rule(None, Some(".*bot"), None).matches(&target));
+        assert!(rule(None, None, Some("abc=")).matches(&target));
+        assert!(!rule(None, None, Some("abc")).matches(&target));
+        assert!(!rule(Some("10.*"), Some("other"), None).matches(&target));
+        assert!(!rule(Some("("), None, None).matches(&target));
+        assert!(!rule(None, None, None).matches(&target));
+        assert!(!BanRule {
+            mytsid: Some("x".to_owned()),
+            ..Default::default()
+        }
+        .matches(&target));
+    }
+
+    #[test]
+    fn test_matching() {
+        let bans = [
+            BanEntry {
+                banid: 1,
+                name: "spam.*".to_owned(),
+                ..Default::default()
+            },
+            BanEntry {
+                banid: 2,
+                ip: "192\\.168\\..*".to_owned(),
+                ..Default::default()
+            },
+        ];
+
+        let target = BanTarget {
+            name: Some("Spammer"),
+            ..Default::default()
+        };
+        let matched: Vec<u64> = matching(&bans, &target).iter().map(|b| b.banid).collect();
+        assert_eq!(matched, [1]);
+    }
+}
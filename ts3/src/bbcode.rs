@@ -0,0 +1,264 @@
+//! Builders for BBCode formatted messages.
+//!
+//! All text passed to the builders is escaped, so user-provided values (e.g. nicknames) cannot
+//! inject their own tags into the message.
+//!
+//! # Examples
+//!
+//! ```
+//! use ts3::bbcode::{self, BBCode};
+//! use ts3::shared::ClientId;
+//!
+//! let msg = BBCode::from("Welcome ")
+//!     + bbcode::client(ClientId(5), "abc=", "[b]Name")
+//!     + bbcode::bold(bbcode::color("#ff0000", "!"));
+//!
+//! assert_eq!(
+//!     msg.as_str(),
+//!     "Welcome [URL=client://5/abc=~%5Bb%5DName][\u{200b}b]Name[/URL][B][COLOR=#ff0000]![/COLOR][/B]"
+//! );
+//! ```
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::ops::{Add, AddAssign};
+
+use crate::shared::{ChannelId, ClientId};
+
+/// A BBCode formatted text.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BBCode {
+    buf: String,
+}
+
+impl BBCode {
+    /// Creates a new, empty `BBCode`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the formatted text.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    /// Consumes this `BBCode`, returning the formatted text.
+    #[inline]
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+
+    /// Appends escaped `text`.
+    pub fn push_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.buf.push(c);
+
+            // A zero-width space after an opening bracket prevents the client from parsing
+            // the following text as a tag while keeping the text visually unchanged.
+            if c == '[' {
+                self.buf.push('\u{200b}');
+            }
+        }
+    }
+
+    fn tag(name: &str, arg: Option<&str>, inner: BBCode) -> Self {
+        let mut buf = String::with_capacity(inner.buf.len() + name.len() * 2 + 5);
+
+        buf.push('[');
+        buf.push_str(name);
+        if let Some(arg) = arg {
+            buf.push('=');
+            buf.push_str(arg);
+        }
+        buf.push(']');
+        buf.push_str(&inner.buf);
+        buf.push_str("[/");
+        buf.push_str(name);
+        buf.push(']');
+
+        Self { buf }
+    }
+}
+
+impl Display for BBCode {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.buf)
+    }
+}
+
+impl From<&str> for BBCode {
+    #[inline]
+    fn from(value: &str) -> Self {
+        text(value)
+    }
+}
+
+impl From<String> for BBCode {
+    #[inline]
+    fn from(value: String) -> Self {
+        text(&value)
+    }
+}
+
+impl From<BBCode> for String {
+    #[inline]
+    fn from(value: BBCode) -> Self {
+        value.buf
+    }
+}
+
+impl<T> Add<T> for BBCode
+where
+    T: Into<BBCode>,
+{
+    type Output = BBCode;
+
+    #[inline]
+    fn add(mut self, rhs: T) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T> AddAssign<T> for BBCode
+where
+    T: Into<BBCode>,
+{
+    #[inline]
+    fn add_assign(&mut self, rhs: T) {
+        self.buf.push_str(&rhs.into().buf);
+    }
+}
+
+/// Creates an escaped plain text.
+pub fn text(text: &str) -> BBCode {
+    let mut code = BBCode::new();
+    code.push_text(text);
+    code
+}
+
+/// Creates a bold text.
+pub fn bold<T>(inner: T) -> BBCode
+where
+    T: Into<BBCode>,
+{
+    BBCode::tag("B", None, inner.into())
+}
+
+/// Creates an italic text.
+pub fn italic<T>(inner: T) -> BBCode
+where
+    T: Into<BBCode>,
+{
+    BBCode::tag("I", None, inner.into())
+}
+
+/// Creates an underlined text.
+pub fn underline<T>(inner: T) -> BBCode
+where
+    T: Into<BBCode>,
+{
+    BBCode::tag("U", None, inner.into())
+}
+
+/// Creates a colored text. `color` is either a color name (e.g. `red`) or a hex color
+/// (e.g. `#ff0000`). Invalid characters are removed from `color`.
+pub fn color<T>(color: &str, inner: T) -> BBCode
+where
+    T: Into<BBCode>,
+{
+    let color: String = color
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '#')
+        .collect();
+
+    BBCode::tag("COLOR", Some(&color), inner.into())
+}
+
+/// Creates a link to `url`.
+pub fn url<T>(url: &str, inner: T) -> BBCode
+where
+    T: Into<BBCode>,
+{
+    let mut encoded = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            '[' | ']' | '"' | ' ' => {
+                write!(encoded, "%{:02X}", c as u32).unwrap();
+            }
+            c if c.is_whitespace() || c.is_control() => {
+                percent_encode_char(&mut encoded, c);
+            }
+            c => encoded.push(c),
+        }
+    }
+
+    BBCode::tag("URL", Some(&encoded), inner.into())
+}
+
+/// Creates a link to the client with the given `clid`, `uid` and `nickname`. The link text
+/// is the `nickname`.
+pub fn client(clid: ClientId, uid: &str, nickname: &str) -> BBCode {
+    let mut link = format!("client://{}/", clid);
+    percent_encode(&mut link, uid);
+    link.push('~');
+    percent_encode(&mut link, nickname);
+
+    BBCode::tag("URL", Some(&link), text(nickname))
+}
+
+/// Creates a link to the channel `cid`. The link text is the channel `name`.
+pub fn channel(cid: ChannelId, name: &str) -> BBCode {
+    BBCode::tag("URL", Some(&format!("channelid://{}", cid)), text(name))
+}
+
+/// Percent-encodes all characters in `s` except for unreserved characters and the base64
+/// characters used in unique identifiers.
+fn percent_encode(buf: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' | '+' | '/' | '=' => {
+                buf.push(c)
+            }
+            c => percent_encode_char(buf, c),
+        }
+    }
+}
+
+fn percent_encode_char(buf: &mut String, c: char) {
+    let mut bytes = [0; 4];
+    for b in c.encode_utf8(&mut bytes).bytes() {
+        write!(buf, "%{:02X}", b).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bold, channel, color, text, url};
+    use crate::shared::ChannelId;
+
+    #[test]
+    fn test_text_escape() {
+        assert_eq!(text("Hello").as_str(), "Hello");
+        assert_eq!(text("[b]x[/b]").as_str(), "[\u{200b}b]x[\u{200b}/b]");
+    }
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(bold("x").as_str(), "[B]x[/B]");
+        assert_eq!(
+            color("red]", bold("x")).as_str(),
+            "[COLOR=red][B]x[/B][/COLOR]"
+        );
+        assert_eq!(
+            url("https://example.com/a b]", "link").as_str(),
+            "[URL=https://example.com/a%20b%5D]link[/URL]"
+        );
+        assert_eq!(
+            channel(ChannelId(3), "Lobby äö").as_str(),
+            "[URL=channelid://3]Lobby äö[/URL]"
+        );
+    }
+}
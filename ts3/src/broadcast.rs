@@ -0,0 +1,242 @@
+//! Broadcasts to all virtual servers with per-server placeholders.
+//!
+//! [`Client::gm`] sends the same message to every virtual server. A [`Broadcast`] renders a
+//! template for every online virtual server and sends the result to that server instead,
+//! either using [`Broadcast::send`] or [`Client::gm_with`].
+//! Placeholders are written as `%name%` and resolved using the [`ServerInfo`] of the server,
+//! which is requested right before the message is sent to that server.
+//!
+//! The built-in placeholders are:
+//!
+//! | Placeholder        | Value                                      |
+//! | ------------------ | ------------------------------------------ |
+//! | `%server_id%`      | The id of the virtual server.              |
+//! | `%server_name%`    | The name of the virtual server.            |
+//! | `%server_port%`    | The voice port of the virtual server.      |
+//! | `%clients_online%` | The number of clients, excluding queries.  |
+//! | `%max_clients%`    | The number of slots.                       |
+//! | `%platform%`       | The platform the server is running on.     |
+//! | `%version%`        | The version of the server.                 |
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ts3::broadcast::Broadcast;
+//! use ts3::Client;
+//!
+//! # async fn run(client: Client) -> Result<(), ts3::Error> {
+//! let broadcast = Broadcast::new().placeholder(|name, _| match name {
+//!     "downtime" => Some("10 minutes".to_owned()),
+//!     _ => None,
+//! });
+//!
+//! broadcast
+//!     .send(&client, "%server_name% restarts for maintenance (%downtime%)")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{self, Debug, Formatter};
+
+use crate::client::{Result, ServerSelection};
+use crate::request::TextMessageTarget;
+use crate::response::{MessageOutcome, ServerInfo, VirtualServerStatus};
+use crate::shared::ServerId;
+use crate::Client;
+
+type Placeholder = Box<dyn Fn(&str, &ServerInfo) -> Option<String> + Send + Sync>;
+
+/// A message template sent to all online virtual servers. See the [module documentation]
+/// for the supported placeholders.
+///
+/// [module documentation]: self
+#[derive(Default)]
+pub struct Broadcast {
+    placeholder: Option<Placeholder>,
+}
+
+impl Broadcast {
+    /// Creates a new `Broadcast` only resolving the built-in placeholders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolves additional placeholders using `f`. `f` is called with the name of the
+    /// placeholder without the `%` signs and is called before the built-in placeholders are
+    /// resolved, allowing to override them. Placeholders resolved by neither are sent
+    /// unchanged.
+    pub fn placeholder<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &ServerInfo) -> Option<String> + Send + Sync + 'static,
+    {
+        self.placeholder = Some(Box::new(f));
+        self
+    }
+
+    /// Renders `template` for the server described by `info`.
+    pub fn render(&self, template: &str, info: &ServerInfo) -> String {
+        render(template, |name| self.resolve(name, info))
+    }
+
+    /// Sends `template` to all online virtual servers, rendered for every server. Returns the
+    /// outcome for every server. Servers whose [`ServerInfo`] cannot be decoded are skipped,
+    /// otherwise sending stops at the first error.
+    ///
+    /// Every server is selected while the message is sent. Afterwards the server selected
+    /// before is selected again. If no server was selected the last server stays selected.
+    pub async fn send(
+        &self,
+        client: &Client,
+        template: &str,
+    ) -> Result<Vec<(ServerId, MessageOutcome)>> {
+        let selected = client.selected_server();
+        let res = self.send_all(client, template).await;
+
+        if client.selected_server() == selected {
+            return res;
+        }
+        match selected {
+            Some(ServerSelection::Id(sid)) => client.use_sid(sid).await?,
+            Some(ServerSelection::Port(port)) => client.use_port(port).await?,
+            None => (),
+        }
+
+        res
+    }
+
+    async fn send_all(
+        &self,
+        client: &Client,
+        template: &str,
+    ) -> Result<Vec<(ServerId, MessageOutcome)>> {
+        let mut outcomes = Vec::new();
+
+        for server in client.serverlist().await?.iter() {
+            if !matches!(server.virtualserver_status, VirtualServerStatus::Online) {
+                continue;
+            }
+
+            let sid = server.virtualserver_id;
+            client.use_sid(sid).await?;
+
+            // The number of clients changes between broadcasts, the info is never reused.
+            let info = match client.serverinfo().await {
+                Ok(info) => info,
+                Err(err) if err.is_decode() => continue,
+                Err(err) => return Err(err),
+            };
+
+            let msg = self.render(template, &info);
+            let outcome = client
+                .sendtextmessage_outcome(TextMessageTarget::Server, &msg)
+                .await?;
+            outcomes.push((sid, outcome));
+        }
+
+        Ok(outcomes)
+    }
+
+    fn resolve(&self, name: &str, info: &ServerInfo) -> Option<String> {
+        if let Some(value) = self.placeholder.as_ref().and_then(|f| f(name, info)) {
+            return Some(value);
+        }
+
+        let value = match name {
+            "server_id" => info.virtualserver_id.0.to_string(),
+            "server_name" => info.virtualserver_name.clone(),
+            "server_port" => info.virtualserver_port.to_string(),
+            "clients_online" => info
+                .virtualserver_clientsonline
+                .saturating_sub(info.virtualserver_queryclientsonline)
+                .to_string(),
+            "max_clients" => info.virtualserver_maxclients.to_string(),
+            "platform" => info.virtualserver_platform.clone(),
+            "version" => info.virtualserver_version.clone(),
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+impl Debug for Broadcast {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Broadcast")
+            .field("placeholder", &self.placeholder.is_some())
+            .finish()
+    }
+}
+
+/// Replaces all `%name%` placeholders in `template` with the value returned by `resolve`.
+/// Placeholders for which `resolve` returns `None` are kept unchanged.
+pub(crate) fn render<F>(template: &str, resolve: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(template.len());
+
+    let mut rest = template;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let value = rest
+            .find('%')
+            .map(|end| &rest[..end])
+            .filter(|name| is_placeholder(name))
+            .and_then(|name| Some((name, resolve(name)?)));
+
+        match value {
+            Some((name, value)) => {
+                out.push_str(&value);
+                rest = &rest[name.len() + 1..];
+            }
+            None => out.push('%'),
+        }
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Returns `true` if `name` is a valid placeholder name.
+fn is_placeholder(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Broadcast;
+    use crate::response::ServerInfo;
+    use crate::shared::ServerId;
+
+    #[test]
+    fn test_render() {
+        let info = ServerInfo {
+            virtualserver_id: ServerId(3),
+            virtualserver_name: "Test".to_owned(),
+            virtualserver_clientsonline: 5,
+            virtualserver_queryclientsonline: 1,
+            ..Default::default()
+        };
+
+        let broadcast = Broadcast::new().placeholder(|name, info| match name {
+            "server_name" => Some(info.virtualserver_name.to_uppercase()),
+            "downtime" => Some("5m".to_owned()),
+            _ => None,
+        });
+
+        assert_eq!(
+            broadcast.render("%server_name% (%server_id%): %clients_online%", &info),
+            "TEST (3): 4"
+        );
+        assert_eq!(
+            broadcast.render("down for %downtime%", &info),
+            "down for 5m"
+        );
+        assert_eq!(broadcast.render("100% %unknown%", &info), "100% %unknown%");
+        assert_eq!(broadcast.render("50%% %", &info), "50%% %");
+        assert_eq!(broadcast.render("%%server_id%", &info), "%3");
+    }
+}
@@ -1,32 +1,72 @@
 // Required for ts3_derive macro.
 #[allow(unused_imports)]
 use crate as ts3;
-use crate::request::{Request, RequestBuilder, ServerNotifyRegister, TextMessageTarget};
-use crate::response::Whoami;
+use crate::broadcast::Broadcast;
+#[cfg(feature = "serde")]
+use crate::kv::ConfigStore;
+use crate::kv::KvStore;
+use crate::membership::{self, MembershipStore, MemoryStore, TemporaryMembership};
+use crate::protocol::{trim_line, DEFAULT_MAX_LINE_LENGTH, REDACTED_KEYS};
+use crate::proxy::Proxy;
+use crate::request::{
+    ClientListOptions, ClientProperties, QueryLoginListOptions, Request, RequestBuilder,
+    ServerNotifyRegister, TempPassword, TextMessageTarget, TypedCommand, UseOptions,
+    VirtualServerProperties,
+};
+use crate::response::{Response, Whoami};
+use crate::schedule::{self, Schedule, ScheduleHandle};
+use crate::scope::{Instance, VirtualServer};
 use crate::shared::list::Pipe;
-
-pub use async_trait::async_trait;
-
-use crate::shared::{ClientDatabaseId, List, ServerGroupId, ServerId};
+use crate::shared::{ChannelId, ClientDatabaseId, ClientId, List, ServerGroupId, ServerId};
+use crate::voice::VoiceState;
 use crate::{
-    event::{EventHandler, Handler},
-    response::{ApiKey, Version},
-    shared::ApiKeyScope,
-    Decode, Error, ErrorKind,
+    event::{
+        panic_message, Dispatch, Event, EventFilter, EventHandler, FnHandler, Handler, InFlight,
+        Nicknames, Occupancy, ReasonId, Reconnects, ReplayBuffer, Tasks,
+    },
+    response::{
+        ApiKey, BanEntry, ChannelGroup, ChannelInfo, ClientDbInfo, ClientInfo, ClientListEntry,
+        Complaint, ConnectionInfo, CustomProperty, CustomSearchEntry, FileEntry, FileTransferInit,
+        HostInfo, MembershipChange, Message, MessageEntry, MessageOutcome, PermissionOverview,
+        QueryLogin, ServerGroup, ServerGroupMembership, ServerInfo, ServerListEntry, Snapshot,
+        SnapshotDeployed, TempPasswordEntry, Token, Version, VirtualServerCreated,
+    },
+    shared::{ApiKeyScope, GroupType},
+    Decode, Error, ErrorKind, LossyGuard, TolerantGuard,
 };
 use bytes::Bytes;
 use std::{
+    borrow::Cow,
+    collections::hash_map::RandomState,
     convert::From,
+    fmt::{self, Debug, Formatter},
+    future::{poll_fn, Future},
+    hash::{BuildHasher, Hasher},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+    pin::Pin,
     result,
-    sync::{Arc, RwLock},
-    time::Duration,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+    ReadHalf, WriteHalf,
 };
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::{
-    net::{TcpStream, ToSocketAddrs},
-    sync::{mpsc, oneshot},
-    task::spawn,
-    time::sleep,
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, error::TrySendError},
+        oneshot, watch,
+    },
+    task::{spawn, JoinHandle},
+    time::{sleep, sleep_until, timeout},
+};
+#[cfg(feature = "rustls")]
+use tokio_rustls::{
+    rustls::{pki_types::ServerName, ClientConfig, RootCertStore},
+    TlsConnector,
 };
 
 pub type Result<T> = result::Result<T, Error>;
@@ -36,88 +76,1513 @@ impl Error {
         use ErrorKind::*;
 
         match &self.0 {
-            TS3 { id, msg: _ } => *id == 0,
+            TS3 { id, .. } => *id == 0,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the failed operation may succeed when it is retried later. This
+    /// includes connection errors, flood protection and a full command queue.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind() {
+            ErrorKind::TS3 { id, .. } => matches!(*id, ERROR_CLIENT_IS_FLOODING),
+            ErrorKind::Io(_)
+            | ErrorKind::TimedOut
+            | ErrorKind::SendError
+            | ErrorKind::QueueFull => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the failed operation can never succeed when it is retried without
+    /// changes. This includes bans, missing permissions and invalid parameters.
+    pub fn is_fatal(&self) -> bool {
+        match self.kind() {
+            ErrorKind::TS3 { id, .. } => matches!(
+                *id,
+                ERROR_COMMAND_NOT_FOUND | ERROR_INVALID_LOGIN | ERROR_PARAMETER_QUOTE
+                    ..=ERROR_PARAMETER_MISSING
+                        | ERROR_INSUFFICIENT_PERMISSIONS
+                        | ERROR_BANNED
+                        | ERROR_FLOOD_BANNED
+            ),
+            ErrorKind::TooLong { .. }
+            | ErrorKind::InvalidRequest { .. }
+            | ErrorKind::Unsupported { .. } => true,
             _ => false,
         }
     }
+
+    /// Returns the time to wait before retrying if the command was rejected by the flood
+    /// protection of the server. Returns `None` for all other errors or if the server did
+    /// not include the time.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.kind() {
+            ErrorKind::TS3 {
+                id: ERROR_CLIENT_IS_FLOODING,
+                extra_msg: Some(extra_msg),
+                ..
+            } => extra_msg
+                .split(' ')
+                .find_map(|word| word.parse().ok())
+                .map(Duration::from_secs),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the command was rejected by [`Client::try_send`] because the command
+    /// queue was full.
+    pub fn is_queue_full(&self) -> bool {
+        matches!(self.kind(), ErrorKind::QueueFull)
+    }
+
+    /// Returns `true` if the server responded with an empty result set.
+    pub(crate) fn is_empty_result(&self) -> bool {
+        matches!(self.kind(), ErrorKind::TS3 { id: 1281, .. })
+    }
+
+    /// Returns `true` if the command was rejected by the flood protection of the server.
+    pub(crate) fn is_flooding(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::TS3 {
+                id: ERROR_CLIENT_IS_FLOODING,
+                ..
+            }
+        )
+    }
+
+    /// Returns `true` if the server responded that no valid virtual server is selected.
+    pub(crate) fn is_invalid_server(&self) -> bool {
+        matches!(self.kind(), ErrorKind::TS3 { id: 1024, .. })
+    }
+
+    /// Returns `true` if the response of the server could not be decoded.
+    pub(crate) fn is_decode(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Decode(_)
+                | ErrorKind::ParseInt(_)
+                | ErrorKind::ParseFloat(_)
+                | ErrorKind::Utf8(_)
+        )
+    }
+
+    /// Returns `true` if the server responded with a duplicate entry error.
+    pub(crate) fn is_duplicate_entry(&self) -> bool {
+        matches!(self.kind(), ErrorKind::TS3 { id: 2561, .. })
+    }
 }
 
+// Error ids returned by the server.
+const ERROR_COMMAND_NOT_FOUND: u16 = 0x0100;
+const ERROR_INVALID_LOGIN: u16 = 0x0208;
+const ERROR_CLIENT_IS_FLOODING: u16 = 0x020c;
+const ERROR_PARAMETER_QUOTE: u16 = 0x0600;
+const ERROR_PARAMETER_MISSING: u16 = 0x0606;
+const ERROR_INSUFFICIENT_PERMISSIONS: u16 = 0x0a08;
+const ERROR_BANNED: u16 = 0x0d01;
+const ERROR_FLOOD_BANNED: u16 = 0x0d03;
+
 struct Cmd {
     bytes: Bytes,
     resp: oneshot::Sender<Result<Vec<u8>>>,
+    /// The time the command was queued.
+    queued: Instant,
+}
+
+impl Cmd {
+    fn new(bytes: Bytes, resp: oneshot::Sender<Result<Vec<u8>>>) -> Self {
+        Self {
+            bytes,
+            resp,
+            queued: Instant::now(),
+        }
+    }
+}
+
+/// The priority of commands sent by a [`Client`]. High priority commands are sent before
+/// any queued normal priority commands. Commands are never reordered once sent, responses
+/// are always matched to their commands.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
+}
+
+/// What happens when an event handler panics. Set using [`ClientBuilder::panic_policy`].
+///
+/// The panic is always reported to [`EventHandler::error`], the error returns the panic
+/// message from [`Error::as_handler_panic`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PanicPolicy {
+    /// Only report the panic. Other events are still handled.
+    #[default]
+    Report,
+    /// Report the panic and disconnect the client using [`Client::quit`].
+    Disconnect,
+}
+
+/// The server software a [`Client`] is connected to. Set using [`ClientBuilder::flavor`].
+///
+/// Servers other than the TeamSpeak 3 server implement the ServerQuery protocol with small
+/// differences. For these servers the client reads the welcome banner of the server, accepts
+/// reason ids that do not fit the TeamSpeak 3 range and fields with a known different
+/// format, like the non-numeric build of TeaSpeak, and rejects commands the server does not
+/// implement before sending them. All other fields failing to decode are still errors.
+/// Unknown keys are always ignored.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ServerFlavor {
+    /// The TeamSpeak 3 server.
+    #[default]
+    TeamSpeak3,
+    /// Newer TeamSpeak server builds, shipped alongside the TeamSpeak 5 client.
+    TeamSpeak,
+    /// The TeaSpeak server.
+    TeaSpeak,
+}
+
+impl ServerFlavor {
+    /// Returns `true` if the server implements `command`.
+    pub fn supports(&self, command: &str) -> bool {
+        match self {
+            // TeaSpeak has no API keys.
+            Self::TeaSpeak => !matches!(command, "apikeyadd" | "apikeylist" | "apikeydel"),
+            Self::TeamSpeak3 | Self::TeamSpeak => true,
+        }
+    }
+
+    /// Returns `true` if the client should tolerate deviations from the TeamSpeak 3 server.
+    pub(crate) fn is_tolerant(&self) -> bool {
+        !matches!(self, Self::TeamSpeak3)
+    }
+
+    /// Returns the number of lines of the welcome banner, not counting blank lines.
+    fn banner_lines(&self) -> usize {
+        match self {
+            Self::TeamSpeak3 | Self::TeamSpeak => 2,
+            // TeaSpeak appends its version.
+            Self::TeaSpeak => 3,
+        }
+    }
+}
+
+/// What to do with running event handler tasks in [`Client::disconnect`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Shutdown {
+    /// Wait until all event handler tasks are completed. Deadlocks if used from within an
+    /// event handler, since the handler waits for itself.
+    #[default]
+    Wait,
+    /// Abort all event handler tasks at their next `.await`.
+    Abort,
+    /// Leave the tasks running in the background.
+    Detach,
+}
+
+/// A virtual server selected using the `use` command. Returned from
+/// [`Client::selected_server`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ServerSelection {
+    /// The server was selected by its id.
+    Id(ServerId),
+    /// The server was selected by its voice port.
+    Port(u16),
+}
+
+/// The direction of a line passed to the [`ClientBuilder::on_wire`] hook.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// The line was sent to the server.
+    Sent,
+    /// The line was received from the server.
+    Received,
+}
+
+type WireHook = Arc<dyn Fn(Direction, &str) + Send + Sync>;
+
+type SlowCommandHook = Arc<dyn Fn(&SlowCommand) + Send + Sync>;
+
+type SetupHook =
+    Arc<dyn Fn(Client) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync>;
+
+/// A command whose response took longer than the threshold set with
+/// [`ClientBuilder::on_slow_command`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SlowCommand {
+    /// The name of the command. Arguments are omitted, they may contain passwords.
+    pub command: String,
+    /// The time the command waited in the queue before it was sent. A long wait means many
+    /// commands are sent at the same time or the previous commands were slow.
+    pub queued: Duration,
+    /// The time between sending the command and receiving its response. A long time means
+    /// the server is overloaded or throttles the client.
+    pub server: Duration,
+}
+
+impl SlowCommand {
+    /// Returns the total time from queueing the command until its response was received.
+    pub fn total(&self) -> Duration {
+        self.queued + self.server
+    }
+}
+
+/// Calls `hook` with `line`, redacting the values of sensitive keys.
+fn wire(hook: &WireHook, direction: Direction, line: &[u8]) {
+    let line = String::from_utf8_lossy(line);
+    hook(direction, &redact(&line));
+}
+
+/// Returns the name of the command in `bytes`.
+fn command_name(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes.split(|b| *b == b' ').next().unwrap_or_default())
+}
+
+/// Replaces the values of all [`REDACTED_KEYS`] in `line` with `***`.
+fn redact(line: &str) -> Cow<'_, str> {
+    if !REDACTED_KEYS.iter().any(|key| line.contains(key)) {
+        return Cow::Borrowed(line);
+    }
+
+    let mut out = String::with_capacity(line.len());
+    for (i, args) in line.split(' ').enumerate() {
+        if i != 0 {
+            out.push(' ');
+        }
+
+        for (j, arg) in args.split('|').enumerate() {
+            if j != 0 {
+                out.push('|');
+            }
+
+            match arg.split_once('=') {
+                Some((key, _)) if REDACTED_KEYS.contains(&key) => {
+                    out.push_str(key);
+                    out.push_str("=***");
+                }
+                _ => out.push_str(arg),
+            }
+        }
+    }
+
+    Cow::Owned(out)
+}
+
+/// The receiving end of the command queues of both priorities and the commands of the
+/// setup hook.
+struct CmdQueue {
+    setup: mpsc::Receiver<Cmd>,
+    high: mpsc::Receiver<Cmd>,
+    normal: mpsc::Receiver<Cmd>,
+}
+
+impl CmdQueue {
+    /// Polls for the next command, preferring commands of the setup hook and then high
+    /// priority commands.
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<Cmd>> {
+        if let Poll::Ready(Some(cmd)) = self.setup.poll_recv(cx) {
+            return Poll::Ready(Some(cmd));
+        }
+
+        if let Poll::Ready(Some(cmd)) = self.high.poll_recv(cx) {
+            return Poll::Ready(Some(cmd));
+        }
+
+        self.normal.poll_recv(cx)
+    }
+
+    /// Closes all queues and returns all queued commands.
+    fn close(&mut self) -> Vec<Cmd> {
+        self.setup.close();
+        self.high.close();
+        self.normal.close();
+
+        let mut cmds = Vec::new();
+        while let Ok(cmd) = self.setup.try_recv() {
+            cmds.push(cmd);
+        }
+        while let Ok(cmd) = self.high.try_recv() {
+            cmds.push(cmd);
+        }
+        while let Ok(cmd) = self.normal.try_recv() {
+            cmds.push(cmd);
+        }
+
+        cmds
+    }
 }
 
 pub(crate) struct ClientInner {
-    pub(crate) handler: Arc<dyn EventHandler>,
+    pub(crate) handler: Arc<dyn Dispatch>,
+    /// The tasks spawned for `handler`.
+    pub(crate) in_flight: Arc<InFlight>,
+    /// The tasks spawned for all handlers.
+    pub(crate) tasks: Arc<Tasks>,
+    pub(crate) whoami: Option<Whoami>,
+    pub(crate) ignore_own_messages: bool,
+    pub(crate) filter: Arc<EventFilter>,
+    pub(crate) replay: ReplayBuffer,
+    /// The channels of all clients while occupancy is tracked.
+    pub(crate) occupancy: Option<Occupancy>,
+    /// Recently left clients if reconnects are correlated.
+    pub(crate) reconnects: Option<Reconnects>,
+    /// The nicknames of all clients while nickname changes are tracked.
+    pub(crate) nicknames: Option<Nicknames>,
+    /// The last requested uptimes.
+    uptime: UptimeCache,
+    /// Receives events while no event handler is set.
+    pub(crate) dead_letter: Option<mpsc::Sender<Event>>,
+    /// Whether an event handler was set by the user.
+    pub(crate) handler_set: bool,
+    /// The selected server and the `use` command that selected it.
+    selected: Option<(ServerSelection, Bytes)>,
+    /// Persists temporary group memberships.
+    membership_store: Arc<dyn MembershipStore>,
+    /// Set once the connection is closed or the client is disconnected.
+    closed: watch::Sender<bool>,
+    /// Unset while the client reconnects, until the setup hook for the new connection
+    /// completed.
+    connected: watch::Sender<bool>,
+    /// The settings the connection was opened with.
+    pub(crate) transport: Transport,
 }
 
 impl ClientInner {
-    fn new() -> ClientInner {
+    fn new(builder: &ClientBuilder, transport: Transport) -> ClientInner {
         ClientInner {
             handler: Arc::new(Handler),
+            in_flight: Arc::default(),
+            tasks: Arc::new(Tasks::new(builder.max_concurrent_handlers)),
+            whoami: None,
+            ignore_own_messages: false,
+            filter: Arc::default(),
+            replay: ReplayBuffer::new(builder.replay_buffer),
+            occupancy: None,
+            reconnects: builder.reconnect_window.map(Reconnects::new),
+            nicknames: None,
+            uptime: UptimeCache::default(),
+            dead_letter: None,
+            handler_set: false,
+            selected: None,
+            membership_store: Arc::new(MemoryStore::new()),
+            closed: watch::Sender::new(false),
+            connected: watch::Sender::new(true),
+            transport,
+        }
+    }
+
+    /// Forgets the state tied to the selected virtual server, after another server was
+    /// selected or the connection was lost.
+    fn reset_session(&mut self) {
+        self.occupancy = None;
+        self.nicknames = None;
+        self.uptime.server = None;
+        if let Some(reconnects) = &mut self.reconnects {
+            reconnects.clear();
         }
     }
 }
 
+/// Marks the connection of a [`Client`] as closed when dropped.
+struct ClosedGuard(Client);
+
+impl Drop for ClosedGuard {
+    fn drop(&mut self) {
+        self.0.set_closed();
+    }
+}
+
 /// A Client used to send commands to the serverquery interface.
 #[derive(Clone)]
 pub struct Client {
     tx: mpsc::Sender<Cmd>,
+    priority_tx: mpsc::Sender<Cmd>,
+    /// The queue of the commands sent by the setup hook.
+    setup_tx: mpsc::Sender<Cmd>,
+    priority: Priority,
+    /// Whether the client was passed to the setup hook.
+    in_setup: bool,
+    pub(crate) lossy: bool,
+    pub(crate) flavor: ServerFlavor,
+    pub(crate) panic_policy: PanicPolicy,
     pub(crate) inner: Arc<RwLock<ClientInner>>,
 }
 
-impl Client {
-    /// Create a new connection
-    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Client> {
-        let (tx, mut rx) = mpsc::channel::<Cmd>(32);
+/// The keepalive behavior of a [`Client`]. The server disconnects query clients which
+/// are idle for a few minutes, a keepalive command is sent to prevent this.
+///
+/// The keepalive is paused while other commands are sent.
+#[derive(Clone, Debug)]
+pub struct Keepalive {
+    command: &'static str,
+    interval: Duration,
+    jitter: Duration,
+}
+
+impl Keepalive {
+    /// Creates a new `Keepalive` sending `version` every 60 seconds.
+    pub fn new() -> Self {
+        Self {
+            command: "version",
+            interval: Duration::from_secs(60),
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Sets the command sent as keepalive. Defaults to `version`. The command must not
+    /// have any side effects, `whoami` is a common alternative.
+    pub fn command(mut self, command: &'static str) -> Self {
+        self.command = command;
+        self
+    }
+
+    /// Sets the time without any commands before a keepalive is sent. Defaults to 60
+    /// seconds.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the maximum random delay added to every interval. Defaults to no jitter.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the next delay between keepalives, including a random jitter.
+    fn delay(&self) -> Duration {
+        if self.jitter.is_zero() {
+            return self.interval;
+        }
+
+        let nanos = RandomState::new().build_hasher().finish() % self.jitter.as_nanos() as u64;
+        self.interval + Duration::from_nanos(nanos)
+    }
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The reconnect behavior of a [`Client`]. Set using [`ClientBuilder::reconnect`].
+///
+/// The delay before the first attempt is [`delay`], it doubles after every failed attempt
+/// up to [`max_delay`].
+///
+/// [`delay`]: Self::delay
+/// [`max_delay`]: Self::max_delay
+#[derive(Clone, Debug)]
+pub struct Reconnect {
+    delay: Duration,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+}
+
+impl Reconnect {
+    /// Creates a new `Reconnect` waiting one second before the first attempt, at most 60
+    /// seconds between attempts and never giving up.
+    pub fn new() -> Self {
+        Self {
+            delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: None,
+        }
+    }
+
+    /// Sets the delay before the first attempt. Defaults to one second.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Sets the maximum delay between two attempts. Defaults to 60 seconds.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the number of attempts after which the client gives up and is closed. Unlimited
+    /// by default.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Returns the delay before the attempt `attempt`, starting at `0`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for Reconnect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A builder for a [`Client`] with custom configuration.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use ts3::{Client, Keepalive};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///     let client = Client::builder()
+///         .keepalive(Keepalive::new().command("whoami").jitter(Duration::from_secs(5)))
+///         .connect("localhost:10011")
+///         .await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder {
+    keepalive: Option<Keepalive>,
+    watchdog: Option<Duration>,
+    connect_timeout: Duration,
+    proxy: Option<Proxy>,
+    #[cfg(feature = "rustls")]
+    tls_config: Option<Arc<ClientConfig>>,
+    queue_size: usize,
+    max_line_length: usize,
+    on_wire: Option<WireHook>,
+    setup: Option<SetupHook>,
+    on_slow_command: Option<(Duration, SlowCommandHook)>,
+    lossy: bool,
+    flavor: ServerFlavor,
+    replay_buffer: usize,
+    reconnect_window: Option<Duration>,
+    reconnect: Option<Reconnect>,
+    panic_policy: PanicPolicy,
+    max_concurrent_handlers: Option<usize>,
+}
+
+/// Connects to the first reachable address `addr` resolves to, trying each address for at
+/// most `timeout`. Returns the error of the last attempt if no address is reachable.
+async fn connect_any<A: ToSocketAddrs>(addr: A, timeout: Duration) -> Result<TcpStream> {
+    let addrs = interleave(lookup_host(addr).await.map_err(|e| Error(e.into()))?);
+
+    let mut last_err = Error(ErrorKind::Io(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "could not resolve to any address",
+    )));
+
+    for addr in addrs {
+        match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(err)) => last_err = Error(err.into()),
+            Err(_) => last_err = Error(ErrorKind::TimedOut),
+        }
+    }
+
+    Err(last_err)
+}
+
+/// Connects to `addr` through `proxy`. `addr` is not resolved locally, host names are
+/// passed to the proxy, which resolves them.
+async fn connect_proxy<A: ServerAddr>(
+    proxy: &Proxy,
+    addr: A,
+    timeout: Duration,
+) -> Result<TcpStream> {
+    let (host, port) = addr.host_port().map_err(|e| Error(e.into()))?;
+
+    let mut stream = connect_any(proxy.addr(), timeout).await?;
+    match tokio::time::timeout(timeout, proxy.handshake(&mut stream, &host, port)).await {
+        Ok(Ok(())) => Ok(stream),
+        Ok(Err(err)) => Err(Error(err.into())),
+        Err(_) => Err(Error(ErrorKind::TimedOut)),
+    }
+}
+
+/// A connection opened by [`Transport::connect`].
+pub(crate) trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Stream for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// The settings used to open the connection of a [`Client`]. File transfer connections are
+/// opened using the same settings.
+#[derive(Clone, Debug)]
+pub(crate) struct Transport {
+    proxy: Option<Proxy>,
+    connect_timeout: Duration,
+    #[cfg(feature = "rustls")]
+    tls: Option<(Arc<ClientConfig>, ServerName<'static>)>,
+}
+
+impl Transport {
+    /// Opens a connection to `addr`, through the proxy and over TLS if configured.
+    pub(crate) async fn connect<A: ServerAddr>(&self, addr: A) -> Result<Box<dyn Stream>> {
+        let stream = match &self.proxy {
+            Some(proxy) => connect_proxy(proxy, addr, self.connect_timeout).await?,
+            None => connect_any(addr, self.connect_timeout).await?,
+        };
+
+        #[cfg(feature = "rustls")]
+        if let Some((config, server_name)) = &self.tls {
+            let connector = TlsConnector::from(config.clone());
+            return match tokio::time::timeout(
+                self.connect_timeout,
+                connector.connect(server_name.clone(), stream),
+            )
+            .await
+            {
+                Ok(Ok(stream)) => Ok(Box::new(stream)),
+                Ok(Err(err)) => Err(Error(err.into())),
+                Err(_) => Err(Error(ErrorKind::TimedOut)),
+            };
+        }
+
+        Ok(Box::new(stream))
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The address of a server passed to [`ClientBuilder::connect`]. Implemented for the same
+/// types as tokio's [`ToSocketAddrs`].
+///
+/// Unlike [`ToSocketAddrs`], the host and port can be obtained without resolving the
+/// address, so that host names are resolved by a [`Proxy`] instead of locally.
+pub trait ServerAddr: ToSocketAddrs + sealed::Sealed {
+    /// Returns the unresolved host and the port of the address.
+    #[doc(hidden)]
+    fn host_port(&self) -> io::Result<(String, u16)>;
+}
+
+macro_rules! impl_server_addr {
+    ($($ty:ty => |$addr:ident| $body:expr;)*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl ServerAddr for $ty {
+                fn host_port(&self) -> io::Result<(String, u16)> {
+                    let $addr = self;
+                    Ok($body)
+                }
+            }
+        )*
+    };
+}
+
+impl_server_addr! {
+    SocketAddr => |addr| (addr.ip().to_string(), addr.port());
+    SocketAddrV4 => |addr| (addr.ip().to_string(), addr.port());
+    SocketAddrV6 => |addr| (addr.ip().to_string(), addr.port());
+    (IpAddr, u16) => |addr| (addr.0.to_string(), addr.1);
+    (Ipv4Addr, u16) => |addr| (addr.0.to_string(), addr.1);
+    (Ipv6Addr, u16) => |addr| (addr.0.to_string(), addr.1);
+    (&str, u16) => |addr| (addr.0.to_owned(), addr.1);
+    (String, u16) => |addr| (addr.0.clone(), addr.1);
+    str => |addr| split_host_port(addr)?;
+    String => |addr| split_host_port(addr)?;
+}
+
+impl sealed::Sealed for &[SocketAddr] {}
+
+impl ServerAddr for &[SocketAddr] {
+    fn host_port(&self) -> io::Result<(String, u16)> {
+        match self.first() {
+            Some(addr) => addr.host_port(),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any address",
+            )),
+        }
+    }
+}
+
+impl<T> sealed::Sealed for &T where T: ServerAddr + ?Sized {}
+
+impl<T> ServerAddr for &T
+where
+    T: ServerAddr + ?Sized,
+{
+    fn host_port(&self) -> io::Result<(String, u16)> {
+        (**self).host_port()
+    }
+}
+
+/// Splits `addr` in the form `host:port` or `[ipv6]:port`.
+fn split_host_port(addr: &str) -> io::Result<(String, u16)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "invalid socket address");
+
+    let (host, port) = addr.rsplit_once(':').ok_or_else(invalid)?;
+    let host = match host.strip_prefix('[') {
+        Some(host) => host.strip_suffix(']').ok_or_else(invalid)?,
+        None => host,
+    };
+    let port = port.parse().map_err(|_| invalid())?;
+
+    Ok((host.to_owned(), port))
+}
+
+/// The time after which a cached uptime is requested again.
+const UPTIME_REFRESH: Duration = Duration::from_secs(60);
+
+/// Uptimes together with the time they were requested.
+#[derive(Debug, Default)]
+struct UptimeCache {
+    server: Option<(Duration, Instant)>,
+    instance: Option<(Duration, Instant)>,
+}
+
+impl UptimeCache {
+    /// Returns the cached uptime advanced by the time since it was requested, or `None` if
+    /// it must be requested again.
+    fn get(entry: Option<(Duration, Instant)>) -> Option<Duration> {
+        let (uptime, time) = entry?;
+        let elapsed = time.elapsed();
+        (elapsed < UPTIME_REFRESH).then(|| uptime + elapsed)
+    }
+}
+
+/// Reads the welcome banner sent by the server after connecting. The number of lines
+/// depends on the `flavor` of the server.
+async fn read_banner<R>(reader: &mut R, flavor: ServerFlavor, max: usize) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut buf = Vec::new();
+    for _ in 0..flavor.banner_lines() {
+        buf.clear();
+        read_line(reader, &mut buf, max).await?;
+    }
+
+    Ok(())
+}
+
+/// Reads the next line into `buf`, without the terminator and surrounding whitespace.
+/// Returns an error if the connection was closed before a full line was read or the line is
+/// longer than `max` bytes.
+///
+/// Lines are terminated by `\n\r`, but some servers send `\r\n` or a lone `\r` instead.
+/// Blank lines are skipped, so they do not shift the pairing of responses and errors.
+async fn read_line<R>(reader: &mut R, buf: &mut Vec<u8>, max: usize) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let start = buf.len();
+
+    loop {
+        buf.truncate(start);
+        read_until_cr(reader, buf, max).await?;
+
+        if !buf[start..].ends_with(b"\r") {
+            return Err(connection_closed());
+        }
+
+        let Some(line) = trim_line(&buf[start..]) else {
+            continue;
+        };
+
+        let offset = line.as_ptr() as usize - buf.as_ptr() as usize;
+        let len = line.len();
+        buf.copy_within(offset..offset + len, start);
+        buf.truncate(start + len);
+        return Ok(());
+    }
+}
+
+/// Reads until the next `\r` into `buf`, including the `\r`. Returns an error if the line
+/// is longer than `max` bytes without the `\n\r` terminator, instead of buffering an
+/// unbounded line.
+async fn read_until_cr<R>(reader: &mut R, buf: &mut Vec<u8>, max: usize) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let start = buf.len();
+    let limit = max.saturating_add(2);
+    (&mut *reader)
+        .take(u64::try_from(limit).unwrap_or(u64::MAX))
+        .read_until(b'\r', buf)
+        .await
+        .map_err(|e| Error(e.into()))?;
+
+    if buf.len() - start == limit && !buf.ends_with(b"\r") {
+        return Err(Error(ErrorKind::LineTooLong { max }));
+    }
+
+    Ok(())
+}
+
+fn connection_closed() -> Error {
+    Error(ErrorKind::Io(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "connection closed",
+    )))
+}
+
+/// Converts a file transfer rejected with a non-zero `status` into an error.
+fn check_transfer(init: FileTransferInit) -> Result<FileTransferInit> {
+    match init.status {
+        0 => Ok(init),
+        id => Err(Error(ErrorKind::TS3 {
+            id,
+            msg: init.msg,
+            extra_msg: None,
+        })),
+    }
+}
+
+/// Returns a TLS configuration trusting the Mozilla root certificates.
+#[cfg(feature = "rustls")]
+fn default_tls_config() -> Arc<ClientConfig> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+
+    let config = ClientConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .expect("default protocol versions are supported")
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+
+    Arc::new(config)
+}
+
+/// Orders `addrs` alternating between address families, starting with the family of the
+/// first address, as recommended by RFC 8305. The order within a family is kept.
+fn interleave<I>(addrs: I) -> Vec<SocketAddr>
+where
+    I: IntoIterator<Item = SocketAddr>,
+{
+    let mut addrs = addrs.into_iter().peekable();
+    let prefer_v6 = addrs.peek().is_some_and(SocketAddr::is_ipv6);
+
+    let (v6, v4): (Vec<_>, Vec<_>) = addrs.partition(SocketAddr::is_ipv6);
+    let (mut first, mut second) = match prefer_v6 {
+        true => (v6.into_iter(), v4.into_iter()),
+        false => (v4.into_iter(), v6.into_iter()),
+    };
+
+    let mut out = Vec::with_capacity(first.len() + second.len());
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => return out,
+            (a, b) => out.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+impl Debug for ClientBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("keepalive", &self.keepalive)
+            .field("watchdog", &self.watchdog)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("proxy", &self.proxy)
+            .field("queue_size", &self.queue_size)
+            .field("max_line_length", &self.max_line_length)
+            .field("lossy", &self.lossy)
+            .field("flavor", &self.flavor)
+            .field("replay_buffer", &self.replay_buffer)
+            .field("reconnect_window", &self.reconnect_window)
+            .field("reconnect", &self.reconnect)
+            .field("panic_policy", &self.panic_policy)
+            .field("max_concurrent_handlers", &self.max_concurrent_handlers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a new `ClientBuilder` with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            keepalive: Some(Keepalive::new()),
+            watchdog: None,
+            connect_timeout: Duration::from_secs(10),
+            proxy: None,
+            #[cfg(feature = "rustls")]
+            tls_config: None,
+            queue_size: 32,
+            max_line_length: DEFAULT_MAX_LINE_LENGTH,
+            on_wire: None,
+            setup: None,
+            on_slow_command: None,
+            lossy: false,
+            flavor: ServerFlavor::TeamSpeak3,
+            replay_buffer: 0,
+            reconnect_window: None,
+            reconnect: None,
+            panic_policy: PanicPolicy::Report,
+            max_concurrent_handlers: None,
+        }
+    }
+
+    /// Sets the [`Keepalive`] of the client. Defaults to [`Keepalive::new`].
+    pub fn keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Disables the keepalive. The server disconnects the client if it is idle for too
+    /// long.
+    pub fn no_keepalive(mut self) -> Self {
+        self.keepalive = None;
+        self
+    }
+
+    /// Enables the watchdog, closing the connection if the server does not respond to a
+    /// command within `window`. This detects connections that died silently, which would
+    /// otherwise block all commands forever. Disabled by default.
+    ///
+    /// If nothing was sent or received for `window`, the watchdog sends the keepalive
+    /// command, or `version` if the keepalive is disabled, to check that the server still
+    /// responds. A dead connection is therefore detected within two windows, even while no
+    /// other commands are sent.
+    ///
+    /// When the watchdog fires, the pending command fails and the event handler receives an
+    /// error. If the client reconnects, see [`reconnect`], the queued commands are sent over
+    /// the new connection. Otherwise all queued commands fail and all further commands fail
+    /// immediately.
+    ///
+    /// [`reconnect`]: Self::reconnect
+    pub fn watchdog(mut self, window: Duration) -> Self {
+        self.watchdog = Some(window);
+        self
+    }
+
+    /// Sets the timeout for a single connection attempt. If `addr` resolves to multiple
+    /// addresses they are tried in turn, alternating between IPv6 and IPv4, until one
+    /// succeeds. Defaults to 10 seconds.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Tunnels the connection through `proxy`. See the [`proxy`] module for more details.
+    ///
+    /// [`proxy`]: crate::proxy
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets the TLS configuration used by [`ClientBuilder::connect_tls`]. Defaults to a
+    /// configuration trusting the Mozilla root certificates.
+    #[cfg(feature = "rustls")]
+    pub fn tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Sets the number of commands that can be queued before being sent. Once the queue is
+    /// full [`Client::send`] waits for a free slot, while [`Client::try_send`] fails
+    /// immediately. Defaults to `32`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `queue_size` is `0`.
+    pub fn queue_size(mut self, queue_size: usize) -> Self {
+        assert!(queue_size != 0, "queue_size must not be 0");
+        self.queue_size = queue_size;
+        self
+    }
+
+    /// Sets the maximum length of a line received from the server in bytes. The connection is
+    /// closed with an error if the server sends a longer line, instead of buffering it until
+    /// the application runs out of memory. Defaults to 16 MiB.
+    ///
+    /// Responses are sent as a single line, the limit must be large enough for the largest
+    /// expected response, e.g. a snapshot created with [`Client::serversnapshotcreate`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_line_length` is `0`.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        assert!(max_line_length != 0, "max_line_length must not be 0");
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    /// Enables lossy decoding of strings in responses and events. Invalid UTF-8 is replaced
+    /// with `U+FFFD REPLACEMENT CHARACTER` and unknown escape sequences are kept as they
+    /// are, instead of failing the whole response or event. Disabled by default.
+    pub fn lossy_decoding(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// Sets the server software the client connects to. See [`ServerFlavor`] for the
+    /// differences. Defaults to [`ServerFlavor::TeamSpeak3`].
+    pub fn flavor(mut self, flavor: ServerFlavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Keeps the last `len` events dispatched to the event handler, so that a handler
+    /// attached shortly after [`Client::servernotifyregister`] can still receive events that
+    /// arrived before it was set. See [`Client::replay_into`]. Defaults to `0`, which
+    /// disables the buffer.
+    pub fn replay_buffer(mut self, len: usize) -> Self {
+        self.replay_buffer = len;
+        self
+    }
+
+    /// Synthesizes an [`Event::ClientReconnected`] when a client enters the server at most
+    /// `window` after a client with the same unique identifier left it. Disabled by default.
+    ///
+    /// Only clients whose `cliententerview` event was received are correlated, clients that
+    /// were already connected when the events were registered are unknown.
+    pub fn reconnect_window(mut self, window: Duration) -> Self {
+        self.reconnect_window = Some(window);
+        self
+    }
+
+    /// Opens a new connection to the same address when the connection is lost, instead of
+    /// closing the client. Disabled by default.
+    ///
+    /// The command waiting for its response when the connection is lost fails, since it is
+    /// unknown whether the server executed it. Queued commands are kept and sent once the
+    /// client is reconnected. Commands sent while reconnecting are queued as well.
+    ///
+    /// Nothing from the previous connection carries over to the new one: the client is not
+    /// logged in, no virtual server is selected and no events are registered. The cached
+    /// [`Client::me`] is cleared. Use the [`setup`] hook to set up the new session. The
+    /// connection is not reopened after [`Client::quit`] or [`Client::disconnect`].
+    ///
+    /// Scheduled actions, see [`Client::schedule`], are paused while reconnecting.
+    ///
+    /// Not to be confused with [`reconnect_window`], which correlates reconnecting
+    /// TeamSpeak clients.
+    ///
+    /// [`setup`]: Self::setup
+    /// [`reconnect_window`]: Self::reconnect_window
+    pub fn reconnect(mut self, reconnect: Reconnect) -> Self {
+        self.reconnect = Some(reconnect);
+        self
+    }
+
+    /// Sets what happens when an event handler panics. Defaults to [`PanicPolicy::Report`].
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    /// Limits the number of event handler invocations running at the same time. Further
+    /// events are queued until a running handler completes. Unlimited by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is `0`.
+    pub fn max_concurrent_handlers(mut self, limit: usize) -> Self {
+        assert!(limit != 0, "limit must not be 0");
+        self.max_concurrent_handlers = Some(limit);
+        self
+    }
+
+    /// Sets a hook called with every raw line sent to or received from the server. Values
+    /// of sensitive keys, like passwords and API keys, are replaced with `***`.
+    ///
+    /// The hook is called from the tasks handling the connection and should return quickly.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # async fn run() -> Result<(), ts3::Error> {
+    /// let client = Client::builder()
+    ///     .on_wire(|direction, line| println!("{:?}: {}", direction, line))
+    ///     .connect("localhost:10011")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_wire<F>(mut self, f: F) -> Self
+    where
+        F: Fn(Direction, &str) + Send + Sync + 'static,
+    {
+        self.on_wire = Some(Arc::new(f));
+        self
+    }
+
+    /// Sets a hook called for every command whose response takes longer than `threshold`,
+    /// measured from queueing the command. The [`SlowCommand`] splits the time into the wait
+    /// in the command queue and the round trip to the server, which helps telling a busy
+    /// client apart from an overloaded or throttling server.
+    ///
+    /// The hook is called from the task sending the commands and should return quickly.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use ts3::Client;
+    /// # async fn run() -> Result<(), ts3::Error> {
+    /// let client = Client::builder()
+    ///     .on_slow_command(Duration::from_secs(1), |cmd| {
+    ///         eprintln!(
+    ///             "{} took {:?} ({:?} queued, {:?} server)",
+    ///             cmd.command,
+    ///             cmd.total(),
+    ///             cmd.queued,
+    ///             cmd.server
+    ///         )
+    ///     })
+    ///     .connect("localhost:10011")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_slow_command<F>(mut self, threshold: Duration, f: F) -> Self
+    where
+        F: Fn(&SlowCommand) + Send + Sync + 'static,
+    {
+        self.on_slow_command = Some((threshold, Arc::new(f)));
+        self
+    }
+
+    /// Sets a hook that runs after every connection is established: before the client is
+    /// returned and again every time the client reconnects, see [`reconnect`]. Use it to log
+    /// in, select a virtual server, register for events or for other setup of the session.
+    ///
+    /// Until the hook completes only the commands sent by the hook are sent, all other
+    /// commands stay queued.
+    ///
+    /// If the hook fails for the first connection the client is disconnected and connecting
+    /// fails with the error of the hook. If it fails after reconnecting, the error is passed
+    /// to the event handler and the client tries to reconnect again.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # use ts3::request::ServerNotifyRegister;
+    /// # async fn run() -> Result<(), ts3::Error> {
+    /// let client = Client::builder()
+    ///     .setup(|client| async move {
+    ///         client.login("serveradmin", "password").await?;
+    ///         client.use_sid(1).await?;
+    ///         client.servernotifyregister(ServerNotifyRegister::Server).await
+    ///     })
+    ///     .connect("localhost:10011")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`reconnect`]: Self::reconnect
+    pub fn setup<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(Client) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.setup = Some(Arc::new(move |client| Box::pin(f(client))));
+        self
+    }
+
+    /// Connects to the server at `addr` using the configuration of this builder.
+    pub async fn connect<A: ServerAddr>(self, addr: A) -> Result<Client> {
+        let transport = self.transport();
+        self.start(transport, addr).await
+    }
+
+    /// Connects to the server at `addr` over TLS using the configuration of this builder.
+    /// The certificate of the server is verified against `server_name`.
+    ///
+    /// This is useful if the query port is exposed behind a TLS terminating proxy, like
+    /// stunnel. The TeamSpeak server itself does not support TLS on the query port.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # async fn run() -> Result<(), ts3::Error> {
+    /// let server_name = "ts.example.com".try_into().unwrap();
+    /// let client = Client::builder()
+    ///     .connect_tls("ts.example.com:10012", server_name)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "rustls")]
+    pub async fn connect_tls<A: ServerAddr>(
+        self,
+        addr: A,
+        server_name: ServerName<'static>,
+    ) -> Result<Client> {
+        let config = match &self.tls_config {
+            Some(config) => config.clone(),
+            None => default_tls_config(),
+        };
+
+        let mut transport = self.transport();
+        transport.tls = Some((config, server_name));
+        self.start(transport, addr).await
+    }
+
+    /// Returns the [`Transport`] opening plain TCP connections using this builder.
+    fn transport(&self) -> Transport {
+        Transport {
+            proxy: self.proxy.clone(),
+            connect_timeout: self.connect_timeout,
+            #[cfg(feature = "rustls")]
+            tls: None,
+        }
+    }
+
+    /// Connects to `addr` using `transport` and starts the tasks handling the connection.
+    async fn start<A: ServerAddr>(self, transport: Transport, addr: A) -> Result<Client> {
+        // The address is only kept if it is needed to reconnect.
+        let reconnect = match self.reconnect.clone() {
+            Some(reconnect) => Some((reconnect, addr.host_port().map_err(|e| Error(e.into()))?)),
+            None => None,
+        };
+
+        let connector = Connector {
+            transport: transport.clone(),
+            reconnect,
+            setup: self.setup.clone(),
+            flavor: self.flavor,
+            max_line_length: self.max_line_length,
+            watchdog: self.watchdog,
+            on_wire: self.on_wire.clone(),
+            on_slow_command: self.on_slow_command.clone(),
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            last_received: Arc::new(Mutex::new(Instant::now())),
+        };
+
+        let (reader, writer) = connector.open(addr).await?;
+
+        let (tx, rx) = mpsc::channel::<Cmd>(self.queue_size);
+        let (priority_tx, priority_rx) = mpsc::channel::<Cmd>(self.queue_size);
+        let (setup_tx, setup_rx) = mpsc::channel::<Cmd>(self.queue_size);
+        let queue = CmdQueue {
+            setup: setup_rx,
+            high: priority_rx,
+            normal: rx,
+        };
+
+        // Create a new inner client
+        let client = Client {
+            tx,
+            priority_tx,
+            setup_tx,
+            priority: Priority::Normal,
+            in_setup: false,
+            lossy: self.lossy,
+            flavor: self.flavor,
+            panic_policy: self.panic_policy,
+            // handler: Arc::new(RwLock::new()),
+            inner: Arc::new(RwLock::new(ClientInner::new(&self, transport))),
+        };
+
+        let conn = connector.start_reader(client.clone(), reader, writer);
+        let last_activity = connector.last_activity.clone();
+        let last_received = connector.last_received.clone();
+        let (ready_tx, ready_rx) = oneshot::channel();
+        spawn(connector.run(client.clone(), conn, queue, ready_tx));
+
+        // Watchdog probe loop. The write task closes the connection if the probe is not
+        // answered within the window.
+        if let Some(window) = self.watchdog {
+            let tx = client.priority_tx.clone();
+            let last_activity = last_activity.clone();
+            let closed = client.inner.read().unwrap().closed.subscribe();
+            let command = match &self.keepalive {
+                Some(keepalive) => keepalive.command,
+                None => "version",
+            };
+            spawn(async move {
+                loop {
+                    // Wait until nothing was sent or received for the window.
+                    loop {
+                        let last =
+                            (*last_activity.lock().unwrap()).max(*last_received.lock().unwrap());
+                        let deadline = last + window;
+                        if deadline <= Instant::now() {
+                            break;
+                        }
+
+                        sleep_until(deadline.into()).await;
+                    }
+
+                    if *closed.borrow() {
+                        break;
+                    }
+
+                    let (resp_tx, resp_rx) = oneshot::channel();
+                    let cmd = Cmd::new(Bytes::from_static(command.as_bytes()), resp_tx);
+
+                    if tx.send(cmd).await.is_err() {
+                        break;
+                    }
+                    let _ = resp_rx.await;
+                }
+            });
+        }
+
+        // Keepalive loop
+        if let Some(keepalive) = self.keepalive {
+            let tx = client.tx.clone();
+            spawn(async move {
+                loop {
+                    let delay = keepalive.delay();
+
+                    // Wait until no command was sent for the delay.
+                    loop {
+                        let deadline = *last_activity.lock().unwrap() + delay;
+                        if deadline <= Instant::now() {
+                            break;
+                        }
+
+                        sleep_until(deadline.into()).await;
+                    }
+
+                    let (resp_tx, _) = oneshot::channel();
+                    let cmd = Cmd::new(Bytes::from_static(keepalive.command.as_bytes()), resp_tx);
+
+                    if tx.send(cmd).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // Wait for the setup hook of the first connection.
+        if let Err(err) = ready_rx.await.unwrap_or_else(|_| Err(connection_closed())) {
+            let _ = client.disconnect(Shutdown::Abort).await;
+            return Err(err);
+        }
+
+        Ok(client)
+    }
+}
+
+/// A connection to the server. Replaced by a new connection when the client reconnects.
+struct Connection {
+    writer: WriteHalf<Box<dyn Stream>>,
+    /// Receives the responses read by the read task. Closed once the read task stops.
+    read_rx: mpsc::Receiver<(Vec<u8>, Error)>,
+    read_handle: JoinHandle<()>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.read_handle.abort();
+    }
+}
+
+/// Why a [`Connection`] was lost.
+#[derive(Copy, Clone, Debug)]
+enum Lost {
+    /// The server closed the connection or reading from it failed.
+    Closed,
+    /// The server did not respond within the watchdog window.
+    TimedOut,
+}
+
+impl Lost {
+    /// Returns the error for commands that cannot be sent because of the lost connection.
+    fn error(self) -> Error {
+        match self {
+            Self::Closed => connection_closed(),
+            Self::TimedOut => Error(ErrorKind::TimedOut),
+        }
+    }
+}
+
+/// Returns why the connection was lost once the read task of `conn` stopped. Responses
+/// without a command are dropped.
+fn poll_lost(conn: &mut Connection, cx: &mut Context<'_>) -> Poll<Lost> {
+    while let Poll::Ready(resp) = conn.read_rx.poll_recv(cx) {
+        if resp.is_none() {
+            return Poll::Ready(Lost::Closed);
+        }
+    }
+
+    Poll::Pending
+}
+
+/// Opens the connections of a [`Client`] and sends the queued commands over them.
+struct Connector {
+    transport: Transport,
+    /// The reconnect behavior and the address of the server, if the client reconnects.
+    reconnect: Option<(Reconnect, (String, u16))>,
+    setup: Option<SetupHook>,
+    flavor: ServerFlavor,
+    max_line_length: usize,
+    watchdog: Option<Duration>,
+    on_wire: Option<WireHook>,
+    on_slow_command: Option<(Duration, SlowCommandHook)>,
+    /// The time the last command was sent. Used to pause the keepalive while commands are
+    /// sent.
+    last_activity: Arc<Mutex<Instant>>,
+    /// The time the last line was received. Used by the watchdog to probe idle connections.
+    last_received: Arc<Mutex<Instant>>,
+}
 
-        let stream = TcpStream::connect(addr)
-            .await
-            .map_err(|e| Error(e.into()))?;
+impl Connector {
+    /// Opens a connection to `addr` and reads the welcome banner.
+    async fn open<A: ServerAddr>(
+        &self,
+        addr: A,
+    ) -> Result<(
+        BufReader<ReadHalf<Box<dyn Stream>>>,
+        WriteHalf<Box<dyn Stream>>,
+    )> {
+        let stream = self.transport.connect(addr).await?;
 
-        let (reader, mut writer) = stream.into_split();
+        let (reader, writer) = tokio::io::split(stream);
         let mut reader = BufReader::new(reader);
+        read_banner(&mut reader, self.flavor, self.max_line_length).await?;
 
-        // Read initial welcome message
-        {
-            let mut buf = Vec::new();
-            reader
-                .read_until(b'\r', &mut buf)
-                .await
-                .map_err(|e| Error(e.into()))?;
-            buf.clear();
-            reader
-                .read_until(b'\r', &mut buf)
-                .await
-                .map_err(|e| Error(e.into()))?;
-        }
+        Ok((reader, writer))
+    }
 
+    /// Starts the task reading from `reader`, which dispatches events and passes responses
+    /// to the returned [`Connection`].
+    fn start_reader(
+        &self,
+        client: Client,
+        mut reader: BufReader<ReadHalf<Box<dyn Stream>>>,
+        writer: WriteHalf<Box<dyn Stream>>,
+    ) -> Connection {
         // read_tx and read_rx are used to communicate between the read and the write
         // thread
-        let (read_tx, mut read_rx) = mpsc::channel(32);
+        let (read_tx, read_rx) = mpsc::channel(32);
 
-        // Create a new inner client
-        let client = Client {
-            tx,
-            // handler: Arc::new(RwLock::new()),
-            inner: Arc::new(RwLock::new(ClientInner::new())),
-        };
+        *self.last_received.lock().unwrap() = Instant::now();
+
+        let last_received = self.last_received.clone();
+        let on_wire = self.on_wire.clone();
+        let max_line_length = self.max_line_length;
+        let read_handle = spawn(async move {
+            // The line buffer is reused for all lines.
+            let mut buf = Vec::new();
 
-        // Read task
-        let client2 = client.clone();
-        spawn(async move {
             loop {
-                let client = client2.clone();
+                let client = client.clone();
 
-                // Read from the buffer until a '\r' indicating the end of a line
-                let mut buf = Vec::new();
-                if let Err(err) = reader.read_until(b'\r', &mut buf).await {
-                    client.handle_error(Error(err.into()));
-                    continue;
+                // Read from the buffer until a '\r' indicating the end of a line. The
+                // connection is unusable after an error or eof.
+                buf.clear();
+                if let Err(err) = read_line(&mut reader, &mut buf, max_line_length).await {
+                    // The server closes the connection after `quit`, which is not an error.
+                    if !client.is_closed() {
+                        client.handle_error(err);
+                    }
+                    return;
                 }
+                *last_received.lock().unwrap() = Instant::now();
 
-                // Remove the last two bytes '\n' and '\r'
-                buf.truncate(buf.len() - 2);
+                if let Some(on_wire) = &on_wire {
+                    wire(on_wire, Direction::Received, &buf);
+                }
 
                 // If the received data is an event dispatch it to the correct handler and wait for
                 // the next line.
@@ -137,14 +1602,19 @@ impl Client {
                         }
                     },
                     false => {
-                        // Clone the current buffer, which contains the response data
-                        let resp = buf.clone();
+                        // Take the current buffer, which contains the response data
+                        let resp = std::mem::take(&mut buf);
 
                         // Read next line for the error
-                        buf.clear();
-                        if let Err(err) = reader.read_until(b'\r', &mut buf).await {
-                            client.handle_error(Error(err.into()));
-                            continue;
+                        if let Err(err) = read_line(&mut reader, &mut buf, max_line_length).await {
+                            if !client.is_closed() {
+                                client.handle_error(err);
+                            }
+                            return;
+                        }
+
+                        if let Some(on_wire) = &on_wire {
+                            wire(on_wire, Direction::Received, &buf);
                         }
 
                         match Error::decode(&buf) {
@@ -160,58 +1630,738 @@ impl Client {
             }
         });
 
-        // Write Task
-        spawn(async move {
-            while let Some(cmd) = rx.recv().await {
-                // Write the command string
-                if let Err(err) = writer.write_all(&cmd.bytes).await {
-                    let _ = cmd.resp.send(Err(Error(err.into())));
-                    continue;
+        Connection {
+            writer,
+            read_rx,
+            read_handle,
+        }
+    }
+
+    /// Sends the queued commands over `conn`, opening a new connection whenever it is lost
+    /// if the client reconnects. This is the write task of the client.
+    ///
+    /// The result of the setup hook for `conn` is sent to `ready`.
+    async fn run(
+        self,
+        client: Client,
+        mut conn: Connection,
+        mut queue: CmdQueue,
+        ready: oneshot::Sender<Result<()>>,
+    ) {
+        // The client is closed once the task stops, including if it is aborted.
+        let _closed = ClosedGuard(client.clone());
+
+        // Commands are still sent if the hook failed, so that the client can be disconnected
+        // cleanly.
+        let res = match self.setup(&client, &mut conn, &mut queue, None).await {
+            Ok(res) => res,
+            Err(lost) => Err(lost.error()),
+        };
+        let _ = ready.send(res);
+
+        loop {
+            let Some(lost) = self.serve(&client, &mut conn, &mut queue).await else {
+                return;
+            };
+            drop(conn);
+
+            // The identity and the state of the session are gone with the connection.
+            {
+                let mut inner = client.inner.write().unwrap();
+                inner.connected.send_replace(false);
+                inner.whoami = None;
+                inner.reset_session();
+            }
+
+            match self.reconnect(&client, &mut queue).await {
+                Some(new) => {
+                    conn = new;
+                    client.inner.read().unwrap().connected.send_replace(true);
                 }
+                None => {
+                    for cmd in queue.close() {
+                        let _ = cmd.resp.send(Err(lost.error()));
+                    }
 
-                // Write a '\n' to send the command
-                if let Err(err) = writer.write_all(&[b'\n']).await {
-                    let _ = cmd.resp.send(Err(Error(err.into())));
-                    continue;
+                    return;
                 }
+            }
+        }
+    }
+
+    /// Sends the queued commands over `conn` until the connection is lost. Returns `None`
+    /// once the queue is closed.
+    async fn serve(
+        &self,
+        client: &Client,
+        conn: &mut Connection,
+        queue: &mut CmdQueue,
+    ) -> Option<Lost> {
+        loop {
+            let next = poll_fn(|cx| match poll_lost(conn, cx) {
+                Poll::Ready(lost) => Poll::Ready(Err(lost)),
+                Poll::Pending => queue.poll_recv(cx).map(Ok),
+            })
+            .await;
 
-                // Wait for the response from the reader task
-                let (resp, err) = read_rx.recv().await.unwrap();
+            let res = match next {
+                Ok(Some(cmd)) => self.send(client, conn, cmd).await,
+                Ok(None) => return None,
+                Err(lost) => Err(lost),
+            };
 
-                // Write the response to the channel sent with the request. resp is None when
-                // an error occured.
-                let _ = cmd.resp.send(match err.ok() {
-                    true => Ok(resp),
-                    false => Err(err),
-                });
+            if let Err(lost) = res {
+                return Some(lost);
+            }
+        }
+    }
+
+    /// Runs the setup hook for the new connection `conn` and selects the server `selected`
+    /// before the connection was lost again. Only the commands of the hook are sent until it
+    /// completes, all other commands stay queued. Returns the result of the hook, or an
+    /// error if the connection was lost first.
+    async fn setup(
+        &self,
+        client: &Client,
+        conn: &mut Connection,
+        queue: &mut CmdQueue,
+        selected: Option<(ServerSelection, Bytes)>,
+    ) -> result::Result<Result<()>, Lost> {
+        if self.setup.is_none() && selected.is_none() {
+            return Ok(Ok(()));
+        }
+
+        let setup = self.setup.clone();
+        let client2 = Client {
+            in_setup: true,
+            ..client.clone()
+        };
+        let mut task = spawn(async move {
+            if let Some(setup) = setup {
+                setup(client2.clone()).await?;
             }
+
+            client2.restore_selection(selected).await;
+            Ok(())
         });
 
-        // Keepalive loop
-        let tx2 = client.tx.clone();
-        spawn(async move {
-            loop {
-                let tx = tx2.clone();
-                sleep(Duration::from_secs(60)).await;
-                {
-                    let (resp_tx, _) = oneshot::channel();
-                    if let Err(_) = tx
-                        .send(Cmd {
-                            bytes: Bytes::from_static("version".as_bytes()),
-                            resp: resp_tx,
-                        })
-                        .await
-                    {}
+        loop {
+            let next = poll_fn(|cx| {
+                if let Poll::Ready(res) = Pin::new(&mut task).poll(cx) {
+                    return Poll::Ready(Ok(Err(res)));
+                }
+
+                match poll_lost(conn, cx) {
+                    Poll::Ready(lost) => Poll::Ready(Err(lost)),
+                    Poll::Pending => queue.setup.poll_recv(cx).map(|cmd| Ok(Ok(cmd))),
+                }
+            })
+            .await;
+
+            match next {
+                Ok(Ok(Some(cmd))) => {
+                    if let Err(lost) = self.send(client, conn, cmd).await {
+                        task.abort();
+                        return Err(lost);
+                    }
+                }
+                Ok(Ok(None)) => return Err(Lost::Closed),
+                Ok(Err(Ok(res))) => return Ok(res),
+                Ok(Err(Err(err))) => {
+                    let msg = match err.try_into_panic() {
+                        Ok(payload) => panic_message(payload),
+                        Err(err) => err.to_string(),
+                    };
+                    return Ok(Err(Error(ErrorKind::HandlerPanic(msg))));
+                }
+                Err(lost) => {
+                    task.abort();
+                    return Err(lost);
+                }
+            }
+        }
+    }
+
+    /// Sends `cmd` over `conn` and passes the response to the sender of the command.
+    /// Returns an error if the connection was lost.
+    async fn send(
+        &self,
+        client: &Client,
+        conn: &mut Connection,
+        cmd: Cmd,
+    ) -> result::Result<(), Lost> {
+        let sent = Instant::now();
+        *self.last_activity.lock().unwrap() = sent;
+
+        if let Some(on_wire) = &self.on_wire {
+            wire(on_wire, Direction::Sent, &cmd.bytes);
+        }
+
+        // Write the command string
+        if let Err(err) = conn.writer.write_all(&cmd.bytes).await {
+            let _ = cmd.resp.send(Err(Error(err.into())));
+            return Ok(());
+        }
+
+        // Write a '\n' to send the command
+        if let Err(err) = conn.writer.write_all(b"\n").await {
+            let _ = cmd.resp.send(Err(Error(err.into())));
+            return Ok(());
+        }
+
+        // Wait for the response from the reader task
+        let resp = match self.watchdog {
+            Some(window) => match timeout(window, conn.read_rx.recv()).await {
+                Ok(resp) => resp,
+                Err(_) => {
+                    // The connection is considered dead. Stop reading from it and fail
+                    // the pending command.
+                    let _ = cmd.resp.send(Err(Lost::TimedOut.error()));
+                    client.handle_error(Lost::TimedOut.error());
+                    return Err(Lost::TimedOut);
                 }
+            },
+            None => conn.read_rx.recv().await,
+        };
+
+        // The reader task stops once the connection is closed.
+        let Some((resp, err)) = resp else {
+            let _ = cmd.resp.send(Err(Lost::Closed.error()));
+            return Err(Lost::Closed);
+        };
+
+        if let Some((threshold, hook)) = &self.on_slow_command {
+            if cmd.queued.elapsed() > *threshold {
+                hook(&SlowCommand {
+                    command: command_name(&cmd.bytes).into_owned(),
+                    queued: sent - cmd.queued,
+                    server: sent.elapsed(),
+                });
             }
+        }
+
+        // Write the response to the channel sent with the request. resp is None when
+        // an error occured.
+        let _ = cmd.resp.send(match err.ok() {
+            true => Ok(resp),
+            false => Err(err),
         });
 
+        Ok(())
+    }
+
+    /// Opens a new connection after the connection was lost and runs the setup hook for it.
+    /// Returns `None` if the client does not reconnect, was disconnected or all attempts
+    /// failed.
+    async fn reconnect(&self, client: &Client, queue: &mut CmdQueue) -> Option<Connection> {
+        let (reconnect, addr) = self.reconnect.as_ref()?;
+
+        // The selection is recorded again once the server is selected on the new connection.
+        let selected = client.inner.write().unwrap().selected.take();
+
+        let mut attempt = 0;
+        while reconnect.max_attempts.is_none_or(|max| attempt < max) {
+            // Stop waiting once the client is disconnected.
+            if timeout(reconnect.backoff(attempt), client.closed())
+                .await
+                .is_ok()
+            {
+                return None;
+            }
+            attempt += 1;
+
+            let (reader, writer) = match self.open(addr).await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    client.handle_error(err);
+                    continue;
+                }
+            };
+
+            let mut conn = self.start_reader(client.clone(), reader, writer);
+            match self.setup(client, &mut conn, queue, selected.clone()).await {
+                Ok(Ok(())) => return Some(conn),
+                Ok(Err(err)) => client.handle_error(err),
+                // The read task reported why the connection was lost.
+                Err(_) => (),
+            }
+        }
+
+        None
+    }
+}
+
+/// Login credentials and session setup used by [`Client::connect_with`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use ts3::request::ServerNotifyRegister;
+/// use ts3::{Client, Credentials};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), ts3::Error> {
+///     let credentials = Credentials::new("serveradmin", "password")
+///         .sid(1)
+///         .nickname("bot")
+///         .notify(ServerNotifyRegister::Server);
+///
+///     let client = Client::connect_with("localhost:10011", credentials).await?;
+///     # Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Credentials {
+    username: String,
+    password: String,
+    server: Option<ServerSelection>,
+    nickname: Option<String>,
+    notify: Vec<ServerNotifyRegister>,
+}
+
+impl Credentials {
+    /// Creates new `Credentials` logging in with `username` and `password`.
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_owned(),
+            password: password.to_owned(),
+            server: None,
+            nickname: None,
+            notify: Vec::new(),
+        }
+    }
+
+    /// Selects the virtual server with the given id after logging in.
+    pub fn sid<T>(mut self, sid: T) -> Self
+    where
+        T: Into<ServerId>,
+    {
+        self.server = Some(ServerSelection::Id(sid.into()));
+        self
+    }
+
+    /// Selects the virtual server running on the given voice port after logging in.
+    pub fn port(mut self, port: u16) -> Self {
+        self.server = Some(ServerSelection::Port(port));
+        self
+    }
+
+    /// Sets the nickname of the query client after selecting the virtual server.
+    pub fn nickname(mut self, nickname: &str) -> Self {
+        self.nickname = Some(nickname.to_owned());
+        self
+    }
+
+    /// Registers for the given events after selecting the virtual server. Can be called
+    /// multiple times to register for multiple events.
+    pub fn notify(mut self, event: ServerNotifyRegister) -> Self {
+        self.notify.push(event);
+        self
+    }
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("server", &self.server)
+            .field("nickname", &self.nickname)
+            .field("notify", &self.notify)
+            .finish()
+    }
+}
+
+impl Client {
+    /// Create a new connection
+    pub async fn connect<A: ServerAddr>(addr: A) -> Result<Client> {
+        ClientBuilder::new().connect(addr).await
+    }
+
+    /// Create a new connection over TLS. See [`ClientBuilder::connect_tls`] for more
+    /// details.
+    #[cfg(feature = "rustls")]
+    pub async fn connect_tls<A: ServerAddr>(
+        addr: A,
+        server_name: ServerName<'static>,
+    ) -> Result<Client> {
+        ClientBuilder::new().connect_tls(addr, server_name).await
+    }
+
+    /// Connects to `addr` and sets up the session using `credentials`: logs in, selects
+    /// the virtual server, sets the nickname and registers for events, in that order.
+    pub async fn connect_with<A: ServerAddr>(addr: A, credentials: Credentials) -> Result<Client> {
+        let client = Self::connect(addr).await?;
+        client
+            .login(&credentials.username, &credentials.password)
+            .await?;
+
+        match credentials.server {
+            Some(ServerSelection::Id(sid)) => client.use_sid(sid).await?,
+            Some(ServerSelection::Port(port)) => client.use_port(port).await?,
+            None => (),
+        }
+
+        if let Some(nickname) = &credentials.nickname {
+            let req = RequestBuilder::new("clientupdate").arg("client_nickname", nickname.as_str());
+            client.send::<(), _>(req).await?;
+            client.refresh_me().await;
+        }
+
+        for event in credentials.notify {
+            client.servernotifyregister(event).await?;
+        }
+
         Ok(client)
     }
 
+    /// Returns a new [`ClientBuilder`] to create a `Client` with custom configuration.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Returns a handle to this `Client` sending all commands with the given [`Priority`].
+    /// The returned `Client` shares the connection with this `Client`.
+    ///
+    /// ```no_run
+    /// # use ts3::{Client, Priority};
+    /// # use ts3::shared::{ChannelId, ClientId};
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// client
+    ///     .with_priority(Priority::High)
+    ///     .clientmove(&[ClientId(5)], ChannelId(1), None)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_priority(&self, priority: Priority) -> Client {
+        Client {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the commands operating on the server instance.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// for server in client.instance().serverlist().await?.iter() {
+    ///     println!("{}: {}", server.virtualserver_id, server.virtualserver_name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn instance(&self) -> Instance<'_> {
+        Instance::new(self)
+    }
+
+    /// Selects the virtual server with the given id and returns the commands operating on
+    /// it.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// let server = client.server(1).await?;
+    /// println!("{}", server.serverinfo().await?.virtualserver_name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn server<T>(&self, sid: T) -> Result<VirtualServer<'_>>
+    where
+        T: Into<ServerId>,
+    {
+        let sid = sid.into();
+        self.use_sid(sid).await?;
+        Ok(VirtualServer::new(self, sid))
+    }
+
+    /// Selects the virtual server running on the given voice port and returns the commands
+    /// operating on it.
+    pub async fn server_by_port(&self, port: u16) -> Result<VirtualServer<'_>> {
+        self.use_port(port).await?;
+
+        let sid = match self.me() {
+            Some(me) => me.virtualserver_id,
+            None => self.whoami().await?.virtualserver_id,
+        };
+        Ok(VirtualServer::new(self, sid))
+    }
+
+    /// Returns the cached result of the last [`whoami`] command. The cache is refreshed
+    /// automatically after every successful `login`, `logout` and `use` command. This allows
+    /// cheap access to the own client id, e.g. to ignore events caused by this client.
+    ///
+    /// Returns `None` if no `whoami` command completed yet, or if refreshing the cache after
+    /// the last `login`, `logout` or `use` command failed.
+    ///
+    /// [`whoami`]: Self::whoami
+    pub fn me(&self) -> Option<Whoami> {
+        self.inner.read().unwrap().whoami.clone()
+    }
+
+    /// Returns the virtual server selected by the last successful [`use_sid`] or
+    /// [`use_port`] command. Returns `None` if no server was selected or after [`logout`].
+    ///
+    /// If a command fails because the server is no longer selected, e.g. after the virtual
+    /// server was restarted, the server is selected again and the command is retried once.
+    /// After the client reconnected, see [`ClientBuilder::reconnect`], the server is selected
+    /// again before any queued command is sent, unless the setup hook selected a server.
+    ///
+    /// [`use_sid`]: Self::use_sid
+    /// [`use_port`]: Self::use_port
+    /// [`logout`]: Self::logout
+    pub fn selected_server(&self) -> Option<ServerSelection> {
+        self.inner
+            .read()
+            .unwrap()
+            .selected
+            .as_ref()
+            .map(|(server, _)| *server)
+    }
+
+    /// Refreshes the cached [`Whoami`]. The previous identity is no longer valid, so the
+    /// cache is cleared first and stays empty if the `whoami` command fails. Errors are
+    /// ignored as the command this follows already succeeded.
+    async fn refresh_me(&self) {
+        self.inner.write().unwrap().whoami = None;
+        let _ = self.whoami().await;
+    }
+
     pub fn set_event_handler<H: EventHandler + 'static>(&self, handler: H) {
         let mut data = self.inner.write().unwrap();
         data.handler = Arc::new(handler);
+        data.in_flight = Arc::default();
+        data.handler_set = true;
+    }
+
+    /// Sets the event handler like [`set_event_handler`], but waits until all events passed
+    /// to the previous handler are handled before it is dropped. Events arriving after this
+    /// call are passed to `handler` right away.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # use ts3::event::EventHandler;
+    /// # async fn run(client: Client) {
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    ///
+    /// client.replace_event_handler(Handler).await;
+    /// # }
+    /// ```
+    ///
+    /// [`set_event_handler`]: Self::set_event_handler
+    pub async fn replace_event_handler<H: EventHandler + 'static>(&self, handler: H) {
+        let (old, in_flight) = {
+            let mut data = self.inner.write().unwrap();
+            data.handler_set = true;
+            (
+                std::mem::replace(&mut data.handler, Arc::new(handler)),
+                std::mem::take(&mut data.in_flight),
+            )
+        };
+
+        in_flight.wait().await;
+        drop(old);
+    }
+
+    /// Sets a function as event handler. Unlike [`set_event_handler`], the returned future
+    /// is spawned directly without being boxed, avoiding an allocation for every event
+    /// unless it has to wait for [`ClientBuilder::max_concurrent_handlers`]. Errors are
+    /// handled like the default [`EventHandler::error`], panics of the function according
+    /// to the [`PanicPolicy`].
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # use ts3::event::Event;
+    /// # fn run(client: Client) {
+    /// client.set_event_handler_fn(|_client, event| async move {
+    ///     if let Event::ClientEnterView(event) = event {
+    ///         println!("Client {} joined!", event.client_nickname);
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    ///
+    /// [`set_event_handler`]: Self::set_event_handler
+    pub fn set_event_handler_fn<F, Fut>(&self, f: F)
+    where
+        F: Fn(Client, Event) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut data = self.inner.write().unwrap();
+        data.handler = Arc::new(FnHandler(f));
+        data.in_flight = Arc::default();
+        data.handler_set = true;
+    }
+
+    /// Creates a queue receiving all events that arrive while no event handler is set,
+    /// instead of discarding them. Once [`set_event_handler`] or [`set_event_handler_fn`] was
+    /// called, events are no longer sent to the queue.
+    ///
+    /// At most `capacity` events are kept until they are received, further events are
+    /// dropped. Calling this again replaces the previous queue.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # async fn run(client: Client) {
+    /// let mut rx = client.dead_letter_queue(64);
+    ///
+    /// while let Ok(event) = rx.try_recv() {
+    ///     println!("missed event: {:?}", event);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is `0`.
+    ///
+    /// [`set_event_handler`]: Self::set_event_handler
+    /// [`set_event_handler_fn`]: Self::set_event_handler_fn
+    pub fn dead_letter_queue(&self, capacity: usize) -> mpsc::Receiver<Event> {
+        let (tx, rx) = mpsc::channel(capacity);
+
+        let mut data = self.inner.write().unwrap();
+        data.dead_letter = Some(tx);
+        rx
+    }
+
+    /// Sets whether `textmessage` events sent by this client should be suppressed instead
+    /// of being dispatched to the event handler. This prevents a bot from reacting to its own
+    /// messages. Defaults to `false`.
+    ///
+    /// Messages are matched against the `clid` cached by [`me`]. No messages are suppressed
+    /// while the cache is empty.
+    ///
+    /// [`me`]: Self::me
+    pub fn set_ignore_own_messages(&self, ignore: bool) {
+        let mut data = self.inner.write().unwrap();
+        data.ignore_own_messages = ignore;
+    }
+
+    /// Sets the [`EventFilter`] applied to events before they are dispatched to the event
+    /// handler. Events dropped by the filter are never seen by the handler. Decoding errors
+    /// are always passed to [`EventHandler::error`].
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// use ts3::event::EventFilter;
+    /// use ts3::shared::ChannelId;
+    ///
+    /// # async fn run(client: Client) {
+    /// client.set_event_filter(EventFilter::new().channel(ChannelId(1)));
+    /// # }
+    /// ```
+    pub fn set_event_filter(&self, filter: EventFilter) {
+        let mut data = self.inner.write().unwrap();
+        data.filter = Arc::new(filter);
+    }
+
+    /// Starts tracking which channels of the selected virtual server are occupied by regular
+    /// clients. Once tracked, the client synthesizes [`Event::ChannelBecameEmpty`] and
+    /// [`Event::ChannelBecameOccupied`] events from the `cliententerview`, `clientleftview`
+    /// and `clientmoved` events and dispatches them like events sent by the server.
+    ///
+    /// The current channel of every client is requested using [`clientlist`]. The server must
+    /// send events of all channels, see [`ServerNotifyRegister::Channel`]. Tracking stops when
+    /// another virtual server is selected or the client logs out, call this again to resume.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// use ts3::request::ServerNotifyRegister;
+    /// use ts3::shared::ChannelId;
+    ///
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// client
+    ///     .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+    ///     .await?;
+    /// client.track_occupancy().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`clientlist`]: Self::clientlist
+    /// [`ServerNotifyRegister::Channel`]: crate::request::ServerNotifyRegister::Channel
+    pub async fn track_occupancy(&self) -> Result<()> {
+        let clients = self.clientlist(ClientListOptions::default()).await?;
+
+        let occupancy = Occupancy::new(
+            clients
+                .iter()
+                .filter(|client| !client.is_query())
+                .map(|client| (client.clid, client.cid)),
+        );
+        self.inner.write().unwrap().occupancy = Some(occupancy);
+        Ok(())
+    }
+
+    /// Starts tracking the nicknames of all clients on the selected virtual server. The
+    /// server sends no event when a client changes its nickname, the client list is requested
+    /// every `interval` instead and an [`Event::ClientNicknameChanged`] is dispatched for every
+    /// changed nickname. Nickname changes are only noticed on the next refresh.
+    ///
+    /// Tracking stops when the returned handle is cancelled. When another virtual server is
+    /// selected, the nicknames of its clients are tracked from the next refresh on.
+    pub async fn track_nicknames(&self, interval: Duration) -> Result<ScheduleHandle> {
+        self.refresh_nicknames().await?;
+
+        Ok(
+            self.schedule(Schedule::every(interval), |client| async move {
+                client.refresh_nicknames().await
+            }),
+        )
+    }
+
+    /// Requests the client list and dispatches an event for every changed nickname.
+    async fn refresh_nicknames(&self) -> Result<()> {
+        let clients = self.clientlist(ClientListOptions::default()).await?;
+        let clients = clients
+            .iter()
+            .map(|client| (client.clid, client.client_nickname.clone()));
+
+        // Nicknames are not compared with the clients of another server.
+        let events = self
+            .inner
+            .write()
+            .unwrap()
+            .nicknames
+            .get_or_insert_with(Nicknames::default)
+            .refresh(clients);
+        for event in events {
+            self.dispatch_decoded(event);
+        }
+
+        Ok(())
+    }
+
+    /// Passes all events kept in the replay buffer to `handler`, oldest first, and clears
+    /// the buffer. The events were already dispatched to the event handler set at the time
+    /// they arrived. Every event is handled before the next one is passed to `handler`.
+    ///
+    /// The buffer is only enabled if configured using [`ClientBuilder::replay_buffer`].
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # use ts3::event::EventHandler;
+    /// # async fn run(client: Client) {
+    /// struct Handler;
+    ///
+    /// impl EventHandler for Handler {}
+    ///
+    /// client.replay_into(&Handler).await;
+    /// client.set_event_handler(Handler);
+    /// # }
+    /// ```
+    pub async fn replay_into<H>(&self, handler: &H)
+    where
+        H: EventHandler,
+    {
+        let events = self.inner.write().unwrap().replay.take();
+        for event in events {
+            handler.handle(self.clone(), event).await;
+        }
     }
 
     /// Sends a [`Request`] to the server.
@@ -224,30 +2374,189 @@ impl Client {
         self.send_inner(request.into()).await
     }
 
+    /// Executes the [`TypedCommand`] `cmd` and returns its decoded response.
+    pub async fn execute<C>(&self, cmd: C) -> Result<C::Response>
+    where
+        C: TypedCommand,
+        <C::Response as Decode>::Error: Into<Error>,
+    {
+        self.send(cmd.apply(RequestBuilder::new(C::NAME))).await
+    }
+
+    /// Like [`send`], but fails with an error for which [`Error::is_queue_full`] returns
+    /// `true` instead of waiting if the command queue is full.
+    ///
+    /// [`send`]: Self::send
+    pub async fn try_send<T, R>(&self, request: R) -> Result<T>
+    where
+        T: Decode,
+        T::Error: Into<Error>,
+        R: Into<Request>,
+    {
+        let bytes = Bytes::from(request.into().buf.into_bytes());
+        self.check_supported(&bytes)?;
+
+        let (resp_tx, resp_rx) = oneshot::channel();
+        match self.queue().try_send(Cmd::new(bytes, resp_tx)) {
+            Ok(()) => self.recv_response(resp_rx).await,
+            Err(TrySendError::Full(_)) => Err(Error(ErrorKind::QueueFull)),
+            Err(TrySendError::Closed(_)) => Err(Error(ErrorKind::SendError)),
+        }
+    }
+
     async fn send_inner<T>(&self, request: Request) -> Result<T>
     where
         T: Decode,
         T::Error: Into<Error>,
     {
-        let tx = self.tx.clone();
+        let bytes = Bytes::from(request.buf.into_bytes());
+
+        let resp_rx = self.enqueue(bytes.clone()).await?;
+        match self.recv_response(resp_rx).await {
+            // The server was deselected, e.g. because it was restarted. Select it again and
+            // retry the command once.
+            Err(err) if err.is_invalid_server() && self.reselect(&bytes).await => {
+                let resp_rx = self.enqueue(bytes).await?;
+                self.recv_response(resp_rx).await
+            }
+            res => res,
+        }
+    }
+
+    /// Selects the last selected server again after a command `bytes` failed because no
+    /// server is selected. Returns `true` if the server was selected.
+    async fn reselect(&self, bytes: &[u8]) -> bool {
+        // Never retry the selection itself.
+        if bytes.split(|b| *b == b' ').next() == Some(b"use") {
+            return false;
+        }
+
+        // Send the same `use` command including all options.
+        let req = match &self.inner.read().unwrap().selected {
+            Some((_, req)) => req.clone(),
+            None => return false,
+        };
+
+        match self.enqueue(req).await {
+            Ok(resp_rx) => self.recv_response::<()>(resp_rx).await.is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Selects the server that was selected before the client reconnected again, unless the
+    /// setup hook selected a server. Errors are passed to the event handler, the new
+    /// connection is still used without a selected server.
+    async fn restore_selection(&self, selected: Option<(ServerSelection, Bytes)>) {
+        let Some((server, bytes)) = selected else {
+            return;
+        };
+
+        if self.selected_server().is_some() {
+            return;
+        }
+
+        if let Err(err) = self.select(server, bytes).await {
+            self.handle_error(err);
+        }
+    }
+
+    /// Sends a messaging command, reporting flood protection as [`MessageOutcome`] instead
+    /// of an error.
+    async fn send_message(&self, request: RequestBuilder) -> Result<MessageOutcome> {
+        match self.send::<(), _>(request).await {
+            Ok(()) => Ok(MessageOutcome::Sent),
+            Err(err) if err.is_flooding() => Ok(MessageOutcome::RateLimited {
+                retry_after: err.retry_after(),
+            }),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Queues the command `bytes` without waiting for the response. Returns a receiver for
+    /// the response.
+    async fn enqueue(&self, bytes: Bytes) -> Result<oneshot::Receiver<Result<Vec<u8>>>> {
+        self.check_supported(&bytes)?;
 
         // Create a new channel for receiving the response
         let (resp_tx, resp_rx) = oneshot::channel();
 
-        match tx
-            .send(Cmd {
-                bytes: Bytes::from(request.buf.into_bytes()),
-                resp: resp_tx,
-            })
-            .await
-        {
-            Ok(_) => {
-                let resp = resp_rx.await.unwrap()?;
-                let val = T::decode(&resp).map_err(|e| e.into())?;
-                Ok(val)
-            }
-            Err(_) => Err(Error(ErrorKind::SendError)),
-        }
+        match self.queue().send(Cmd::new(bytes, resp_tx)).await {
+            Ok(_) => Ok(resp_rx),
+            Err(_) => Err(Error(ErrorKind::SendError)),
+        }
+    }
+
+    /// Returns a new [`Batch`] to send multiple commands back-to-back.
+    pub fn batch(&self) -> Batch {
+        Batch {
+            client: self.clone(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Returns the [`ServerFlavor`] the client was created with.
+    pub fn flavor(&self) -> ServerFlavor {
+        self.flavor
+    }
+
+    /// Returns an error if the command `bytes` is not implemented by the server flavor.
+    fn check_supported(&self, bytes: &[u8]) -> Result<()> {
+        let command = command_name(bytes);
+
+        match self.flavor.supports(&command) {
+            true => Ok(()),
+            false => Err(Error(ErrorKind::Unsupported {
+                command: command.into_owned(),
+                flavor: self.flavor,
+            })),
+        }
+    }
+
+    /// Returns the queue for commands with the priority of this client.
+    fn queue(&self) -> &mpsc::Sender<Cmd> {
+        if self.in_setup {
+            return &self.setup_tx;
+        }
+
+        match self.priority {
+            Priority::Normal => &self.tx,
+            Priority::High => &self.priority_tx,
+        }
+    }
+
+    async fn recv_response<T>(&self, resp_rx: oneshot::Receiver<Result<Vec<u8>>>) -> Result<T>
+    where
+        T: Decode,
+        T::Error: Into<Error>,
+    {
+        let resp = resp_rx.await.unwrap()?;
+
+        let _guard = LossyGuard::set(self.lossy);
+        let _tolerant = TolerantGuard::set(self.flavor.is_tolerant());
+        let val = T::decode(&resp).map_err(|e| e.into())?;
+        Ok(val)
+    }
+
+    /// Waits until the connection is closed or the client is disconnected.
+    pub(crate) async fn closed(&self) {
+        let mut closed = self.inner.read().unwrap().closed.subscribe();
+        let _ = closed.wait_for(|closed| *closed).await;
+    }
+
+    /// Waits until the client is connected, i.e. not reconnecting. Never completes if the
+    /// client gave up reconnecting.
+    pub(crate) async fn connected(&self) {
+        let mut connected = self.inner.read().unwrap().connected.subscribe();
+        let _ = connected.wait_for(|connected| *connected).await;
+    }
+
+    /// Returns `true` if the connection is closed or the client is disconnected.
+    pub(crate) fn is_closed(&self) -> bool {
+        *self.inner.read().unwrap().closed.borrow()
+    }
+
+    fn set_closed(&self) {
+        self.inner.read().unwrap().closed.send_replace(true);
     }
 
     pub(crate) fn handle_error<E>(&self, error: E)
@@ -259,6 +2568,84 @@ impl Client {
     }
 }
 
+/// A sequence of commands sent back-to-back. Created using [`Client::batch`].
+///
+/// All commands are queued before waiting for the first response, avoiding a round trip
+/// between commands. Every command is executed, even if a previous command failed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ts3::request::RequestBuilder;
+/// use ts3::Client;
+///
+/// # async fn run(client: Client) -> Result<(), ts3::Error> {
+/// let results = client
+///     .batch()
+///     .push(RequestBuilder::new("channelcreate").arg("channel_name", "Lobby"))
+///     .push(RequestBuilder::new("whoami"))
+///     .execute()
+///     .await;
+///
+/// for result in results {
+///     println!("{:?}", result?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Batch {
+    client: Client,
+    requests: Vec<Request>,
+}
+
+impl Batch {
+    /// Appends `request` to the batch.
+    pub fn push<R>(mut self, request: R) -> Self
+    where
+        R: Into<Request>,
+    {
+        self.requests.push(request.into());
+        self
+    }
+
+    /// Returns the number of commands in the batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if the batch contains no commands.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Sends all commands and returns the result of each command in the order they were
+    /// added. Other commands sent using the same [`Client`] in the meantime may be executed
+    /// between the commands of the batch.
+    pub async fn execute(self) -> Vec<Result<Response>> {
+        let mut pending = Vec::with_capacity(self.requests.len());
+        for request in self.requests {
+            pending.push(
+                self.client
+                    .enqueue(Bytes::from(request.buf.into_bytes()))
+                    .await,
+            );
+        }
+
+        let mut results = Vec::with_capacity(pending.len());
+        for resp_rx in pending {
+            results.push(match resp_rx {
+                Ok(resp_rx) => self.client.recv_response(resp_rx).await,
+                Err(err) => Err(err),
+            });
+        }
+
+        results
+    }
+}
+
 // TS3 Commands go here
 impl Client {
     /// Creates a new apikey using the specified scope, for the invoking user. The default
@@ -319,8 +2706,47 @@ impl Client {
         self.send(req).await
     }
 
+    /// Lists query logins matching `options`. Returns an empty list if no logins match.
+    ///
+    /// If a virtual server is selected only the logins of that server are listed, otherwise
+    /// the logins of all virtual servers and the server instance.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// use ts3::request::QueryLoginListOptions;
+    ///
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// let options = QueryLoginListOptions {
+    ///     pattern: Some("bot%"),
+    ///     start: Some(0),
+    ///     duration: Some(100),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// for login in client.queryloginlist(options).await?.iter() {
+    ///     println!("{}", login.client_login_name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn queryloginlist(
+        &self,
+        options: QueryLoginListOptions<'_>,
+    ) -> Result<List<QueryLogin, Pipe>> {
+        let req = options.apply(RequestBuilder::new("queryloginlist"));
+
+        match self.send(req).await {
+            Ok(list) => Ok(list),
+            Err(err) if err.is_empty_result() => Ok(List::default()),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Add a new ban rule on the selected virtual server. One of `ip`, `name`, `uid`
     /// and `mytsid` must not be `None`.
+    #[allow(clippy::too_many_arguments)]
     pub async fn banadd(
         &self,
         ip: Option<&str>,
@@ -358,38 +2784,793 @@ impl Client {
         self.send(req).await
     }
 
+    /// Bans the client specified with `clid` from the selected virtual server. `time` is the
+    /// duration of the ban in seconds, a `None` value creates a permanent ban.
+    pub async fn banclient(
+        &self,
+        clid: ClientId,
+        time: Option<u64>,
+        banreason: Option<&str>,
+    ) -> Result<()> {
+        let mut req = RequestBuilder::new("banclient").arg("clid", clid);
+
+        if let Some(time) = time {
+            req = req.arg("time", time);
+        }
+        if let Some(banreason) = banreason {
+            req = req.arg("banreason", banreason);
+        }
+
+        self.send(req).await
+    }
+
+    /// Displays a list of active bans on the selected virtual server.
+    pub async fn banlist(&self) -> Result<List<BanEntry, Pipe>> {
+        let req = RequestBuilder::new("banlist");
+        self.send(req).await
+    }
+
+    /// Changes the database properties of the client specified with `cldbid`. Only the
+    /// properties set in `properties` are changed.
+    pub async fn clientdbedit(
+        &self,
+        cldbid: ClientDatabaseId,
+        properties: ClientProperties<'_>,
+    ) -> Result<()> {
+        let req = properties.apply(RequestBuilder::new("clientdbedit").arg("cldbid", cldbid));
+        self.send(req).await
+    }
+
+    /// Displays information about the online client specified with `clid`.
+    pub async fn clientinfo(&self, clid: ClientId) -> Result<ClientInfo> {
+        let req = RequestBuilder::new("clientinfo").arg("clid", clid);
+        self.send(req).await
+    }
+
+    /// Displays database information about the client specified with `cldbid`.
+    pub async fn clientdbinfo(&self, cldbid: ClientDatabaseId) -> Result<ClientDbInfo> {
+        let req = RequestBuilder::new("clientdbinfo").arg("cldbid", cldbid);
+        self.send(req).await
+    }
+
+    /// Displays a list of clients online on the selected virtual server.
+    pub async fn clientlist(
+        &self,
+        options: ClientListOptions,
+    ) -> Result<List<ClientListEntry, Pipe>> {
+        let req = options.apply(RequestBuilder::new("clientlist"));
+        self.send(req).await
+    }
+
+    /// Returns the clients online on the selected virtual server whose nickname contains
+    /// `nick_fragment`, ignoring case, best matches first. ServerQuery clients are never
+    /// returned. See [`ClientListEntry::find`] for the order and to search an existing client
+    /// list instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// use ts3::event::ReasonId;
+    ///
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// match client.find_client("bob").await?.as_slice() {
+    ///     [target] => {
+    ///         client
+    ///             .clientkick(&[target.clid], ReasonId::ServerKick, None)
+    ///             .await?
+    ///     }
+    ///     [] => println!("no client found"),
+    ///     _ => println!("nickname is ambiguous"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn find_client(&self, nick_fragment: &str) -> Result<Vec<ClientListEntry>> {
+        let clients = self.clientlist(ClientListOptions::default()).await?;
+
+        Ok(ClientListEntry::find(&clients, nick_fragment)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the clients in the channel `cid` of the selected virtual server. ServerQuery
+    /// clients are never returned. See [`ClientListEntry::in_channel`] to filter an existing
+    /// client list instead.
+    ///
+    /// The server has no command listing the clients of a single channel, this filters the
+    /// list of all clients returned by [`clientlist`].
+    ///
+    /// [`clientlist`]: Self::clientlist
+    pub async fn clients_in_channel(&self, cid: ChannelId) -> Result<Vec<ClientListEntry>> {
+        let clients = self.clientlist(ClientListOptions::default()).await?;
+
+        Ok(ClientListEntry::in_channel(&clients, cid)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the voice states of all clients on the selected virtual server. ServerQuery
+    /// clients are never returned. See [`VoiceMonitor`] to get notified when the states
+    /// change.
+    ///
+    /// [`VoiceMonitor`]: crate::voice::VoiceMonitor
+    pub async fn voice_states(&self) -> Result<Vec<VoiceState>> {
+        let clients = self
+            .clientlist(ClientListOptions {
+                voice: true,
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(clients
+            .iter()
+            .filter(|client| !client.is_query())
+            .map(VoiceState::from)
+            .collect())
+    }
+
+    /// Returns the time since the selected virtual server was started. The uptime is taken
+    /// from [`serverinfo`] and cached, it is requested again after one minute or when
+    /// another virtual server is selected.
+    ///
+    /// [`serverinfo`]: Self::serverinfo
+    pub async fn server_uptime(&self) -> Result<Duration> {
+        if let Some(uptime) = UptimeCache::get(self.inner.read().unwrap().uptime.server) {
+            return Ok(uptime);
+        }
+
+        let uptime = self.serverinfo().await?.uptime();
+        self.inner.write().unwrap().uptime.server = Some((uptime, Instant::now()));
+        Ok(uptime)
+    }
+
+    /// Returns the time since the server instance was started. The uptime is taken from
+    /// [`hostinfo`] and cached, it is requested again after one minute.
+    ///
+    /// [`hostinfo`]: Self::hostinfo
+    pub async fn instance_uptime(&self) -> Result<Duration> {
+        if let Some(uptime) = UptimeCache::get(self.inner.read().unwrap().uptime.instance) {
+            return Ok(uptime);
+        }
+
+        let uptime = self.hostinfo().await?.uptime();
+        self.inner.write().unwrap().uptime.instance = Some((uptime, Instant::now()));
+        Ok(uptime)
+    }
+
+    /// Changes the properties of the online client specified with `clid`. Only the
+    /// properties set in `properties` are changed.
+    pub async fn clientedit(&self, clid: ClientId, properties: ClientProperties<'_>) -> Result<()> {
+        let req = properties.apply(RequestBuilder::new("clientedit").arg("clid", clid));
+        self.send(req).await
+    }
+
+    /// Kicks all clients specified with `clids` from their channel or from the server.
+    /// `reasonid` must be either [`ReasonId::ChannelKick`] or [`ReasonId::ServerKick`].
+    pub async fn clientkick(
+        &self,
+        clids: &[ClientId],
+        reasonid: ReasonId,
+        reasonmsg: Option<&str>,
+    ) -> Result<()> {
+        let mut req = RequestBuilder::new("clientkick");
+        for (index, clid) in clids.iter().enumerate() {
+            if index != 0 {
+                req = req.pipe();
+            }
+
+            req = req.arg("clid", *clid);
+        }
+
+        req = req.arg("reasonid", reasonid);
+        if let Some(reasonmsg) = reasonmsg {
+            req = req.arg("reasonmsg", reasonmsg);
+        }
+
+        self.send(req).await
+    }
+
+    /// Moves all clients specified with `clids` to the channel `cid`. `cpw` is the password
+    /// of the channel, if it has one.
+    pub async fn clientmove(
+        &self,
+        clids: &[ClientId],
+        cid: ChannelId,
+        cpw: Option<&str>,
+    ) -> Result<()> {
+        let mut req = RequestBuilder::new("clientmove");
+        for (index, clid) in clids.iter().enumerate() {
+            if index != 0 {
+                req = req.pipe();
+            }
+
+            req = req.arg("clid", *clid);
+        }
+
+        req = req.arg("cid", cid);
+        if let Some(cpw) = cpw {
+            req = req.arg("cpw", cpw);
+        }
+
+        self.send(req).await
+    }
+
+    /// Sends a poke message to the client specified with `clid`.
+    ///
+    /// See [`clientpoke_outcome`] to handle the flood protection of the server.
+    ///
+    /// [`clientpoke_outcome`]: Self::clientpoke_outcome
+    pub async fn clientpoke(&self, clid: ClientId, msg: &str) -> Result<()> {
+        let req = RequestBuilder::new("clientpoke")
+            .arg("clid", clid)
+            .arg("msg", msg);
+        self.send(req).await
+    }
+
+    /// Like [`clientpoke`], but reports a poke rejected by the flood protection as
+    /// [`MessageOutcome::RateLimited`] instead of an error.
+    ///
+    /// [`clientpoke`]: Self::clientpoke
+    pub async fn clientpoke_outcome(&self, clid: ClientId, msg: &str) -> Result<MessageOutcome> {
+        let req = RequestBuilder::new("clientpoke")
+            .arg("clid", clid)
+            .arg("msg", msg);
+        self.send_message(req).await
+    }
+
+    /// Displays the properties of the channel `cid`.
+    pub async fn channelinfo(&self, cid: ChannelId) -> Result<ChannelInfo> {
+        let req = RequestBuilder::new("channelinfo").arg("cid", cid);
+        self.send(req).await
+    }
+
+    /// Displays a list of channel groups available on the selected virtual server.
+    pub async fn channelgrouplist(&self) -> Result<List<ChannelGroup, Pipe>> {
+        let req = RequestBuilder::new("channelgrouplist");
+        self.send(req).await
+    }
+
+    /// Like [`channelgrouplist`] but only returns channel groups of the given [`GroupType`].
+    ///
+    /// [`channelgrouplist`]: Self::channelgrouplist
+    pub async fn channelgrouplist_by_type(
+        &self,
+        group_type: GroupType,
+    ) -> Result<List<ChannelGroup, Pipe>> {
+        let mut groups = self.channelgrouplist().await?;
+        groups.retain(|group| group.r#type == group_type);
+        Ok(groups)
+    }
+
+    /// Deletes all complaints about the client with the database id `tcldbid`.
+    pub async fn complaindelall(&self, tcldbid: ClientDatabaseId) -> Result<()> {
+        let req = RequestBuilder::new("complaindelall").arg("tcldbid", tcldbid);
+        self.send(req).await
+    }
+
+    /// Displays a list of complaints on the selected virtual server. If `tcldbid` is given,
+    /// only complaints about the client with that database id are returned.
+    ///
+    /// Returns an empty list if there are no complaints.
+    pub async fn complainlist(
+        &self,
+        tcldbid: Option<ClientDatabaseId>,
+    ) -> Result<List<Complaint, Pipe>> {
+        let mut req = RequestBuilder::new("complainlist");
+        if let Some(tcldbid) = tcldbid {
+            req = req.arg("tcldbid", tcldbid);
+        }
+
+        match self.send(req).await {
+            Ok(list) => Ok(list),
+            Err(err) if err.is_empty_result() => Ok(List::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Removes the custom property `ident` from the client with the database id `cldbid`.
+    pub async fn customdelete(&self, cldbid: ClientDatabaseId, ident: &str) -> Result<()> {
+        let req = RequestBuilder::new("customdelete")
+            .arg("cldbid", cldbid)
+            .arg("ident", ident);
+        self.send(req).await
+    }
+
+    /// Displays all custom properties of the client with the database id `cldbid`.
+    ///
+    /// Returns an empty list if the client has no custom properties.
+    pub async fn custominfo(&self, cldbid: ClientDatabaseId) -> Result<List<CustomProperty, Pipe>> {
+        let req = RequestBuilder::new("custominfo").arg("cldbid", cldbid);
+
+        match self.send(req).await {
+            Ok(list) => Ok(list),
+            Err(err) if err.is_empty_result() => Ok(List::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Searches for custom properties named `ident` with a value matching `pattern`. The
+    /// `pattern` supports the SQL wildcards `%` and `_`.
+    ///
+    /// Returns an empty list if no properties match.
+    pub async fn customsearch(
+        &self,
+        ident: &str,
+        pattern: &str,
+    ) -> Result<List<CustomSearchEntry, Pipe>> {
+        let req = RequestBuilder::new("customsearch")
+            .arg("ident", ident)
+            .arg("pattern", pattern);
+
+        match self.send(req).await {
+            Ok(list) => Ok(list),
+            Err(err) if err.is_empty_result() => Ok(List::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Creates or updates the custom property `ident` of the client with the database id
+    /// `cldbid`.
+    pub async fn customset(
+        &self,
+        cldbid: ClientDatabaseId,
+        ident: &str,
+        value: &str,
+    ) -> Result<()> {
+        let req = RequestBuilder::new("customset")
+            .arg("cldbid", cldbid)
+            .arg("ident", ident)
+            .arg("value", value);
+        self.send(req).await
+    }
+
     /// Sends a text message to all clients on all virtual servers in the TeamSpeak 3
     /// Server instance.
+    ///
+    /// See [`gm_with`] for messages containing per-server placeholders and [`gm_outcome`] to
+    /// handle the flood protection of the server.
+    ///
+    /// [`gm_with`]: Self::gm_with
+    /// [`gm_outcome`]: Self::gm_outcome
     pub async fn gm(&self, msg: &str) -> Result<()> {
         let req = RequestBuilder::new("gm").arg("msg", msg);
         self.send(req).await
     }
 
-    /// Authenticate with the given data.
+    /// Like [`gm`], but reports a message rejected by the flood protection as
+    /// [`MessageOutcome::RateLimited`] instead of an error.
+    ///
+    /// [`gm`]: Self::gm
+    pub async fn gm_outcome(&self, msg: &str) -> Result<MessageOutcome> {
+        let req = RequestBuilder::new("gm").arg("msg", msg);
+        self.send_message(req).await
+    }
+
+    /// Sends `template` to all clients on all online virtual servers, rendered for every
+    /// server using `broadcast`. Returns the outcome for every server.
+    ///
+    /// Unlike [`gm`] the message is sent to every server using `sendtextmessage`, since
+    /// `gm` cannot send a different message to every server. See [`Broadcast::send`] for
+    /// details.
+    ///
+    /// [`gm`]: Self::gm
+    /// [`Broadcast::send`]: crate::broadcast::Broadcast::send
+    pub async fn gm_with(
+        &self,
+        template: &str,
+        broadcast: &Broadcast,
+    ) -> Result<Vec<(ServerId, MessageOutcome)>> {
+        broadcast.send(self, template).await
+    }
+
+    /// Displays details about the server instance including uptime, number of virtual
+    /// servers online, traffic information, etc.
+    pub async fn hostinfo(&self) -> Result<HostInfo> {
+        let req = RequestBuilder::new("hostinfo");
+        self.send(req).await
+    }
+
+    /// Authenticate with the given data. The password is sent in plain text, see the
+    /// [`password`] module.
+    ///
+    /// [`password`]: crate::password
     pub async fn login(&self, username: &str, password: &str) -> Result<()> {
         let req = RequestBuilder::new("login")
             .arg("client_login_name", username)
             .arg("client_login_password", password);
-        self.send(req).await
+        self.send::<(), _>(req).await?;
+        self.refresh_me().await;
+        Ok(())
+    }
+
+    /// Authenticate using an apikey previously created with [`apikeyadd`]. This allows
+    /// bots to authenticate without storing login credentials.
+    ///
+    /// [`apikeyadd`]: Self::apikeyadd
+    pub async fn login_with_apikey(&self, apikey: &str) -> Result<()> {
+        let req = RequestBuilder::new("auth").arg("apikey", apikey);
+        self.send::<(), _>(req).await?;
+        self.refresh_me().await;
+        Ok(())
     }
 
     /// Deselects the active virtual server and logs out from the server instance.
     pub async fn logout(&self) -> Result<()> {
         let req = RequestBuilder::new("logout");
+        self.send::<(), _>(req).await?;
+        {
+            let mut inner = self.inner.write().unwrap();
+            inner.selected = None;
+            inner.reset_session();
+        }
+        self.refresh_me().await;
+        Ok(())
+    }
+
+    /// Sends an offline message to the client specified by `cluid`.
+    pub async fn messageadd(&self, cluid: &str, subject: &str, message: &str) -> Result<()> {
+        let req = RequestBuilder::new("messageadd")
+            .arg("cluid", cluid)
+            .arg("subject", subject)
+            .arg("message", message);
+        self.send(req).await
+    }
+
+    /// Deletes the offline message specified by `msgid` from your inbox.
+    pub async fn messagedel(&self, msgid: u64) -> Result<()> {
+        let req = RequestBuilder::new("messagedel").arg("msgid", msgid);
+        self.send(req).await
+    }
+
+    /// Displays an existing offline message with id `msgid` from your inbox. Please note that
+    /// this does not automatically set the `flag_read` property of the message.
+    pub async fn messageget(&self, msgid: u64) -> Result<Message> {
+        let req = RequestBuilder::new("messageget").arg("msgid", msgid);
+        self.send(req).await
+    }
+
+    /// Displays a list of offline messages you've received.
+    pub async fn messagelist(&self) -> Result<List<MessageEntry, Pipe>> {
+        let req = RequestBuilder::new("messagelist");
+        self.send(req).await
+    }
+
+    /// Updates the `flag_read` property of the offline message specified by `msgid`.
+    pub async fn messageupdateflag(&self, msgid: u64, read: bool) -> Result<()> {
+        let req = RequestBuilder::new("messageupdateflag")
+            .arg("msgid", msgid)
+            .arg("flag", read);
+        self.send(req).await
+    }
+
+    /// Displays all permission values assigned to the client `cldbid` in the channel `cid`,
+    /// including the origin of every value. If `permsid` is `None`, all permissions are
+    /// returned.
+    pub async fn permoverview(
+        &self,
+        cid: ChannelId,
+        cldbid: ClientDatabaseId,
+        permsid: Option<&str>,
+    ) -> Result<List<PermissionOverview, Pipe>> {
+        let mut req = RequestBuilder::new("permoverview")
+            .arg("cid", cid)
+            .arg("cldbid", cldbid);
+        match permsid {
+            Some(permsid) => req = req.arg("permsid", permsid),
+            None => req = req.arg("permid", 0),
+        }
+
+        self.send(req).await
+    }
+
+    /// Restores the default permission settings on the selected virtual server and creates a
+    /// new initial administrator token. Please note that in addition to the default
+    /// permissions, **all permission changes of the virtual server are wiped**.
+    ///
+    /// This method is intended for disaster recovery only and therefore carries the `danger_`
+    /// prefix.
+    pub async fn danger_permreset(&self) -> Result<Token> {
+        let req = RequestBuilder::new("permreset");
+        self.send(req).await
+    }
+
+    /// Send a quit command, disconnecting the client and closing the TCP connection.
+    ///
+    /// The client is considered closed once the command is sent, the server closing the
+    /// connection is not reported to the event handler.
+    pub async fn quit(&self) -> Result<()> {
+        self.set_closed();
+        let req = RequestBuilder::new("quit");
+        self.send(req).await
+    }
+
+    /// Disconnects the client using [`quit`] and shuts down the event handler tasks that are
+    /// still running according to `shutdown`. The tasks are shut down even if [`quit`] fails.
+    ///
+    /// Calling it with [`Shutdown::Wait`] from within an event handler never returns, the
+    /// handler waits for its own task to complete. Use [`Shutdown::Detach`] in event
+    /// handlers instead.
+    ///
+    /// ```no_run
+    /// # use ts3::{Client, Shutdown};
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// client.disconnect(Shutdown::Wait).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`quit`]: Self::quit
+    pub async fn disconnect(&self, shutdown: Shutdown) -> Result<()> {
+        let res = self.quit().await;
+
+        let tasks = self.inner.read().unwrap().tasks.clone();
+        let mut tasks = tasks.take();
+        match shutdown {
+            Shutdown::Wait => while tasks.join_next().await.is_some() {},
+            Shutdown::Abort => {
+                tasks.abort_all();
+                while tasks.join_next().await.is_some() {}
+            }
+            Shutdown::Detach => tasks.detach_all(),
+        }
+
+        res
+    }
+
+    pub async fn sendtextmessage(&self, target: TextMessageTarget, msg: &str) -> Result<()> {
+        let req = RequestBuilder::new("sendtextmessage")
+            .arg("targetmode", target)
+            .arg("msg", msg);
+        self.send(req).await
+    }
+
+    /// Like [`sendtextmessage`], but reports a message rejected by the flood protection as
+    /// [`MessageOutcome::RateLimited`] instead of an error.
+    ///
+    /// [`sendtextmessage`]: Self::sendtextmessage
+    pub async fn sendtextmessage_outcome(
+        &self,
+        target: TextMessageTarget,
+        msg: &str,
+    ) -> Result<MessageOutcome> {
+        let req = RequestBuilder::new("sendtextmessage")
+            .arg("targetmode", target)
+            .arg("msg", msg);
+        self.send_message(req).await
+    }
+
+    /// Creates a new virtual server named `name` and starts it. The voice port is chosen
+    /// by the server.
+    pub async fn servercreate(&self, name: &str) -> Result<VirtualServerCreated> {
+        let req = RequestBuilder::new("servercreate").arg("virtualserver_name", name);
+        self.send(req).await
+    }
+
+    /// Creates a snapshot of the selected virtual server, containing its configuration,
+    /// channels, groups and permissions.
+    pub async fn serversnapshotcreate(&self) -> Result<Snapshot> {
+        self.send(RequestBuilder::new("serversnapshotcreate")).await
+    }
+
+    /// Deploys `snapshot` to the selected virtual server, replacing its configuration. If no
+    /// virtual server is selected a new server is created from the snapshot.
+    ///
+    /// Files of the existing channels are only kept if `keepfiles` is `true`. If `mapping` is
+    /// `true` the response includes the old and new id of every deployed channel.
+    pub async fn serversnapshotdeploy(
+        &self,
+        snapshot: &Snapshot,
+        keepfiles: bool,
+        mapping: bool,
+    ) -> Result<List<SnapshotDeployed, Pipe>> {
+        let mut req = RequestBuilder::new("serversnapshotdeploy");
+
+        if keepfiles {
+            req = req.flag("-keepfiles");
+        }
+        if mapping {
+            req = req.flag("-mapping");
+        }
+
+        req = req.arg("version", snapshot.version);
+        if !snapshot.salt.is_empty() {
+            req = req.arg("salt", snapshot.salt.as_str());
+        }
+        req = req.arg("data", snapshot.data.as_str());
+
         self.send(req).await
     }
 
-    /// Send a quit command, disconnecting the client and closing the TCP connection
-    pub async fn quit(&self) -> Result<()> {
-        let req = RequestBuilder::new("quit");
-        self.send(req).await
-    }
+    /// Creates a new virtual server named `name`, selects it and applies `properties`.
+    /// Returns the created server, including the privilege key for its server admin group.
+    ///
+    /// If applying `properties` fails the server is not deleted and stays selected.
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// use ts3::request::VirtualServerProperties;
+    ///
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// let properties = VirtualServerProperties::new().maxclients(32);
+    /// let server = client.create_and_use("My Server", properties).await?;
+    /// println!("admin token: {}", server.token);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_and_use(
+        &self,
+        name: &str,
+        properties: VirtualServerProperties<'_>,
+    ) -> Result<VirtualServerCreated> {
+        let server = self.servercreate(name).await?;
+        self.use_sid(server.sid).await?;
+
+        if !properties.is_empty() {
+            self.serveredit(properties).await?;
+        }
+
+        Ok(server)
+    }
+
+    /// Initializes the download of the file `name` from the channel `cid`, starting at byte
+    /// `seekpos`. `clientftfid` is an id chosen by the client to identify the transfer.
+    ///
+    /// The file is transferred over a separate connection to the returned port, see
+    /// [`FileTransfer`] for a complete download.
+    ///
+    /// [`FileTransfer`]: crate::ft::FileTransfer
+    pub async fn ftinitdownload(
+        &self,
+        clientftfid: u16,
+        name: &str,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        seekpos: u64,
+    ) -> Result<FileTransferInit> {
+        let req = RequestBuilder::new("ftinitdownload")
+            .arg("clientftfid", clientftfid)
+            .arg("name", name)
+            .arg("cid", cid)
+            .arg("cpw", cpw.unwrap_or_default())
+            .arg("seekpos", seekpos);
+
+        check_transfer(self.send(req).await?)
+    }
+
+    /// Initializes the upload of `size` bytes to the file `name` in the channel `cid`.
+    /// `clientftfid` is an id chosen by the client to identify the transfer. An existing file
+    /// is only replaced if `overwrite` is `true`. If `resume` is `true` an incomplete upload
+    /// is continued at the returned `seekpos`.
+    ///
+    /// The file is transferred over a separate connection to the returned port, see
+    /// [`FileTransfer`] for a complete upload.
+    ///
+    /// [`FileTransfer`]: crate::ft::FileTransfer
+    #[allow(clippy::too_many_arguments)]
+    pub async fn ftinitupload(
+        &self,
+        clientftfid: u16,
+        name: &str,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        size: u64,
+        overwrite: bool,
+        resume: bool,
+    ) -> Result<FileTransferInit> {
+        let req = RequestBuilder::new("ftinitupload")
+            .arg("clientftfid", clientftfid)
+            .arg("name", name)
+            .arg("cid", cid)
+            .arg("cpw", cpw.unwrap_or_default())
+            .arg("size", size)
+            .arg("overwrite", overwrite)
+            .arg("resume", resume);
+
+        check_transfer(self.send(req).await?)
+    }
+
+    /// Lists the files and directories in the directory `path` of the channel `cid`. `cpw` is
+    /// the password of the channel, if it has one. Returns an empty list if the directory is
+    /// empty.
+    ///
+    /// Only the first entry includes `cid` and `path`. See [`walk_files`] for a listing of
+    /// a whole file tree.
+    ///
+    /// [`walk_files`]: Self::walk_files
+    pub async fn ftgetfilelist(
+        &self,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        path: &str,
+    ) -> Result<List<FileEntry, Pipe>> {
+        let req = RequestBuilder::new("ftgetfilelist")
+            .arg("cid", cid)
+            .arg("cpw", cpw.unwrap_or_default())
+            .arg("path", path);
+
+        match self.send(req).await {
+            Ok(list) => Ok(list),
+            Err(err) if err.is_empty_result() => Ok(List::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Lists all files in the directory `path` of the channel `cid` and all of its
+    /// subdirectories. Directories are returned before their contents. `cid` and `path` are set
+    /// on every returned entry.
+    ///
+    /// The listing runs on a new task. Commands rejected because of flooding are retried after
+    /// the time requested by the server. The walk stops after the first error or when the
+    /// receiver is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use ts3::Client;
+    /// # use ts3::shared::ChannelId;
+    /// # async fn run(client: Client) -> Result<(), ts3::Error> {
+    /// let mut files = client.walk_files(ChannelId(1), None, "/");
+    ///
+    /// while let Some(file) = files.recv().await {
+    ///     let file = file?;
+    ///     println!("{}{}", file.path, file.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn walk_files(
+        &self,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        path: &str,
+    ) -> mpsc::Receiver<Result<FileEntry>> {
+        let (tx, rx) = mpsc::channel(32);
+
+        let client = self.clone();
+        let cpw = cpw.map(str::to_owned);
+        let mut dirs = vec![path.to_owned()];
+        tokio::task::spawn(async move {
+            while let Some(path) = dirs.pop() {
+                let entries = loop {
+                    match client.ftgetfilelist(cid, cpw.as_deref(), &path).await {
+                        Err(err) if err.is_flooding() => {
+                            sleep(err.retry_after().unwrap_or(Duration::from_secs(1))).await;
+                        }
+                        res => break res,
+                    }
+                };
+
+                let entries = match entries {
+                    Ok(entries) => entries,
+                    Err(err) => {
+                        let _ = tx.send(Err(err)).await;
+                        return;
+                    }
+                };
+
+                // Push in reverse to visit subdirectories in the order they were listed.
+                let mut subdirs = Vec::new();
+                for mut entry in Vec::from(entries) {
+                    entry.cid = cid;
+                    entry.path = path.clone();
+
+                    if entry.is_directory() {
+                        let sep = if path.ends_with('/') { "" } else { "/" };
+                        subdirs.push(format!("{}{}{}", path, sep, entry.name));
+                    }
 
-    pub async fn sendtextmessage(&self, target: TextMessageTarget, msg: &str) -> Result<()> {
-        let req = RequestBuilder::new("sendtextmessage")
-            .arg("targetmode", target)
-            .arg("msg", msg);
-        self.send(req).await
+                    if tx.send(Ok(entry)).await.is_err() {
+                        return;
+                    }
+                }
+                dirs.extend(subdirs.into_iter().rev());
+            }
+        });
+
+        rx
     }
 
     /// Adds one or more clients to the server group specified with sgid. Please note that a
@@ -418,6 +3599,88 @@ impl Client {
         self.send(req).await
     }
 
+    /// Like [`servergroupaddclient`], but first checks whether the client already is a
+    /// member of the group using [`servergroupsbyclientid`]. This makes idempotent group
+    /// synchronization simpler.
+    ///
+    /// [`servergroupaddclient`]: Self::servergroupaddclient
+    /// [`servergroupsbyclientid`]: Self::servergroupsbyclientid
+    pub async fn servergroupaddclient_verified(
+        &self,
+        sgid: ServerGroupId,
+        cldbid: ClientDatabaseId,
+    ) -> Result<MembershipChange> {
+        if self.is_servergroup_member(sgid, cldbid).await? {
+            return Ok(MembershipChange::AlreadyMember);
+        }
+
+        match self.servergroupaddclient(sgid, cldbid).await {
+            Ok(()) => Ok(MembershipChange::Added),
+            // The client was added concurrently.
+            Err(err) if err.is_duplicate_entry() => Ok(MembershipChange::AlreadyMember),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Like [`servergroupdelclient`], but first checks whether the client is a member of the
+    /// group using [`servergroupsbyclientid`].
+    ///
+    /// [`servergroupdelclient`]: Self::servergroupdelclient
+    /// [`servergroupsbyclientid`]: Self::servergroupsbyclientid
+    pub async fn servergroupdelclient_verified(
+        &self,
+        sgid: ServerGroupId,
+        cldbid: ClientDatabaseId,
+    ) -> Result<MembershipChange> {
+        if !self.is_servergroup_member(sgid, cldbid).await? {
+            return Ok(MembershipChange::NotAMember);
+        }
+
+        self.servergroupdelclient(sgid, cldbid).await?;
+        Ok(MembershipChange::Removed)
+    }
+
+    async fn is_servergroup_member(
+        &self,
+        sgid: ServerGroupId,
+        cldbid: ClientDatabaseId,
+    ) -> Result<bool> {
+        match self.servergroupsbyclientid(cldbid).await {
+            Ok(groups) => Ok(groups.iter().any(|group| group.sgid == sgid)),
+            Err(err) if err.is_empty_result() => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Displays a list of server groups available. Depending on your permissions, the output
+    /// may also contain global ServerQuery groups and template groups.
+    pub async fn servergrouplist(&self) -> Result<List<ServerGroup, Pipe>> {
+        let req = RequestBuilder::new("servergrouplist");
+        self.send(req).await
+    }
+
+    /// Like [`servergrouplist`] but only returns server groups of the given [`GroupType`].
+    ///
+    /// [`servergrouplist`]: Self::servergrouplist
+    pub async fn servergrouplist_by_type(
+        &self,
+        group_type: GroupType,
+    ) -> Result<List<ServerGroup, Pipe>> {
+        let mut groups = self.servergrouplist().await?;
+        groups.retain(|group| group.r#type == group_type);
+        Ok(groups)
+    }
+
+    /// Displays all server groups the client specified with `cldbid` is currently residing
+    /// in.
+    pub async fn servergroupsbyclientid(
+        &self,
+        cldbid: ClientDatabaseId,
+    ) -> Result<List<ServerGroupMembership, Pipe>> {
+        let req = RequestBuilder::new("servergroupsbyclientid").arg("cldbid", cldbid);
+        self.send(req).await
+    }
+
     /// Registers for a specified category of events on a virtual server to receive
     /// notification messages. Depending on the notifications you've registered for,
     /// the server will send you a message on every event in the view of your
@@ -430,6 +3693,68 @@ impl Client {
         self.send(req).await
     }
 
+    /// Adds a temporary server password, which can be used to join the selected virtual
+    /// server until it expires. See [`TempPassword`] for the options.
+    pub async fn servertemppasswordadd(&self, password: TempPassword<'_>) -> Result<()> {
+        let req = password.apply(RequestBuilder::new("servertemppasswordadd"));
+        self.send(req).await
+    }
+
+    /// Deletes the temporary server password `pw`.
+    pub async fn servertemppassworddel(&self, pw: &str) -> Result<()> {
+        let req = RequestBuilder::new("servertemppassworddel").arg("pw", pw);
+        self.send(req).await
+    }
+
+    /// Displays the temporary server passwords of the selected virtual server.
+    pub async fn servertemppasswordlist(&self) -> Result<List<TempPasswordEntry, Pipe>> {
+        let req = RequestBuilder::new("servertemppasswordlist");
+        self.send(req).await
+    }
+
+    /// Changes the properties of the selected virtual server. Only the properties set in
+    /// `properties` are changed.
+    pub async fn serveredit(&self, properties: VirtualServerProperties<'_>) -> Result<()> {
+        let req = properties.apply(RequestBuilder::new("serveredit"));
+        self.send(req).await
+    }
+
+    /// Displays a list of virtual servers including their id, status, number of clients
+    /// online, etc.
+    pub async fn serverlist(&self) -> Result<List<ServerListEntry, Pipe>> {
+        let req = RequestBuilder::new("serverlist");
+        self.send(req).await
+    }
+
+    /// Displays detailed configuration information about the selected virtual server
+    /// including unique ID, number of clients online, configuration, etc.
+    pub async fn serverinfo(&self) -> Result<ServerInfo> {
+        let req = RequestBuilder::new("serverinfo");
+        self.send(req).await
+    }
+
+    /// Stops the entire TeamSpeak 3 Server instance by shutting down the process.
+    pub async fn serverprocessstop(&self) -> Result<()> {
+        let req = RequestBuilder::new("serverprocessstop");
+        self.send(req).await
+    }
+
+    /// Like [`serverprocessstop`], but sends `reasonmsg` to the clients before they are
+    /// disconnected.
+    ///
+    /// [`serverprocessstop`]: Self::serverprocessstop
+    pub async fn serverprocessstop_with_reason(&self, reasonmsg: &str) -> Result<()> {
+        let req = RequestBuilder::new("serverprocessstop").arg("reasonmsg", reasonmsg);
+        self.send(req).await
+    }
+
+    /// Displays detailed connection information about the selected virtual server including
+    /// uptime, traffic information, etc.
+    pub async fn serverrequestconnectioninfo(&self) -> Result<ConnectionInfo> {
+        let req = RequestBuilder::new("serverrequestconnectioninfo");
+        self.send(req).await
+    }
+
     /// Starts the virtual server specified with sid. Depending on your permissions,
     /// you're able to start either your own virtual server only or all virtual
     /// servers in the server instance.  
@@ -443,8 +3768,7 @@ impl Client {
 
     /// Stops the virtual server specified with sid. Depending on your permissions,
     /// you're able to stop either your own virtual server only or all virtual
-    /// servers in the server instance. The reasonmsg parameter specifies a
-    /// text message that is sent to the clients before the client disconnects.
+    /// servers in the server instance.
     pub async fn serverstop<T>(&self, sid: T) -> Result<()>
     where
         T: Into<ServerId>,
@@ -453,19 +3777,73 @@ impl Client {
         self.send(req).await
     }
 
+    /// Like [`serverstop`], but sends `reasonmsg` to the clients before they are
+    /// disconnected.
+    ///
+    /// [`serverstop`]: Self::serverstop
+    pub async fn serverstop_with_reason<T>(&self, sid: T, reasonmsg: &str) -> Result<()>
+    where
+        T: Into<ServerId>,
+    {
+        let req = RequestBuilder::new("serverstop")
+            .arg("sid", sid.into())
+            .arg("reasonmsg", reasonmsg);
+        self.send(req).await
+    }
+
     /// Switch to the virtualserver (voice) with the given server id
     pub async fn use_sid<T>(&self, sid: T) -> Result<()>
     where
         T: Into<ServerId>,
     {
-        let req = RequestBuilder::new("use").arg("sid", sid.into());
-        self.send(req).await
+        self.use_sid_with(sid, UseOptions::default()).await
+    }
+
+    /// Like [`use_sid`], but with additional [`UseOptions`]. Stopped servers can only be
+    /// selected with `virtual` enabled.
+    ///
+    /// [`use_sid`]: Self::use_sid
+    pub async fn use_sid_with<T>(&self, sid: T, options: UseOptions<'_>) -> Result<()>
+    where
+        T: Into<ServerId>,
+    {
+        let sid = sid.into();
+        let req = RequestBuilder::new("use").arg("sid", sid);
+        self.use_inner(ServerSelection::Id(sid), options.apply(req))
+            .await
     }
 
     /// Like `use_sid` but instead use_port uses the voice port to connect to the virtualserver
     pub async fn use_port(&self, port: u16) -> Result<()> {
+        self.use_port_with(port, UseOptions::default()).await
+    }
+
+    /// Like [`use_port`], but with additional [`UseOptions`].
+    ///
+    /// [`use_port`]: Self::use_port
+    pub async fn use_port_with(&self, port: u16, options: UseOptions<'_>) -> Result<()> {
         let req = RequestBuilder::new("use").arg("port", port);
-        self.send(req).await
+        self.use_inner(ServerSelection::Port(port), options.apply(req))
+            .await
+    }
+
+    async fn use_inner(&self, server: ServerSelection, req: RequestBuilder) -> Result<()> {
+        let bytes = Bytes::from(req.build().buf.into_bytes());
+        self.select(server, bytes).await
+    }
+
+    /// Sends the `use` command `bytes` selecting `server` and records the selection.
+    async fn select(&self, server: ServerSelection, bytes: Bytes) -> Result<()> {
+        let resp_rx = self.enqueue(bytes.clone()).await?;
+        self.recv_response::<()>(resp_rx).await?;
+
+        {
+            let mut inner = self.inner.write().unwrap();
+            inner.selected = Some((server, bytes));
+            inner.reset_session();
+        }
+        self.refresh_me().await;
+        Ok(())
     }
 
     /// Returns information about the server version
@@ -474,9 +3852,597 @@ impl Client {
         self.send(req).await
     }
 
-    /// Returns information about the query client connected
+    /// Returns information about the query client connected. The result is cached and
+    /// available using [`me`].
+    ///
+    /// [`me`]: Self::me
     pub async fn whoami(&self) -> Result<Whoami> {
         let req = RequestBuilder::new("whoami");
+        let whoami: Whoami = self.send(req).await?;
+
+        let mut inner = self.inner.write().unwrap();
+        inner.whoami = Some(whoami.clone());
+        Ok(whoami)
+    }
+}
+
+// Helpers built on top of the TS3 commands go here
+impl Client {
+    /// Returns a [`KvStore`] for the client with the database id `cldbid`, storing values
+    /// in the custom properties of the client.
+    pub fn kv(&self, cldbid: ClientDatabaseId) -> KvStore {
+        KvStore::new(self.clone(), cldbid)
+    }
+
+    /// Returns a [`ConfigStore`] storing a configuration of type `T` in the custom property
+    /// `prefix` of this query client. This keeps the settings of a bot on the server itself,
+    /// without any local state.
+    ///
+    /// The database id of the query client is requested using [`whoami`].
+    ///
+    /// [`whoami`]: Self::whoami
+    #[cfg(feature = "serde")]
+    pub async fn config<T>(&self, prefix: &str) -> Result<ConfigStore<T>>
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let whoami = self.whoami().await?;
+        Ok(ConfigStore::new(self.kv(whoami.client_database_id), prefix))
+    }
+
+    /// Sets the store persisting temporary group memberships. Defaults to a
+    /// [`MemoryStore`]. See the [`membership`] module for more details.
+    ///
+    /// [`membership`]: crate::membership
+    pub fn set_membership_store<S>(&self, store: S)
+    where
+        S: MembershipStore + 'static,
+    {
+        self.inner.write().unwrap().membership_store = Arc::new(store);
+    }
+
+    pub(crate) fn membership_store(&self) -> Arc<dyn MembershipStore> {
+        self.inner.read().unwrap().membership_store.clone()
+    }
+
+    /// Adds the client `cldbid` to the server group `sgid` of the selected server and removes
+    /// it again after `duration`. See the [`membership`] module for more details.
+    ///
+    /// The membership is saved in the [`MembershipStore`] before the client is added to the
+    /// group. If the client cannot be added it is removed from the store again. Returns
+    /// `None` if the client already is a member of the group, the membership is then left
+    /// untouched and never removed.
+    ///
+    /// [`membership`]: crate::membership
+    pub async fn add_to_group_temporarily(
+        &self,
+        cldbid: ClientDatabaseId,
+        sgid: ServerGroupId,
+        duration: Duration,
+    ) -> Result<Option<TemporaryMembership>> {
+        let sid = self.selected_sid().await?;
+
+        let membership = TemporaryMembership {
+            sid,
+            sgid,
+            cldbid,
+            expires: SystemTime::now() + duration,
+        };
+
+        let store = self.membership_store();
+        store
+            .save(&membership)
+            .await
+            .map_err(|err| Error(err.into()))?;
+
+        match self.servergroupaddclient_verified(sgid, cldbid).await {
+            Ok(MembershipChange::AlreadyMember) => {
+                store
+                    .remove(&membership)
+                    .await
+                    .map_err(|err| Error(err.into()))?;
+                Ok(None)
+            }
+            Ok(_) => {
+                membership::schedule(self, membership.clone());
+                Ok(Some(membership))
+            }
+            Err(err) => {
+                let _ = store.remove(&membership).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Schedules the removal of all memberships in the [`MembershipStore`] belonging to the
+    /// selected virtual server. Memberships that expired while the application was not
+    /// running are removed right away. Returns the number of scheduled memberships.
+    ///
+    /// This should be called once after setting the store and after selecting a virtual
+    /// server.
+    pub async fn resume_temporary_memberships(&self) -> Result<usize> {
+        let sid = self.selected_sid().await?;
+        let memberships = self
+            .membership_store()
+            .load()
+            .await
+            .map_err(|err| Error(err.into()))?;
+
+        let mut len = 0;
+        for membership in memberships.into_iter().filter(|m| m.sid == sid) {
+            membership::schedule(self, membership);
+            len += 1;
+        }
+
+        Ok(len)
+    }
+
+    /// Returns the id of the selected virtual server.
+    async fn selected_sid(&self) -> Result<ServerId> {
+        match self.me() {
+            Some(me) => Ok(me.virtualserver_id),
+            None => Ok(self.whoami().await?.virtualserver_id),
+        }
+    }
+
+    /// Runs `action` according to `schedule` on the runtime of the client. See the
+    /// [`schedule`] module for more details.
+    ///
+    /// [`schedule`]: crate::schedule
+    pub fn schedule<F, Fut>(&self, schedule: Schedule, action: F) -> ScheduleHandle
+    where
+        F: Fn(Client) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        schedule::spawn(self.clone(), schedule, action)
+    }
+
+    /// Returns all unread offline messages and marks them as read. Messages are only marked
+    /// as read after all of them were received, if receiving a message fails all messages
+    /// stay unread.
+    pub async fn inbox(&self) -> Result<Vec<Message>> {
+        let list = match self.messagelist().await {
+            Ok(list) => list,
+            Err(err) if err.is_empty_result() => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut messages = Vec::new();
+        for entry in list.iter().filter(|entry| !entry.flag_read) {
+            messages.push(self.messageget(entry.msgid).await?);
+        }
+
+        for message in &messages {
+            self.messageupdateflag(message.msgid, true).await?;
+        }
+
+        Ok(messages)
+    }
+
+    /// Sends an offline message to the client specified by `cluid`. This is an alias for
+    /// [`messageadd`].
+    ///
+    /// [`messageadd`]: Self::messageadd
+    pub async fn send_offline_message(&self, cluid: &str, subject: &str, body: &str) -> Result<()> {
+        self.messageadd(cluid, subject, body).await
+    }
+
+    /// Pokes the client `clid` with `msg`. If the message is too long for a poke or the poke
+    /// fails, e.g. because of insufficient permissions, the message is sent as a private text
+    /// message instead, split into multiple messages if necessary. If a part is rate limited
+    /// the remaining parts are not sent.
+    pub async fn poke_or_message(&self, clid: ClientId, msg: &str) -> Result<MessageOutcome> {
+        if msg.chars().count() <= MAX_POKE_LENGTH {
+            if let Ok(MessageOutcome::Sent) = self.clientpoke_outcome(clid, msg).await {
+                return Ok(MessageOutcome::Sent);
+            }
+        }
+
+        for chunk in split_message(msg, MAX_MESSAGE_LENGTH) {
+            let outcome = self
+                .sendtextmessage_outcome(TextMessageTarget::Client(clid), chunk)
+                .await?;
+
+            if outcome != MessageOutcome::Sent {
+                return Ok(outcome);
+            }
+        }
+
+        Ok(MessageOutcome::Sent)
+    }
+
+    /// Sets the description of the channel `cid`. The length of the escaped description is
+    /// validated against [`MAX_CHANNEL_DESCRIPTION_LENGTH`] before being sent, as the server
+    /// rejects longer descriptions. `overflow` specifies how descriptions exceeding the limit
+    /// are handled. Use [`split_channel_description`] to spread a long description over
+    /// multiple channels instead.
+    ///
+    /// [`split_channel_description`]: crate::split_channel_description
+    pub async fn set_channel_description(
+        &self,
+        cid: ChannelId,
+        desc: &str,
+        overflow: Overflow,
+    ) -> Result<()> {
+        let desc = truncate(desc, MAX_CHANNEL_DESCRIPTION_LENGTH, overflow)?;
+
+        let req = RequestBuilder::new("channeledit")
+            .arg("cid", cid)
+            .arg("channel_description", desc.as_str());
         self.send(req).await
     }
 }
+
+/// Checks that `s` is at most `max` bytes long once escaped, truncating it if `overflow` is
+/// [`Overflow::Truncate`].
+fn truncate(s: &str, max: usize, overflow: Overflow) -> Result<String> {
+    let len = s.chars().map(escaped_len).sum();
+    if len <= max {
+        return Ok(s.to_owned());
+    }
+
+    match overflow {
+        Overflow::Error => Err(Error(ErrorKind::TooLong { len, max })),
+        Overflow::Truncate => {
+            let mut len = '…'.len_utf8();
+            let mut s: String = s
+                .chars()
+                .take_while(|c| {
+                    len += escaped_len(*c);
+                    len <= max
+                })
+                .collect();
+            s.push('…');
+            Ok(s)
+        }
+    }
+}
+
+/// Returns the number of bytes `c` takes up once escaped.
+fn escaped_len(c: char) -> usize {
+    crate::escape(c.encode_utf8(&mut [0; 4])).len()
+}
+
+/// Splits the channel description `desc` into chunks that each fit into a channel
+/// description, see [`MAX_CHANNEL_DESCRIPTION_LENGTH`]. Chunks are split at the last
+/// whitespace if possible.
+pub fn split_channel_description(desc: &str) -> Vec<&str> {
+    split_by(desc, MAX_CHANNEL_DESCRIPTION_LENGTH, escaped_len)
+}
+
+/// The maximum length of a channel description in bytes, after escaping it for the query
+/// protocol.
+pub const MAX_CHANNEL_DESCRIPTION_LENGTH: usize = 8192;
+
+/// How to handle a value that exceeds its maximum length.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Overflow {
+    /// Return an error without sending the value.
+    #[default]
+    Error,
+    /// Truncate the value and append an ellipsis (`…`).
+    Truncate,
+}
+
+/// The maximum number of characters in a poke message.
+const MAX_POKE_LENGTH: usize = 100;
+
+/// The maximum number of characters in a text message.
+const MAX_MESSAGE_LENGTH: usize = 1024;
+
+/// Splits `msg` into chunks of at most `max` characters. Chunks are split at the last
+/// whitespace if possible. No chunk is empty unless `msg` is empty or only whitespace.
+fn split_message(msg: &str, max: usize) -> Vec<&str> {
+    split_by(msg, max, |_| 1)
+}
+
+/// Like [`split_message`], but measures the length of every char using `len`.
+fn split_by(mut msg: &str, max: usize, len: fn(char) -> usize) -> Vec<&str> {
+    let measure = |s: &str| s.chars().map(len).sum::<usize>();
+    let mut chunks = Vec::new();
+
+    // Leading whitespace would be split off as an empty chunk.
+    if measure(msg) > max {
+        msg = msg.trim_start();
+    }
+
+    while measure(msg) > max {
+        // Byte index of the first char that doesn't fit into the chunk.
+        let mut total = 0;
+        let (end, _) = msg
+            .char_indices()
+            .find(|(_, c)| {
+                total += len(*c);
+                total > max
+            })
+            .unwrap();
+
+        let split = match msg[..end].rfind(char::is_whitespace) {
+            Some(index) if index > 0 => index,
+            _ => end,
+        };
+
+        chunks.push(msg[..split].trim_end());
+        msg = msg[split..].trim_start();
+    }
+
+    if !msg.is_empty() || chunks.is_empty() {
+        chunks.push(msg);
+    }
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use tokio::sync::{mpsc, oneshot};
+
+    use std::net::SocketAddr;
+
+    use super::{
+        interleave, read_line, redact, split_channel_description, split_message, truncate, Cmd,
+        CmdQueue, Keepalive, Overflow, Reconnect, ServerAddr, MAX_CHANNEL_DESCRIPTION_LENGTH,
+    };
+    use crate::{Error, ErrorKind};
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("", 5), [""]);
+        assert_eq!(split_message("Hello", 5), ["Hello"]);
+        assert_eq!(split_message("Hello World", 5), ["Hello", "World"]);
+        assert_eq!(split_message("Hello World", 8), ["Hello", "World"]);
+        assert_eq!(split_message("HelloWorld", 4), ["Hell", "oWor", "ld"]);
+        assert_eq!(split_message("äöüäöü", 3), ["äöü", "äöü"]);
+
+        // Leading and repeated whitespace never produces empty chunks.
+        assert_eq!(split_message(" \tHello World", 6), ["Hello", "World"]);
+        assert_eq!(split_message("  HelloWorld", 4), ["Hell", "oWor", "ld"]);
+        assert_eq!(split_message("Hello      World", 5), ["Hello", "World"]);
+        assert_eq!(split_message("          ", 4), [""]);
+        for max in 1..12 {
+            for chunk in split_message(" a  bc   def    ghij ", max) {
+                assert!(!chunk.is_empty(), "{}", max);
+                assert!(chunk.chars().count() <= max, "{}", max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("Hello", 5, Overflow::Error).unwrap(), "Hello");
+        assert!(truncate("Hello World", 5, Overflow::Error).is_err());
+        assert_eq!(
+            truncate("Hello World", 7, Overflow::Truncate).unwrap(),
+            "Hell…"
+        );
+
+        // The limit applies to the escaped value.
+        let max = MAX_CHANNEL_DESCRIPTION_LENGTH;
+        let desc = " ".repeat(max / 2);
+        assert_eq!(truncate(&desc, max, Overflow::Error).unwrap(), desc);
+        let desc = " ".repeat(max / 2 + 1);
+        let err = truncate(&desc, max, Overflow::Error).unwrap_err();
+        assert!(matches!(err.0, ErrorKind::TooLong { len, .. } if len == max + 2));
+
+        let desc = truncate(&desc, max, Overflow::Truncate).unwrap();
+        assert!(desc.ends_with('…'));
+        assert_eq!(crate::escape(&desc).len(), max - 1);
+    }
+
+    #[test]
+    fn test_split_channel_description() {
+        let max = MAX_CHANNEL_DESCRIPTION_LENGTH;
+        let desc = "a".repeat(max);
+        assert_eq!(split_channel_description(&desc), [&desc]);
+
+        // Every `|` takes up two bytes once escaped.
+        let desc = format!("{} {}", "|".repeat(max / 2), "|".repeat(10));
+        let chunks = split_channel_description(&desc);
+        assert_eq!(chunks, ["|".repeat(max / 2), "|".repeat(10)]);
+        for chunk in chunks {
+            assert!(crate::escape(chunk).len() <= max);
+        }
+    }
+
+    #[test]
+    fn test_keepalive_delay() {
+        let keepalive = Keepalive::new().interval(Duration::from_secs(10));
+        assert_eq!(keepalive.delay(), Duration::from_secs(10));
+
+        let keepalive = keepalive.jitter(Duration::from_secs(5));
+        for _ in 0..100 {
+            let delay = keepalive.delay();
+            assert!(delay >= Duration::from_secs(10) && delay < Duration::from_secs(15));
+        }
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let reconnect = Reconnect::new()
+            .delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(10));
+
+        let delays: Vec<u64> = (0..6)
+            .map(|attempt| reconnect.backoff(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8, 10, 10]);
+        assert_eq!(reconnect.backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn test_read_line() {
+        let mut reader = &b"a=1\n\r\rb\r\n\r c=2 \n\rd"[..];
+        let mut buf = Vec::new();
+
+        read_line(&mut reader, &mut buf, 16).await.unwrap();
+        assert_eq!(buf, b"a=1");
+
+        // Blank lines are skipped and a lone '\r' terminates a line.
+        buf.clear();
+        read_line(&mut reader, &mut buf, 16).await.unwrap();
+        assert_eq!(buf, b"b");
+
+        buf.clear();
+        read_line(&mut reader, &mut buf, 16).await.unwrap();
+        assert_eq!(buf, b"c=2");
+
+        // The connection was closed before the line was terminated.
+        buf.clear();
+        assert!(read_line(&mut reader, &mut buf, 16).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_line_transcript() {
+        // Lines terminated by "\r\n" with blank keepalive lines in between.
+        let mut reader =
+            &b"TS3\r\n\r\nWelcome\r\nversion=3.13.7 build=1\r\n\r\nerror id=0 msg=ok\r\n"[..];
+        let mut buf = Vec::new();
+
+        let mut lines = Vec::new();
+        while read_line(&mut reader, &mut buf, 64).await.is_ok() {
+            lines.push(String::from_utf8(std::mem::take(&mut buf)).unwrap());
+        }
+
+        assert_eq!(
+            lines,
+            [
+                "TS3",
+                "Welcome",
+                "version=3.13.7 build=1",
+                "error id=0 msg=ok"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_line_max() {
+        let mut reader = &b"abc\n\rabcd\n\r"[..];
+        let mut buf = Vec::new();
+
+        read_line(&mut reader, &mut buf, 3).await.unwrap();
+        assert_eq!(buf, b"abc");
+
+        buf.clear();
+        let err = read_line(&mut reader, &mut buf, 3).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line exceeds the maximum length of 3 bytes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cmd_queue_priority() {
+        let (tx, normal) = mpsc::channel(4);
+        let (priority_tx, high) = mpsc::channel(4);
+        let (setup_tx, setup) = mpsc::channel(4);
+        let mut queue = CmdQueue {
+            setup,
+            high,
+            normal,
+        };
+
+        for (tx, cmd) in [
+            (&tx, "a"),
+            (&priority_tx, "b"),
+            (&tx, "c"),
+            (&setup_tx, "d"),
+        ] {
+            let (resp, _) = oneshot::channel();
+            let bytes = Bytes::from_static(cmd.as_bytes());
+            tx.send(Cmd::new(bytes, resp)).await.unwrap();
+        }
+
+        for expected in ["d", "b", "a"] {
+            let cmd = poll_fn(|cx| queue.poll_recv(cx)).await.unwrap();
+            assert_eq!(cmd.bytes, expected);
+        }
+        assert_eq!(queue.close().len(), 1);
+    }
+
+    #[test]
+    fn test_server_addr_host_port() {
+        assert_eq!(
+            "ts.example.com:10011".host_port().unwrap(),
+            ("ts.example.com".to_owned(), 10011)
+        );
+        assert_eq!(
+            "[::1]:10011".host_port().unwrap(),
+            ("::1".to_owned(), 10011)
+        );
+        assert_eq!(
+            ("example.onion", 10011).host_port().unwrap(),
+            ("example.onion".to_owned(), 10011)
+        );
+        assert_eq!(
+            "10.0.0.1:10011"
+                .parse::<SocketAddr>()
+                .unwrap()
+                .host_port()
+                .unwrap(),
+            ("10.0.0.1".to_owned(), 10011)
+        );
+        assert!("ts.example.com".host_port().is_err());
+        assert!("[::1:10011".host_port().is_err());
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(redact("whoami"), "whoami");
+        assert_eq!(
+            redact("login client_login_name=serveradmin client_login_password=secret"),
+            "login client_login_name=serveradmin client_login_password=***"
+        );
+        assert_eq!(
+            redact("apikey=abc id=1|apikey=def id=2"),
+            "apikey=*** id=1|apikey=*** id=2"
+        );
+        assert_eq!(redact("apikeydel id=1"), "apikeydel id=1");
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let ts3 = |id| {
+            Error(ErrorKind::TS3 {
+                id,
+                msg: String::new(),
+                extra_msg: None,
+            })
+        };
+
+        assert!(ts3(524).is_retryable() && !ts3(524).is_fatal());
+        assert!(ts3(1538).is_fatal() && !ts3(1538).is_retryable());
+        assert!(ts3(2568).is_fatal());
+        assert!(ts3(3329).is_fatal());
+        assert!(!ts3(1281).is_fatal() && !ts3(1281).is_retryable());
+        assert!(Error(ErrorKind::TimedOut).is_retryable());
+
+        let err = Error::decode(
+            b"error id=524 msg=client\\sis\\sflooding extra_msg=please\\swait\\s3\\sseconds",
+        )
+        .unwrap();
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(3)));
+
+        // Errors of a field are classified by the error of the field.
+        let err = ts3(524).with_decode_context(b"id=1", b"id", b"1");
+        assert!(err.decode_context().is_some());
+        assert!(err.is_retryable() && !err.is_fatal());
+        let err = ts3(2568).with_decode_context(b"id=1", b"id", b"1");
+        assert!(err.is_fatal() && !err.is_retryable());
+    }
+
+    #[test]
+    fn test_interleave() {
+        let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+        let (a4, b4, c4) = (addr("1.0.0.1:1"), addr("1.0.0.2:1"), addr("1.0.0.3:1"));
+        let (a6, b6) = (addr("[::1]:1"), addr("[::2]:1"));
+
+        assert_eq!(interleave([]), []);
+        assert_eq!(interleave([a4, b4]), [a4, b4]);
+        assert_eq!(interleave([a6, b6, a4, b4, c4]), [a6, a4, b6, b4, c4]);
+        assert_eq!(interleave([a4, b4, c4, a6]), [a4, a6, b4, c4]);
+    }
+}
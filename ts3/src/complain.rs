@@ -0,0 +1,330 @@
+//! Automatic moderation based on complaints.
+//!
+//! A [`ComplaintModerator`] periodically polls the complaint list and invokes a callback once
+//! a client has received a given number of complaints. The client can optionally be banned.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::complain::ComplaintModerator;
+//! use ts3::Client;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let (_moderator, mut events) = ComplaintModerator::new(3)
+//!         .ban(Some(Duration::from_secs(60 * 60)), "Too many complaints")
+//!         .on_threshold(|target| println!("{} received too many complaints", target.name))
+//!         .spawn(client);
+//!
+//!     while let Some(event) = events.recv().await {
+//!         println!("{:?}", event);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::request::ClientListOptions;
+use crate::response::Complaint;
+use crate::shared::ClientDatabaseId;
+use crate::{Client, Error};
+
+/// A client which crossed the complaint threshold.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ComplaintTarget {
+    pub cldbid: ClientDatabaseId,
+    /// The name of the client at the time of the most recent complaint.
+    pub name: String,
+    /// All current complaints about the client.
+    pub complaints: Vec<Complaint>,
+}
+
+/// An event emitted by a [`ComplaintModerator`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ComplaintEvent {
+    /// A client crossed the complaint threshold.
+    Threshold {
+        target: ComplaintTarget,
+        /// `true` if the client was banned.
+        banned: bool,
+    },
+    /// An error occured while polling complaints or banning a client.
+    Error(Error),
+}
+
+#[derive(Clone, Debug)]
+struct Ban {
+    time: Option<Duration>,
+    reason: String,
+}
+
+type Callback = Arc<dyn Fn(&ComplaintTarget) + Send + Sync>;
+
+/// Periodically polls `complainlist` and reacts to clients with at least `threshold`
+/// complaints. Each client triggers only once until its complaints drop below the threshold
+/// again.
+#[derive(Clone)]
+pub struct ComplaintModerator {
+    threshold: usize,
+    interval: Duration,
+    ban: Option<Ban>,
+    callback: Option<Callback>,
+}
+
+impl ComplaintModerator {
+    /// Creates a new `ComplaintModerator` triggering at `threshold` complaints.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            interval: Duration::from_secs(30),
+            ban: None,
+            callback: None,
+        }
+    }
+
+    /// Sets the interval in which the complaint list is polled. Defaults to 30 seconds.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Bans clients crossing the threshold for `time`, or permanently if `time` is `None`.
+    /// Online clients are banned using `banclient`, offline clients by their unique
+    /// identifier using `banadd`.
+    pub fn ban(mut self, time: Option<Duration>, reason: &str) -> Self {
+        self.ban = Some(Ban {
+            time,
+            reason: reason.to_owned(),
+        });
+        self
+    }
+
+    /// Sets a callback invoked for every client crossing the threshold. The callback is
+    /// invoked before the client is banned.
+    pub fn on_threshold<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ComplaintTarget) + Send + Sync + 'static,
+    {
+        self.callback = Some(Arc::new(f));
+        self
+    }
+
+    /// Returns all clients that crossed the threshold since the last call. `triggered` holds
+    /// all clients which already triggered and is updated.
+    fn check(
+        &self,
+        complaints: Vec<Complaint>,
+        triggered: &mut HashSet<ClientDatabaseId>,
+    ) -> Vec<ComplaintTarget> {
+        let mut targets: HashMap<ClientDatabaseId, Vec<Complaint>> = HashMap::new();
+        for complaint in complaints {
+            targets
+                .entry(complaint.tcldbid)
+                .or_default()
+                .push(complaint);
+        }
+
+        // Forget clients whose complaints were removed, so they can trigger again.
+        triggered.retain(|cldbid| {
+            targets
+                .get(cldbid)
+                .map(|c| c.len() >= self.threshold)
+                .unwrap_or(false)
+        });
+
+        let mut crossed = Vec::new();
+        for (cldbid, mut complaints) in targets {
+            if complaints.len() < self.threshold || triggered.contains(&cldbid) {
+                continue;
+            }
+
+            triggered.insert(cldbid);
+
+            complaints.sort_by_key(|c| c.timestamp);
+            crossed.push(ComplaintTarget {
+                cldbid,
+                name: complaints
+                    .last()
+                    .map(|c| c.tname.clone())
+                    .unwrap_or_default(),
+                complaints,
+            });
+        }
+
+        crossed
+    }
+
+    async fn ban_client(
+        &self,
+        client: &Client,
+        ban: &Ban,
+        cldbid: ClientDatabaseId,
+    ) -> Result<(), Error> {
+        let time = ban.time.map(|time| time.as_secs());
+
+        let clients = client.clientlist(ClientListOptions::default()).await?;
+        let clids: Vec<_> = clients
+            .iter()
+            .filter(|c| c.client_database_id == cldbid)
+            .map(|c| c.clid)
+            .collect();
+
+        if clids.is_empty() {
+            let info = client.clientdbinfo(cldbid).await?;
+            return client
+                .banadd(
+                    None,
+                    None,
+                    Some(&info.client_unique_identifier),
+                    None,
+                    time,
+                    Some(&ban.reason),
+                    Some(&info.client_nickname),
+                )
+                .await;
+        }
+
+        for clid in clids {
+            client.banclient(clid, time, Some(&ban.reason)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn poll(
+        &self,
+        client: &Client,
+        triggered: &mut HashSet<ClientDatabaseId>,
+        events: &mpsc::Sender<ComplaintEvent>,
+    ) -> Result<(), Error> {
+        let complaints = client.complainlist(None).await?.into_inner();
+
+        for target in self.check(complaints, triggered) {
+            if let Some(callback) = &self.callback {
+                callback(&target);
+            }
+
+            let mut banned = false;
+            if let Some(ban) = &self.ban {
+                match self.ban_client(client, ban, target.cldbid).await {
+                    Ok(()) => banned = true,
+                    Err(err) => {
+                        let _ = events.try_send(ComplaintEvent::Error(err));
+                    }
+                }
+            }
+
+            let _ = events.try_send(ComplaintEvent::Threshold { target, banned });
+        }
+
+        Ok(())
+    }
+
+    /// Spawns a new task running the `ComplaintModerator` using `client`. Returns a handle to
+    /// stop the task and a receiver for all emitted [`ComplaintEvent`]s. Events are dropped
+    /// if the receiver is not drained.
+    pub fn spawn(
+        self,
+        client: Client,
+    ) -> (ComplaintModeratorHandle, mpsc::Receiver<ComplaintEvent>) {
+        let (tx, rx) = mpsc::channel(32);
+
+        let handle = tokio::task::spawn(async move {
+            let mut triggered = HashSet::new();
+
+            loop {
+                if let Err(err) = self.poll(&client, &mut triggered, &tx).await {
+                    let _ = tx.try_send(ComplaintEvent::Error(err));
+                }
+
+                sleep(self.interval).await;
+            }
+        });
+
+        (ComplaintModeratorHandle { handle }, rx)
+    }
+}
+
+impl Debug for ComplaintModerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComplaintModerator")
+            .field("threshold", &self.threshold)
+            .field("interval", &self.interval)
+            .field("ban", &self.ban)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A handle to a running [`ComplaintModerator`]. The `ComplaintModerator` is stopped when
+/// the handle is dropped.
+#[derive(Debug)]
+pub struct ComplaintModeratorHandle {
+    handle: JoinHandle<()>,
+}
+
+impl ComplaintModeratorHandle {
+    /// Stops the `ComplaintModerator`.
+    #[inline]
+    pub fn stop(self) {}
+}
+
+impl Drop for ComplaintModeratorHandle {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::ComplaintModerator;
+    use crate::response::Complaint;
+    use crate::shared::ClientDatabaseId;
+
+    fn complaint(tcldbid: u64, timestamp: u64) -> Complaint {
+        Complaint {
+            tcldbid: ClientDatabaseId(tcldbid),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let moderator = ComplaintModerator::new(2);
+        let mut triggered = HashSet::new();
+
+        let targets = moderator.check(vec![complaint(1, 0), complaint(2, 0)], &mut triggered);
+        assert!(targets.is_empty());
+
+        let complaints = vec![complaint(1, 0), complaint(2, 0), complaint(1, 1)];
+        let targets = moderator.check(complaints.clone(), &mut triggered);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].cldbid, ClientDatabaseId(1));
+
+        // Already triggered.
+        assert!(moderator.check(complaints, &mut triggered).is_empty());
+
+        // Complaints removed, triggers again afterwards.
+        assert!(moderator.check(vec![], &mut triggered).is_empty());
+        let targets = moderator.check(vec![complaint(1, 0), complaint(1, 1)], &mut triggered);
+        assert_eq!(targets.len(), 1);
+    }
+}
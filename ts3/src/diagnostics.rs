@@ -0,0 +1,46 @@
+//! Integration with [`miette`](https://docs.rs/miette).
+//!
+//! With the `diagnostics` feature enabled [`Error`] implements [`Diagnostic`]. Errors
+//! returned while decoding a response point at the value of the field that could not be
+//! decoded:
+//!
+//! ```text
+//! ts3::decode
+//!
+//!   × failed to decode field `clid` at byte 11: failed to parse integer: invalid digit found in string
+//!    ╭────
+//!  1 │ cid=1 clid=x client_nickname=bot
+//!    ·            ┬
+//!    ·            ╰── invalid value for `clid`
+//!    ╰────
+//! ```
+
+use std::fmt::Display;
+use std::iter;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+
+use crate::Error;
+
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.decode_context()
+            .map(|_| Box::new("ts3::decode") as Box<dyn Display>)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.decode_context()
+            .map(|context| &context.line as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let context = self.decode_context()?;
+        let label = LabeledSpan::new(
+            Some(format!("invalid value for `{}`", context.field())),
+            context.offset(),
+            context.len(),
+        );
+
+        Some(Box::new(iter::once(label)))
+    }
+}
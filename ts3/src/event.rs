@@ -2,170 +2,442 @@
 #[allow(unused_imports)]
 use crate as ts3;
 
-use crate::client::Client;
+use crate::client::{Client, PanicPolicy};
+use crate::response::Entry;
 use crate::shared::list::Comma;
-use crate::shared::{ChannelGroupId, ChannelId, ClientDatabaseId, ClientId, List, ServerGroupId};
-use crate::{Decode, DecodeError, Error, ErrorKind};
+use crate::shared::{
+    ChannelGroupId, ChannelId, ClientDatabaseId, ClientId, CountryCode, IconId, List, ServerGroupId,
+};
+use crate::{Decode, Encode, Error, ErrorKind, LossyGuard, TolerantGuard};
 use async_trait::async_trait;
-use tokio::task::spawn;
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Debug, Formatter};
+use std::future::{poll_fn, Future};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinSet;
+
+/// Returns the message of a panic from its `payload`.
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(msg) => (*msg).to_owned(),
+            Err(_) => String::from("Box<dyn Any>"),
+        },
+    }
+}
 
 impl Client {
-    // Check buf for an event key. If one is found, a new task is spawned, the event
-    // is dispatched to the associated handler and true is returned. If buf does not
-    // contain event data, false is returned.
+    // Check buf for an event key. If one is found, the event is decoded and dispatched to
+    // the event handler and true is returned. If buf does not contain event data, false is
+    // returned.
     pub(crate) fn dispatch_event(&self, buf: &[u8]) -> bool {
-        let c = self.clone();
-        let handler = c.inner.read().unwrap().handler.clone();
-
-        // Split of the first argument (separated by ' '). It contains the event name.
-        // The rest of the buffer contains the event data.
-        let (event_name, rest): (&[u8], &[u8]);
-        {
-            let vec: Vec<&[u8]> = buf.splitn(2, |c| *c == b' ').collect();
-            event_name = vec[0];
-            rest = vec[1];
-        }
-
-        // buf contains the event data which will be moved to the event task.
-        let buf = rest.to_owned();
-
-        match event_name {
-            b"notifycliententerview" => {
-                let event = match ClientEnterView::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
+        let _guard = LossyGuard::set(self.lossy);
+        let _tolerant = TolerantGuard::set(self.flavor.is_tolerant());
 
-                spawn(async move { handler.cliententerview(c, event).await });
+        let event = match Event::from_line(buf) {
+            Some(Ok(event)) => event,
+            Some(Err(err)) => {
+                let handler = self.inner.read().unwrap().handler.clone();
+                handler.error(self.clone(), err);
+                return true;
             }
-            b"notifyclientleftview" => {
-                let event = match ClientLeftView::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.clientleftview(c, event).await });
-            }
-            b"notifyserveredited" => {
-                let event = match ServerEdited::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.serveredited(c, event).await });
-            }
-            b"notifychanneldescriptionchanged" => {
-                let event = match ChannelDescriptionChanged::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.channeldescriptionchanged(c, event).await });
+            None => return false,
+        };
+
+        if let Event::TextMessage(event) = &event {
+            if self.is_own_message(event) {
+                return true;
             }
-            b"notifychannelpasswordchanged" => {
-                let event = match ChannelPasswordChanged::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.channelpasswordchanged(c, event).await });
+        }
+
+        // Synthesized events are dispatched after the event causing them.
+        let synthesized = {
+            let mut inner = self.inner.write().unwrap();
+            let mut events = match &mut inner.occupancy {
+                Some(occupancy) => occupancy.update(&event),
+                None => Vec::new(),
+            };
+            if let Some(reconnects) = &mut inner.reconnects {
+                events.extend(reconnects.update(&event, Instant::now()));
             }
-            b"notifychannelmoved" => {
-                let event = match ChannelMoved::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.channelmoved(c, event).await });
+            if let Some(nicknames) = &mut inner.nicknames {
+                nicknames.update(&event);
             }
-            b"notifychanneledited" => {
-                let event = match ChannelEdited::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.channeledited(c, event).await });
+
+            events
+        };
+
+        self.dispatch_decoded(event);
+        for event in synthesized {
+            self.dispatch_decoded(event);
+        }
+
+        true
+    }
+
+    /// Dispatches the decoded `event` to the event handler, unless it is dropped by the
+    /// event filter.
+    pub(crate) fn dispatch_decoded(&self, event: Event) {
+        let (handler, tasks, guard, filter) = {
+            let inner = self.inner.read().unwrap();
+            (
+                inner.handler.clone(),
+                inner.tasks.clone(),
+                inner.in_flight.enter(),
+                inner.filter.clone(),
+            )
+        };
+
+        if !filter.matches(&event) {
+            return;
+        }
+
+        {
+            let mut inner = self.inner.write().unwrap();
+            inner.replay.push(&event);
+
+            if let Some(tx) = inner.dead_letter.as_ref().filter(|_| !inner.handler_set) {
+                // The event is dropped if the queue is full or was closed.
+                let _ = tx.try_send(event);
+                return;
             }
-            b"notifychannelcreated" => {
-                let event = match ChannelCreated::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.channelcreated(c, event).await });
+        }
+
+        handler.dispatch(&tasks, self.clone(), event, guard);
+    }
+
+    // Reports a panic of an event handler task to `handler` and disconnects the client if
+    // configured.
+    async fn handler_panicked<D>(&self, handler: &D, payload: Box<dyn Any + Send>)
+    where
+        D: Dispatch + ?Sized,
+    {
+        let msg = panic_message(payload);
+        handler.error(self.clone(), Error(ErrorKind::HandlerPanic(msg)));
+
+        if self.panic_policy == PanicPolicy::Disconnect {
+            let _ = self.quit().await;
+        }
+    }
+
+    // Returns true if the client is configured to ignore its own messages and event was
+    // sent by this client.
+    fn is_own_message(&self, event: &TextMessage) -> bool {
+        let inner = self.inner.read().unwrap();
+
+        match &inner.whoami {
+            Some(whoami) => {
+                inner.ignore_own_messages && whoami.client_id == event.invoker.invokerid
             }
-            b"notifychanneldeleted" => {
-                let event = match ChannelDeleted::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.channeldeleted(c, event).await });
+            None => false,
+        }
+    }
+}
+
+/// Dispatches decoded events to an event handler.
+pub(crate) trait Dispatch: Send + Sync {
+    /// Spawns a new task in `tasks` handling `event`. `guard` is held until the task
+    /// completes.
+    fn dispatch(
+        self: Arc<Self>,
+        tasks: &Arc<Tasks>,
+        client: Client,
+        event: Event,
+        guard: InFlightGuard,
+    );
+
+    fn error(&self, client: Client, error: Error);
+}
+
+impl<H> Dispatch for H
+where
+    H: EventHandler + 'static,
+{
+    fn dispatch(
+        self: Arc<Self>,
+        tasks: &Arc<Tasks>,
+        client: Client,
+        event: Event,
+        guard: InFlightGuard,
+    ) {
+        tasks.spawn(client.clone(), self.clone(), async move {
+            self.handle(client, event).await;
+            drop(guard);
+        });
+    }
+
+    fn error(&self, client: Client, error: Error) {
+        EventHandler::error(self, client, error)
+    }
+}
+
+/// The most recently dispatched events. See [`ClientBuilder::replay_buffer`].
+///
+/// [`ClientBuilder::replay_buffer`]: crate::ClientBuilder::replay_buffer
+pub(crate) struct ReplayBuffer {
+    events: VecDeque<Event>,
+    capacity: usize,
+}
+
+impl ReplayBuffer {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Appends `event`, dropping the oldest event if the buffer is full.
+    pub(crate) fn push(&mut self, event: &Event) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+    }
+
+    /// Removes and returns all buffered events, oldest first.
+    pub(crate) fn take(&mut self) -> VecDeque<Event> {
+        std::mem::take(&mut self.events)
+    }
+}
+
+/// A boxed event handler task.
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The tasks spawned for all events of a client.
+pub(crate) struct Tasks {
+    tasks: Mutex<JoinSet<()>>,
+    /// Limits the number of concurrently running handlers. Has [`Semaphore::MAX_PERMITS`]
+    /// permits if the number is unlimited.
+    limit: Arc<Semaphore>,
+    /// Handlers waiting for a permit of `limit`.
+    queued: Mutex<VecDeque<Task>>,
+}
+
+impl Tasks {
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        Self {
+            tasks: Mutex::default(),
+            limit: Arc::new(Semaphore::new(limit.unwrap_or(Semaphore::MAX_PERMITS))),
+            queued: Mutex::default(),
+        }
+    }
+
+    /// Spawns `f` handling an event for `handler`. A panic in `f` is handled according to
+    /// the [`PanicPolicy`] of `client`. If the number of concurrent handlers is limited, `f`
+    /// is queued until a permit is available.
+    pub(crate) fn spawn<D, F>(self: &Arc<Self>, client: Client, handler: Arc<D>, f: F)
+    where
+        D: Dispatch + ?Sized + 'static,
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let f = async move {
+            if let Err(payload) = catch_unwind(f).await {
+                client.handler_panicked(&*handler, payload).await;
             }
-            b"notifyclientmoved" => {
-                let event = match ClientMoved::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.clientmoved(c, event).await });
+        };
+
+        let mut queued = self.queued.lock().unwrap();
+
+        // Only handlers waiting for a permit are boxed.
+        if queued.is_empty() {
+            if let Ok(permit) = self.limit.clone().try_acquire_owned() {
+                self.start(permit, f);
+                return;
             }
-            b"notifytextmessage" => {
-                let event = match TextMessage::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.textmessage(c, event).await });
+        }
+
+        queued.push_back(Box::pin(f));
+        drop(queued);
+        self.start_queued();
+    }
+
+    /// Starts queued handlers while permits of `limit` are available. The queue stays locked
+    /// while acquiring permits, so a handler queued concurrently is always started by either
+    /// the caller queueing it or the handler releasing the next permit.
+    fn start_queued(self: &Arc<Self>) {
+        let mut queued = self.queued.lock().unwrap();
+
+        while !queued.is_empty() {
+            let Ok(permit) = self.limit.clone().try_acquire_owned() else {
+                return;
+            };
+
+            let task = queued.pop_front().unwrap();
+            self.start(permit, task);
+        }
+    }
+
+    /// Spawns `f` holding `permit`. Once `f` completes, the next queued handler is started.
+    fn start<F>(self: &Arc<Self>, permit: OwnedSemaphorePermit, f: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let tasks = Arc::downgrade(self);
+        let mut joinset = self.tasks.lock().unwrap();
+
+        // Remove completed tasks, otherwise their results are kept until the client is
+        // disconnected.
+        while joinset.try_join_next().is_some() {}
+        joinset.spawn(async move {
+            f.await;
+            drop(permit);
+
+            if let Some(tasks) = tasks.upgrade() {
+                tasks.start_queued();
             }
-            b"notifytokenused" => {
-                let event = match TokenUsed::decode(&buf) {
-                    Ok(event) => event,
-                    Err(err) => {
-                        handler.error(c, err);
-                        return true;
-                    }
-                };
-
-                spawn(async move { handler.tokenused(c, event).await });
+        });
+    }
+
+    /// Removes all tasks spawned until now. Queued handlers are included and start once a
+    /// permit is available.
+    pub(crate) fn take(&self) -> JoinSet<()> {
+        let queued = std::mem::take(&mut *self.queued.lock().unwrap());
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+
+        for task in queued {
+            let limit = self.limit.clone();
+            tasks.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                task.await;
+            });
+        }
+
+        tasks
+    }
+}
+
+impl Debug for Tasks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tasks")
+            .field("tasks", &self.tasks)
+            .field("limit", &self.limit)
+            .field("queued", &self.queued.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// Polls `f` to completion, returning the panic payload if `f` panics.
+async fn catch_unwind<F>(f: F) -> Result<(), Box<dyn Any + Send>>
+where
+    F: Future<Output = ()>,
+{
+    let mut f = pin!(f);
+    poll_fn(
+        |cx| match panic::catch_unwind(AssertUnwindSafe(|| f.as_mut().poll(cx))) {
+            Ok(Poll::Ready(())) => Poll::Ready(Ok(())),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        },
+    )
+    .await
+}
+
+/// Counts the tasks spawned for an event handler which have not completed yet.
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    count: AtomicUsize,
+    notify: Notify,
+}
+
+impl InFlight {
+    /// Registers a new task. The task is completed once the returned guard is dropped.
+    pub(crate) fn enter(self: &Arc<Self>) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self.clone())
+    }
+
+    /// Waits until all registered tasks are completed.
+    pub(crate) async fn wait(&self) {
+        loop {
+            // Create the future before checking the count to not miss a notification.
+            let notified = self.notify.notified();
+            if self.count.load(Ordering::SeqCst) == 0 {
+                return;
             }
-            _ => return false,
+
+            notified.await;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct InFlightGuard(Arc<InFlight>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.notify.notify_waiters();
         }
+    }
+}
 
-        true
+/// Calls the method of `handler` matching `event`. The default of [`EventHandler::handle`].
+async fn dispatch_method<H>(handler: &H, client: Client, event: Event)
+where
+    H: EventHandler + ?Sized,
+{
+    match event {
+        Event::ClientEnterView(event) => handler.cliententerview(client, event).await,
+        Event::ClientLeftView(event) => handler.clientleftview(client, event).await,
+        Event::ServerEdited(event) => handler.serveredited(client, event).await,
+        Event::ChannelDescriptionChanged(event) => {
+            handler.channeldescriptionchanged(client, event).await
+        }
+        Event::ChannelPasswordChanged(event) => handler.channelpasswordchanged(client, event).await,
+        Event::ChannelMoved(event) => handler.channelmoved(client, event).await,
+        Event::ChannelEdited(event) => handler.channeledited(client, event).await,
+        Event::ChannelCreated(event) => handler.channelcreated(client, event).await,
+        Event::ChannelDeleted(event) => handler.channeldeleted(client, event).await,
+        Event::ClientMoved(event) => handler.clientmoved(client, event).await,
+        Event::TextMessage(event) => handler.textmessage(client, event).await,
+        Event::TokenUsed(event) => handler.tokenused(client, event).await,
+        Event::ChannelBecameEmpty(event) => handler.channelbecameempty(client, event).await,
+        Event::ChannelBecameOccupied(event) => handler.channelbecameoccupied(client, event).await,
+        Event::ClientReconnected(event) => handler.clientreconnected(client, event).await,
+        Event::ClientNicknameChanged(event) => handler.clientnicknamechanged(client, event).await,
+    }
+}
+
+/// An event handler using a function returning a concrete future. See
+/// [`Client::set_event_handler_fn`].
+pub(crate) struct FnHandler<F>(pub(crate) F);
+
+impl<F, Fut> Dispatch for FnHandler<F>
+where
+    F: Fn(Client, Event) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    fn dispatch(
+        self: Arc<Self>,
+        tasks: &Arc<Tasks>,
+        client: Client,
+        event: Event,
+        guard: InFlightGuard,
+    ) {
+        // The function is called on the spawned task, so a panic is handled like a panic of
+        // the returned future.
+        let handler = self.clone();
+        tasks.spawn(client.clone(), handler, async move {
+            (self.0)(client, event).await;
+            drop(guard);
+        });
+    }
+
+    fn error(&self, client: Client, error: Error) {
+        EventHandler::error(&Handler, client, error)
     }
 }
 
@@ -173,6 +445,13 @@ impl Client {
 /// In order to receive events you must subscribe to the events you want to receive using servernotifyregister.
 #[async_trait]
 pub trait EventHandler: Send + Sync {
+    /// Handles any event. All events pass through this method, which calls the method
+    /// matching the event by default. Handlers wrapping another handler can override it to
+    /// forward all events using a single method.
+    async fn handle(&self, client: Client, event: Event) {
+        dispatch_method(self, client, event).await
+    }
+
     async fn cliententerview(&self, _client: Client, _event: ClientEnterView) {}
     async fn clientleftview(&self, _client: Client, _event: ClientLeftView) {}
     async fn serveredited(&self, _client: Client, _event: ServerEdited) {}
@@ -185,62 +464,469 @@ pub trait EventHandler: Send + Sync {
     async fn clientmoved(&self, _client: Client, _event: ClientMoved) {}
     async fn textmessage(&self, _client: Client, _event: TextMessage) {}
     async fn tokenused(&self, _client: Client, _event: TokenUsed) {}
+    async fn channelbecameempty(&self, _client: Client, _event: ChannelBecameEmpty) {}
+    async fn channelbecameoccupied(&self, _client: Client, _event: ChannelBecameOccupied) {}
+    async fn clientreconnected(&self, _client: Client, _event: ClientReconnected) {}
+    async fn clientnicknamechanged(&self, _client: Client, _event: ClientNicknameChanged) {}
 
     fn error(&self, _client: Client, error: Error) {
         println!("connection error: {}", error);
     }
 }
 
+/// Any event sent by the server.
+///
+/// [`ChannelBecameEmpty`] and [`ChannelBecameOccupied`] are not sent by the server, they are
+/// synthesized by the client once [`Client::track_occupancy`] was called. The same applies
+/// to [`ClientReconnected`] if [`ClientBuilder::reconnect_window`] is set and to
+/// [`ClientNicknameChanged`] once [`Client::track_nicknames`] was called.
+///
+/// [`ChannelBecameEmpty`]: Self::ChannelBecameEmpty
+/// [`ChannelBecameOccupied`]: Self::ChannelBecameOccupied
+/// [`ClientReconnected`]: Self::ClientReconnected
+/// [`ClientNicknameChanged`]: Self::ClientNicknameChanged
+/// [`ClientBuilder::reconnect_window`]: crate::ClientBuilder::reconnect_window
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Event {
+    ClientEnterView(ClientEnterView),
+    ClientLeftView(ClientLeftView),
+    ServerEdited(ServerEdited),
+    ChannelDescriptionChanged(ChannelDescriptionChanged),
+    ChannelPasswordChanged(ChannelPasswordChanged),
+    ChannelMoved(ChannelMoved),
+    ChannelEdited(ChannelEdited),
+    ChannelCreated(ChannelCreated),
+    ChannelDeleted(ChannelDeleted),
+    ClientMoved(ClientMoved),
+    TextMessage(TextMessage),
+    TokenUsed(TokenUsed),
+    ChannelBecameEmpty(ChannelBecameEmpty),
+    ChannelBecameOccupied(ChannelBecameOccupied),
+    ClientReconnected(ClientReconnected),
+    ClientNicknameChanged(ClientNicknameChanged),
+}
+
+impl Event {
+    /// Decodes the event from a line received from the server. Returns `None` if the line
+    /// does not contain a known event.
+    pub(crate) fn from_line(buf: &[u8]) -> Option<Result<Self, Error>> {
+        // Split of the first argument (separated by ' '). It contains the event name.
+        // The rest of the buffer contains the event data. The event is decoded directly
+        // from buf, only the decoded fields are moved to the event task.
+        let (name, rest) = match buf.iter().position(|c| *c == b' ') {
+            Some(index) => (&buf[..index], &buf[index + 1..]),
+            None => (buf, &[][..]),
+        };
+
+        Self::decode(name, rest)
+    }
+
+    /// Decodes the event with the name `name` from `buf`. Returns `None` if `name` is not a
+    /// known event.
+    pub(crate) fn decode(name: &[u8], buf: &[u8]) -> Option<Result<Self, Error>> {
+        Some(match name {
+            b"notifycliententerview" => ClientEnterView::decode(buf).map(Self::ClientEnterView),
+            b"notifyclientleftview" => ClientLeftView::decode(buf).map(Self::ClientLeftView),
+            b"notifyserveredited" => ServerEdited::decode(buf).map(Self::ServerEdited),
+            b"notifychanneldescriptionchanged" => {
+                ChannelDescriptionChanged::decode(buf).map(Self::ChannelDescriptionChanged)
+            }
+            b"notifychannelpasswordchanged" => {
+                ChannelPasswordChanged::decode(buf).map(Self::ChannelPasswordChanged)
+            }
+            b"notifychannelmoved" => ChannelMoved::decode(buf).map(Self::ChannelMoved),
+            b"notifychanneledited" => ChannelEdited::decode(buf).map(Self::ChannelEdited),
+            b"notifychannelcreated" => ChannelCreated::decode(buf).map(Self::ChannelCreated),
+            b"notifychanneldeleted" => ChannelDeleted::decode(buf).map(Self::ChannelDeleted),
+            b"notifyclientmoved" => ClientMoved::decode(buf).map(Self::ClientMoved),
+            b"notifytextmessage" => TextMessage::decode(buf).map(Self::TextMessage),
+            b"notifytokenused" => TokenUsed::decode(buf).map(Self::TokenUsed),
+            _ => return None,
+        })
+    }
+}
+
+impl Event {
+    /// Returns all fields of the event. This allows reading fields which are not included
+    /// in the event struct.
+    ///
+    /// ```
+    /// use ts3::event::Event;
+    ///
+    /// fn client_type(event: &Event) -> Option<u8> {
+    ///     match event {
+    ///         Event::ClientEnterView(_) => event.raw().get("client_type").ok(),
+    ///         _ => None,
+    ///     }
+    /// }
+    /// ```
+    pub fn raw(&self) -> &Entry {
+        match self {
+            Self::ClientEnterView(event) => &event.raw,
+            Self::ClientLeftView(event) => &event.raw,
+            Self::ServerEdited(event) => &event.raw,
+            Self::ChannelDescriptionChanged(event) => &event.raw,
+            Self::ChannelPasswordChanged(event) => &event.raw,
+            Self::ChannelMoved(event) => &event.raw,
+            Self::ChannelEdited(event) => &event.raw,
+            Self::ChannelCreated(event) => &event.raw,
+            Self::ChannelDeleted(event) => &event.raw,
+            Self::ClientMoved(event) => &event.raw,
+            Self::TextMessage(event) => &event.raw,
+            Self::TokenUsed(event) => &event.raw,
+            Self::ChannelBecameEmpty(event) => &event.raw,
+            Self::ChannelBecameOccupied(event) => &event.raw,
+            Self::ClientReconnected(event) => &event.raw,
+            Self::ClientNicknameChanged(event) => &event.raw,
+        }
+    }
+
+    /// Returns the event as [`EventMeta`] trait object.
+    fn meta(&self) -> &dyn EventMeta {
+        match self {
+            Self::ClientEnterView(event) => event,
+            Self::ClientLeftView(event) => event,
+            Self::ServerEdited(event) => event,
+            Self::ChannelDescriptionChanged(event) => event,
+            Self::ChannelPasswordChanged(event) => event,
+            Self::ChannelMoved(event) => event,
+            Self::ChannelEdited(event) => event,
+            Self::ChannelCreated(event) => event,
+            Self::ChannelDeleted(event) => event,
+            Self::ClientMoved(event) => event,
+            Self::TextMessage(event) => event,
+            Self::TokenUsed(event) => event,
+            Self::ChannelBecameEmpty(event) => event,
+            Self::ChannelBecameOccupied(event) => event,
+            Self::ClientReconnected(event) => event,
+            Self::ClientNicknameChanged(event) => event,
+        }
+    }
+}
+
+/// Metadata shared by many events. This allows writing middleware, like logging or
+/// filtering by invoker, once for all events.
+///
+/// ```
+/// use ts3::event::{Event, EventMeta};
+///
+/// fn log(event: &Event) {
+///     if let Some(invoker) = event.invoker() {
+///         println!("{} caused {:?}", invoker.invokername, event);
+///     }
+/// }
+/// ```
+pub trait EventMeta {
+    /// Returns the client that caused the event. Returns `None` if the event does not
+    /// include an invoker.
+    fn invoker(&self) -> Option<Invoker> {
+        None
+    }
+
+    /// Returns the reason of the event. Returns `None` if the event does not include a
+    /// reason.
+    fn reason(&self) -> Option<ReasonId> {
+        None
+    }
+}
+
+impl EventMeta for Event {
+    fn invoker(&self) -> Option<Invoker> {
+        self.meta().invoker()
+    }
+
+    fn reason(&self) -> Option<ReasonId> {
+        self.meta().reason()
+    }
+}
+
+/// Drops events before they are dispatched to the event handler. Set using
+/// [`Client::set_event_filter`].
+///
+/// An event is dispatched if it matches all configured conditions. An event matches the
+/// channels if it refers to one of them, e.g. as the source or target channel of a move. An
+/// event matches the clients if one of them is the subject or the invoker of the event.
+/// Events not referring to any channel or client never match a channel or client condition.
+///
+/// ```
+/// use ts3::event::{Event, EventFilter};
+/// use ts3::shared::ChannelId;
+///
+/// let filter = EventFilter::new()
+///     .channel(ChannelId(1))
+///     .channel(ChannelId(2))
+///     .predicate(|event| !matches!(event, Event::ChannelEdited(_)));
+/// ```
+#[derive(Default)]
+pub struct EventFilter {
+    channels: Vec<ChannelId>,
+    clients: Vec<ClientId>,
+    predicate: Option<Predicate>,
+}
+
+type Predicate = Box<dyn Fn(&Event) -> bool + Send + Sync>;
+
+impl EventFilter {
+    /// Creates a new `EventFilter` matching all events.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only dispatches events referring to `cid` or any other configured channel.
+    pub fn channel(mut self, cid: ChannelId) -> Self {
+        self.channels.push(cid);
+        self
+    }
+
+    /// Only dispatches events referring to `clid` or any other configured client.
+    pub fn client(mut self, clid: ClientId) -> Self {
+        self.clients.push(clid);
+        self
+    }
+
+    /// Only dispatches events for which `f` returns `true`. Replaces any previously set
+    /// predicate.
+    pub fn predicate<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Event) -> bool + Send + Sync + 'static,
+    {
+        self.predicate = Some(Box::new(f));
+        self
+    }
+
+    /// Returns `true` if `event` should be dispatched.
+    pub fn matches(&self, event: &Event) -> bool {
+        if !self.channels.is_empty()
+            && !event_channels(event)
+                .iter()
+                .flatten()
+                .any(|cid| self.channels.contains(cid))
+        {
+            return false;
+        }
+
+        if !self.clients.is_empty()
+            && !event_clients(event)
+                .iter()
+                .flatten()
+                .any(|clid| self.clients.contains(clid))
+        {
+            return false;
+        }
+
+        match &self.predicate {
+            Some(f) => f(event),
+            None => true,
+        }
+    }
+}
+
+impl Debug for EventFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventFilter")
+            .field("channels", &self.channels)
+            .field("clients", &self.clients)
+            .field("predicate", &self.predicate.is_some())
+            .finish()
+    }
+}
+
+/// Returns the channels `event` refers to.
+fn event_channels(event: &Event) -> [Option<ChannelId>; 2] {
+    match event {
+        Event::ClientEnterView(event) => [Some(event.cfid), Some(event.ctid)],
+        Event::ClientLeftView(event) => [Some(event.cfid), Some(event.ctid)],
+        Event::ChannelDescriptionChanged(event) => [Some(event.cid), None],
+        Event::ChannelPasswordChanged(event) => [Some(event.cid), None],
+        Event::ChannelMoved(event) => [Some(event.cid), Some(event.cpid)],
+        Event::ChannelEdited(event) => [Some(event.cid), None],
+        Event::ChannelCreated(event) => [Some(event.cid), Some(event.cpid)],
+        Event::ChannelDeleted(event) => [Some(event.cid), None],
+        Event::ClientMoved(event) => [Some(event.ctid), None],
+        Event::ChannelBecameEmpty(event) => [Some(event.cid), None],
+        Event::ChannelBecameOccupied(event) => [Some(event.cid), None],
+        Event::ServerEdited(_)
+        | Event::TextMessage(_)
+        | Event::TokenUsed(_)
+        | Event::ClientReconnected(_)
+        | Event::ClientNicknameChanged(_) => [None, None],
+    }
+}
+
+/// Returns the clients `event` refers to, either as the subject or the invoker.
+fn event_clients(event: &Event) -> [Option<ClientId>; 2] {
+    let clid = match event {
+        Event::ClientEnterView(event) => Some(event.clid),
+        Event::ClientLeftView(event) => Some(event.clid),
+        Event::ClientMoved(event) => Some(event.clid),
+        Event::TokenUsed(event) => Some(event.clid),
+        Event::ChannelBecameOccupied(event) => Some(event.clid),
+        Event::ClientNicknameChanged(event) => Some(event.clid),
+        Event::ClientReconnected(event) => {
+            return [Some(event.old_clid), Some(event.new_clid)];
+        }
+        _ => None,
+    };
+
+    [clid, event.invoker().map(|invoker| invoker.invokerid)]
+}
+
+/// Implements [`EventMeta`] for events with the `invoker` field (`invoker`) and the
+/// `reasonid` field (`reason`).
+macro_rules! impl_event_meta {
+    ($($event:ty { $($meta:ident),* })*) => {
+        $(
+            impl EventMeta for $event {
+                $(impl_event_meta!(@$meta);)*
+            }
+        )*
+    };
+    (@invoker) => {
+        fn invoker(&self) -> Option<Invoker> {
+            // Events which were not caused by a client do not include the invoker fields.
+            if self.invoker.invokername.is_empty() {
+                return None;
+            }
+
+            Some(self.invoker.clone())
+        }
+    };
+    (@reason) => {
+        fn reason(&self) -> Option<ReasonId> {
+            Some(self.reasonid)
+        }
+    };
+}
+
+impl_event_meta! {
+    ClientEnterView { reason }
+    ClientLeftView { invoker, reason }
+    ServerEdited { invoker, reason }
+    ChannelDescriptionChanged { invoker }
+    ChannelPasswordChanged { invoker }
+    ChannelMoved { invoker, reason }
+    ChannelEdited { invoker, reason }
+    ChannelCreated { invoker }
+    ChannelDeleted { invoker }
+    ClientMoved { invoker, reason }
+    TextMessage { invoker }
+    TokenUsed {}
+    ChannelBecameEmpty {}
+    ChannelBecameOccupied {}
+    ClientReconnected {}
+    ClientNicknameChanged {}
+}
+
+/// The client that caused an event. The server is the invoker of events it caused itself,
+/// e.g. deleting a temporary channel, with an `invokerid` of `0`, an `invokername` of
+/// `Server` and an empty `invokeruid`.
+///
+/// Events include the invoker fields inline, which are decoded into this struct by marking
+/// the field with `#[ts3(flatten)]`.
+#[derive(Clone, Debug, Decode, Default, PartialEq, Eq, Hash)]
+pub struct Invoker {
+    pub invokerid: ClientId,
+    pub invokername: String,
+    pub invokeruid: String,
+}
+
 /// Defines a reason why an event happened. Used in multiple event types.
-#[derive(Debug)]
+///
+/// The ids are not contiguous, e.g. a server shutdown is reported with an id of `11`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum ReasonId {
     /// Switched channel themselves or joined server
-    SwitchChannel = 0,
-    // Moved by another client or channel
+    #[default]
+    SwitchChannel,
+    /// Moved by another client or channel
     Moved,
-    // Left server because of timeout (disconnect)
+    /// The client became visible or invisible by subscribing to a channel
+    Subscription,
+    /// Left server because of timeout (disconnect)
     Timeout,
-    // Kicked from channel
+    /// Kicked from channel
     ChannelKick,
-    // Kicked from server
+    /// Kicked from server
     ServerKick,
-    // Banned from server
+    /// Banned from server
     Ban,
-    // Left server themselves
+    /// The virtual server was stopped
+    ServerStop,
+    /// Left server themselves
     ServerLeave,
-    // Edited channel or server
+    /// The channel was updated
+    ChannelUpdate,
+    /// Edited channel or server
     Edited,
-    // Left server due shutdown
+    /// Left server due shutdown
     ServerShutdown,
+    /// A reason id not known to this version of the library.
+    Unknown(u8),
+}
+
+impl ReasonId {
+    /// Returns the raw reason id sent by the server.
+    pub fn value(self) -> u8 {
+        match self {
+            Self::SwitchChannel => 0,
+            Self::Moved => 1,
+            Self::Subscription => 2,
+            Self::Timeout => 3,
+            Self::ChannelKick => 4,
+            Self::ServerKick => 5,
+            Self::Ban => 6,
+            Self::ServerStop => 7,
+            Self::ServerLeave => 8,
+            Self::ChannelUpdate => 9,
+            Self::Edited => 10,
+            Self::ServerShutdown => 11,
+            Self::Unknown(id) => id,
+        }
+    }
+}
+
+impl From<u8> for ReasonId {
+    fn from(id: u8) -> Self {
+        match id {
+            0 => Self::SwitchChannel,
+            1 => Self::Moved,
+            2 => Self::Subscription,
+            3 => Self::Timeout,
+            4 => Self::ChannelKick,
+            5 => Self::ServerKick,
+            6 => Self::Ban,
+            7 => Self::ServerStop,
+            8 => Self::ServerLeave,
+            9 => Self::ChannelUpdate,
+            10 => Self::Edited,
+            11 => Self::ServerShutdown,
+            id => Self::Unknown(id),
+        }
+    }
 }
 
 impl Decode for ReasonId {
     type Error = Error;
 
     fn decode(buf: &[u8]) -> Result<ReasonId, Self::Error> {
-        match u8::decode(buf)? {
-            0 => Ok(Self::SwitchChannel),
-            1 => Ok(Self::Moved),
-            2 => Ok(Self::Timeout),
-            3 => Ok(Self::ChannelKick),
-            4 => Ok(Self::ServerKick),
-            5 => Ok(Self::Ban),
-            6 => Ok(Self::ServerLeave),
-            7 => Ok(Self::Edited),
-            8 => Ok(Self::ServerShutdown),
-            b => Err(Error(ErrorKind::Decode(DecodeError::InvalidReasonId(b)))),
+        match u8::decode(buf) {
+            Ok(id) => Ok(Self::from(id)),
+            // Other servers send reason ids outside of the range of the TeamSpeak 3 server.
+            Err(_) if crate::skip_invalid_fields() => Ok(Self::Unknown(u8::MAX)),
+            Err(err) => Err(err),
         }
     }
 }
 
-impl Default for ReasonId {
-    fn default() -> ReasonId {
-        ReasonId::SwitchChannel
+impl Encode for ReasonId {
+    fn encode(&self, buf: &mut String) {
+        self.value().encode(buf)
     }
 }
 
 /// Data for a `cliententerview` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ClientEnterView {
     pub cfid: ChannelId,
     pub ctid: ChannelId,
@@ -269,33 +955,44 @@ pub struct ClientEnterView {
     pub client_is_talker: bool,
     pub client_nickname_phoentic: String,
     pub client_needed_serverquey_view_power: u64,
-    pub client_icon_id: u64,
+    pub client_icon_id: IconId,
     pub client_country: String,
     pub client_channel_group_inherited_channel_id: u64,
     pub client_badges: String,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
+}
+
+impl ClientEnterView {
+    /// Returns the country of the client.
+    pub fn country(&self) -> Option<CountryCode> {
+        CountryCode::new(&self.client_country)
+    }
 }
 
 /// Data for a `clientleftview` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ClientLeftView {
     pub cfid: ChannelId,
     pub ctid: ChannelId,
     pub reasonid: ReasonId,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
     pub reasonmsg: String,
     pub bantime: u64,
     pub clid: ClientId,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `serveredited` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ServerEdited {
     pub reasonid: ReasonId,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
     pub virtualserver_name: String,
     pub virtualserver_codec_encryption_mode: String,
     pub virtualserver_default_server_group: ServerGroupId,
@@ -308,46 +1005,62 @@ pub struct ServerEdited {
     pub virtualserver_hostbutton_url: String,
     pub virtualserver_hostbutton_gfx_url: String,
     pub virtualserver_name_phoentic: String,
-    pub virtualserver_icon_id: u64,
+    pub virtualserver_icon_id: IconId,
     pub virtualserver_hostbanner_mode: String,
     pub virtualserver_channel_temp_delete_delay_default: u64,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `channeldescriptionchanged` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ChannelDescriptionChanged {
     pub cid: ChannelId,
+    /// Empty if the server did not include the invoker.
+    #[ts3(flatten)]
+    pub invoker: Invoker,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `channelpasswordchanged` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ChannelPasswordChanged {
     pub cid: ChannelId,
+    /// Empty if the server did not include the invoker.
+    #[ts3(flatten)]
+    pub invoker: Invoker,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `channelmoved` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ChannelMoved {
     pub cid: ChannelId,
     pub cpid: ChannelId,
     pub order: u64,
     pub reasonid: ReasonId,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
-/// Data for a `channeledited` event. The fields `cid`, `reasonid`,
-/// `invokerid`, `invokername` and `invokeruid` are always included.
+/// Data for a `channeledited` event. The fields `cid`, `reasonid` and
+/// `invoker` are always included.
 /// All fields prefixed channel_... are only included if the value of
 /// the channel was changed.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ChannelEdited {
     pub cid: ChannelId,
     pub reasonid: ReasonId,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
     pub channel_name: String,
     pub channel_topic: String,
     // 4 for Opus Voice, 5 for Opus Music
@@ -368,11 +1081,14 @@ pub struct ChannelEdited {
     pub channel_flag_maxfamilyclients_inherited: bool,
     pub channel_needed_talk_power: u32,
     pub channel_name_phonetic: String,
-    pub channel_icon_id: u64,
+    pub channel_icon_id: IconId,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `channelcreated` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ChannelCreated {
     pub cid: ChannelId,
     pub cpid: ChannelId,
@@ -396,48 +1112,59 @@ pub struct ChannelCreated {
     pub channel_flag_maxfamilyclients_inherited: bool,
     pub channel_needed_talk_power: u32,
     pub channel_name_phonetic: String,
-    pub channel_icon_id: u64,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
+    pub channel_icon_id: IconId,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `channeldeleted` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ChannelDeleted {
-    /// 0 if deleted by the server after exceeding the channel_delete_delay.
-    pub invokerid: ClientId,
-    /// "Server" if deleted by the server after exceeding the channel_delete_delay.
-    pub invokername: String,
-    /// Empty if deleted by the server after exceeding the channel_delete_delay.
-    pub invokeruid: String,
+    /// The server if deleted after exceeding the channel_delete_delay.
+    #[ts3(flatten)]
+    pub invoker: Invoker,
     pub cid: ChannelId,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `clientmoved` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct ClientMoved {
     pub ctid: ChannelId,
     pub reasonid: ReasonId,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
-    pub clid: ChannelId,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
+    /// The client that was moved.
+    ///
+    /// This field was a [`ChannelId`] in earlier releases, which was a mistake. Code
+    /// comparing it with channel ids must compare it with the [`ClientId`] of the client
+    /// instead.
+    pub clid: ClientId,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `textmessage` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct TextMessage {
     pub targetmode: u64,
     pub msg: String,
     pub target: ClientId,
-    pub invokerid: ClientId,
-    pub invokername: String,
-    pub invokeruid: String,
+    #[ts3(flatten)]
+    pub invoker: Invoker,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
 }
 
 /// Data for a `tokenused` event.
-#[derive(Debug, Decode, Default)]
+#[derive(Clone, Debug, Decode, Default)]
 pub struct TokenUsed {
     pub clid: ClientId,
     pub cldbid: ClientDatabaseId,
@@ -448,6 +1175,242 @@ pub struct TokenUsed {
     pub token1: u64,
     /// ChannelID for the token, 0 if Server Group.
     pub token2: u64,
+    /// All fields of the event, including fields not included in this struct.
+    #[ts3(raw)]
+    pub raw: Entry,
+}
+
+/// Data for a `ChannelBecameEmpty` event, synthesized when the last client left the
+/// channel `cid`. ServerQuery clients are not counted.
+#[derive(Clone, Debug, Default)]
+pub struct ChannelBecameEmpty {
+    pub cid: ChannelId,
+    /// Always empty, the event was not sent by the server.
+    pub raw: Entry,
+}
+
+/// Data for a `ChannelBecameOccupied` event, synthesized when the client `clid` entered the
+/// empty channel `cid`. ServerQuery clients are not counted.
+#[derive(Clone, Debug, Default)]
+pub struct ChannelBecameOccupied {
+    pub cid: ChannelId,
+    pub clid: ClientId,
+    /// Always empty, the event was not sent by the server.
+    pub raw: Entry,
+}
+
+/// Data for a `ClientReconnected` event, synthesized when the client with the unique
+/// identifier `cluid` entered the server again shortly after it left. See
+/// [`ClientBuilder::reconnect_window`].
+///
+/// [`ClientBuilder::reconnect_window`]: crate::ClientBuilder::reconnect_window
+#[derive(Clone, Debug, Default)]
+pub struct ClientReconnected {
+    /// The `clid` the client had before it left.
+    pub old_clid: ClientId,
+    /// The `clid` the client has now.
+    pub new_clid: ClientId,
+    pub cluid: String,
+    /// Always empty, the event was not sent by the server.
+    pub raw: Entry,
+}
+
+/// Data for a `ClientNicknameChanged` event, synthesized when the client `clid` changed its
+/// nickname from `old` to `new`. See [`Client::track_nicknames`].
+#[derive(Clone, Debug, Default)]
+pub struct ClientNicknameChanged {
+    pub clid: ClientId,
+    pub old: String,
+    pub new: String,
+    /// Always empty, the event was not sent by the server.
+    pub raw: Entry,
+}
+
+/// The channel of every regular client, used to synthesize the [`ChannelBecameEmpty`] and
+/// [`ChannelBecameOccupied`] events. See [`Client::track_occupancy`].
+#[derive(Debug, Default)]
+pub(crate) struct Occupancy {
+    channels: HashMap<ClientId, ChannelId>,
+    clients: HashMap<ChannelId, usize>,
+}
+
+impl Occupancy {
+    /// Creates a new `Occupancy` from the channels of all regular clients.
+    pub(crate) fn new<I>(clients: I) -> Self
+    where
+        I: IntoIterator<Item = (ClientId, ChannelId)>,
+    {
+        let mut occupancy = Self::default();
+        for (clid, cid) in clients {
+            occupancy.enter(clid, cid);
+        }
+
+        occupancy
+    }
+
+    /// Applies `event` and returns the events synthesized from it.
+    pub(crate) fn update(&mut self, event: &Event) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        match event {
+            // ServerQuery clients are never counted.
+            Event::ClientEnterView(event) if event.client_type != 1 => {
+                events.extend(self.enter(event.clid, event.ctid));
+            }
+            Event::ClientLeftView(event) => {
+                events.extend(self.leave(event.clid));
+            }
+            // Moves of clients which are not tracked are ServerQuery clients.
+            Event::ClientMoved(event) if self.channels.contains_key(&event.clid) => {
+                events.extend(self.leave(event.clid));
+                events.extend(self.enter(event.clid, event.ctid));
+            }
+            Event::ChannelDeleted(event) => {
+                self.clients.remove(&event.cid);
+            }
+            _ => (),
+        }
+
+        events
+    }
+
+    fn enter(&mut self, clid: ClientId, cid: ChannelId) -> Option<Event> {
+        self.channels.insert(clid, cid);
+
+        let count = self.clients.entry(cid).or_default();
+        *count += 1;
+
+        (*count == 1).then(|| {
+            Event::ChannelBecameOccupied(ChannelBecameOccupied {
+                cid,
+                clid,
+                raw: Entry::default(),
+            })
+        })
+    }
+
+    fn leave(&mut self, clid: ClientId) -> Option<Event> {
+        let cid = self.channels.remove(&clid)?;
+
+        let count = self.clients.get_mut(&cid)?;
+        *count -= 1;
+        if *count > 0 {
+            return None;
+        }
+
+        self.clients.remove(&cid);
+        Some(Event::ChannelBecameEmpty(ChannelBecameEmpty {
+            cid,
+            raw: Entry::default(),
+        }))
+    }
+}
+
+/// The unique identifiers of connected and recently left clients, used to synthesize the
+/// [`ClientReconnected`] event. See [`ClientBuilder::reconnect_window`].
+///
+/// [`ClientBuilder::reconnect_window`]: crate::ClientBuilder::reconnect_window
+#[derive(Debug)]
+pub(crate) struct Reconnects {
+    window: Duration,
+    connected: HashMap<ClientId, String>,
+    /// The previous `clid` and the time it left, keyed by the unique identifier.
+    left: HashMap<String, (ClientId, Instant)>,
+}
+
+impl Reconnects {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            connected: HashMap::new(),
+            left: HashMap::new(),
+        }
+    }
+
+    /// Applies `event` received at `now` and returns the event synthesized from it.
+    pub(crate) fn update(&mut self, event: &Event, now: Instant) -> Option<Event> {
+        let window = self.window;
+        self.left
+            .retain(|_, (_, time)| now.saturating_duration_since(*time) <= window);
+
+        match event {
+            Event::ClientEnterView(event) => {
+                let uid = event.client_unique_identifier.clone();
+                self.connected.insert(event.clid, uid.clone());
+
+                let (old_clid, _) = self.left.remove(&uid)?;
+                Some(Event::ClientReconnected(ClientReconnected {
+                    old_clid,
+                    new_clid: event.clid,
+                    cluid: uid,
+                    raw: Entry::default(),
+                }))
+            }
+            Event::ClientLeftView(event) => {
+                let uid = self.connected.remove(&event.clid)?;
+                self.left.insert(uid, (event.clid, now));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Forgets all clients. Client ids are only unique on a single virtual server.
+    pub(crate) fn clear(&mut self) {
+        self.connected.clear();
+        self.left.clear();
+    }
+}
+
+/// The nickname of every client, used to synthesize the [`ClientNicknameChanged`] event. See
+/// [`Client::track_nicknames`].
+#[derive(Debug, Default)]
+pub(crate) struct Nicknames {
+    nicknames: HashMap<ClientId, String>,
+}
+
+impl Nicknames {
+    /// Applies `event`. The server sends no event when a nickname changes, the nicknames of
+    /// clients entering and leaving are kept to not report them on the next [`refresh`].
+    ///
+    /// [`refresh`]: Self::refresh
+    pub(crate) fn update(&mut self, event: &Event) {
+        match event {
+            Event::ClientEnterView(event) => {
+                self.nicknames
+                    .insert(event.clid, event.client_nickname.clone());
+            }
+            Event::ClientLeftView(event) => {
+                self.nicknames.remove(&event.clid);
+            }
+            _ => (),
+        }
+    }
+
+    /// Replaces all nicknames with `clients` and returns an event for every client whose
+    /// nickname differs from the known one.
+    pub(crate) fn refresh<I>(&mut self, clients: I) -> Vec<Event>
+    where
+        I: IntoIterator<Item = (ClientId, String)>,
+    {
+        let mut events = Vec::new();
+        let old = std::mem::take(&mut self.nicknames);
+
+        for (clid, new) in clients {
+            if let Some(old) = old.get(&clid).filter(|old| **old != new) {
+                events.push(Event::ClientNicknameChanged(ClientNicknameChanged {
+                    clid,
+                    old: old.clone(),
+                    new: new.clone(),
+                    raw: Entry::default(),
+                }));
+            }
+
+            self.nicknames.insert(clid, new);
+        }
+
+        events
+    }
 }
 
 // Empty default impl for EventHandler
@@ -455,3 +1418,294 @@ pub struct TokenUsed {
 pub(crate) struct Handler;
 
 impl EventHandler for Handler {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{
+        Event, EventFilter, EventMeta, Invoker, Nicknames, Occupancy, ReasonId, Reconnects,
+        ReplayBuffer,
+    };
+    use crate::shared::{ChannelId, ClientId};
+    use crate::{Decode, Encode, TolerantGuard};
+
+    #[test]
+    fn test_event_meta() {
+        let event = Event::decode(
+            b"notifyclientmoved",
+            b"ctid=2 reasonid=1 invokerid=3 invokername=Admin invokeruid=abc= clid=4",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(event.reason(), Some(ReasonId::Moved));
+        assert_eq!(
+            event.invoker(),
+            Some(Invoker {
+                invokerid: ClientId(3),
+                invokername: "Admin".to_owned(),
+                invokeruid: "abc=".to_owned(),
+            })
+        );
+
+        let event = Event::decode(b"notifyclientmoved", b"ctid=2 reasonid=0 clid=4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.reason(), Some(ReasonId::SwitchChannel));
+        assert_eq!(event.invoker(), None);
+
+        let event = Event::decode(b"notifyclientmoved", b"ctid=2 reasonid=42 clid=4")
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.reason(), Some(ReasonId::Unknown(42)));
+        assert_eq!(event.reason().unwrap().value(), 42);
+    }
+
+    #[test]
+    fn test_reason_id() {
+        for id in 0..=12 {
+            let reason = ReasonId::decode(id.to_string().as_bytes()).unwrap();
+            assert_eq!(reason.value(), id);
+
+            let mut buf = String::new();
+            reason.encode(&mut buf);
+            assert_eq!(buf, id.to_string());
+        }
+
+        assert_eq!(ReasonId::from(11), ReasonId::ServerShutdown);
+        assert_eq!(ReasonId::from(12), ReasonId::Unknown(12));
+
+        // Reason ids outside of the range are only accepted from other servers.
+        assert!(ReasonId::decode(b"300").is_err());
+        {
+            let _tolerant = TolerantGuard::set(true);
+            assert_eq!(ReasonId::decode(b"300").unwrap(), ReasonId::Unknown(255));
+
+            // Other fields are not skipped.
+            assert!(
+                Event::decode(b"notifyclientmoved", b"ctid=2 reasonid=1 clid=x")
+                    .unwrap()
+                    .is_err()
+            );
+        }
+
+        let event = Event::decode(
+            b"notifytextmessage",
+            b"targetmode=1 msg=Hi target=2 invokerid=3 invokername=Admin invokeruid=abc=",
+        )
+        .unwrap()
+        .unwrap();
+        match event {
+            Event::TextMessage(event) => {
+                assert_eq!(event.raw.get::<String>("msg").unwrap(), "Hi");
+                assert_eq!(event.msg, "Hi");
+                assert_eq!(event.invoker.invokerid, ClientId(3));
+                assert_eq!(event.invoker.invokeruid, "abc=");
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        let event = Event::decode(b"notifychannelpasswordchanged", b"cid=1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.reason(), None);
+        assert_eq!(event.invoker(), None);
+
+        let event = Event::decode(
+            b"notifychanneldescriptionchanged",
+            b"cid=1 invokerid=3 invokername=Admin invokeruid=abc=",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(event.invoker().unwrap().invokerid, ClientId(3));
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let moved = Event::decode(
+            b"notifyclientmoved",
+            b"ctid=2 reasonid=1 invokerid=3 invokername=Admin invokeruid=abc= clid=4",
+        )
+        .unwrap()
+        .unwrap();
+        let edited = Event::decode(b"notifyserveredited", b"reasonid=10 invokerid=5")
+            .unwrap()
+            .unwrap();
+
+        let filter = EventFilter::new();
+        assert!(filter.matches(&moved));
+        assert!(filter.matches(&edited));
+
+        let filter = EventFilter::new()
+            .channel(ChannelId(1))
+            .channel(ChannelId(2));
+        assert!(filter.matches(&moved));
+        assert!(!filter.matches(&edited));
+
+        let filter = EventFilter::new().channel(ChannelId(1));
+        assert!(!filter.matches(&moved));
+
+        let filter = EventFilter::new().client(ClientId(4));
+        assert!(filter.matches(&moved));
+        let filter = EventFilter::new().client(ClientId(3));
+        assert!(filter.matches(&moved));
+        let filter = EventFilter::new().client(ClientId(5));
+        assert!(!filter.matches(&moved));
+
+        let filter = EventFilter::new()
+            .channel(ChannelId(2))
+            .predicate(|event| matches!(event, Event::ServerEdited(_)));
+        assert!(!filter.matches(&moved));
+    }
+
+    #[test]
+    fn test_replay_buffer() {
+        let event = |cid: &str| {
+            Event::decode(b"notifychanneldeleted", format!("cid={}", cid).as_bytes())
+                .unwrap()
+                .unwrap()
+        };
+
+        let mut buffer = ReplayBuffer::new(0);
+        buffer.push(&event("1"));
+        assert!(buffer.take().is_empty());
+
+        let mut buffer = ReplayBuffer::new(2);
+        buffer.push(&event("1"));
+        buffer.push(&event("2"));
+        buffer.push(&event("3"));
+
+        let cids: Vec<_> = buffer
+            .take()
+            .into_iter()
+            .map(|event| match event {
+                Event::ChannelDeleted(event) => event.cid,
+                event => panic!("unexpected event: {:?}", event),
+            })
+            .collect();
+        assert_eq!(cids, [ChannelId(2), ChannelId(3)]);
+        assert!(buffer.take().is_empty());
+    }
+
+    #[test]
+    fn test_occupancy() {
+        let event = |name: &str, buf: &str| {
+            Event::decode(name.as_bytes(), buf.as_bytes())
+                .unwrap()
+                .unwrap()
+        };
+        let mut occupancy = Occupancy::new([(ClientId(1), ChannelId(1))]);
+
+        // ServerQuery clients are not counted.
+        let events = occupancy.update(&event(
+            "notifycliententerview",
+            "ctid=2 clid=5 client_type=1",
+        ));
+        assert!(events.is_empty());
+        assert!(occupancy
+            .update(&event("notifyclientmoved", "ctid=3 clid=5"))
+            .is_empty());
+
+        let events = occupancy.update(&event(
+            "notifycliententerview",
+            "ctid=1 clid=2 client_type=0",
+        ));
+        assert!(events.is_empty());
+
+        let events = occupancy.update(&event("notifyclientmoved", "ctid=2 clid=1"));
+        assert!(matches!(
+            &events[..],
+            [Event::ChannelBecameOccupied(event)]
+                if event.cid == ChannelId(2) && event.clid == ClientId(1)
+        ));
+
+        let events = occupancy.update(&event("notifyclientleftview", "cfid=1 clid=2"));
+        assert!(matches!(
+            &events[..],
+            [Event::ChannelBecameEmpty(event)] if event.cid == ChannelId(1)
+        ));
+
+        let events = occupancy.update(&event("notifyclientmoved", "ctid=1 clid=1"));
+        let cids: Vec<_> = events
+            .iter()
+            .map(|event| match event {
+                Event::ChannelBecameEmpty(event) => (event.cid, false),
+                Event::ChannelBecameOccupied(event) => (event.cid, true),
+                event => panic!("unexpected event: {:?}", event),
+            })
+            .collect();
+        assert_eq!(cids, [(ChannelId(2), false), (ChannelId(1), true)]);
+    }
+
+    #[test]
+    fn test_reconnects() {
+        let enter = |clid: u64| {
+            let buf = format!("ctid=1 clid={} client_unique_identifier=abc=", clid);
+            Event::decode(b"notifycliententerview", buf.as_bytes())
+                .unwrap()
+                .unwrap()
+        };
+        let left = |clid: u64| {
+            let buf = format!("cfid=1 clid={}", clid);
+            Event::decode(b"notifyclientleftview", buf.as_bytes())
+                .unwrap()
+                .unwrap()
+        };
+
+        let now = Instant::now();
+        let mut reconnects = Reconnects::new(Duration::from_secs(10));
+
+        // Clients that left without a known unique identifier are not correlated.
+        assert!(reconnects.update(&left(1), now).is_none());
+        assert!(reconnects.update(&enter(2), now).is_none());
+
+        assert!(reconnects.update(&left(2), now).is_none());
+        match reconnects.update(&enter(3), now + Duration::from_secs(5)) {
+            Some(Event::ClientReconnected(event)) => {
+                assert_eq!(event.old_clid, ClientId(2));
+                assert_eq!(event.new_clid, ClientId(3));
+                assert_eq!(event.cluid, "abc=");
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        // The client reconnected after the window elapsed.
+        assert!(reconnects.update(&left(3), now).is_none());
+        assert!(reconnects
+            .update(&enter(4), now + Duration::from_secs(11))
+            .is_none());
+
+        // Clients of another virtual server are not correlated.
+        assert!(reconnects.update(&left(4), now).is_none());
+        reconnects.clear();
+        assert!(reconnects.update(&enter(5), now).is_none());
+    }
+
+    #[test]
+    fn test_nicknames() {
+        let clients = |nicknames: &[(u64, &str)]| {
+            nicknames
+                .iter()
+                .map(|(clid, nickname)| (ClientId(*clid), nickname.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let mut nicknames = Nicknames::default();
+        assert!(nicknames.refresh(clients(&[(1, "Alice")])).is_empty());
+
+        // The nickname of a client that entered is known before the next refresh.
+        let event = Event::decode(b"notifycliententerview", b"clid=2 client_nickname=Bob")
+            .unwrap()
+            .unwrap();
+        nicknames.update(&event);
+
+        let events = nicknames.refresh(clients(&[(1, "Alice"), (2, "Bobby"), (3, "Carol")]));
+        match &events[..] {
+            [Event::ClientNicknameChanged(event)] => {
+                assert_eq!(event.clid, ClientId(2));
+                assert_eq!((event.old.as_str(), event.new.as_str()), ("Bob", "Bobby"));
+            }
+            events => panic!("unexpected events: {:?}", events),
+        }
+    }
+}
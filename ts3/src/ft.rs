@@ -0,0 +1,501 @@
+//! File transfers over the file transfer port of the server.
+//!
+//! A transfer is initialized on the query connection, the file itself is sent over a
+//! separate TCP connection to the file transfer port (`30033` by default). [`FileTransfer`]
+//! handles both steps and limits the number of parallel transfers and their bandwidth.
+//!
+//! The file transfer connection is opened using the same proxy, connect timeout and TLS
+//! configuration as the connection of the [`Client`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ts3::ft::FileTransfer;
+//! use ts3::shared::ChannelId;
+//! use ts3::Client;
+//!
+//! # async fn run(client: Client) -> Result<(), ts3::Error> {
+//! let transfer = FileTransfer::new(client, "localhost")
+//!     .max_parallel(2)
+//!     .bandwidth(64 * 1024);
+//!
+//! let mut buf = Vec::new();
+//! let outcome = transfer.download(ChannelId(1), None, "/icon_1234", &mut buf).await?;
+//! assert!(outcome.is_complete());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio::time::{sleep_until, Instant};
+
+use crc32fast::Hasher;
+
+use crate::client::{Result, Stream};
+use crate::response::{FileTransferInit, ServerInfo};
+use crate::shared::{ChannelId, IconId, ServerGroupId};
+use crate::{Client, Error, ErrorKind};
+
+/// The size of the buffer used to copy file contents.
+const CHUNK_SIZE: usize = 8192;
+
+/// The smallest id of an uploaded icon. Lower ids are built into the TeamSpeak client.
+const MIN_CUSTOM_ICON_ID: u32 = 1000;
+
+/// Downloads and uploads files using a [`Client`].
+///
+/// Clones share the limit of parallel transfers.
+#[derive(Clone)]
+pub struct FileTransfer {
+    client: Client,
+    host: String,
+    max_parallel: usize,
+    permits: Arc<Semaphore>,
+    download_bandwidth: Option<u64>,
+    upload_bandwidth: Option<u64>,
+    /// The total bandwidth of the server in both directions, set by `server_limits`.
+    server_bandwidth: (Option<u64>, Option<u64>),
+    next_id: Arc<AtomicU16>,
+}
+
+impl FileTransfer {
+    /// Creates a new `FileTransfer` initializing transfers using `client`. `host` is the
+    /// address of the server, the file transfer connection is opened to the port returned by
+    /// the server.
+    ///
+    /// By default at most `4` transfers run in parallel and the bandwidth is not limited.
+    pub fn new(client: Client, host: &str) -> Self {
+        Self {
+            client,
+            host: host.to_owned(),
+            max_parallel: 4,
+            permits: Arc::new(Semaphore::new(4)),
+            download_bandwidth: None,
+            upload_bandwidth: None,
+            server_bandwidth: (None, None),
+            next_id: Arc::default(),
+        }
+    }
+
+    /// Sets the maximum number of transfers running in parallel. Further transfers wait until
+    /// a running transfer completes. Defaults to `4`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is `0`.
+    pub fn max_parallel(mut self, limit: usize) -> Self {
+        assert!(limit != 0, "max_parallel must be greater than 0");
+
+        self.max_parallel = limit;
+        self.permits = Arc::new(Semaphore::new(limit));
+        self
+    }
+
+    /// Limits the bandwidth of every single transfer to `bytes_per_second`, in both
+    /// directions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_second` is `0`.
+    pub fn bandwidth(mut self, bytes_per_second: u64) -> Self {
+        assert!(bytes_per_second != 0, "bandwidth must be greater than 0");
+
+        self.download_bandwidth = Some(bytes_per_second);
+        self.upload_bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    /// Limits the bandwidth of every single transfer so that [`max_parallel`] transfers
+    /// together stay within the file transfer bandwidth configured on the server, as returned
+    /// from [`Client::serverinfo`]. Unlimited server directions are left unchanged.
+    ///
+    /// If a [`bandwidth`] is set as well, the lower limit is used. The order in which the
+    /// limits are set does not matter.
+    ///
+    /// [`max_parallel`]: Self::max_parallel
+    /// [`bandwidth`]: Self::bandwidth
+    pub fn server_limits(mut self, info: &ServerInfo) -> Self {
+        let total = |total: u64| match total {
+            u64::MAX | 0 => None,
+            total => Some(total),
+        };
+
+        self.server_bandwidth = (
+            total(info.virtualserver_max_download_total_bandwidth),
+            total(info.virtualserver_max_upload_total_bandwidth),
+        );
+        self
+    }
+
+    /// Returns the bandwidth limit of a single download.
+    fn download_limit(&self) -> Option<u64> {
+        limit(
+            self.download_bandwidth,
+            self.server_bandwidth.0,
+            self.max_parallel,
+        )
+    }
+
+    /// Returns the bandwidth limit of a single upload.
+    fn upload_limit(&self) -> Option<u64> {
+        limit(
+            self.upload_bandwidth,
+            self.server_bandwidth.1,
+            self.max_parallel,
+        )
+    }
+
+    /// Downloads the file `name` from the channel `cid` into `writer`. `cpw` is the password
+    /// of the channel, if it has one.
+    pub async fn download<W>(
+        &self,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        name: &str,
+        writer: &mut W,
+    ) -> Result<TransferOutcome>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        self.download_with(cid, cpw, name, writer, DownloadOptions::new())
+            .await
+    }
+
+    /// Downloads the file `name` from the channel `cid` into `writer` using `options`. `cpw`
+    /// is the password of the channel, if it has one.
+    ///
+    /// # Examples
+    ///
+    /// Continue an incomplete download:
+    ///
+    /// ```no_run
+    /// use ts3::ft::{DownloadOptions, FileTransfer, TransferOutcome};
+    /// use ts3::shared::ChannelId;
+    ///
+    /// # async fn run(transfer: FileTransfer, mut buf: Vec<u8>) -> Result<(), ts3::Error> {
+    /// // `buf` contains the bytes received by the previous download.
+    /// let options = DownloadOptions::new().offset(buf.len() as u64);
+    /// match transfer.download_with(ChannelId(1), None, "/backup.zip", &mut buf, options).await? {
+    ///     TransferOutcome::Complete { .. } => println!("done"),
+    ///     outcome => println!("failed: {:?}", outcome),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_with<W>(
+        &self,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        name: &str,
+        writer: &mut W,
+        options: DownloadOptions,
+    ) -> Result<TransferOutcome>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let _permit = self.permits.acquire().await.unwrap();
+
+        let init = self
+            .client
+            .ftinitdownload(self.next_id(), name, cid, cpw, options.offset)
+            .await?;
+        let stream = self.connect(&init).await?;
+
+        // The server reports the size of the whole file, not only the remaining part.
+        let expected = init.size.saturating_sub(options.offset);
+
+        let mut crc = Hasher::new();
+        let received = copy(
+            &mut stream.take(expected),
+            writer,
+            self.download_limit(),
+            |buf| crc.update(buf),
+        )
+        .await
+        .map_err(|err| Error(err.into()))?;
+
+        if received != expected {
+            return Ok(TransferOutcome::Incomplete { received, expected });
+        }
+
+        let actual = crc.finalize();
+        match options.crc32 {
+            Some(expected) if actual != expected => {
+                Ok(TransferOutcome::ChecksumMismatch { expected, actual })
+            }
+            _ => Ok(TransferOutcome::Complete { bytes: received }),
+        }
+    }
+
+    /// Downloads the icon `icon_id`. Icons are stored in the file `/icon_<id>` of channel `0`.
+    ///
+    /// Returns an error if the download is incomplete.
+    pub async fn icon(&self, icon_id: IconId) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let name = format!("/icon_{}", icon_id);
+
+        match self.download(ChannelId(0), None, &name, &mut buf).await? {
+            TransferOutcome::Complete { .. } => Ok(buf),
+            outcome => Err(Error(ErrorKind::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("icon download failed: {:?}", outcome),
+            )))),
+        }
+    }
+
+    /// Downloads the icons of all server groups on the selected virtual server. Groups without
+    /// an icon or with one of the built-in icons, which have an id below `1000`, are not
+    /// included. Icons shared by multiple groups are only downloaded once.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use ts3::ft::FileTransfer;
+    ///
+    /// # async fn run(transfer: FileTransfer) -> Result<(), ts3::Error> {
+    /// for (sgid, icon) in transfer.servergroup_icons().await? {
+    ///     println!("group {} has a {} byte icon", sgid, icon.len());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn servergroup_icons(&self) -> Result<HashMap<ServerGroupId, Vec<u8>>> {
+        let groups = self.client.servergrouplist().await?;
+
+        let mut icons: HashMap<IconId, Vec<u8>> = HashMap::new();
+        let mut out = HashMap::new();
+        for group in groups
+            .iter()
+            .filter(|group| group.iconid.0 >= MIN_CUSTOM_ICON_ID)
+        {
+            let icon = match icons.get(&group.iconid) {
+                Some(icon) => icon.clone(),
+                None => {
+                    let icon = self.icon(group.iconid).await?;
+                    icons.insert(group.iconid, icon.clone());
+                    icon
+                }
+            };
+
+            out.insert(group.sgid, icon);
+        }
+
+        Ok(out)
+    }
+
+    /// Uploads `size` bytes from `reader` to the file `name` in the channel `cid`. `cpw` is
+    /// the password of the channel, if it has one. An existing file is only replaced if
+    /// `overwrite` is `true`.
+    pub async fn upload<R>(
+        &self,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        name: &str,
+        reader: &mut R,
+        size: u64,
+        overwrite: bool,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let _permit = self.permits.acquire().await.unwrap();
+
+        let init = self
+            .client
+            .ftinitupload(self.next_id(), name, cid, cpw, size, overwrite, false)
+            .await?;
+        let mut stream = self.connect(&init).await?;
+
+        copy(
+            &mut reader.take(size),
+            &mut stream,
+            self.upload_limit(),
+            |_| (),
+        )
+        .await
+        .map_err(|err| Error(err.into()))?;
+        stream.shutdown().await.map_err(|err| Error(err.into()))
+    }
+
+    fn next_id(&self) -> u16 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Opens the file transfer connection for `init` and identifies the transfer.
+    async fn connect(&self, init: &FileTransferInit) -> Result<Box<dyn Stream>> {
+        let transport = self.client.inner.read().unwrap().transport.clone();
+
+        let mut stream = transport.connect((self.host.as_str(), init.port)).await?;
+        stream
+            .write_all(init.ftkey.as_bytes())
+            .await
+            .map_err(|err| Error(ErrorKind::Io(err)))?;
+
+        Ok(stream)
+    }
+}
+
+impl Debug for FileTransfer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileTransfer")
+            .field("host", &self.host)
+            .field("max_parallel", &self.max_parallel)
+            .field("download_bandwidth", &self.download_limit())
+            .field("upload_bandwidth", &self.upload_limit())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Options for [`FileTransfer::download_with`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DownloadOptions {
+    offset: u64,
+    crc32: Option<u32>,
+}
+
+impl DownloadOptions {
+    /// Creates new `DownloadOptions` downloading the whole file without verification.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts the download at byte `offset` of the file, e.g. to continue an incomplete
+    /// download. Defaults to `0`.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Verifies the downloaded bytes against the CRC-32 (IEEE) checksum `expected`. The
+    /// checksum only covers the bytes downloaded by this transfer, starting at the
+    /// [`offset`].
+    ///
+    /// [`offset`]: Self::offset
+    pub fn crc32(mut self, expected: u32) -> Self {
+        self.crc32 = Some(expected);
+        self
+    }
+}
+
+/// The result of a download. Returned from [`FileTransfer::download`] and
+/// [`FileTransfer::download_with`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TransferOutcome {
+    /// The file was downloaded and verified.
+    Complete { bytes: u64 },
+    /// The connection was closed before the whole file was received. The download can be
+    /// continued with [`DownloadOptions::offset`].
+    Incomplete { received: u64, expected: u64 },
+    /// The checksum of the downloaded bytes does not match the expected checksum.
+    ChecksumMismatch { expected: u32, actual: u32 },
+}
+
+impl TransferOutcome {
+    /// Returns `true` if the file was downloaded and verified.
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete { .. })
+    }
+}
+
+/// Returns the bandwidth limit of a single transfer, the lower of the `explicit` limit and
+/// the share of the `server` total of `max_parallel` transfers.
+fn limit(explicit: Option<u64>, server: Option<u64>, max_parallel: usize) -> Option<u64> {
+    let share = server.map(|total| (total / max_parallel as u64).max(1));
+
+    match (explicit, share) {
+        (Some(explicit), Some(share)) => Some(explicit.min(share)),
+        (explicit, share) => explicit.or(share),
+    }
+}
+
+/// Copies all bytes from `reader` to `writer`, sending at most `bandwidth` bytes per second
+/// on average. Every copied chunk is passed to `inspect`. Returns the number of bytes copied.
+async fn copy<R, W, F>(
+    reader: &mut R,
+    writer: &mut W,
+    bandwidth: Option<u64>,
+    mut inspect: F,
+) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    F: FnMut(&[u8]),
+{
+    let chunk_size = match bandwidth {
+        Some(bandwidth) => CHUNK_SIZE.min(bandwidth as usize),
+        None => CHUNK_SIZE,
+    };
+    let mut buf = vec![0; chunk_size];
+
+    let start = Instant::now();
+    let mut total = 0;
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+
+        writer.write_all(&buf[..n]).await?;
+        inspect(&buf[..n]);
+        total += n as u64;
+
+        if let Some(bandwidth) = bandwidth {
+            sleep_until(start + Duration::from_secs_f64(total as f64 / bandwidth as f64)).await;
+        }
+    }
+
+    writer.flush().await?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::{copy, limit};
+
+    #[test]
+    fn test_limit() {
+        assert_eq!(limit(None, None, 4), None);
+        assert_eq!(limit(Some(100), None, 4), Some(100));
+        assert_eq!(limit(None, Some(1000), 4), Some(250));
+        assert_eq!(limit(Some(100), Some(1000), 4), Some(100));
+        assert_eq!(limit(Some(500), Some(1000), 4), Some(250));
+        assert_eq!(limit(None, Some(2), 4), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_copy() {
+        let mut out = Vec::new();
+        let n = copy(&mut &[1, 2, 3][..], &mut out, None, |_| ())
+            .await
+            .unwrap();
+
+        assert_eq!(n, 3);
+        assert_eq!(out, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_copy_bandwidth() {
+        let data = vec![0; 300];
+
+        let start = Instant::now();
+        let mut out = Vec::new();
+        let n = copy(&mut &data[..], &mut out, Some(1000), |_| ())
+            .await
+            .unwrap();
+
+        assert_eq!(n, 300);
+        assert_eq!(out, data);
+        assert!(start.elapsed().as_millis() >= 300);
+    }
+}
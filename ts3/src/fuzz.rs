@@ -0,0 +1,69 @@
+//! Entry points for the fuzz targets in `fuzz/`. Only compiled with `--cfg fuzzing`, which is
+//! set by `cargo fuzz`.
+//!
+//! Every function accepts arbitrary input and must never panic. Run a target from the `ts3`
+//! directory with e.g. `cargo +nightly fuzz run line`.
+
+use crate::event::Event;
+use crate::response::{Entry, Response};
+use crate::shared::list::bytes_split;
+use crate::{Decode, Error};
+
+/// Decodes `buf` as a [`Response`] and as an [`Entry`] and reads all of its fields.
+pub fn entry(buf: &[u8]) {
+    let _ = Response::decode(buf);
+
+    let Ok(entry) = Entry::decode(buf) else {
+        return;
+    };
+
+    for key in buf
+        .split(|b| *b == b' ')
+        .filter_map(|kv| kv.split(|b| *b == b'=').next())
+    {
+        let key = String::from_utf8_lossy(key);
+        let _ = entry.get::<String>(&key);
+        let _ = entry.get::<u64>(&key);
+        let _ = entry.get::<bool>(&key);
+    }
+
+    let _ = format!("{:?}", entry);
+}
+
+/// Decodes `buf` as an escaped string.
+pub fn string(buf: &[u8]) {
+    let _ = String::decode(buf);
+    let _ = crate::unescape_lossy(buf);
+}
+
+/// Checks that splitting a buffer and joining the segments with the pattern restores the
+/// buffer. The first byte of `buf` selects the length of the pattern, between `1` and `3`
+/// bytes. The pattern is taken from the following bytes, the remaining bytes are split.
+pub fn bytes_split_roundtrip(buf: &[u8]) {
+    let Some((len, buf)) = buf.split_first() else {
+        return;
+    };
+
+    let len = usize::from(*len % 4).max(1);
+    if buf.len() < len {
+        return;
+    }
+    let (pat, buf) = buf.split_at(len);
+
+    let segments: Vec<&[u8]> = bytes_split(buf, pat).collect();
+    assert_eq!(segments.join(pat), buf);
+}
+
+/// Decodes `buf` like a line received from the server, either as an event or as an error.
+pub fn line(buf: &[u8]) {
+    if let Some(event) = Event::from_line(buf) {
+        if let Ok(event) = event {
+            let _ = event.raw();
+        }
+        return;
+    }
+
+    if buf.starts_with(b"error") {
+        let _ = Error::decode(buf);
+    }
+}
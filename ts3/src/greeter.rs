@@ -0,0 +1,263 @@
+//! Greeting clients joining the server.
+//!
+//! A [`Greeter`] wraps an [`EventHandler`] and sends a welcome message to every regular
+//! client entering the server. The message is chosen by the server groups of the client and
+//! sent as private text message or as poke. Clients are greeted at most once per cooldown,
+//! so reconnecting repeatedly does not produce a greeting every time. All events are
+//! forwarded to the wrapped handler.
+//!
+//! Templates use the same `%name%` placeholders as [`Broadcast`]:
+//!
+//! | Placeholder      | Value                                   |
+//! | ---------------- | --------------------------------------- |
+//! | `%nickname%`     | The nickname of the client.             |
+//! | `%clid%`         | The id of the client.                   |
+//! | `%uid%`          | The unique identifier of the client.    |
+//! | `%country%`      | The country code of the client, if any. |
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::greeter::{Delivery, Greeter};
+//! use ts3::request::ServerNotifyRegister;
+//! use ts3::shared::ServerGroupId;
+//! use ts3::Client;
+//!
+//! struct Handler;
+//!
+//! impl ts3::event::EventHandler for Handler {}
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let greeter = Greeter::new(Handler)
+//!         .template("Welcome %nickname%!")
+//!         .group_template(ServerGroupId(8), "Welcome back, %nickname%.")
+//!         .cooldown(Duration::from_secs(600))
+//!         .delivery(Delivery::Poke);
+//!
+//!     client.set_event_handler(greeter);
+//!     client
+//!         .servernotifyregister(ServerNotifyRegister::Server)
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Broadcast`]: crate::broadcast::Broadcast
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::broadcast::render;
+use crate::event::{ClientEnterView, Event, EventHandler};
+use crate::request::TextMessageTarget;
+use crate::shared::ServerGroupId;
+use crate::{Client, Error};
+
+/// How a [`Greeter`] delivers the welcome message.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Delivery {
+    /// Sends a private text message.
+    #[default]
+    Message,
+    /// Pokes the client. Pokes are limited to 100 characters by the server.
+    Poke,
+}
+
+/// An [`EventHandler`] greeting joining clients. See the [module level documentation] for
+/// more details.
+///
+/// [module level documentation]: crate::greeter
+pub struct Greeter<H> {
+    inner: H,
+    template: Option<String>,
+    groups: Vec<(ServerGroupId, String)>,
+    cooldown: Duration,
+    delivery: Delivery,
+    /// The time every client was last greeted, keyed by the unique identifier.
+    greeted: Mutex<HashMap<String, Instant>>,
+}
+
+impl<H> Greeter<H>
+where
+    H: EventHandler,
+{
+    /// Creates a new `Greeter` wrapping `inner`. No client is greeted until a template is
+    /// set.
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            template: None,
+            groups: Vec::new(),
+            cooldown: Duration::from_secs(300),
+            delivery: Delivery::Message,
+            greeted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the template sent to clients not matching any group template.
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_owned());
+        self
+    }
+
+    /// Sets the template sent to clients in the server group `sgid`. If a client is in
+    /// multiple groups with a template, the template added first is used.
+    pub fn group_template(mut self, sgid: ServerGroupId, template: &str) -> Self {
+        self.groups.push((sgid, template.to_owned()));
+        self
+    }
+
+    /// Sets the time after greeting a client in which it is not greeted again. Defaults to
+    /// 5 minutes.
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets how the welcome message is delivered. Defaults to [`Delivery::Message`].
+    pub fn delivery(mut self, delivery: Delivery) -> Self {
+        self.delivery = delivery;
+        self
+    }
+
+    /// Returns the rendered welcome message for the client of `event`, or `None` if it
+    /// should not be greeted.
+    fn message(&self, event: &ClientEnterView, now: Instant) -> Option<String> {
+        // ServerQuery clients are never greeted.
+        if event.client_type != 0 {
+            return None;
+        }
+
+        let template = self
+            .groups
+            .iter()
+            .find(|(sgid, _)| event.client_servergroups.contains(sgid))
+            .map(|(_, template)| template)
+            .or(self.template.as_ref())?;
+
+        {
+            let mut greeted = self.greeted.lock().unwrap();
+            greeted.retain(|_, time| now.saturating_duration_since(*time) < self.cooldown);
+
+            if greeted.contains_key(&event.client_unique_identifier) {
+                return None;
+            }
+            greeted.insert(event.client_unique_identifier.clone(), now);
+        }
+
+        Some(render(template, |name| {
+            let value = match name {
+                "nickname" => event.client_nickname.clone(),
+                "clid" => event.clid.0.to_string(),
+                "uid" => event.client_unique_identifier.clone(),
+                "country" => event.client_country.clone(),
+                _ => return None,
+            };
+
+            Some(value)
+        }))
+    }
+}
+
+impl<H> Greeter<H>
+where
+    H: EventHandler,
+{
+    async fn greet(&self, client: &Client, event: &ClientEnterView) {
+        let Some(msg) = self.message(event, Instant::now()) else {
+            return;
+        };
+
+        let res = match self.delivery {
+            Delivery::Message => {
+                client
+                    .sendtextmessage(TextMessageTarget::Client(event.clid), &msg)
+                    .await
+            }
+            Delivery::Poke => client.clientpoke(event.clid, &msg).await,
+        };
+
+        if let Err(err) = res {
+            self.inner.error(client.clone(), err);
+        }
+    }
+}
+
+#[async_trait]
+impl<H> EventHandler for Greeter<H>
+where
+    H: EventHandler,
+{
+    async fn handle(&self, client: Client, event: Event) {
+        if let Event::ClientEnterView(event) = &event {
+            self.greet(&client, event).await;
+        }
+
+        self.inner.handle(client, event).await
+    }
+
+    fn error(&self, client: Client, error: Error) {
+        self.inner.error(client, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Greeter;
+    use crate::event::{ClientEnterView, Handler};
+    use crate::shared::{ClientId, ServerGroupId};
+
+    fn enter(uid: &str, groups: &[u64]) -> ClientEnterView {
+        ClientEnterView {
+            clid: ClientId(5),
+            client_nickname: String::from("Alice"),
+            client_unique_identifier: uid.to_owned(),
+            client_servergroups: groups
+                .iter()
+                .map(|sgid| ServerGroupId(*sgid))
+                .collect::<Vec<_>>()
+                .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_greeter_message() {
+        let greeter = Greeter::new(Handler)
+            .template("Hello %nickname% (%clid%)!")
+            .group_template(ServerGroupId(8), "Welcome back, %nickname%. %unknown%")
+            .cooldown(Duration::from_secs(60));
+
+        let now = Instant::now();
+        assert_eq!(
+            greeter.message(&enter("a=", &[6]), now).unwrap(),
+            "Hello Alice (5)!"
+        );
+        assert_eq!(
+            greeter.message(&enter("b=", &[6, 8]), now).unwrap(),
+            "Welcome back, Alice. %unknown%"
+        );
+
+        // Clients are not greeted again within the cooldown.
+        assert!(greeter.message(&enter("a=", &[6]), now).is_none());
+        assert!(greeter
+            .message(&enter("a=", &[6]), now + Duration::from_secs(61))
+            .is_some());
+
+        // ServerQuery clients are never greeted.
+        let mut query = enter("c=", &[]);
+        query.client_type = 1;
+        assert!(greeter.message(&query, now).is_none());
+    }
+}
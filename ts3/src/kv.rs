@@ -0,0 +1,127 @@
+//! Durable per-client key-value storage using custom client properties.
+//!
+//! Custom properties are stored in the server database and persist across restarts of both
+//! the server and the query client.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ts3::shared::ClientDatabaseId;
+//! use ts3::Client;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let kv = client.kv(ClientDatabaseId(5));
+//!     kv.set("forum_account", "alice").await?;
+//!     assert_eq!(kv.get("forum_account").await?.as_deref(), Some("alice"));
+//!
+//!     let clients = kv.find_clients_with("forum_account", "alice").await?;
+//!     println!("{:?}", clients);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(feature = "serde")]
+mod config;
+
+use std::collections::HashMap;
+
+use crate::shared::ClientDatabaseId;
+use crate::{Client, Error};
+
+#[cfg(feature = "serde")]
+pub use config::ConfigStore;
+
+/// A key-value store backed by the custom properties of a single client. Created using
+/// [`Client::kv`].
+#[derive(Clone)]
+pub struct KvStore {
+    client: Client,
+    cldbid: ClientDatabaseId,
+}
+
+impl KvStore {
+    pub(crate) fn new(client: Client, cldbid: ClientDatabaseId) -> Self {
+        Self { client, cldbid }
+    }
+
+    /// Returns the database id of the client this store belongs to.
+    #[inline]
+    pub fn cldbid(&self) -> ClientDatabaseId {
+        self.cldbid
+    }
+
+    /// Returns the value of `key`, or `None` if it is not set.
+    pub async fn get(&self, key: &str) -> Result<Option<String>, Error> {
+        let props = self.client.custominfo(self.cldbid).await?;
+
+        Ok(props
+            .into_inner()
+            .into_iter()
+            .find(|prop| prop.ident == key)
+            .map(|prop| prop.value))
+    }
+
+    /// Returns all keys and values of the store.
+    pub async fn get_all(&self) -> Result<HashMap<String, String>, Error> {
+        let props = self.client.custominfo(self.cldbid).await?;
+
+        Ok(props
+            .into_inner()
+            .into_iter()
+            .map(|prop| (prop.ident, prop.value))
+            .collect())
+    }
+
+    /// Sets `key` to `value`, replacing any previous value.
+    pub async fn set(&self, key: &str, value: &str) -> Result<(), Error> {
+        self.client.customset(self.cldbid, key, value).await
+    }
+
+    /// Removes `key` from the store.
+    pub async fn remove(&self, key: &str) -> Result<(), Error> {
+        self.client.customdelete(self.cldbid, key).await
+    }
+
+    /// Returns all clients on the selected virtual server which have `key` set to exactly
+    /// `value`. This is not limited to the client of this store.
+    pub async fn find_clients_with(
+        &self,
+        key: &str,
+        value: &str,
+    ) -> Result<Vec<ClientDatabaseId>, Error> {
+        let entries = self
+            .client
+            .customsearch(key, &escape_pattern(value))
+            .await?;
+
+        Ok(entries
+            .into_inner()
+            .into_iter()
+            // The search matches case insensitive, only keep exact matches.
+            .filter(|entry| entry.ident == key && entry.value == value)
+            .map(|entry| entry.cldbid)
+            .collect())
+    }
+}
+
+/// Replaces SQL wildcards in `value` with `_` so that `value` matches itself. This may match
+/// more values than `value`, which need to be filtered afterwards.
+fn escape_pattern(value: &str) -> String {
+    value.replace('%', "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_pattern;
+
+    #[test]
+    fn test_escape_pattern() {
+        assert_eq!(escape_pattern("abc"), "abc");
+        assert_eq!(escape_pattern("100%"), "100_");
+    }
+}
@@ -0,0 +1,150 @@
+//! Typed configuration stored in a custom client property.
+//!
+//! The configuration is stored as JSON in a single custom property named after the prefix
+//! of the store. Saving replaces the whole property at once, so a load never observes a
+//! partially written configuration.
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::KvStore;
+use crate::{Error, ErrorKind};
+
+/// A configuration of type `T` stored in the custom properties of a client. Created using
+/// [`Client::config`].
+///
+/// ```no_run
+/// use serde::{Deserialize, Serialize};
+/// # use ts3::Client;
+///
+/// #[derive(Debug, Default, Serialize, Deserialize)]
+/// struct Settings {
+///     welcome: String,
+///     afk_channel: Option<u64>,
+/// }
+///
+/// # async fn run(client: Client) -> Result<(), ts3::Error> {
+/// let store = client.config::<Settings>("mybot").await?;
+///
+/// let mut settings = store.load().await?;
+/// settings.welcome = String::from("Hello!");
+/// store.save(&settings).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client::config`]: crate::Client::config
+pub struct ConfigStore<T> {
+    kv: KvStore,
+    prefix: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ConfigStore<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub(crate) fn new(kv: KvStore, prefix: &str) -> Self {
+        Self {
+            kv,
+            prefix: prefix.to_owned(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the name of the property storing this configuration.
+    #[inline]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Loads the configuration. If no configuration was saved yet, all fields must be
+    /// `Option`s or have a default value using `#[serde(default)]`.
+    pub async fn load(&self) -> Result<T, Error> {
+        let value = self.kv.get(&self.prefix).await?;
+        decode(value.as_deref().unwrap_or("{}"))
+    }
+
+    /// Stores `config`, replacing the previously stored configuration.
+    pub async fn save(&self, config: &T) -> Result<(), Error> {
+        self.kv.set(&self.prefix, &encode(config)?).await
+    }
+}
+
+fn encode<T>(config: &T) -> Result<String, Error>
+where
+    T: Serialize,
+{
+    serde_json::to_string(config).map_err(|err| Error(ErrorKind::Config(err.to_string())))
+}
+
+fn decode<T>(value: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    serde_json::from_str(value).map_err(|err| Error(ErrorKind::Config(err.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::{decode, encode};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Mode {
+        Poke,
+        Message,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        welcome: String,
+        enabled: bool,
+        interval: u64,
+        mode: Mode,
+        afk_channel: Option<u64>,
+        #[serde(default)]
+        limit: u32,
+    }
+
+    #[test]
+    fn test_config_roundtrip() {
+        let settings = Settings {
+            welcome: String::from("Hello World!"),
+            enabled: true,
+            interval: 60,
+            mode: Mode::Poke,
+            afk_channel: None,
+            limit: 0,
+        };
+
+        let value = encode(&settings).unwrap();
+        assert_eq!(
+            value,
+            r#"{"welcome":"Hello World!","enabled":true,"interval":60,"mode":"Poke","afk_channel":null,"limit":0}"#
+        );
+        assert_eq!(decode::<Settings>(&value).unwrap(), settings);
+
+        // Missing fields use their defaults.
+        let value = r#"{"welcome":"Hi","enabled":false,"interval":5,"mode":"Message"}"#;
+        let settings = decode::<Settings>(value).unwrap();
+        assert_eq!(settings.afk_channel, None);
+        assert_eq!(settings.limit, 0);
+    }
+
+    #[test]
+    fn test_config_invalid() {
+        #[derive(Debug, Deserialize)]
+        struct Interval {
+            #[allow(dead_code)]
+            interval: u64,
+        }
+
+        let err = decode::<Interval>(r#"{"interval":"soon"}"#).unwrap_err();
+        assert!(err.to_string().starts_with("invalid config: invalid type"));
+        assert!(decode::<Interval>("{}").is_err());
+    }
+}
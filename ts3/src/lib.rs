@@ -7,6 +7,8 @@
 //!
 //! Connect to a TS3 query interface and select a server
 //! ```no_run
+//! # #[cfg(feature = "client")]
+//! # mod example {
 //! use ts3::Client;
 //!
 //! #[tokio::main]
@@ -19,9 +21,13 @@
 //!
 //!     Ok(())
 //! }
+//! # }
+//! # fn main() {}
 //! ```
 //!
 //! ```no_run
+//! # #[cfg(feature = "client")]
+//! # mod example {
 //! use ts3::{Client, async_trait};
 //! use ts3::request::{TextMessageTarget};
 //! use ts3::event::{EventHandler, ClientEnterView};
@@ -51,37 +57,333 @@
 //!             .await.unwrap();
 //!     }
 //! }
-//!
+//! # }
+//! # fn main() {}
 //! ```
+//!
+//! # Features
+//!
+//! - `client` (default): The async [`Client`] and the helpers built on it. Without it only
+//!   the protocol layer is compiled: [`Decode`], escaping, the [`response`] and [`request`]
+//!   types and the line parser in [`protocol`]. It does not depend on tokio and can be used
+//!   e.g. to parse query logs or in WASM.
+//! - `rustls`: Connections over TLS.
+//! - `tower`: Implements `tower::Service` for [`Client`].
+//! - `diagnostics`: Implements `miette::Diagnostic` for [`Error`].
+
+// Parts of the protocol layer are only used by the client.
+#![cfg_attr(not(feature = "client"), allow(dead_code))]
 
 extern crate self as ts3;
 
+#[cfg(feature = "client")]
+pub mod admin;
+#[cfg(feature = "client")]
+pub mod afk;
+#[cfg(feature = "client")]
+pub mod audit;
+#[cfg(feature = "client")]
+pub mod ban;
+pub mod bbcode;
+#[cfg(feature = "client")]
+pub mod broadcast;
+#[cfg(feature = "client")]
 mod client;
+#[cfg(feature = "client")]
+pub mod complain;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "client")]
 pub mod event;
+#[cfg(feature = "client")]
+pub mod ft;
+#[cfg(all(fuzzing, feature = "client"))]
+#[doc(hidden)]
+pub mod fuzz;
+#[cfg(feature = "client")]
+pub mod greeter;
+#[cfg(feature = "client")]
+pub mod kv;
+#[cfg(feature = "client")]
+pub mod membership;
+pub mod password;
+#[cfg(feature = "client")]
+pub mod perm;
+#[cfg(feature = "client")]
+pub mod protect;
+pub mod protocol;
+#[cfg(feature = "client")]
+pub mod provision;
+#[cfg(feature = "client")]
+pub mod proxy;
+#[cfg(feature = "client")]
+mod regex;
 pub mod request;
 pub mod response;
+#[cfg(feature = "client")]
+pub mod schedule;
+#[cfg(feature = "client")]
+mod scope;
+#[cfg(feature = "tower")]
+mod service;
 pub mod shared;
+#[cfg(feature = "client")]
+pub mod stats;
 mod types;
+#[cfg(feature = "client")]
+pub mod voice;
 
+#[cfg(feature = "client")]
 pub use async_trait::async_trait;
-pub use client::Client;
+#[cfg(feature = "client")]
+pub use client::{
+    split_channel_description, Batch, Client, ClientBuilder, Credentials, Direction, Keepalive,
+    Overflow, PanicPolicy, Priority, Reconnect, ServerAddr, ServerFlavor, ServerSelection,
+    Shutdown, SlowCommand, MAX_CHANNEL_DESCRIPTION_LENGTH,
+};
+#[cfg(feature = "client")]
+pub use scope::{Instance, VirtualServer};
 pub use ts3_derive::Decode;
 
 use std::{
-    convert::{Infallible, TryFrom},
-    fmt::{Debug, Write},
+    cell::Cell,
+    convert::Infallible,
+    fmt::{self, Debug, Display, Formatter, Write},
     io,
-    num::ParseIntError,
+    num::{ParseFloatError, ParseIntError},
     str::{from_utf8, Utf8Error},
 };
 
 use thiserror::Error;
 
+use crate::protocol::REDACTED_KEYS;
+#[cfg(feature = "client")]
+use crate::provision::ProvisionError;
+use crate::shared::list::bytes_split;
+
 /// An error that can occur when interacting with the TS3 query API.
 #[derive(Debug, Error)]
 #[error(transparent)]
 pub struct Error(ErrorKind);
 
+impl Error {
+    /// Returns the id of the error returned by the server. Returns `None` if the error did
+    /// not originate from the server.
+    pub fn id(&self) -> Option<u16> {
+        match &self.0 {
+            ErrorKind::TS3 { id, .. } => Some(*id),
+            _ => None,
+        }
+    }
+
+    /// Returns the message of the error returned by the server. Returns `None` if the error
+    /// did not originate from the server.
+    pub fn msg(&self) -> Option<&str> {
+        match &self.0 {
+            ErrorKind::TS3 { msg, .. } => Some(msg),
+            _ => None,
+        }
+    }
+
+    /// Returns the additional details of the error returned by the server, if the server
+    /// included any.
+    pub fn extra_msg(&self) -> Option<&str> {
+        match &self.0 {
+            ErrorKind::TS3 { extra_msg, .. } => extra_msg.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying [`io::Error`] if the error was caused by the connection.
+    pub fn as_io(&self) -> Option<&io::Error> {
+        match &self.0 {
+            ErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns where decoding a response failed. Returns `None` if the error did not occur
+    /// while decoding a field of a response.
+    pub fn decode_context(&self) -> Option<&DecodeContext> {
+        match &self.0 {
+            ErrorKind::Field { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Adds the location of `val` in `buf` to an error returned while decoding the field
+    /// `key`. Errors that already have a location are returned unchanged.
+    #[doc(hidden)]
+    pub fn with_decode_context(self, buf: &[u8], key: &[u8], val: &[u8]) -> Self {
+        if let ErrorKind::Field { .. } = self.0 {
+            return self;
+        }
+
+        // `val` is a subslice of `buf` for all callers, fall back to the whole line
+        // otherwise.
+        let offset = (val.as_ptr() as usize)
+            .checked_sub(buf.as_ptr() as usize)
+            .filter(|offset| offset + val.len() <= buf.len());
+        let (offset, len) = match offset {
+            Some(offset) => (offset, val.len()),
+            None => (0, buf.len()),
+        };
+        let (line, offset, len) = redact_context(buf, offset, len);
+
+        Self(ErrorKind::Field {
+            context: Box::new(DecodeContext {
+                field: String::from_utf8_lossy(key).into_owned(),
+                line,
+                offset,
+                len,
+            }),
+            source: Box::new(self),
+        })
+    }
+
+    /// Returns the error without the [`DecodeContext`].
+    fn kind(&self) -> &ErrorKind {
+        match &self.0 {
+            ErrorKind::Field { source, .. } => source.kind(),
+            kind => kind,
+        }
+    }
+
+    /// Returns the underlying [`ParseIntError`] if an integer in a response could not be
+    /// parsed.
+    pub fn as_parse_int(&self) -> Option<&ParseIntError> {
+        match self.kind() {
+            ErrorKind::ParseInt(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying [`ParseFloatError`] if a float in a response could not be
+    /// parsed.
+    pub fn as_parse_float(&self) -> Option<&ParseFloatError> {
+        match self.kind() {
+            ErrorKind::ParseFloat(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying [`Utf8Error`] if a response contained invalid UTF-8.
+    pub fn as_utf8(&self) -> Option<&Utf8Error> {
+        match self.kind() {
+            ErrorKind::Utf8(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying [`ProvisionError`] if a [`provision`] helper failed.
+    #[cfg(feature = "client")]
+    pub fn as_provision(&self) -> Option<&ProvisionError> {
+        match &self.0 {
+            ErrorKind::Provision(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the panic message if an event handler or the hook set with
+    /// [`ClientBuilder::setup`] panicked. See [`PanicPolicy`].
+    pub fn as_handler_panic(&self) -> Option<&str> {
+        match &self.0 {
+            ErrorKind::HandlerPanic(msg) => Some(msg),
+            _ => None,
+        }
+    }
+}
+
+/// Returns `buf` with the values of all [`REDACTED_KEYS`] replaced by `***`, and the span
+/// `offset..offset + len` of `buf` mapped into the returned line.
+fn redact_context(buf: &[u8], offset: usize, len: usize) -> (String, usize, usize) {
+    let mut line = String::with_capacity(buf.len());
+    let (mut new_offset, mut new_len) = (0, 0);
+
+    let mut start = 0;
+    for arg in buf.split(|b| *b == b' ') {
+        let key_len = arg.iter().position(|b| *b == b'=').unwrap_or(arg.len());
+        let redacted = key_len < arg.len()
+            && REDACTED_KEYS
+                .iter()
+                .any(|key| key.as_bytes() == &arg[..key_len]);
+
+        if (start..=start + arg.len()).contains(&offset) {
+            if redacted && offset > start + key_len {
+                new_offset = line.len() + key_len + 1;
+                new_len = 3;
+            } else {
+                new_offset = line.len() + String::from_utf8_lossy(&arg[..offset - start]).len();
+                new_len = len;
+            }
+        }
+
+        if redacted {
+            line.push_str(&String::from_utf8_lossy(&arg[..key_len]));
+            line.push_str("=***");
+        } else {
+            line.push_str(&String::from_utf8_lossy(arg));
+        }
+
+        start += arg.len();
+        if let Some(sep) = buf.get(start) {
+            line.push(*sep as char);
+        }
+        start += 1;
+    }
+
+    // Raw fields point at the whole line.
+    if len == buf.len() {
+        new_len = line.len();
+    }
+
+    (line, new_offset, new_len)
+}
+
+/// The location in a response at which decoding failed. Returned by
+/// [`Error::decode_context`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeContext {
+    field: String,
+    line: String,
+    offset: usize,
+    len: usize,
+}
+
+impl DecodeContext {
+    /// Returns the name of the field that could not be decoded.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the entry of the response containing the field, as it was received. The values
+    /// of sensitive fields, like passwords and api keys, are replaced with `***`.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Returns the offset of the value of the field in [`line`](Self::line) in bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the length of the value of the field in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the value of the field is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Display for DecodeContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "field `{}` at byte {}", self.field, self.offset)
+    }
+}
+
 impl From<Infallible> for Error {
     fn from(value: Infallible) -> Self {
         match value {}
@@ -92,21 +394,58 @@ impl From<Infallible> for Error {
 enum ErrorKind {
     /// Error returned from the ts3 interface. id of 0 indicates no error.
     #[error("TS3 error {id}: {msg}")]
-    TS3 { id: u16, msg: String },
+    TS3 {
+        id: u16,
+        msg: String,
+        /// Additional details, e.g. the time to wait when flood protection was triggered.
+        extra_msg: Option<String>,
+    },
     /// Io error from the underlying tcp stream.
     #[error("io: {0}")]
     Io(#[from] io::Error),
     /// Error occured while decoding the server response.
     #[error("failed to decode stream: {0}")]
     Decode(#[from] DecodeError),
+    /// A field of the response could not be decoded.
+    #[error("failed to decode {context}: {source}")]
+    Field {
+        context: Box<DecodeContext>,
+        source: Box<Error>,
+    },
     #[error("failed to parse integer: {0}")]
     ParseInt(#[from] ParseIntError),
+    #[error("failed to parse float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
     #[error("recevied invalid utf8: {0}")]
     Utf8(#[from] Utf8Error),
     #[error("send error")]
     SendError,
+    #[error("connection timed out")]
+    TimedOut,
+    #[error("command queue is full")]
+    QueueFull,
     #[error("no field")]
     NoField,
+    #[cfg(feature = "client")]
+    #[error("`{command}` is not supported by {flavor:?} servers")]
+    Unsupported {
+        command: String,
+        flavor: ServerFlavor,
+    },
+    #[error("invalid `{command}` request: {reason}")]
+    InvalidRequest { command: String, reason: String },
+    #[error("value too long: {len} bytes, at most {max} allowed")]
+    TooLong { len: usize, max: usize },
+    #[error("line exceeds the maximum length of {max} bytes")]
+    LineTooLong { max: usize },
+    #[error("event handler panicked: {0}")]
+    HandlerPanic(String),
+    #[cfg(all(feature = "client", feature = "serde"))]
+    #[error("invalid config: {0}")]
+    Config(String),
+    #[cfg(feature = "client")]
+    #[error(transparent)]
+    Provision(#[from] ProvisionError),
 }
 
 #[derive(Debug, Error)]
@@ -115,10 +454,44 @@ enum DecodeError {
     UnexpectedEof,
     #[error("unexpected byte: {0}")]
     UnexpectedByte(u8),
-    #[error("invalid reasonid: {0}")]
-    InvalidReasonId(u8),
     #[error("invalid apikey scope: {0}")]
     InvalidApiKeyScope(String),
+    #[error("invalid group type: {0}")]
+    InvalidGroupType(u8),
+    #[error("expected a single entry, got {0}")]
+    UnexpectedEntries(usize),
+}
+
+/// Returns an error if `buf` contains more than one entry. Used by `#[derive(Decode)]`, so
+/// that a response with multiple entries is not silently decoded from a garbled first entry.
+#[doc(hidden)]
+pub fn single_entry(buf: &[u8]) -> Result<(), Error> {
+    // `|` is escaped in values, so every `|` separates two entries.
+    match buf.iter().filter(|b| **b == b'|').count() {
+        0 => Ok(()),
+        n => Err(Error(DecodeError::UnexpectedEntries(n + 1).into())),
+    }
+}
+
+/// Decodes a list nested in the value `val`, with elements separated by `separator`. Used by
+/// fields marked with `#[ts3(separator = "...")]`.
+///
+/// Separators are escaped in values like any other character, a `|` separator matches `\p`
+/// in `val`. An empty value is decoded as an empty list.
+#[doc(hidden)]
+pub fn decode_separated<T>(val: &[u8], separator: &str) -> Result<Vec<T>, Error>
+where
+    T: Decode,
+    T::Error: Into<Error>,
+{
+    if val.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let separator = escape(separator);
+    bytes_split(val, separator.as_bytes())
+        .map(|elem| T::decode(elem).map_err(|err| err.into()))
+        .collect()
 }
 
 /// Any type implementing `Decode` can be directly decoded from the TS3 stream.
@@ -129,10 +502,56 @@ pub trait Decode: Sized {
     fn decode(buf: &[u8]) -> Result<Self, Self::Error>;
 }
 
+/// Decodes a single key-value pair into a struct. Implemented by `#[derive(Decode)]` and used
+/// to decode fields marked with `#[ts3(flatten)]`.
+#[doc(hidden)]
+pub trait DecodeField {
+    /// Decodes `val` into the field named `key`. Returns `false` if the struct has no field
+    /// named `key`.
+    fn decode_field(&mut self, key: &[u8], val: &[u8]) -> Result<bool, Error>;
+}
+
 pub trait Encode {
     fn encode(&self, buf: &mut String);
 }
 
+/// Escapes `s` for use as a value in a raw command.
+///
+/// ```
+/// assert_eq!(ts3::escape("Hello World|"), "Hello\\sWorld\\p");
+/// ```
+pub fn escape(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+    s.encode(&mut buf);
+    buf
+}
+
+/// Unescapes a value received from the server. This is the inverse of [`escape`].
+///
+/// ```
+/// assert_eq!(ts3::unescape(b"Hello\\sWorld\\p").unwrap(), "Hello World|");
+/// ```
+///
+/// # Errors
+///
+/// Returns an [`Error`] if `buf` contains an invalid escape sequence.
+pub fn unescape(buf: &[u8]) -> Result<String, Error> {
+    unescape_inner(buf, false)
+}
+
+/// Like [`unescape`], but keeps unknown escape sequences and replaces invalid UTF-8 with
+/// `U+FFFD REPLACEMENT CHARACTER` instead of failing.
+///
+/// ```
+/// assert_eq!(ts3::unescape_lossy(b"a\\sb\\xc\xff"), "a b\\xc\u{FFFD}");
+/// ```
+pub fn unescape_lossy(buf: &[u8]) -> String {
+    match unescape_inner(buf, true) {
+        Ok(string) => string,
+        Err(_) => unreachable!(),
+    }
+}
+
 /// Implements `Serialize` for types that can be directly written as they are formatted.
 macro_rules! impl_serialize {
     ($t:ty) => {
@@ -144,17 +563,18 @@ macro_rules! impl_serialize {
     };
 }
 
-/// The `impl_decode` macro implements `Decode` for any type that implements `FromStr`.
+/// The `impl_decode` macro implements `Decode` for any type that implements `FromStr` with
+/// an error convertible into `ErrorKind`.
 macro_rules! impl_decode {
     ($t:ty) => {
         impl Decode for $t {
             type Error = Error;
 
             fn decode(buf: &[u8]) -> std::result::Result<$t, Self::Error> {
-                Ok(from_utf8(buf)
+                from_utf8(buf)
                     .map_err(|e| Error(ErrorKind::Utf8(e)))?
-                    .parse()
-                    .map_err(|e| Error(ErrorKind::ParseInt(e)))?)
+                    .parse::<$t>()
+                    .map_err(|e| Error(e.into()))
             }
         }
     };
@@ -170,54 +590,121 @@ impl Decode for () {
     }
 }
 
-// Implement `Decode` for `String`
-impl Decode for String {
-    type Error = Error;
+thread_local! {
+    /// Whether strings are currently decoded lossy. See [`LossyGuard`].
+    static LOSSY: Cell<bool> = const { Cell::new(false) };
+}
 
-    fn decode(buf: &[u8]) -> Result<String, Self::Error> {
-        // Create a new string, allocating the same length as the buffer. Most
-        // chars are one-byte only.
-        let mut string = String::with_capacity(buf.len());
-
-        // Create a peekable iterator to iterate over all bytes, appending all bytes
-        // and replacing escaped chars.
-        let mut iter = buf.into_iter().peekable();
-        while let Some(b) = iter.next() {
-            match b {
-                // Match any escapes, starting with a '\' followed by another char.
-                b'\\' => {
-                    match iter.peek() {
-                        Some(c) => match c {
-                            b'\\' => string.push('\\'),
-                            b'/' => string.push('/'),
-                            b's' => string.push(' '),
-                            b'p' => string.push('|'),
-                            b'a' => string.push(7u8 as char),
-                            b'b' => string.push(8u8 as char),
-                            b'f' => string.push(12u8 as char),
-                            b'n' => string.push(10u8 as char),
-                            b'r' => string.push(13u8 as char),
-                            b't' => string.push(9u8 as char),
-                            b'v' => string.push(11u8 as char),
-                            _ => {
-                                return Err(Error(ErrorKind::Decode(DecodeError::UnexpectedByte(
-                                    **c,
-                                ))))
-                            }
-                        },
-                        None => {
-                            return Err(Error(ErrorKind::Decode(DecodeError::UnexpectedEof.into())))
+/// Enables or disables lossy decoding of strings on the current thread until the guard is
+/// dropped. Decoding is synchronous, so all `decode` calls made while the guard is alive use
+/// the mode of the guard.
+pub(crate) struct LossyGuard {
+    prev: bool,
+}
+
+impl LossyGuard {
+    pub(crate) fn set(lossy: bool) -> Self {
+        Self {
+            prev: LOSSY.with(|cell| cell.replace(lossy)),
+        }
+    }
+}
+
+impl Drop for LossyGuard {
+    fn drop(&mut self) {
+        LOSSY.with(|cell| cell.set(self.prev));
+    }
+}
+
+thread_local! {
+    /// Whether tolerant fields failing to decode are skipped. See [`TolerantGuard`].
+    static TOLERANT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Enables or disables skipping fields marked with `#[ts3(tolerant)]` that fail to decode on
+/// the current thread until the guard is dropped. Skipped fields keep their default value.
+/// Works like [`LossyGuard`].
+pub(crate) struct TolerantGuard {
+    prev: bool,
+}
+
+impl TolerantGuard {
+    pub(crate) fn set(tolerant: bool) -> Self {
+        Self {
+            prev: TOLERANT.with(|cell| cell.replace(tolerant)),
+        }
+    }
+}
+
+impl Drop for TolerantGuard {
+    fn drop(&mut self) {
+        TOLERANT.with(|cell| cell.set(self.prev));
+    }
+}
+
+/// Returns `true` if fields marked with `#[ts3(tolerant)]` failing to decode should be
+/// skipped. Used by `#[derive(Decode)]`.
+#[doc(hidden)]
+pub fn skip_invalid_fields() -> bool {
+    TOLERANT.with(|cell| cell.get())
+}
+
+/// Unescapes `buf`. If `lossy` is `true` unknown escape sequences are kept and invalid UTF-8
+/// is replaced, otherwise an error is returned.
+fn unescape_inner(buf: &[u8], lossy: bool) -> Result<String, Error> {
+    // Most values contain no escapes, allocating the same length as the buffer is enough.
+    let mut bytes = Vec::with_capacity(buf.len());
+
+    // Create a peekable iterator to iterate over all bytes, appending all bytes
+    // and replacing escaped chars.
+    let mut iter = buf.iter().peekable();
+    while let Some(b) = iter.next() {
+        match b {
+            // Match any escapes, starting with a '\' followed by another char.
+            b'\\' => {
+                match iter.peek() {
+                    Some(c) => match c {
+                        b'\\' => bytes.push(b'\\'),
+                        b'/' => bytes.push(b'/'),
+                        b's' => bytes.push(b' '),
+                        b'p' => bytes.push(b'|'),
+                        b'a' => bytes.push(7),
+                        b'b' => bytes.push(8),
+                        b'f' => bytes.push(12),
+                        b'n' => bytes.push(10),
+                        b'r' => bytes.push(13),
+                        b't' => bytes.push(9),
+                        b'v' => bytes.push(11),
+                        _ if lossy => {
+                            bytes.push(b'\\');
+                            continue;
                         }
-                    }
-                    iter.next();
+                        _ => {
+                            return Err(Error(ErrorKind::Decode(DecodeError::UnexpectedByte(**c))))
+                        }
+                    },
+                    None if lossy => bytes.push(b'\\'),
+                    None => return Err(Error(ErrorKind::Decode(DecodeError::UnexpectedEof))),
                 }
-                _ => string.push(char::try_from(*b).unwrap()),
+                iter.next();
             }
+            _ => bytes.push(*b),
         }
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(string) => Ok(string),
+        Err(err) if lossy => Ok(String::from_utf8_lossy(err.as_bytes()).into_owned()),
+        Err(err) => Err(Error(ErrorKind::Utf8(err.utf8_error()))),
+    }
+}
+
+// Implement `Decode` for `String`
+impl Decode for String {
+    type Error = Error;
 
-        // Shrink the string to its fitting size before returning it.
-        string.shrink_to_fit();
-        Ok(string)
+    fn decode(buf: &[u8]) -> Result<String, Self::Error> {
+        unescape_inner(buf, LOSSY.with(|cell| cell.get()))
     }
 }
 
@@ -248,8 +735,8 @@ impl Encode for bool {
             writer,
             "{}",
             match self {
-                false => b'0',
-                true => b'1',
+                false => '0',
+                true => '1',
             }
         )
         .unwrap();
@@ -260,15 +747,13 @@ impl Decode for bool {
     type Error = Error;
 
     fn decode(buf: &[u8]) -> Result<bool, Self::Error> {
-        match buf.get(0) {
+        match buf.first() {
             Some(b) => match b {
-                b'0' => Ok(true),
-                b'1' => Ok(false),
-                _ => Err(Error(ErrorKind::Decode(
-                    DecodeError::UnexpectedByte(*b).into(),
-                ))),
+                b'0' => Ok(false),
+                b'1' => Ok(true),
+                _ => Err(Error(ErrorKind::Decode(DecodeError::UnexpectedByte(*b)))),
             },
-            None => Err(Error(ErrorKind::Decode(DecodeError::UnexpectedEof.into()))),
+            None => Err(Error(ErrorKind::Decode(DecodeError::UnexpectedEof))),
         }
     }
 }
@@ -288,6 +773,9 @@ impl_decode!(u32);
 impl_decode!(u64);
 impl_decode!(u128);
 
+impl_decode!(f32);
+impl_decode!(f64);
+
 impl_serialize!(isize);
 impl_serialize!(i8);
 impl_serialize!(i16);
@@ -302,9 +790,13 @@ impl_serialize!(u32);
 impl_serialize!(u64);
 impl_serialize!(u128);
 
+impl_serialize!(f32);
+impl_serialize!(f64);
+
 impl Error {
     fn decode(buf: &[u8]) -> Result<Error, Error> {
-        let (mut id, mut msg) = (0, String::new());
+        let (mut id, mut msg, mut extra_msg) = (0, String::new(), None);
+        let buf = buf.trim_ascii_end();
 
         // Error is a key-value map separated by ' ' with only the id and msg key.
         for s in buf.split(|c| *c == b' ') {
@@ -316,7 +808,7 @@ impl Error {
             // Get both key and value from the buffer, separated by a '='.
             let parts: Vec<&[u8]> = s.splitn(2, |c| *c == b'=').collect();
 
-            match parts.get(0) {
+            match parts.first() {
                 Some(key) => {
                     // Extract the value.
                     let val = match parts.get(1) {
@@ -332,6 +824,9 @@ impl Error {
                         b"msg" => {
                             msg = String::decode(val)?;
                         }
+                        b"extra_msg" => {
+                            extra_msg = Some(String::decode(val)?);
+                        }
                         _ => (),
                     }
                 }
@@ -339,13 +834,23 @@ impl Error {
             }
         }
 
-        Ok(Error(ErrorKind::TS3 { id, msg }))
+        Ok(Error(ErrorKind::TS3 { id, msg, extra_msg }))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Decode, Error, ErrorKind};
+    use std::error::Error as _;
+    use std::io;
+
+    use super::{escape, unescape, unescape_lossy, Decode, Encode, Error, ErrorKind, LossyGuard};
+    use crate::shared::IconId;
+
+    #[derive(Debug, Default, Decode)]
+    struct Entry {
+        cid: u64,
+        clid: u64,
+    }
 
     #[test]
     fn test_string_decode() {
@@ -353,13 +858,138 @@ mod tests {
         assert_eq!(String::decode(buf).unwrap(), "Hello World!".to_owned());
     }
 
+    #[test]
+    fn test_escape_roundtrip() {
+        let s = "a b|c\\d/e\tf\ng";
+        assert_eq!(escape(s), "a\\sb\\pc\\\\d\\/e\\tf\\ng");
+        assert_eq!(unescape(escape(s).as_bytes()).unwrap(), s);
+        assert!(unescape(b"\\x").is_err());
+    }
+
+    #[test]
+    fn test_string_decode_utf8() {
+        assert_eq!(String::decode("äöü\\s€".as_bytes()).unwrap(), "äöü €");
+        assert!(String::decode(b"\xff").is_err());
+    }
+
+    #[test]
+    fn test_string_decode_lossy() {
+        assert_eq!(unescape_lossy(b"a\\qb\\"), "a\\qb\\");
+        assert_eq!(unescape_lossy(b"\xffa"), "\u{FFFD}a");
+
+        {
+            let _guard = LossyGuard::set(true);
+            assert_eq!(String::decode(b"a\\q").unwrap(), "a\\q");
+        }
+        assert!(String::decode(b"a\\q").is_err());
+    }
+
+    #[test]
+    fn test_bool_decode() {
+        assert!(!bool::decode(b"0").unwrap());
+        assert!(bool::decode(b"1").unwrap());
+    }
+
+    #[test]
+    fn test_bool_encode() {
+        for (value, expected) in [(false, "0"), (true, "1")] {
+            let mut buf = String::new();
+            value.encode(&mut buf);
+            assert_eq!(buf, expected);
+            assert_eq!(bool::decode(buf.as_bytes()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_icon_id_decode() {
+        assert_eq!(IconId::decode(b"1234").unwrap(), IconId(1234));
+        assert_eq!(IconId::decode(b"4294967295").unwrap(), IconId(u32::MAX));
+        // Sign extended by some servers.
+        assert_eq!(IconId::decode(b"-1").unwrap(), IconId(u32::MAX));
+        assert_eq!(IconId::decode(b"-1234").unwrap(), IconId(4294966062));
+        assert!(IconId::decode(b"4294967296").is_err());
+        assert!(IconId::decode(b"-2147483649").is_err());
+    }
+
     #[test]
     fn test_error_decode() {
         let buf = b"error id=0 msg=ok";
         let (id, msg) = match Error::decode(buf).unwrap().0 {
-            ErrorKind::TS3 { id, msg } => (id, msg),
+            ErrorKind::TS3 { id, msg, .. } => (id, msg),
             _ => unreachable!(),
         };
-        assert!(id == 0 && msg == "ok".to_owned());
+        assert!(id == 0 && msg == "ok");
+
+        let err = Error::decode(
+            b"error id=524 msg=client\\sis\\sflooding extra_msg=please\\swait\\s3\\sseconds\n\r",
+        )
+        .unwrap();
+        assert_eq!(err.extra_msg(), Some("please wait 3 seconds"));
+    }
+
+    #[test]
+    fn test_error_accessors() {
+        let err = Error::decode(b"error id=2568 msg=insufficient\\sclient\\spermissions").unwrap();
+        assert_eq!(err.id(), Some(2568));
+        assert_eq!(err.msg(), Some("insufficient client permissions"));
+        assert_eq!(err.extra_msg(), None);
+        assert!(err.as_io().is_none() && err.source().is_none());
+
+        let err = Error(ErrorKind::Io(io::Error::new(
+            io::ErrorKind::ConnectionReset,
+            "reset",
+        )));
+        assert_eq!(err.id(), None);
+        assert_eq!(err.as_io().unwrap().kind(), io::ErrorKind::ConnectionReset);
+        assert!(err.source().unwrap().is::<io::Error>());
+
+        let err = u32::decode(b"x").unwrap_err();
+        assert!(err.as_parse_int().is_some());
+        assert!(err.source().unwrap().is::<std::num::ParseIntError>());
+    }
+
+    #[test]
+    fn test_decode_context() {
+        let err = Entry::decode(b"cid=1 clid=x client_nickname=bot").unwrap_err();
+        let context = err.decode_context().unwrap();
+        assert_eq!(context.field(), "clid");
+        assert_eq!(context.line(), "cid=1 clid=x client_nickname=bot");
+        assert_eq!((context.offset(), context.len()), (11, 1));
+        assert_eq!(
+            err.to_string(),
+            "failed to decode field `clid` at byte 11: failed to parse integer: invalid digit \
+             found in string"
+        );
+
+        // The underlying error stays accessible.
+        assert!(err.as_parse_int().is_some());
+        let source = err.source().unwrap();
+        assert!(source.source().unwrap().is::<std::num::ParseIntError>());
+
+        assert!(Entry::decode(b"cid=1 clid=2").unwrap().clid == 2);
+        assert!(u32::decode(b"x").unwrap_err().decode_context().is_none());
+    }
+
+    #[test]
+    fn test_decode_context_redacted() {
+        #[derive(Debug, Default, Decode)]
+        struct Entry {
+            pw: String,
+            apikey: u32,
+            clid: u32,
+        }
+
+        let err = Entry::decode(b"pw=secret clid=x").unwrap_err();
+        let context = err.decode_context().unwrap();
+        assert_eq!(context.line(), "pw=*** clid=x");
+        assert_eq!((context.offset(), context.len()), (12, 1));
+
+        // The invalid value itself is redacted.
+        let err = Entry::decode(b"clid=1 apikey=secret pw=hunter2").unwrap_err();
+        let context = err.decode_context().unwrap();
+        assert_eq!(context.field(), "apikey");
+        assert_eq!(context.line(), "clid=1 apikey=*** pw=***");
+        assert_eq!((context.offset(), context.len()), (14, 3));
+        assert!(!err.to_string().contains("secret"));
     }
 }
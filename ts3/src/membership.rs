@@ -0,0 +1,171 @@
+//! Time-limited server group memberships.
+//!
+//! The server has no native expiry for group memberships. A temporary membership added
+//! with [`Client::add_to_group_temporarily`] is removed by a task of the client once it
+//! expires. Pending removals are persisted in a [`MembershipStore`], so that they can be
+//! resumed with [`Client::resume_temporary_memberships`] after the application restarted,
+//! instead of leaving the client in the group forever.
+//!
+//! Memberships are only removed while their virtual server is selected. A membership
+//! expiring while another server is selected stays in the store until the memberships are
+//! resumed with its server selected.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::membership::MemoryStore;
+//! use ts3::shared::{ClientDatabaseId, ServerGroupId};
+//! use ts3::Client;
+//!
+//! # async fn run() -> Result<(), ts3::Error> {
+//! let client = Client::connect("localhost:10011").await?;
+//! client.login("serveradmin", "password").await?;
+//! client.use_sid(1).await?;
+//!
+//! // Use a persistent store in a real application.
+//! client.set_membership_store(MemoryStore::new());
+//! client.resume_temporary_memberships().await?;
+//!
+//! client
+//!     .add_to_group_temporarily(ClientDatabaseId(5), ServerGroupId(9), Duration::from_secs(3600))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Client::add_to_group_temporarily`]: crate::Client::add_to_group_temporarily
+//! [`Client::resume_temporary_memberships`]: crate::Client::resume_temporary_memberships
+
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+
+use crate::client::Result;
+use crate::schedule::Schedule;
+use crate::shared::{ClientDatabaseId, ServerGroupId, ServerId};
+use crate::{Client, Error, ErrorKind};
+
+/// A server group membership that is removed once it expires.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TemporaryMembership {
+    /// The virtual server the group belongs to.
+    pub sid: ServerId,
+    pub sgid: ServerGroupId,
+    pub cldbid: ClientDatabaseId,
+    /// The time at which the membership is removed.
+    pub expires: SystemTime,
+}
+
+/// Persists pending removals of [`TemporaryMembership`]s. Set using
+/// [`Client::set_membership_store`].
+///
+/// [`Client::set_membership_store`]: crate::Client::set_membership_store
+#[async_trait]
+pub trait MembershipStore: Send + Sync {
+    /// Stores `membership`. Called before the client is added to the group.
+    async fn save(&self, membership: &TemporaryMembership) -> io::Result<()>;
+
+    /// Removes `membership`. Called after the client was removed from the group.
+    async fn remove(&self, membership: &TemporaryMembership) -> io::Result<()>;
+
+    /// Returns all stored memberships.
+    async fn load(&self) -> io::Result<Vec<TemporaryMembership>>;
+}
+
+#[async_trait]
+impl<S> MembershipStore for Arc<S>
+where
+    S: MembershipStore + ?Sized,
+{
+    async fn save(&self, membership: &TemporaryMembership) -> io::Result<()> {
+        (**self).save(membership).await
+    }
+
+    async fn remove(&self, membership: &TemporaryMembership) -> io::Result<()> {
+        (**self).remove(membership).await
+    }
+
+    async fn load(&self) -> io::Result<Vec<TemporaryMembership>> {
+        (**self).load().await
+    }
+}
+
+/// A [`MembershipStore`] keeping memberships in memory. Pending removals are lost when the
+/// application exits. This is the default store of a [`Client`].
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    memberships: Mutex<Vec<TemporaryMembership>>,
+}
+
+impl MemoryStore {
+    /// Creates a new, empty `MemoryStore`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl MembershipStore for MemoryStore {
+    async fn save(&self, membership: &TemporaryMembership) -> io::Result<()> {
+        self.memberships.lock().unwrap().push(membership.clone());
+        Ok(())
+    }
+
+    async fn remove(&self, membership: &TemporaryMembership) -> io::Result<()> {
+        let mut memberships = self.memberships.lock().unwrap();
+        if let Some(index) = memberships.iter().position(|m| m == membership) {
+            memberships.remove(index);
+        }
+        Ok(())
+    }
+
+    async fn load(&self) -> io::Result<Vec<TemporaryMembership>> {
+        Ok(self.memberships.lock().unwrap().clone())
+    }
+}
+
+/// Schedules the removal of `membership` once it expires. Errors are reported to the event
+/// handler, the membership then stays in the store and is retried when the memberships
+/// are resumed. This also applies if the connection is closed or another virtual server is
+/// selected before the membership expires.
+pub(crate) fn schedule(client: &Client, membership: TemporaryMembership) {
+    let delay = membership
+        .expires
+        .duration_since(SystemTime::now())
+        .unwrap_or_default();
+
+    client.schedule(Schedule::after(delay), move |client| {
+        let membership = membership.clone();
+        async move { expire(&client, &membership).await }
+    });
+}
+
+/// Removes the client of `membership` from the group and the membership from the store.
+///
+/// The virtual server of the membership must still be selected. Other tasks may send
+/// commands at any time, switching the server would run them against the wrong one.
+async fn expire(client: &Client, membership: &TemporaryMembership) -> Result<()> {
+    let selected = client.me().map(|me| me.virtualserver_id);
+    if selected != Some(membership.sid) {
+        return Err(Error(ErrorKind::InvalidRequest {
+            command: String::from("servergroupdelclient"),
+            reason: format!(
+                "virtual server {} is not selected, the membership is kept in the store",
+                membership.sid.0
+            ),
+        }));
+    }
+
+    client
+        .servergroupdelclient_verified(membership.sgid, membership.cldbid)
+        .await?;
+    client
+        .membership_store()
+        .remove(membership)
+        .await
+        .map_err(|err| Error(err.into()))
+}
@@ -0,0 +1,55 @@
+//! Hashing of server and channel passwords.
+//!
+//! The server stores server and channel passwords as the base64 encoded SHA-1 hash of the
+//! password. This is the format returned in `virtualserver_password` and
+//! `channel_password`. Commands always take the password in plain text.
+//!
+//! There is no option to send a hashed password instead: `login` only accepts the plain
+//! text password and `use` takes no password, since query clients do not need the server
+//! password. Connect using TLS, enabled by the `rustls` feature, to protect the password in
+//! transit.
+//!
+//! ```
+//! use ts3::password;
+//!
+//! assert_eq!(password::hash("password"), "W6ph5Mm5Pz8GgiULbPgzG37mj9g=");
+//! ```
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+
+/// Returns the hash of `password` as stored by the server.
+pub fn hash(password: &str) -> String {
+    base64(&sha1(password.as_bytes()))
+}
+
+/// Returns `true` if `password` matches the stored `hash`.
+pub fn verify(password: &str, hash: &str) -> bool {
+    self::hash(password) == hash
+}
+
+/// Computes the SHA-1 digest of `buf`.
+fn sha1(buf: &[u8]) -> [u8; 20] {
+    Sha1::digest(buf).into()
+}
+
+/// Encodes `buf` using standard base64 with padding.
+pub(crate) fn base64(buf: &[u8]) -> String {
+    STANDARD.encode(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash, verify};
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash("password"), "W6ph5Mm5Pz8GgiULbPgzG37mj9g=");
+        assert!(verify("password", "W6ph5Mm5Pz8GgiULbPgzG37mj9g="));
+        assert!(!verify("Password", "W6ph5Mm5Pz8GgiULbPgzG37mj9g="));
+
+        // The hash is computed over the UTF-8 encoded password.
+        assert_eq!(hash("pässwörd"), "9Rfd8dMqES/xrVXGbRsSyzjn6Pc=");
+    }
+}
@@ -0,0 +1,577 @@
+//! Helpers for editing permissions in bulk, copying them between servers and computing
+//! effective permission values.
+
+// Required for ts3_derive macro.
+#[allow(unused_imports)]
+use crate as ts3;
+
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+use crate::client::Result;
+use crate::request::RequestBuilder;
+use crate::response::{PermissionOrigin, PermissionOverview};
+use crate::shared::list::Pipe;
+use crate::shared::{ChannelGroupId, ChannelId, ClientDatabaseId, List, ServerGroupId};
+use crate::{Client, Decode};
+
+/// The target of a permission edit.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PermissionTarget {
+    ServerGroup(ServerGroupId),
+    ChannelGroup(ChannelGroupId),
+    Channel(ChannelId),
+    Client(ClientDatabaseId),
+}
+
+impl PermissionTarget {
+    fn request(&self, suffix: &str) -> RequestBuilder {
+        match self {
+            Self::ServerGroup(sgid) => {
+                RequestBuilder::new(format!("servergroup{}", suffix)).arg("sgid", *sgid)
+            }
+            Self::ChannelGroup(cgid) => {
+                RequestBuilder::new(format!("channelgroup{}", suffix)).arg("cgid", *cgid)
+            }
+            Self::Channel(cid) => {
+                RequestBuilder::new(format!("channel{}", suffix)).arg("cid", *cid)
+            }
+            Self::Client(cldbid) => {
+                RequestBuilder::new(format!("client{}", suffix)).arg("cldbid", *cldbid)
+            }
+        }
+    }
+
+    /// Returns `true` if the target supports the `permnegated` flag.
+    fn supports_negated(&self) -> bool {
+        matches!(self, Self::ServerGroup(_))
+    }
+
+    /// Returns `true` if the target supports the `permskip` flag.
+    fn supports_skip(&self) -> bool {
+        matches!(self, Self::ServerGroup(_) | Self::Client(_))
+    }
+}
+
+/// A single permission value assigned to a [`PermissionTarget`].
+///
+/// The `permnegated` flag is only used for server groups and the `permskip` flag is only used
+/// for server groups and clients. They are ignored for all other targets.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Decode)]
+pub struct PermissionAssignment {
+    pub permsid: String,
+    pub permvalue: i64,
+    pub permnegated: bool,
+    pub permskip: bool,
+}
+
+impl PermissionAssignment {
+    /// Creates a new `PermissionAssignment` with the given permission name and value.
+    pub fn new<T>(permsid: T, permvalue: i64) -> Self
+    where
+        T: ToString,
+    {
+        Self {
+            permsid: permsid.to_string(),
+            permvalue,
+            permnegated: false,
+            permskip: false,
+        }
+    }
+
+    /// Sets the `permnegated` flag.
+    pub fn negated(mut self, negated: bool) -> Self {
+        self.permnegated = negated;
+        self
+    }
+
+    /// Sets the `permskip` flag.
+    pub fn skip(mut self, skip: bool) -> Self {
+        self.permskip = skip;
+        self
+    }
+}
+
+/// A change that is applied to a permission by [`BulkEdit`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PermissionChange {
+    /// The currently assigned permission, `None` if the permission is not yet assigned.
+    pub old: Option<PermissionAssignment>,
+    /// The new permission, `None` if the permission is removed.
+    pub new: Option<PermissionAssignment>,
+}
+
+/// All permissions assigned to a [`PermissionTarget`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PermissionSet {
+    permissions: Vec<PermissionAssignment>,
+}
+
+impl PermissionSet {
+    /// Creates a new `PermissionSet` from a list of permissions.
+    #[inline]
+    pub fn new(permissions: Vec<PermissionAssignment>) -> Self {
+        Self { permissions }
+    }
+
+    /// Returns the permissions in this `PermissionSet`.
+    #[inline]
+    pub fn permissions(&self) -> &[PermissionAssignment] {
+        &self.permissions
+    }
+
+    /// Consumes this `PermissionSet`, returning the contained permissions.
+    #[inline]
+    pub fn into_inner(self) -> Vec<PermissionAssignment> {
+        self.permissions
+    }
+}
+
+impl From<Vec<PermissionAssignment>> for PermissionSet {
+    #[inline]
+    fn from(value: Vec<PermissionAssignment>) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Returns all permissions currently assigned to the `target`.
+pub async fn list(client: &Client, target: PermissionTarget) -> Result<Vec<PermissionAssignment>> {
+    let req = target.request("permlist").flag("-permsid");
+
+    match client
+        .send::<List<PermissionAssignment, Pipe>, _>(req)
+        .await
+    {
+        Ok(list) => Ok(list.into_inner()),
+        Err(err) if err.is_empty_result() => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Exports all permissions assigned to `target` into a [`PermissionSet`].
+///
+/// The set can later be applied to a target on another virtual server or instance using
+/// [`apply`].
+pub async fn export(client: &Client, target: PermissionTarget) -> Result<PermissionSet> {
+    list(client, target).await.map(PermissionSet::new)
+}
+
+/// Applies a [`PermissionSet`] to `target`, so that afterwards it has exactly the permissions
+/// contained in `set`. Permissions of `target` that are not contained in `set` are removed.
+///
+/// Use [`BulkEdit`] directly for more control over the applied changes.
+pub async fn apply(
+    client: &Client,
+    target: PermissionTarget,
+    set: PermissionSet,
+) -> Result<Vec<PermissionChange>> {
+    BulkEdit::new(target, set.into_inner())
+        .remove_unlisted(true)
+        .execute(client)
+        .await
+}
+
+/// Returns the effective value of the permission `permsid` for the client `cldbid` in the
+/// channel `cid`, or `None` if the permission is not granted at all.
+///
+/// See [`effective_value`] for how the value is computed.
+pub async fn effective_permission(
+    client: &Client,
+    cldbid: ClientDatabaseId,
+    cid: ChannelId,
+    permsid: &str,
+) -> Result<Option<i64>> {
+    match client.permoverview(cid, cldbid, Some(permsid)).await {
+        Ok(values) => Ok(effective_value(&values)),
+        Err(err) if err.is_empty_result() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Whether a client may join or subscribe to a channel. Returned from [`can_client_join`]
+/// and [`can_client_subscribe`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Verdict {
+    /// The client is allowed to join or subscribe.
+    Allowed,
+    /// The power of the client is lower than the power needed by the channel.
+    InsufficientPower {
+        /// The power permission of the client, e.g. `i_channel_join_power`.
+        permsid: &'static str,
+        /// The power needed by the channel.
+        needed: i64,
+        /// The effective power of the client.
+        granted: i64,
+    },
+    /// The channel has a password and the client is not allowed to ignore it. Joining
+    /// succeeds if the client provides the password.
+    PasswordRequired,
+}
+
+impl Verdict {
+    /// Returns `true` if the client is allowed to join or subscribe.
+    #[inline]
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allowed)
+    }
+}
+
+/// Checks whether the client `cldbid` may join the channel `cid` without a password. The
+/// effective `i_channel_join_power` of the client is compared against the
+/// `i_channel_needed_join_power` of the channel, then the password of the channel is checked
+/// unless the client has `b_channel_join_ignore_password`.
+///
+/// Other restrictions, e.g. the maximum number of clients, are not checked.
+///
+/// # Examples
+///
+/// ```no_run
+/// use ts3::perm::{self, Verdict};
+/// use ts3::shared::{ChannelId, ClientDatabaseId};
+/// use ts3::Client;
+///
+/// # async fn run(client: Client) -> Result<(), ts3::Error> {
+/// match perm::can_client_join(&client, ClientDatabaseId(5), ChannelId(3)).await? {
+///     Verdict::InsufficientPower { permsid, needed, granted } => {
+///         println!("{} is {}, but {} is needed", permsid, granted, needed);
+///     }
+///     Verdict::PasswordRequired => println!("the channel needs a password"),
+///     _ => (),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn can_client_join(
+    client: &Client,
+    cldbid: ClientDatabaseId,
+    cid: ChannelId,
+) -> Result<Verdict> {
+    let verdict = check_power(
+        client,
+        cldbid,
+        cid,
+        "i_channel_join_power",
+        "i_channel_needed_join_power",
+    )
+    .await?;
+    if !verdict.is_allowed() {
+        return Ok(verdict);
+    }
+
+    if client.channelinfo(cid).await?.channel_flag_password {
+        let ignore =
+            effective_permission(client, cldbid, cid, "b_channel_join_ignore_password").await?;
+        if ignore.unwrap_or(0) == 0 {
+            return Ok(Verdict::PasswordRequired);
+        }
+    }
+
+    Ok(Verdict::Allowed)
+}
+
+/// Checks whether the client `cldbid` may subscribe to the channel `cid`. The effective
+/// `i_channel_subscribe_power` of the client is compared against the
+/// `i_channel_needed_subscribe_power` of the channel.
+pub async fn can_client_subscribe(
+    client: &Client,
+    cldbid: ClientDatabaseId,
+    cid: ChannelId,
+) -> Result<Verdict> {
+    check_power(
+        client,
+        cldbid,
+        cid,
+        "i_channel_subscribe_power",
+        "i_channel_needed_subscribe_power",
+    )
+    .await
+}
+
+async fn check_power(
+    client: &Client,
+    cldbid: ClientDatabaseId,
+    cid: ChannelId,
+    power: &'static str,
+    needed_power: &str,
+) -> Result<Verdict> {
+    // The needed power is a permission of the channel itself.
+    let needed = match client.permoverview(cid, cldbid, Some(needed_power)).await {
+        Ok(values) => values
+            .iter()
+            .find(|v| v.origin() == Some(PermissionOrigin::Channel(cid)))
+            .map_or(0, |v| v.v),
+        Err(err) if err.is_empty_result() => 0,
+        Err(err) => return Err(err),
+    };
+    if needed <= 0 {
+        return Ok(Verdict::Allowed);
+    }
+
+    let granted = effective_permission(client, cldbid, cid, power)
+        .await?
+        .unwrap_or(0);
+    if granted < needed {
+        return Ok(Verdict::InsufficientPower {
+            permsid: power,
+            needed,
+            granted,
+        });
+    }
+
+    Ok(Verdict::Allowed)
+}
+
+/// Computes the effective value of a single permission from all values returned by
+/// [`Client::permoverview`], the same way the server does:
+///
+/// 1. The highest value of all server groups is used. If any server group has the negated
+///    flag set, the lowest value of the negated server groups is used instead.
+/// 2. A client permission overrides the server group value.
+/// 3. If the skip flag is set on the value from step 1 or 2, channel permissions are ignored.
+/// 4. Otherwise channel, channel group and channel client permissions override the value in
+///    that order.
+pub fn effective_value(values: &[PermissionOverview]) -> Option<i64> {
+    let mut value = None;
+    let mut skip = false;
+
+    let server_groups = values
+        .iter()
+        .filter(|v| matches!(v.origin(), Some(PermissionOrigin::ServerGroup(_))));
+    if server_groups.clone().any(|v| v.n) {
+        if let Some(v) = server_groups.filter(|v| v.n).min_by_key(|v| v.v) {
+            value = Some(v.v);
+            skip = v.s;
+        }
+    } else if let Some(v) = server_groups.max_by_key(|v| v.v) {
+        value = Some(v.v);
+        skip = v.s;
+    }
+
+    if let Some(v) = values
+        .iter()
+        .find(|v| matches!(v.origin(), Some(PermissionOrigin::Client(_))))
+    {
+        value = Some(v.v);
+        skip = v.s;
+    }
+
+    if skip {
+        return value;
+    }
+
+    if let Some(v) = values
+        .iter()
+        .find(|v| matches!(v.origin(), Some(PermissionOrigin::Channel(_))))
+    {
+        value = Some(v.v);
+    }
+
+    if let Some(v) = values
+        .iter()
+        .find(|v| matches!(v.origin(), Some(PermissionOrigin::ChannelGroup(..))))
+    {
+        value = Some(v.v);
+    }
+
+    if let Some(v) = values
+        .iter()
+        .find(|v| matches!(v.origin(), Some(PermissionOrigin::ChannelClient(..))))
+    {
+        value = Some(v.v);
+    }
+
+    value
+}
+
+/// Applies many [`PermissionAssignment`]s to a [`PermissionTarget`].
+///
+/// Only permissions that differ from the currently assigned values are sent to the server.
+/// They are sent in batches of [`batch_size`] permissions, waiting [`delay`] between each
+/// batch to avoid hitting the flood protection of the server.
+///
+/// [`batch_size`]: Self::batch_size
+/// [`delay`]: Self::delay
+#[derive(Clone, Debug)]
+pub struct BulkEdit {
+    target: PermissionTarget,
+    assignments: Vec<PermissionAssignment>,
+    batch_size: usize,
+    delay: Duration,
+    dry_run: bool,
+    remove_unlisted: bool,
+}
+
+impl BulkEdit {
+    /// Creates a new `BulkEdit` applying `assignments` to `target`.
+    pub fn new(target: PermissionTarget, assignments: Vec<PermissionAssignment>) -> Self {
+        Self {
+            target,
+            assignments,
+            batch_size: 100,
+            delay: Duration::from_millis(500),
+            dry_run: false,
+            remove_unlisted: false,
+        }
+    }
+
+    /// Sets the maximum number of permissions sent in a single request. Defaults to `100`.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the delay between two requests. Defaults to 500ms.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// If `dry_run` is `true`, only the changes are computed but not applied.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// If `remove_unlisted` is `true`, all permissions currently assigned to the target that are
+    /// not part of the assignments are removed. Defaults to `false`.
+    pub fn remove_unlisted(mut self, remove_unlisted: bool) -> Self {
+        self.remove_unlisted = remove_unlisted;
+        self
+    }
+
+    /// Computes the changes against the currently assigned permissions and applies them,
+    /// unless this is a dry run. Returns all changes that were (or would be) applied.
+    pub async fn execute(self, client: &Client) -> Result<Vec<PermissionChange>> {
+        let current = list(client, self.target).await?;
+
+        let mut changes = Vec::new();
+        for mut new in self.assignments.iter().cloned() {
+            if !self.target.supports_negated() {
+                new.permnegated = false;
+            }
+            if !self.target.supports_skip() {
+                new.permskip = false;
+            }
+
+            let old = current.iter().find(|perm| perm.permsid == new.permsid);
+            if old != Some(&new) {
+                changes.push(PermissionChange {
+                    old: old.cloned(),
+                    new: Some(new),
+                });
+            }
+        }
+
+        if self.remove_unlisted {
+            for old in &current {
+                if !self
+                    .assignments
+                    .iter()
+                    .any(|perm| perm.permsid == old.permsid)
+                {
+                    changes.push(PermissionChange {
+                        old: Some(old.clone()),
+                        new: None,
+                    });
+                }
+            }
+        }
+
+        if self.dry_run {
+            return Ok(changes);
+        }
+
+        let updated: Vec<&PermissionAssignment> = changes
+            .iter()
+            .filter_map(|change| change.new.as_ref())
+            .collect();
+        let removed: Vec<&PermissionAssignment> = changes
+            .iter()
+            .filter(|change| change.new.is_none())
+            .filter_map(|change| change.old.as_ref())
+            .collect();
+
+        let mut requests = Vec::new();
+        for batch in updated.chunks(self.batch_size) {
+            let mut req = self.target.request("addperm");
+            for (index, perm) in batch.iter().enumerate() {
+                if index != 0 {
+                    req = req.pipe();
+                }
+
+                req = req
+                    .arg("permsid", perm.permsid.as_str())
+                    .arg("permvalue", perm.permvalue);
+
+                if self.target.supports_negated() {
+                    req = req.arg("permnegated", perm.permnegated);
+                }
+                if self.target.supports_skip() {
+                    req = req.arg("permskip", perm.permskip);
+                }
+            }
+
+            requests.push(req);
+        }
+
+        for batch in removed.chunks(self.batch_size) {
+            let mut req = self.target.request("delperm");
+            for (index, perm) in batch.iter().enumerate() {
+                if index != 0 {
+                    req = req.pipe();
+                }
+
+                req = req.arg("permsid", perm.permsid.as_str());
+            }
+
+            requests.push(req);
+        }
+
+        for (index, req) in requests.into_iter().enumerate() {
+            if index != 0 {
+                sleep(self.delay).await;
+            }
+
+            client.send::<(), _>(req).await?;
+        }
+
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::effective_value;
+    use crate::response::PermissionOverview;
+    use crate::shared::list::Pipe;
+    use crate::shared::List;
+    use crate::Decode;
+
+    fn decode(buf: &[u8]) -> Vec<PermissionOverview> {
+        List::<PermissionOverview, Pipe>::decode(buf)
+            .unwrap()
+            .into_inner()
+    }
+
+    #[test]
+    fn test_effective_value() {
+        assert_eq!(effective_value(&[]), None);
+
+        let values = decode(b"t=0 id1=2 id2=0 p=a v=10 n=0 s=0|t=0 id1=3 id2=0 p=a v=20 n=0 s=0");
+        assert_eq!(effective_value(&values), Some(20));
+
+        let values = decode(b"t=0 id1=2 id2=0 p=a v=10 n=1 s=0|t=0 id1=3 id2=0 p=a v=20 n=0 s=0");
+        assert_eq!(effective_value(&values), Some(10));
+
+        let values = decode(b"t=0 id1=2 id2=0 p=a v=10 n=0 s=0|t=3 id1=1 id2=5 p=a v=30 n=0 s=0");
+        assert_eq!(effective_value(&values), Some(30));
+
+        let values = decode(b"t=0 id1=2 id2=0 p=a v=10 n=0 s=1|t=3 id1=1 id2=5 p=a v=30 n=0 s=0");
+        assert_eq!(effective_value(&values), Some(10));
+
+        let values = decode(b"t=1 id1=2 id2=0 p=a v=5 n=0 s=0|t=2 id1=1 id2=0 p=a v=7 n=0 s=0");
+        assert_eq!(effective_value(&values), Some(7));
+    }
+}
@@ -0,0 +1,213 @@
+//! Moving clients back into the channels they are pinned to.
+//!
+//! A [`MoveProtection`] wraps an [`EventHandler`] and watches all `clientmoved` events.
+//! Pinned clients are moved back into their channel and clients moved out of a protected
+//! channel by someone else are moved back into it. Moves invoked by whitelisted clients and
+//! by the query client itself are ignored. All events are forwarded to the wrapped handler.
+//!
+//! The server does not include the previous channel in `clientmoved` events. It is tracked
+//! from all events received, clients connected before the events were registered are only
+//! known after calling [`MoveProtection::sync`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ts3::protect::MoveProtection;
+//! use ts3::request::ServerNotifyRegister;
+//! use ts3::shared::ChannelId;
+//! use ts3::Client;
+//!
+//! struct Handler;
+//!
+//! impl ts3::event::EventHandler for Handler {}
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let protection = MoveProtection::new(Handler)
+//!         .pin("xGhHmSoQ6zHVOYA2SsqGNMMpvZc=", ChannelId(4))
+//!         .protect_channel(ChannelId(7))
+//!         .whitelist("Ue2eHfMsL1/7ZGVkOkkWqD1N8NU=");
+//!     protection.sync(&client).await?;
+//!
+//!     client.set_event_handler(protection);
+//!     client
+//!         .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::event::{ClientEnterView, ClientMoved, Event, EventHandler};
+use crate::request::ClientListOptions;
+use crate::shared::{ChannelId, ClientId};
+use crate::{Client, Error};
+
+/// The unique identifier and current channel of a client.
+#[derive(Clone, Debug)]
+struct Tracked {
+    uid: String,
+    cid: ChannelId,
+}
+
+/// An [`EventHandler`] moving clients back into their channels. See the
+/// [module level documentation] for more details.
+///
+/// [module level documentation]: crate::protect
+pub struct MoveProtection<H> {
+    inner: H,
+    /// The channel of every pinned client, keyed by the unique identifier.
+    pinned: HashMap<String, ChannelId>,
+    channels: HashSet<ChannelId>,
+    whitelist: HashSet<String>,
+    clients: Mutex<HashMap<ClientId, Tracked>>,
+}
+
+impl<H> MoveProtection<H>
+where
+    H: EventHandler,
+{
+    /// Creates a new `MoveProtection` wrapping `inner` without any pinned clients or
+    /// protected channels.
+    pub fn new(inner: H) -> Self {
+        Self {
+            inner,
+            pinned: HashMap::new(),
+            channels: HashSet::new(),
+            whitelist: HashSet::new(),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Pins the client with the unique identifier `uid` to the channel `cid`. The client is
+    /// moved back whenever it is moved into another channel, including moves by the client
+    /// itself.
+    pub fn pin(mut self, uid: &str, cid: ChannelId) -> Self {
+        self.pinned.insert(uid.to_owned(), cid);
+        self
+    }
+
+    /// Protects the channel `cid`. Clients moved out of the channel by another client are
+    /// moved back into it, clients leaving it on their own are not.
+    pub fn protect_channel(mut self, cid: ChannelId) -> Self {
+        self.channels.insert(cid);
+        self
+    }
+
+    /// Ignores all moves invoked by the client with the unique identifier `uid`.
+    pub fn whitelist(mut self, uid: &str) -> Self {
+        self.whitelist.insert(uid.to_owned());
+        self
+    }
+
+    /// Requests the current channel of all clients on the selected virtual server. Without
+    /// it, only clients that entered the server after the `MoveProtection` was installed are
+    /// known.
+    pub async fn sync(&self, client: &Client) -> Result<(), Error> {
+        let list = client
+            .clientlist(ClientListOptions {
+                uid: true,
+                ..Default::default()
+            })
+            .await?;
+
+        let mut clients = self.clients.lock().unwrap();
+        for entry in list.iter().filter(|entry| !entry.is_query()) {
+            clients.insert(
+                entry.clid,
+                Tracked {
+                    uid: entry.client_unique_identifier.clone(),
+                    cid: entry.cid,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Updates the channel of the moved client and returns the channel it must be moved back
+    /// into, if any.
+    fn target(&self, client: &Client, event: &ClientMoved) -> Option<ChannelId> {
+        let (uid, prev) = {
+            let mut clients = self.clients.lock().unwrap();
+            let tracked = clients.get_mut(&event.clid)?;
+            let prev = std::mem::replace(&mut tracked.cid, event.ctid);
+            (tracked.uid.clone(), prev)
+        };
+
+        // Ignore the moves back by this client.
+        let me = client.me().map(|me| me.client_id);
+        if me == Some(event.invoker.invokerid) || self.whitelist.contains(&event.invoker.invokeruid)
+        {
+            return None;
+        }
+
+        match self.pinned.get(&uid) {
+            Some(&cid) if cid != event.ctid => Some(cid),
+            Some(_) => None,
+            None if self.channels.contains(&prev)
+                && prev != event.ctid
+                && event.invoker.invokerid != event.clid =>
+            {
+                Some(prev)
+            }
+            None => None,
+        }
+    }
+}
+
+impl<H> MoveProtection<H>
+where
+    H: EventHandler,
+{
+    fn track(&self, event: &ClientEnterView) {
+        // ServerQuery clients cannot be moved by other clients.
+        if event.client_type == 0 {
+            self.clients.lock().unwrap().insert(
+                event.clid,
+                Tracked {
+                    uid: event.client_unique_identifier.clone(),
+                    cid: event.ctid,
+                },
+            );
+        }
+    }
+
+    async fn move_back(&self, client: &Client, event: &ClientMoved) {
+        if let Some(cid) = self.target(client, event) {
+            if let Err(err) = client.clientmove(&[event.clid], cid, None).await {
+                self.inner.error(client.clone(), err);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<H> EventHandler for MoveProtection<H>
+where
+    H: EventHandler,
+{
+    async fn handle(&self, client: Client, event: Event) {
+        match &event {
+            Event::ClientEnterView(event) => self.track(event),
+            Event::ClientLeftView(event) => {
+                self.clients.lock().unwrap().remove(&event.clid);
+            }
+            Event::ClientMoved(event) => self.move_back(&client, event).await,
+            _ => (),
+        }
+
+        self.inner.handle(client, event).await
+    }
+
+    fn error(&self, client: Client, error: Error) {
+        self.inner.error(client, error)
+    }
+}
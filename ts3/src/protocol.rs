@@ -0,0 +1,238 @@
+//! Parsing of raw lines of the ServerQuery protocol.
+//!
+//! This is the same parser used by [`Client`], exposed for tools that process query
+//! traffic from other sources, like logs or packet captures. Together with [`Decode`],
+//! [`Response`] and the response types it does not depend on any runtime and is available
+//! with the `client` feature disabled.
+//!
+//! [`Client`]: crate::Client
+//! [`Decode`]: crate::Decode
+
+pub mod commands;
+
+use crate::response::Response;
+use crate::{Decode, Error, ErrorKind};
+
+/// The default maximum length of a line. See [`LineDecoder::with_max_line_length`].
+pub(crate) const DEFAULT_MAX_LINE_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Keys whose values are never passed to the `on_wire` hook of the client or stored in a
+/// [`DecodeContext`].
+///
+/// [`DecodeContext`]: crate::DecodeContext
+pub(crate) const REDACTED_KEYS: &[&str] = &[
+    "client_login_password",
+    "apikey",
+    "cpw",
+    "channel_password",
+    "virtualserver_password",
+    "password",
+    "pw",
+    "pw_clear",
+    "tcpw",
+    "token",
+];
+
+/// A single parsed line of the ServerQuery protocol.
+#[derive(Clone, Debug)]
+pub enum ParsedLine {
+    /// An `error` line, terminating the response to a command. An `id` of `0` indicates
+    /// success.
+    Error { id: u16, msg: String },
+    /// A notification, e.g. `notifycliententerview`.
+    Notify {
+        /// The name of the notification, including the `notify` prefix.
+        name: String,
+        data: Response,
+    },
+    /// The data of a response to a command.
+    Data(Response),
+}
+
+/// Parses a single line of the ServerQuery protocol. A trailing line terminator is ignored.
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the line is not valid.
+///
+/// # Examples
+///
+/// ```
+/// use ts3::protocol::{parse_line, ParsedLine};
+///
+/// match parse_line(b"error id=0 msg=ok").unwrap() {
+///     ParsedLine::Error { id, msg } => assert!(id == 0 && msg == "ok"),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn parse_line(line: &[u8]) -> Result<ParsedLine, Error> {
+    let line = line.trim_ascii_end();
+
+    let (first, rest) = match line.iter().position(|b| *b == b' ') {
+        Some(index) => (&line[..index], &line[index + 1..]),
+        None => (line, &[][..]),
+    };
+
+    if first == b"error" {
+        return match Error::decode(line)?.0 {
+            ErrorKind::TS3 { id, msg, .. } => Ok(ParsedLine::Error { id, msg }),
+            _ => unreachable!(),
+        };
+    }
+
+    if first.starts_with(b"notify") && !first.contains(&b'=') {
+        return Ok(ParsedLine::Notify {
+            name: String::decode(first)?,
+            data: Response::decode(rest)?,
+        });
+    }
+
+    Ok(ParsedLine::Data(Response::decode(line)?))
+}
+
+/// Splits bytes received from the server into lines.
+///
+/// Lines are terminated by `\n\r`, but `\r\n` and a lone `\r` are accepted as well.
+/// Blank lines are skipped and surrounding whitespace is removed, like the [`Client`] does.
+///
+/// # Examples
+///
+/// ```
+/// use ts3::protocol::{parse_line, LineDecoder, ParsedLine};
+///
+/// let mut decoder = LineDecoder::new();
+/// decoder.push(b"version=3.13.7 build=1655727713\n\rerror id=0 ");
+/// decoder.push(b"msg=ok\n\r");
+///
+/// let line = decoder.next_line().unwrap().unwrap();
+/// assert!(matches!(parse_line(&line).unwrap(), ParsedLine::Data(_)));
+///
+/// let line = decoder.next_line().unwrap().unwrap();
+/// assert!(matches!(parse_line(&line).unwrap(), ParsedLine::Error { id: 0, .. }));
+///
+/// assert!(decoder.next_line().is_none());
+/// ```
+///
+/// [`Client`]: crate::Client
+#[derive(Clone, Debug)]
+pub struct LineDecoder {
+    buf: Vec<u8>,
+    max_line_length: usize,
+}
+
+impl LineDecoder {
+    /// Creates a new `LineDecoder` accepting lines of up to 16 MiB.
+    pub fn new() -> Self {
+        Self::with_max_line_length(DEFAULT_MAX_LINE_LENGTH)
+    }
+
+    /// Creates a new `LineDecoder` accepting lines of up to `max_line_length` bytes.
+    pub fn with_max_line_length(max_line_length: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            max_line_length,
+        }
+    }
+
+    /// Appends `data` to the bytes not yet returned as a line.
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns the next complete line, without the terminator. Returns `None` if no complete
+    /// line was pushed yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the next line is longer than the maximum line length. The
+    /// buffered bytes are discarded.
+    pub fn next_line(&mut self) -> Option<Result<Vec<u8>, Error>> {
+        loop {
+            let Some(end) = memchr::memchr(b'\r', &self.buf) else {
+                if self.buf.len() > self.max_line_length.saturating_add(2) {
+                    self.buf.clear();
+                    return Some(Err(Error(ErrorKind::LineTooLong {
+                        max: self.max_line_length,
+                    })));
+                }
+
+                return None;
+            };
+
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            if let Some(line) = trim_line(&line) {
+                if line.len() > self.max_line_length {
+                    return Some(Err(Error(ErrorKind::LineTooLong {
+                        max: self.max_line_length,
+                    })));
+                }
+
+                return Some(Ok(line.to_vec()));
+            }
+        }
+    }
+}
+
+impl Default for LineDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns `line` without the line terminator and surrounding whitespace. Returns `None` if
+/// the line is blank.
+pub(crate) fn trim_line(line: &[u8]) -> Option<&[u8]> {
+    // Keys and values never contain unescaped whitespace.
+    let line = line.trim_ascii();
+    (!line.is_empty()).then_some(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_line, LineDecoder, ParsedLine};
+
+    #[test]
+    fn test_parse_line() {
+        match parse_line(b"error id=1281 msg=database\\sempty\\sresult\\sset\n\r").unwrap() {
+            ParsedLine::Error { id, msg } => {
+                assert_eq!(id, 1281);
+                assert_eq!(msg, "database empty result set");
+            }
+            _ => unreachable!(),
+        }
+
+        match parse_line(b"notifytextmessage targetmode=1 msg=Hi invokerid=5").unwrap() {
+            ParsedLine::Notify { name, data } => {
+                assert_eq!(name, "notifytextmessage");
+                assert_eq!(data[0].get::<String>("msg").unwrap(), "Hi");
+                assert_eq!(data[0].get::<u64>("invokerid").unwrap(), 5);
+            }
+            _ => unreachable!(),
+        }
+
+        match parse_line(b"clid=1 client_nickname=a|clid=2 client_nickname=b").unwrap() {
+            ParsedLine::Data(data) => {
+                assert_eq!(data.len(), 2);
+                assert_eq!(data[1].get::<String>("client_nickname").unwrap(), "b");
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_line_decoder() {
+        let mut decoder = LineDecoder::with_max_line_length(8);
+        decoder.push(b"a=1\n\r\n\rb=2\r\nc");
+
+        assert_eq!(decoder.next_line().unwrap().unwrap(), b"a=1");
+        assert_eq!(decoder.next_line().unwrap().unwrap(), b"b=2");
+        assert!(decoder.next_line().is_none());
+
+        decoder.push(b"=3\n\r");
+        assert_eq!(decoder.next_line().unwrap().unwrap(), b"c=3");
+
+        decoder.push(b"0123456789ab");
+        assert!(decoder.next_line().unwrap().is_err());
+        assert!(decoder.next_line().is_none());
+    }
+}
@@ -0,0 +1,471 @@
+//! A table of the ServerQuery commands and the arguments they accept.
+//!
+//! The table is checked in below and follows the command reference of the ServerQuery
+//! manual. Every entry lists the required and optional arguments, the flags (without the
+//! leading `-`) and the property prefixes of a command. A command accepts any argument
+//! starting with one of its property prefixes, e.g. `serveredit` accepts all
+//! `virtualserver_*` properties.
+//!
+//! For every command a function creating a [`CommandBuilder`] is generated, which validates
+//! the request against the table when it is built. Supporting a new server release only
+//! requires updating the table. The requests sent by the methods of [`Client`] are checked
+//! against the table in the integration tests.
+//!
+//! `use` is not part of the table. Servers must be selected using [`Client::use_sid`] or
+//! [`Client::use_port`] so that the client can track the selected server.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use ts3::protocol::commands;
+//! use ts3::response::Response;
+//!
+//! # #[cfg(feature = "client")]
+//! # async fn run(client: ts3::Client) -> Result<(), ts3::Error> {
+//! let req = commands::clientpoke().arg("clid", 5).arg("msg", "Hello").build()?;
+//! client.send::<Response, _>(req).await?;
+//!
+//! // Fails before anything is sent: `clientpoke` requires `msg`.
+//! assert!(commands::clientpoke().arg("clid", 5).build().is_err());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Client`]: crate::Client
+//! [`Client::use_sid`]: crate::Client::use_sid
+//! [`Client::use_port`]: crate::Client::use_port
+
+use crate::request::{Request, RequestBuilder};
+use crate::{Encode, Error, ErrorKind};
+
+/// The arguments accepted by a command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CommandSpec {
+    /// The name of the command.
+    pub name: &'static str,
+    /// Arguments that must be included in the request.
+    pub required: &'static [&'static str],
+    /// Arguments that may be included.
+    pub optional: &'static [&'static str],
+    /// Flags that may be included, without the leading `-`.
+    pub flags: &'static [&'static str],
+    /// Prefixes of properties that may be included, e.g. `virtualserver_`.
+    pub properties: &'static [&'static str],
+}
+
+impl CommandSpec {
+    /// Returns the spec of the command `name`. Returns `None` if the command is not in the
+    /// table.
+    pub fn get(name: &str) -> Option<&'static Self> {
+        COMMANDS.iter().find(|spec| spec.name == name)
+    }
+
+    /// Returns `true` if the command accepts the argument `key`.
+    pub fn accepts(&self, key: &str) -> bool {
+        self.required.contains(&key)
+            || self.optional.contains(&key)
+            || self
+                .properties
+                .iter()
+                .any(|prefix| key.starts_with(prefix) && key.len() > prefix.len())
+    }
+
+    /// Checks that `req` is a valid request for this command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if `req` is a different command, includes an argument or flag
+    /// not accepted by the command or is missing a required argument.
+    pub fn validate(&self, req: &Request) -> Result<(), Error> {
+        let (name, args) = split_command(req);
+        if name != self.name {
+            return Err(self.invalid(format!("request is a `{}` command", name)));
+        }
+
+        // Entries of a request with multiple entries share the arguments, so every
+        // required argument must be included in any of the entries.
+        let mut keys = Vec::new();
+
+        for arg in args.split([' ', '|']).filter(|arg| !arg.is_empty()) {
+            if let Some(flag) = arg.strip_prefix('-') {
+                if !self.flags.contains(&flag) {
+                    return Err(self.invalid(format!("unknown flag `{}`", arg)));
+                }
+                continue;
+            }
+
+            let Some((key, _)) = arg.split_once('=') else {
+                return Err(self.invalid(format!("argument `{}` has no value", arg)));
+            };
+            if !self.accepts(key) {
+                return Err(self.invalid(format!("unknown argument `{}`", key)));
+            }
+            keys.push(key);
+        }
+
+        if let Some(key) = self.required.iter().find(|key| !keys.contains(key)) {
+            return Err(self.invalid(format!("missing argument `{}`", key)));
+        }
+
+        Ok(())
+    }
+
+    fn invalid(&self, reason: String) -> Error {
+        Error(ErrorKind::InvalidRequest {
+            command: self.name.to_owned(),
+            reason,
+        })
+    }
+}
+
+/// Checks that `req` is a valid request for a command in the table. See
+/// [`CommandSpec::validate`].
+///
+/// # Errors
+///
+/// Returns an [`Error`] if the command is not in the table or the request is not valid.
+pub fn validate(req: &Request) -> Result<(), Error> {
+    let (name, _) = split_command(req);
+
+    match CommandSpec::get(name) {
+        Some(spec) => spec.validate(req),
+        None => Err(Error(ErrorKind::InvalidRequest {
+            command: name.to_owned(),
+            reason: "unknown command".to_owned(),
+        })),
+    }
+}
+
+/// Splits the request into the name of the command and its arguments.
+fn split_command(req: &Request) -> (&str, &str) {
+    req.buf.split_once(' ').unwrap_or((&req.buf, ""))
+}
+
+/// A builder for a command in the table. Created by the functions in this module.
+#[derive(Clone, Debug)]
+pub struct CommandBuilder {
+    spec: &'static CommandSpec,
+    builder: RequestBuilder,
+}
+
+impl CommandBuilder {
+    fn new(spec: &'static CommandSpec) -> Self {
+        Self {
+            spec,
+            builder: RequestBuilder::new(spec.name),
+        }
+    }
+
+    /// Returns the spec of the command.
+    pub fn spec(&self) -> &'static CommandSpec {
+        self.spec
+    }
+
+    /// Appends a key-value argument. See [`RequestBuilder::arg`].
+    pub fn arg<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: Encode,
+    {
+        self.builder = self.builder.arg(key, value);
+        self
+    }
+
+    /// Appends a flag, without the leading `-`.
+    pub fn flag<T>(mut self, flag: T) -> Self
+    where
+        T: AsRef<str>,
+    {
+        self.builder = self.builder.flag(format!("-{}", flag.as_ref()));
+        self
+    }
+
+    /// Starts a new entry. See [`RequestBuilder::pipe`].
+    pub fn pipe(mut self) -> Self {
+        self.builder = self.builder.pipe();
+        self
+    }
+
+    /// Builds the request, validating it against the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the request is not valid. See [`CommandSpec::validate`].
+    pub fn build(self) -> Result<Request, Error> {
+        let req = self.builder.build();
+        self.spec.validate(&req)?;
+        Ok(req)
+    }
+}
+
+/// Defines the table and a builder function for every command.
+macro_rules! commands {
+    ($(
+        $name:ident {
+            $(required: [$($req:ident),* $(,)?] $(,)?)?
+            $(optional: [$($opt:ident),* $(,)?] $(,)?)?
+            $(flags: [$($flag:ident),* $(,)?] $(,)?)?
+            $(properties: [$($prefix:ident),* $(,)?] $(,)?)?
+        }
+    )*) => {
+        /// All commands in the table.
+        pub static COMMANDS: &[CommandSpec] = &[$(
+            CommandSpec {
+                name: stringify!($name),
+                required: &[$($(stringify!($req)),*)?],
+                optional: &[$($(stringify!($opt)),*)?],
+                flags: &[$($(stringify!($flag)),*)?],
+                properties: &[$($(stringify!($prefix)),*)?],
+            },
+        )*];
+
+        $(
+            #[doc = concat!("Creates a `", stringify!($name), "` command.")]
+            pub fn $name() -> CommandBuilder {
+                // The table is small, the lookup is not worth a static per command.
+                CommandBuilder::new(CommandSpec::get(stringify!($name)).unwrap())
+            }
+        )*
+    };
+}
+
+commands! {
+    // Instance
+    login { optional: [client_login_name, client_login_password] }
+    logout {}
+    quit {}
+    version {}
+    hostinfo {}
+    instanceinfo {}
+    instanceedit { properties: [serverinstance_] }
+    bindinglist { optional: [subsystem] }
+    whoami {}
+    gm { required: [msg] }
+    logview { optional: [lines, reverse, instance, begin_pos] }
+    logadd { required: [loglevel, logmsg] }
+
+    // Virtual servers
+    serverlist { flags: [uid, short, all, onlyoffline] }
+    serveridgetbyport { required: [virtualserver_port] }
+    serverdelete { required: [sid] }
+    servercreate { required: [virtualserver_name], properties: [virtualserver_] }
+    serverstart { required: [sid] }
+    serverstop { required: [sid], optional: [reasonmsg] }
+    serverprocessstop { optional: [reasonmsg] }
+    serverinfo {}
+    serverrequestconnectioninfo {}
+    serveredit { properties: [virtualserver_] }
+    servernotifyregister { required: [event], optional: [id] }
+    servernotifyunregister {}
+    serversnapshotcreate {}
+    serversnapshotdeploy { optional: [version, salt, data], flags: [mapping, keepfiles] }
+    sendtextmessage { required: [targetmode, msg], optional: [target] }
+    servertemppasswordlist {}
+    servertemppasswordadd { required: [pw, desc, duration], optional: [tcid, tcpw] }
+    servertemppassworddel { required: [pw] }
+
+    // Server groups
+    servergrouplist {}
+    servergroupadd { required: [name], optional: [type] }
+    servergroupdel { required: [sgid, force] }
+    servergroupcopy { required: [ssgid, tsgid, name, type] }
+    servergrouprename { required: [sgid, name] }
+    servergrouppermlist { required: [sgid], flags: [permsid] }
+    servergroupaddperm {
+        required: [sgid, permvalue, permnegated, permskip],
+        optional: [permid, permsid],
+    }
+    servergroupdelperm { required: [sgid], optional: [permid, permsid] }
+    servergroupaddclient { required: [sgid, cldbid] }
+    servergroupdelclient { required: [sgid, cldbid] }
+    servergroupclientlist { required: [sgid], flags: [names] }
+    servergroupsbyclientid { required: [cldbid] }
+
+    // Channels
+    channellist { flags: [topic, flags, voice, limits, icon, secondsempty, banners] }
+    channelinfo { required: [cid] }
+    channelfind { optional: [pattern] }
+    channelmove { required: [cid, cpid], optional: [order] }
+    channelcreate { required: [channel_name], optional: [cpid], properties: [channel_] }
+    channeldelete { required: [cid, force] }
+    channeledit { required: [cid], optional: [cpid], properties: [channel_] }
+    channelpermlist { required: [cid], flags: [permsid] }
+    channeladdperm { required: [cid, permvalue], optional: [permid, permsid] }
+    channeldelperm { required: [cid], optional: [permid, permsid] }
+
+    // Channel groups
+    channelgrouplist {}
+    channelgroupadd { required: [name], optional: [type] }
+    channelgroupdel { required: [cgid, force] }
+    channelgroupcopy { required: [scgid, tcgid, name, type] }
+    channelgrouprename { required: [cgid, name] }
+    channelgroupaddperm { required: [cgid, permvalue], optional: [permid, permsid] }
+    channelgrouppermlist { required: [cgid], flags: [permsid] }
+    channelgroupdelperm { required: [cgid], optional: [permid, permsid] }
+    channelgroupclientlist { optional: [cid, cldbid, cgid] }
+    setclientchannelgroup { required: [cgid, cid, cldbid] }
+
+    // Clients
+    clientlist {
+        flags: [uid, away, voice, times, groups, info, icon, country, ip, badges],
+    }
+    clientinfo { required: [clid] }
+    clientfind { required: [pattern] }
+    clientedit { required: [clid], properties: [client_] }
+    clientdblist { optional: [start, duration], flags: [count] }
+    clientdbinfo { required: [cldbid] }
+    clientdbfind { required: [pattern], flags: [uid] }
+    clientdbedit { required: [cldbid], properties: [client_] }
+    clientdbdelete { required: [cldbid] }
+    clientgetids { required: [cluid] }
+    clientgetdbidfromuid { required: [cluid] }
+    clientgetnamefromuid { required: [cluid] }
+    clientgetnamefromdbid { required: [cldbid] }
+    clientgetuidfromclid { required: [clid] }
+    clientsetserverquerylogin { required: [client_login_name] }
+    clientupdate { properties: [client_] }
+    clientmove { required: [clid, cid], optional: [cpw] }
+    clientkick { required: [clid, reasonid], optional: [reasonmsg] }
+    clientpoke { required: [clid, msg] }
+    clientpermlist { required: [cldbid], flags: [permsid] }
+    clientaddperm { required: [cldbid, permvalue, permskip], optional: [permid, permsid] }
+    clientdelperm { required: [cldbid], optional: [permid, permsid] }
+    channelclientpermlist { required: [cid, cldbid], flags: [permsid] }
+    channelclientaddperm { required: [cid, cldbid, permvalue], optional: [permid, permsid] }
+    channelclientdelperm { required: [cid, cldbid], optional: [permid, permsid] }
+    customsearch { required: [ident, pattern] }
+    custominfo { required: [cldbid] }
+    customset { required: [cldbid, ident, value] }
+    customdelete { required: [cldbid, ident] }
+
+    // Permissions
+    permissionlist {}
+    permidgetbyname { required: [permsid] }
+    permoverview { required: [cid, cldbid], optional: [permid, permsid] }
+    permget { optional: [permid, permsid] }
+    permfind { optional: [permid, permsid] }
+    permreset {}
+    privilegekeylist {}
+    privilegekeyadd {
+        required: [tokentype, tokenid1, tokenid2],
+        optional: [tokendescription, tokencustomset],
+    }
+    privilegekeydelete { required: [token] }
+    privilegekeyuse { required: [token] }
+
+    // Offline messages and complaints
+    messagelist {}
+    messageadd { required: [cluid, subject, message] }
+    messagedel { required: [msgid] }
+    messageget { required: [msgid] }
+    messageupdateflag { required: [msgid, flag] }
+    complainlist { optional: [tcldbid] }
+    complainadd { required: [tcldbid, message] }
+    complaindelall { required: [tcldbid] }
+    complaindel { required: [tcldbid, fcldbid] }
+
+    // Bans
+    banclient { required: [clid], optional: [time, banreason] }
+    banlist { optional: [start, duration] }
+    banadd { optional: [ip, name, uid, mytsid, time, banreason, lastnickname] }
+    bandel { required: [banid] }
+    bandelall {}
+
+    // File transfers
+    ftinitupload {
+        required: [clientftfid, name, cid, cpw, size, overwrite, resume],
+        optional: [proto],
+    }
+    ftinitdownload { required: [clientftfid, name, cid, cpw, seekpos], optional: [proto] }
+    ftlist {}
+    ftgetfilelist { required: [cid, cpw, path] }
+    ftgetfileinfo { required: [cid, cpw, name] }
+    ftstop { required: [serverftfid, delete] }
+    ftdeletefile { required: [cid, cpw, name] }
+    ftcreatedir { required: [cid, cpw, dirname] }
+    ftrenamefile { required: [cid, cpw, oldname, newname], optional: [tcid, tcpw] }
+
+    // Query logins and API keys
+    auth { required: [apikey] }
+    queryloginlist { optional: [pattern, start, duration], flags: [count] }
+    queryloginadd { required: [client_login_name], optional: [cldbid] }
+    querylogindel { required: [cldbid] }
+    apikeyadd { required: [scope], optional: [lifetime, cldbid] }
+    apikeylist { optional: [cldbid, start, duration], flags: [count] }
+    apikeydel { required: [id] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, CommandSpec, COMMANDS};
+    use crate::request::RequestBuilder;
+
+    #[test]
+    fn test_commands_table() {
+        for (index, spec) in COMMANDS.iter().enumerate() {
+            assert!(
+                COMMANDS[..index]
+                    .iter()
+                    .all(|other| other.name != spec.name),
+                "duplicate command {}",
+                spec.name
+            );
+        }
+
+        let spec = CommandSpec::get("servergroupadd").unwrap();
+        assert_eq!(spec.optional, ["type"]);
+        assert!(CommandSpec::get("use").is_none());
+    }
+
+    #[test]
+    fn test_command_builder() {
+        let req = super::clientkick()
+            .arg("clid", 1)
+            .pipe()
+            .arg("clid", 2)
+            .arg("reasonid", 5)
+            .arg("reasonmsg", "bye")
+            .build()
+            .unwrap();
+        assert_eq!(req.buf, "clientkick clid=1|clid=2 reasonid=5 reasonmsg=bye");
+
+        let req = super::serverlist().flag("uid").build().unwrap();
+        assert_eq!(req.buf, "serverlist -uid");
+
+        assert!(super::clientkick().arg("clid", 1).build().is_err());
+        assert!(super::serverlist().flag("foo").build().is_err());
+        assert!(super::clientpoke()
+            .arg("clid", 1)
+            .arg("msg", "a")
+            .arg("cid", 2)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let req = RequestBuilder::new("serveredit")
+            .arg("virtualserver_name", "Test")
+            .arg("virtualserver_maxclients", 32)
+            .build();
+        validate(&req).unwrap();
+
+        let req = RequestBuilder::new("serveredit")
+            .arg("virtualserver_", "Test")
+            .build();
+        assert!(validate(&req).is_err());
+
+        let err = validate(&RequestBuilder::new("nope").build()).unwrap_err();
+        assert_eq!(err.to_string(), "invalid `nope` request: unknown command");
+
+        let err = CommandSpec::get("clientmove")
+            .unwrap()
+            .validate(&RequestBuilder::new("clientmove").arg("clid", 1).build())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid `clientmove` request: missing argument `cid`"
+        );
+    }
+}
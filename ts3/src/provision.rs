@@ -0,0 +1,134 @@
+//! Helpers for provisioning virtual servers, e.g. from a hosting panel.
+//!
+//! ```no_run
+//! use ts3::provision;
+//! use ts3::request::VirtualServerProperties;
+//! use ts3::Client;
+//!
+//! # async fn run(client: Client) -> Result<(), ts3::Error> {
+//! // The instance is licensed for 512 slots.
+//! provision::check_slots(&client, 32, 512).await?;
+//! let port = provision::free_port(&client, 9987..=10100).await?;
+//!
+//! let properties = VirtualServerProperties::new().maxclients(32).port(port);
+//! client.create_and_use("Customer", properties).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::RangeInclusive;
+
+use crate::client::Result;
+use crate::response::ServerListEntry;
+use crate::{Client, Error, ErrorKind};
+
+/// An error returned from the provisioning helpers. Returned from [`Error::as_provision`].
+///
+/// [`Error::as_provision`]: crate::Error::as_provision
+#[derive(Clone, Debug, PartialEq, Eq, Hash, thiserror::Error)]
+#[non_exhaustive]
+pub enum ProvisionError {
+    /// Every port in the range is used by a virtual server.
+    #[error("no free voice port in {start}-{end}")]
+    NoFreePort { start: u16, end: u16 },
+    /// The requested number of slots exceeds the slots left on the instance.
+    #[error("{requested} slots requested, but only {available} are available")]
+    NotEnoughSlots { requested: u32, available: u32 },
+}
+
+/// Returns the lowest port in `range` not used by any server in `servers`.
+pub fn find_free_port(servers: &[ServerListEntry], range: RangeInclusive<u16>) -> Option<u16> {
+    range.into_iter().find(|port| {
+        !servers
+            .iter()
+            .any(|server| server.virtualserver_port == *port)
+    })
+}
+
+/// Returns the lowest port in `range` not used by any virtual server of the instance,
+/// including stopped servers.
+///
+/// # Errors
+///
+/// Returns [`ProvisionError::NoFreePort`] if all ports in `range` are used.
+pub async fn free_port(client: &Client, range: RangeInclusive<u16>) -> Result<u16> {
+    let servers = client.serverlist().await?;
+
+    let err = ProvisionError::NoFreePort {
+        start: *range.start(),
+        end: *range.end(),
+    };
+    find_free_port(&servers, range).ok_or(Error(ErrorKind::Provision(err)))
+}
+
+/// Returns the sum of the slots of all servers in `servers`, including stopped servers.
+pub fn used_slots(servers: &[ServerListEntry]) -> u32 {
+    servers.iter().fold(0, |sum, server| {
+        sum.saturating_add(server.virtualserver_maxclients)
+    })
+}
+
+/// Checks that a new server with `maxclients` slots fits into the `license_slots` of the
+/// instance. The slots used by existing servers are read using [`Client::serverlist`].
+///
+/// Stopped servers are counted as well, since they use their slots again once they are
+/// started. [`Client::hostinfo`] only reports the slots of running servers.
+///
+/// The query interface does not expose the license of the instance, `license_slots` has
+/// to be provided by the caller.
+///
+/// # Errors
+///
+/// Returns [`ProvisionError::NotEnoughSlots`] if `maxclients` exceeds the remaining slots.
+pub async fn check_slots(client: &Client, maxclients: u32, license_slots: u32) -> Result<()> {
+    let servers = client.serverlist().await?;
+
+    let available = license_slots.saturating_sub(used_slots(&servers));
+    if maxclients > available {
+        return Err(Error(ErrorKind::Provision(
+            ProvisionError::NotEnoughSlots {
+                requested: maxclients,
+                available,
+            },
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_free_port, used_slots};
+    use crate::response::{ServerListEntry, VirtualServerStatus};
+
+    #[test]
+    fn test_find_free_port() {
+        let server = |port| ServerListEntry {
+            virtualserver_port: port,
+            ..Default::default()
+        };
+        let servers = [server(9987), server(9988), server(9990)];
+
+        assert_eq!(find_free_port(&servers, 9987..=10000), Some(9989));
+        assert_eq!(find_free_port(&servers, 9990..=10000), Some(9991));
+        assert_eq!(find_free_port(&servers, 9987..=9988), None);
+        assert_eq!(find_free_port(&[], 9987..=9987), Some(9987));
+    }
+
+    #[test]
+    fn test_used_slots() {
+        let server = |maxclients, status| ServerListEntry {
+            virtualserver_maxclients: maxclients,
+            virtualserver_status: status,
+            ..Default::default()
+        };
+        let servers = [
+            server(32, VirtualServerStatus::Online),
+            server(64, VirtualServerStatus::Offline),
+            server(10, VirtualServerStatus::Online),
+        ];
+
+        assert_eq!(used_slots(&servers), 106);
+        assert_eq!(used_slots(&[]), 0);
+    }
+}
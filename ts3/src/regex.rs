@@ -0,0 +1,116 @@
+//! Regular expressions used by the server, e.g. in ban rules.
+//!
+//! The patterns are server data and may be arbitrarily complex. They are compiled using the
+//! `regex` crate, which matches in linear time. Patterns exceeding the size limit are
+//! rejected instead of being compiled.
+
+use regex::RegexBuilder;
+
+/// The maximum size of a compiled expression in bytes.
+const SIZE_LIMIT: usize = 1 << 20;
+
+/// A compiled regular expression matching the whole input.
+#[derive(Clone, Debug)]
+pub(crate) struct Regex {
+    inner: regex::Regex,
+}
+
+impl Regex {
+    /// Compiles `pattern`. Returns `None` if the pattern is invalid, uses unsupported syntax
+    /// or is too large.
+    pub(crate) fn new(pattern: &str, case_insensitive: bool) -> Option<Self> {
+        let inner = RegexBuilder::new(&format!("^(?:{})$", pattern))
+            .case_insensitive(case_insensitive)
+            .size_limit(SIZE_LIMIT)
+            .build()
+            .ok()?;
+
+        Some(Self { inner })
+    }
+
+    /// Returns `true` if the whole `input` matches the expression.
+    pub(crate) fn is_match(&self, input: &str) -> bool {
+        self.inner.is_match(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Regex;
+
+    fn is_match(pattern: &str, input: &str) -> bool {
+        Regex::new(pattern, false).unwrap().is_match(input)
+    }
+
+    #[test]
+    fn test_regex_literal() {
+        assert!(is_match("abc", "abc"));
+        assert!(!is_match("abc", "abcd"));
+        assert!(!is_match("abc", "xabc"));
+        assert!(is_match("", ""));
+        assert!(is_match("1\\.2\\.3\\.4", "1.2.3.4"));
+        assert!(!is_match("1\\.2\\.3\\.4", "1x2.3.4"));
+    }
+
+    #[test]
+    fn test_regex_quantifiers() {
+        assert!(is_match("a*", ""));
+        assert!(is_match("a*b", "aaab"));
+        assert!(!is_match("a+b", "b"));
+        assert!(is_match("colou?r", "color"));
+        assert!(is_match("colou?r", "colour"));
+        assert!(is_match("\\d{1,3}", "255"));
+        assert!(!is_match("\\d{1,3}", "2550"));
+        assert!(is_match("a{2}", "aa"));
+        assert!(is_match("a{2,}", "aaaa"));
+        assert!(is_match("(a*)*b", "aab"));
+        assert!(is_match(".*bot.*", "my bot 1"));
+    }
+
+    #[test]
+    fn test_regex_classes() {
+        assert!(is_match("[a-c]+", "abcabc"));
+        assert!(!is_match("[a-c]+", "abd"));
+        assert!(is_match("[^0-9]", "x"));
+        assert!(!is_match("[^0-9]", "5"));
+        assert!(is_match("[\\d.]+", "10.0.0.1"));
+        assert!(is_match("[-a]", "-"));
+        assert!(is_match("[]]", "]"));
+        assert!(is_match("\\w+\\s\\w+", "hello world"));
+    }
+
+    #[test]
+    fn test_regex_groups() {
+        assert!(is_match("(foo|bar)baz", "barbaz"));
+        assert!(is_match("(?:foo|bar)+", "foobarfoo"));
+        assert!(is_match("^10\\.0\\.0\\.(1|2)$", "10.0.0.2"));
+        assert!(is_match("a|b|", ""));
+    }
+
+    #[test]
+    fn test_regex_case_insensitive() {
+        let regex = Regex::new("[a-z]+bot", true).unwrap();
+        assert!(regex.is_match("MusicBot"));
+        assert!(!Regex::new("[a-z]+bot", false).unwrap().is_match("MusicBot"));
+    }
+
+    #[test]
+    fn test_regex_invalid() {
+        for pattern in ["(", ")", "[a", "*", "a{2,1}", "[z-a]", "(?=a)"] {
+            assert!(Regex::new(pattern, false).is_none(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_regex_pathological() {
+        // Exponential with a backtracking matcher.
+        let start = Instant::now();
+        assert!(!is_match("(a|a)*b", &"a".repeat(25)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Too large to compile.
+        assert!(Regex::new("((x?){1000}){1000}", false).is_none());
+    }
+}
@@ -1,6 +1,9 @@
+use std::fmt::{self, Debug, Formatter};
+use std::time::Duration;
+
 use crate::{
-    types::{ChannelId, ClientId},
-    Encode,
+    types::{ChannelId, ClientId, IconId},
+    Decode, Encode,
 };
 
 /// An encoded request buffer.
@@ -33,7 +36,9 @@ impl RequestBuilder {
         K: AsRef<str>,
         V: Encode,
     {
-        self.buf += " ";
+        if !self.buf.ends_with('|') {
+            self.buf += " ";
+        }
         self.buf += key.as_ref();
         self.buf += "=";
         value.encode(&mut self.buf);
@@ -49,6 +54,13 @@ impl RequestBuilder {
         self
     }
 
+    /// Starts a new entry in the request. All arguments appended after this call are part of
+    /// the new entry. This is used by commands accepting multiple entries separated by a `|`.
+    pub fn pipe(mut self) -> Self {
+        self.buf += "|";
+        self
+    }
+
     /// Consumes this `RequestBuilder`, returning the constructed [`Request`].
     #[inline]
     pub fn build(self) -> Request {
@@ -63,6 +75,63 @@ impl From<RequestBuilder> for Request {
     }
 }
 
+/// A command with a typed response, executed with [`Client::execute`].
+///
+/// This allows adding commands that are not part of this crate, e.g. extensions of other
+/// servers, that are used like the built-in commands.
+///
+/// ```
+/// use ts3::request::{RequestBuilder, TypedCommand};
+/// use ts3::shared::list::Pipe;
+/// use ts3::shared::List;
+/// use ts3::Decode;
+///
+/// /// The `musicbotlist` command of TeaSpeak servers.
+/// struct MusicBotList {
+///     owner: Option<u64>,
+/// }
+///
+/// #[derive(Debug, Decode, Default)]
+/// struct MusicBot {
+///     client_id: u64,
+///     client_nickname: String,
+/// }
+///
+/// impl TypedCommand for MusicBotList {
+///     type Response = List<MusicBot, Pipe>;
+///
+///     const NAME: &'static str = "musicbotlist";
+///
+///     fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+///         match self.owner {
+///             Some(owner) => req.arg("owner", owner),
+///             None => req,
+///         }
+///     }
+/// }
+///
+/// # #[cfg(feature = "client")]
+/// # async fn run(client: ts3::Client) -> Result<(), ts3::Error> {
+/// let bots = client.execute(MusicBotList { owner: None }).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Client::execute`]: crate::Client::execute
+pub trait TypedCommand {
+    /// The type the response is decoded into. Use `()` for commands without a response.
+    type Response: Decode;
+
+    /// The name of the command.
+    const NAME: &'static str;
+
+    /// Appends the arguments of the command to `req`, which contains the name of the
+    /// command. Returns `req` unchanged by default.
+    fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+        req
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ServerNotifyRegister {
     Server,
@@ -101,9 +170,341 @@ impl Encode for TextMessageTarget {
     }
 }
 
+/// Options for [`Client.clientlist`]. Every enabled option includes additional fields in
+/// the response.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ClientListOptions {
+    pub uid: bool,
+    pub away: bool,
+    pub voice: bool,
+    pub times: bool,
+    pub groups: bool,
+    pub info: bool,
+    pub icon: bool,
+    pub country: bool,
+    pub ip: bool,
+    pub badges: bool,
+}
+
+impl ClientListOptions {
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        let flags = [
+            (self.uid, "-uid"),
+            (self.away, "-away"),
+            (self.voice, "-voice"),
+            (self.times, "-times"),
+            (self.groups, "-groups"),
+            (self.info, "-info"),
+            (self.icon, "-icon"),
+            (self.country, "-country"),
+            (self.ip, "-ip"),
+            (self.badges, "-badges"),
+        ];
+
+        for (enabled, flag) in flags {
+            if enabled {
+                req = req.flag(flag);
+            }
+        }
+
+        req
+    }
+}
+
+/// Options for [`Client::use_sid_with`] and [`Client::use_port_with`].
+///
+/// [`Client::use_sid_with`]: crate::Client::use_sid_with
+/// [`Client::use_port_with`]: crate::Client::use_port_with
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UseOptions<'a> {
+    /// Selects the server in virtual mode. This allows selecting and configuring stopped
+    /// servers.
+    pub r#virtual: bool,
+    /// The nickname of the query client on the selected server.
+    pub client_nickname: Option<&'a str>,
+}
+
+impl<'a> UseOptions<'a> {
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        if let Some(client_nickname) = self.client_nickname {
+            req = req.arg("client_nickname", client_nickname);
+        }
+
+        if self.r#virtual {
+            req = req.flag("-virtual");
+        }
+
+        req
+    }
+}
+
+/// Options for [`Client::queryloginlist`].
+///
+/// [`Client::queryloginlist`]: crate::Client::queryloginlist
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct QueryLoginListOptions<'a> {
+    /// Only lists logins with a login name matching the pattern. The pattern supports the SQL
+    /// wildcards `%` and `_`.
+    pub pattern: Option<&'a str>,
+    /// The offset of the first returned login.
+    pub start: Option<u64>,
+    /// The maximum number of returned logins.
+    pub duration: Option<u64>,
+    /// Includes the total number of matching logins in the `count` field of the first entry.
+    pub count: bool,
+}
+
+impl<'a> QueryLoginListOptions<'a> {
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        if let Some(pattern) = self.pattern {
+            req = req.arg("pattern", pattern);
+        }
+        if let Some(start) = self.start {
+            req = req.arg("start", start);
+        }
+        if let Some(duration) = self.duration {
+            req = req.arg("duration", duration);
+        }
+
+        if self.count {
+            req = req.flag("-count");
+        }
+
+        req
+    }
+}
+
+/// Properties of a client changed using [`Client::clientedit`] and
+/// [`Client::clientdbedit`]. Only properties which were set are sent.
+///
+/// ```
+/// use ts3::request::ClientProperties;
+///
+/// let properties = ClientProperties::new()
+///     .description("Moderator")
+///     .is_talker(true);
+/// ```
+///
+/// [`Client::clientedit`]: crate::Client::clientedit
+/// [`Client::clientdbedit`]: crate::Client::clientdbedit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ClientProperties<'a> {
+    description: Option<&'a str>,
+    icon_id: Option<IconId>,
+    is_talker: Option<bool>,
+}
+
+impl<'a> ClientProperties<'a> {
+    /// Creates a new `ClientProperties` without any properties set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the description of the client.
+    pub fn description(mut self, description: &'a str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Sets the id of the icon shown next to the client.
+    pub fn icon_id(mut self, icon_id: IconId) -> Self {
+        self.icon_id = Some(icon_id);
+        self
+    }
+
+    /// Allows the client to talk in moderated channels regardless of their talk power. Only
+    /// supported by [`Client::clientedit`].
+    ///
+    /// [`Client::clientedit`]: crate::Client::clientedit
+    pub fn is_talker(mut self, is_talker: bool) -> Self {
+        self.is_talker = Some(is_talker);
+        self
+    }
+
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        if let Some(description) = self.description {
+            req = req.arg("client_description", description);
+        }
+        if let Some(icon_id) = self.icon_id {
+            req = req.arg("client_icon_id", icon_id);
+        }
+        if let Some(is_talker) = self.is_talker {
+            req = req.arg("client_is_talker", is_talker);
+        }
+
+        req
+    }
+}
+
+/// Properties of a virtual server changed using [`Client::serveredit`]. Only properties
+/// which were set are sent.
+///
+/// ```
+/// use ts3::request::VirtualServerProperties;
+///
+/// let properties = VirtualServerProperties::new()
+///     .maxclients(64)
+///     .welcomemessage("Welcome!");
+/// ```
+///
+/// [`Client::serveredit`]: crate::Client::serveredit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VirtualServerProperties<'a> {
+    name: Option<&'a str>,
+    welcomemessage: Option<&'a str>,
+    maxclients: Option<u32>,
+    password: Option<&'a str>,
+    hostmessage: Option<&'a str>,
+    port: Option<u16>,
+}
+
+impl<'a> VirtualServerProperties<'a> {
+    /// Creates a new `VirtualServerProperties` without any properties set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the server.
+    pub fn name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets the message sent to clients joining the server.
+    pub fn welcomemessage(mut self, welcomemessage: &'a str) -> Self {
+        self.welcomemessage = Some(welcomemessage);
+        self
+    }
+
+    /// Sets the maximum number of clients connected at the same time.
+    pub fn maxclients(mut self, maxclients: u32) -> Self {
+        self.maxclients = Some(maxclients);
+        self
+    }
+
+    /// Sets the password required to join the server. An empty password removes it.
+    pub fn password(mut self, password: &'a str) -> Self {
+        self.password = Some(password);
+        self
+    }
+
+    /// Sets the message shown to clients in the host message box.
+    pub fn hostmessage(mut self, hostmessage: &'a str) -> Self {
+        self.hostmessage = Some(hostmessage);
+        self
+    }
+
+    /// Sets the voice port of the server.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Returns `true` if no property is set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        if let Some(name) = self.name {
+            req = req.arg("virtualserver_name", name);
+        }
+        if let Some(welcomemessage) = self.welcomemessage {
+            req = req.arg("virtualserver_welcomemessage", welcomemessage);
+        }
+        if let Some(maxclients) = self.maxclients {
+            req = req.arg("virtualserver_maxclients", maxclients);
+        }
+        if let Some(password) = self.password {
+            req = req.arg("virtualserver_password", password);
+        }
+        if let Some(hostmessage) = self.hostmessage {
+            req = req.arg("virtualserver_hostmessage", hostmessage);
+        }
+        if let Some(port) = self.port {
+            req = req.arg("virtualserver_port", port);
+        }
+
+        req
+    }
+}
+
+/// A temporary server password added using [`Client::servertemppasswordadd`].
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use ts3::request::TempPassword;
+/// use ts3::shared::ChannelId;
+///
+/// let password = TempPassword::new("secret", "Event guests", Duration::from_secs(3600))
+///     .channel(ChannelId(5), Some("channel secret"));
+/// ```
+///
+/// [`Client::servertemppasswordadd`]: crate::Client::servertemppasswordadd
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TempPassword<'a> {
+    pw: &'a str,
+    desc: &'a str,
+    duration: Duration,
+    channel: Option<(ChannelId, Option<&'a str>)>,
+}
+
+impl<'a> TempPassword<'a> {
+    /// Creates a new temporary password `pw` valid for `duration`. The duration is rounded
+    /// down to full seconds.
+    pub fn new(pw: &'a str, desc: &'a str, duration: Duration) -> Self {
+        Self {
+            pw,
+            desc,
+            duration,
+            channel: None,
+        }
+    }
+
+    /// Moves clients joining with the password to the channel `tcid`. `tcpw` is the
+    /// password of the channel, if it has one.
+    pub fn channel(mut self, tcid: ChannelId, tcpw: Option<&'a str>) -> Self {
+        self.channel = Some((tcid, tcpw));
+        self
+    }
+
+    pub(crate) fn apply(&self, mut req: RequestBuilder) -> RequestBuilder {
+        req = req
+            .arg("pw", self.pw)
+            .arg("desc", self.desc)
+            .arg("duration", self.duration.as_secs());
+
+        if let Some((tcid, tcpw)) = self.channel {
+            req = req.arg("tcid", tcid).arg("tcpw", tcpw.unwrap_or_default());
+        }
+
+        req
+    }
+}
+
+impl Debug for TempPassword<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TempPassword")
+            .field("pw", &"<redacted>")
+            .field("desc", &self.desc)
+            .field("duration", &self.duration)
+            .field(
+                "channel",
+                &self
+                    .channel
+                    .map(|(tcid, tcpw)| (tcid, tcpw.map(|_| "<redacted>"))),
+            )
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::RequestBuilder;
+    use std::time::Duration;
+
+    use super::{ClientProperties, QueryLoginListOptions, RequestBuilder, TempPassword};
+    use crate::shared::ChannelId;
 
     #[test]
     fn test_request_builder() {
@@ -115,5 +516,66 @@ mod tests {
 
         let cmd = cmd.arg("test", "1234");
         assert_eq!(cmd.clone().buf, "testcmd hello=world test=1234");
+
+        let cmd = cmd.pipe().arg("test", "5678");
+        assert_eq!(cmd.clone().buf, "testcmd hello=world test=1234|test=5678");
+    }
+
+    #[test]
+    fn test_query_login_list_options() {
+        let req = QueryLoginListOptions::default().apply(RequestBuilder::new("queryloginlist"));
+        assert_eq!(req.buf, "queryloginlist");
+
+        let options = QueryLoginListOptions {
+            pattern: Some("bot%"),
+            start: Some(50),
+            duration: Some(25),
+            count: true,
+        };
+        let req = options.apply(RequestBuilder::new("queryloginlist"));
+        assert_eq!(
+            req.buf,
+            "queryloginlist pattern=bot% start=50 duration=25 -count"
+        );
+    }
+
+    #[test]
+    fn test_client_properties() {
+        let req = ClientProperties::new().apply(RequestBuilder::new("clientedit"));
+        assert_eq!(req.buf, "clientedit");
+
+        let req = ClientProperties::new()
+            .description("Hello World")
+            .is_talker(false)
+            .apply(RequestBuilder::new("clientedit"));
+        assert_eq!(
+            req.buf,
+            "clientedit client_description=Hello\\sWorld client_is_talker=0"
+        );
+    }
+
+    #[test]
+    fn test_temp_password() {
+        let req = TempPassword::new("secret", "Guests", Duration::from_secs(60))
+            .apply(RequestBuilder::new("servertemppasswordadd"));
+        assert_eq!(
+            req.buf,
+            "servertemppasswordadd pw=secret desc=Guests duration=60"
+        );
+
+        let req = TempPassword::new("secret", "Guests", Duration::from_secs(60))
+            .channel(ChannelId(5), None)
+            .apply(RequestBuilder::new("servertemppasswordadd"));
+        assert_eq!(
+            req.buf,
+            "servertemppasswordadd pw=secret desc=Guests duration=60 tcid=5 tcpw="
+        );
+
+        let req = TempPassword::new("secret", "Guests", Duration::from_secs(60))
+            .channel(ChannelId(5), Some("hunter2"));
+        let debug = format!("{:?}", req);
+        assert!(!debug.contains("secret"));
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains("Guests"));
     }
 }
@@ -1,10 +1,18 @@
 //! Response types returned by client requests
 
 use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
 use std::ops::Deref;
+use std::time::Duration;
 
-use crate::shared::ApiKeyScope;
-use crate::types::{ApiKeyId, ChannelId, ClientDatabaseId, ClientId, ServerId};
+use bytes::Bytes;
+
+use crate::shared::list::{bytes_split, Comma};
+use crate::shared::{ApiKeyScope, CountryCode, GroupType, List};
+use crate::types::{
+    ApiKeyId, ChannelGroupId, ChannelId, ClientDatabaseId, ClientId, IconId, ServerGroupId,
+    ServerId,
+};
 use crate::{Decode, DecodeError, Error, ErrorKind};
 
 /// A raw response of at least one [`Entry`].
@@ -26,10 +34,12 @@ impl Decode for Response {
     type Error = Error;
 
     fn decode(buf: &[u8]) -> Result<Self, Self::Error> {
-        let mut entries = Vec::new();
+        // All entries share the same buffer.
+        let buf = Bytes::copy_from_slice(buf);
 
-        for entry in buf.split(|b| *b == b'|') {
-            let entry = Entry::decode(entry)?;
+        let mut entries = Vec::new();
+        for entry in bytes_split(&buf, b"|") {
+            let entry = Entry::from_bytes(buf.slice_ref(entry))?;
             entries.push(entry);
         }
 
@@ -37,17 +47,48 @@ impl Decode for Response {
     }
 }
 
+impl Response {
+    /// Returns the only entry of the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] containing the number of entries if the response does not contain
+    /// exactly one entry.
+    pub fn into_single(mut self) -> Result<Entry, Error> {
+        match self.entries.len() {
+            1 => Ok(self.entries.remove(0)),
+            n => Err(Error(DecodeError::UnexpectedEntries(n).into())),
+        }
+    }
+
+    /// Returns all entries of the response.
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+}
+
 /// A single entry of key-value pairs.
-#[derive(Clone, Debug)]
+///
+/// Keys and values are not copied out of the response, fields are only decoded when they are
+/// accessed.
+#[derive(Clone, Default)]
 pub struct Entry {
-    fields: HashMap<String, Option<String>>,
+    buf: Bytes,
+    fields: Vec<Field>,
+}
+
+/// The position of a key-value pair in the buffer of an [`Entry`].
+#[derive(Copy, Clone, Debug)]
+struct Field {
+    key: (usize, usize),
+    value: Option<(usize, usize)>,
 }
 
 impl Entry {
     /// Returns `true` if the `Entry` contains the given `key`.
     #[inline]
     pub fn contains(&self, key: &str) -> bool {
-        self.fields.contains_key(key)
+        self.field(key).is_some()
     }
 
     /// Parses and returns the value of a given `key` as `T`.
@@ -61,67 +102,327 @@ impl Entry {
         T: Decode,
         T::Error: Into<Error>,
     {
-        let Some(value) = self.fields.get(key) else {
+        let Some(value) = self.value(key) else {
             return Err(Error(ErrorKind::NoField));
         };
 
-        let Some(value) = value else {
-            return Err(Error(ErrorKind::NoField));
-        };
+        T::decode(value).map_err(|e| e.into())
+    }
 
-        T::decode(value.as_bytes()).map_err(|e| e.into())
+    /// Parses and returns the value of a given `key` as `T`. Returns `default` if the `key`
+    /// does not exist or contains no value.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error`] if the value cannot be decoded into `T`.
+    pub fn get_or<T>(&self, key: &str, default: T) -> Result<T, Error>
+    where
+        T: Decode,
+        T::Error: Into<Error>,
+    {
+        match self.value(key) {
+            Some(value) => T::decode(value).map_err(|e| e.into()),
+            None => Ok(default),
+        }
     }
-}
 
-impl Decode for Entry {
-    type Error = Error;
+    /// Parses and returns the value of a given `key` as `T`. Returns the default value of `T`
+    /// if the `key` does not exist or contains no value.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error`] if the value cannot be decoded into `T`.
+    pub fn get_or_default<T>(&self, key: &str) -> Result<T, Error>
+    where
+        T: Decode + Default,
+        T::Error: Into<Error>,
+    {
+        self.get_or(key, T::default())
+    }
 
-    fn decode(buf: &[u8]) -> Result<Self, Self::Error> {
-        let mut entry = HashMap::new();
+    /// Parses and returns the value of a given `key` as `T`. Returns `None` if the `key` does
+    /// not exist, contains no value or cannot be decoded into `T`.
+    pub fn get_parsed_or_none<T>(&self, key: &str) -> Option<T>
+    where
+        T: Decode,
+    {
+        T::decode(self.value(key)?).ok()
+    }
 
-        // KV pairs separated by ' '.
-        for item in buf.split(|c| *c == b' ') {
-            let mut parts = item.splitn(2, |c| *c == b'=');
+    /// Decodes the whole `Entry` as `T`. This allows using the typed response structs with
+    /// the entries of a raw [`Response`].
+    ///
+    /// ```
+    /// # use ts3::Decode;
+    /// # use ts3::response::{Response, Version};
+    /// let resp = Response::decode(b"version=3.13.7 build=1655727713 platform=Linux").unwrap();
+    ///
+    /// let version = resp[0].to::<Version>().unwrap();
+    /// assert_eq!(version.build, 1655727713);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function returns an [`Error`] if the `Entry` cannot be decoded into `T`.
+    pub fn to<T>(&self) -> Result<T, Error>
+    where
+        T: Decode,
+        T::Error: Into<Error>,
+    {
+        T::decode(&self.buf).map_err(|e| e.into())
+    }
 
-            let Some(key) = parts.next() else {
-                return Err(Error(DecodeError::UnexpectedEof.into()));
-            };
+    /// Returns the value of the field with the given `key`. Returns `None` if the key does not
+    /// exist or contains no value.
+    fn value(&self, key: &str) -> Option<&[u8]> {
+        let (start, end) = self.field(key)?.value?;
+        Some(&self.buf[start..end])
+    }
+
+    /// Returns the field with the given `key`. If the key exists multiple times, the last
+    /// field is returned.
+    fn field(&self, key: &str) -> Option<&Field> {
+        self.fields
+            .iter()
+            .rev()
+            .find(|field| &self.buf[field.key.0..field.key.1] == key.as_bytes())
+    }
 
-            let key = match std::str::from_utf8(key) {
-                Ok(key) => key.to_owned(),
-                Err(err) => return Err(Error(err.into())),
+    /// Creates a new `Entry` from a buffer containing a single entry.
+    fn from_bytes(buf: Bytes) -> Result<Self, Error> {
+        let mut fields = Vec::new();
+
+        // KV pairs separated by ' '.
+        for item in bytes_split(&buf, b" ") {
+            let offset = item.as_ptr() as usize - buf.as_ptr() as usize;
+
+            let (key, value) = match memchr::memchr(b'=', item) {
+                Some(index) => (&item[..index], Some(&item[index + 1..])),
+                None => (item, None),
             };
 
-            let value = match parts.next() {
-                Some(value) => {
-                    let value = match std::str::from_utf8(value) {
-                        Ok(value) => value,
-                        Err(err) => return Err(Error(err.into())),
-                    };
+            if let Err(err) = std::str::from_utf8(key) {
+                return Err(Error(err.into()));
+            }
 
-                    Some(value.to_owned())
+            if let Some(value) = value {
+                if let Err(err) = std::str::from_utf8(value) {
+                    return Err(Error(err.into()));
                 }
-                None => None,
-            };
+            }
 
-            entry.insert(key, value);
+            fields.push(Field {
+                key: (offset, offset + key.len()),
+                value: value.map(|_| (offset + key.len() + 1, offset + item.len())),
+            });
         }
 
-        Ok(Self { fields: entry })
+        Ok(Self { buf, fields })
+    }
+}
+
+impl Debug for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.fields.iter().map(|field| {
+                let key = String::from_utf8_lossy(&self.buf[field.key.0..field.key.1]);
+                let value = field
+                    .value
+                    .map(|(start, end)| String::from_utf8_lossy(&self.buf[start..end]));
+                (key, value)
+            }))
+            .finish()
+    }
+}
+
+impl Decode for Entry {
+    type Error = Error;
+
+    fn decode(buf: &[u8]) -> Result<Self, Self::Error> {
+        Response::decode(buf)?.into_single()
     }
 }
 
 /// Data returned from the `version` command.
 #[derive(Debug, Decode, Default)]
+#[non_exhaustive]
 pub struct Version {
     pub version: String,
+    /// The build number. TeaSpeak sends non-numeric builds, which are decoded as `0`.
+    #[ts3(tolerant)]
     pub build: u64,
     pub platform: String,
-    _priv: (),
+}
+
+/// Information about the server instance returned from [`Client.hostinfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct HostInfo {
+    /// The uptime of the instance in seconds.
+    pub instance_uptime: u64,
+    /// Unix timestamp of the current time on the host.
+    pub host_timestamp_utc: u64,
+    pub virtualservers_running_total: u32,
+    pub virtualservers_total_maxclients: u32,
+    pub virtualservers_total_clients_online: u32,
+    pub virtualservers_total_channels_online: u32,
+    pub connection_bandwidth_sent_last_second_total: u64,
+    pub connection_bandwidth_received_last_second_total: u64,
+    pub connection_bytes_sent_total: u64,
+    pub connection_bytes_received_total: u64,
+}
+
+impl HostInfo {
+    /// Returns the time since the server instance was started.
+    pub fn uptime(&self) -> Duration {
+        Duration::from_secs(self.instance_uptime)
+    }
+}
+
+/// A virtual server returned from [`Client.serverlist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ServerListEntry {
+    pub virtualserver_id: ServerId,
+    pub virtualserver_port: u16,
+    pub virtualserver_status: VirtualServerStatus,
+    pub virtualserver_clientsonline: u32,
+    pub virtualserver_queryclientsonline: u32,
+    pub virtualserver_maxclients: u32,
+    /// The uptime of the server in seconds.
+    pub virtualserver_uptime: u64,
+    pub virtualserver_name: String,
+    pub virtualserver_autostart: bool,
+    pub virtualserver_machine_id: String,
+}
+
+impl ServerListEntry {
+    /// Returns the time since the virtual server was started.
+    pub fn uptime(&self) -> Duration {
+        Duration::from_secs(self.virtualserver_uptime)
+    }
+}
+
+/// A temporary server password returned from [`Client.servertemppasswordlist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct TempPasswordEntry {
+    /// The nickname of the client that created the password.
+    pub nickname: String,
+    /// The unique id of the client that created the password.
+    pub uid: String,
+    pub desc: String,
+    /// The password in plain text.
+    pub pw_clear: String,
+    /// Unix timestamp of the creation of the password.
+    pub start: u64,
+    /// Unix timestamp at which the password expires.
+    pub end: u64,
+    /// The channel clients joining with the password are moved to.
+    pub tcid: ChannelId,
+}
+
+/// A file or directory returned from [`Client.ftgetfilelist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct FileEntry {
+    /// The channel containing the file. Only included in the first entry of a response.
+    pub cid: ChannelId,
+    /// The directory containing the file. Only included in the first entry of a response.
+    pub path: String,
+    pub name: String,
+    /// The size of the file in bytes. `0` for directories.
+    pub size: u64,
+    /// Unix timestamp of the last modification.
+    pub datetime: u64,
+    /// `0` for directories, `1` for files.
+    pub r#type: u8,
+}
+
+impl FileEntry {
+    /// Returns `true` if the entry is a directory.
+    pub fn is_directory(&self) -> bool {
+        self.r#type == 0
+    }
+}
+
+/// A file transfer initialized using [`Client.ftinitdownload`] or [`Client.ftinitupload`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct FileTransferInit {
+    pub clientftfid: u16,
+    pub serverftfid: u16,
+    /// The key sent over the file transfer connection to identify the transfer.
+    pub ftkey: String,
+    /// The file transfer port of the server.
+    pub port: u16,
+    /// The size of the file. Only set for downloads.
+    pub size: u64,
+    /// The offset the upload starts at. Only set for uploads.
+    pub seekpos: u64,
+    /// A non-zero error id if the transfer could not be initialized.
+    pub status: u16,
+    pub msg: String,
+}
+
+/// A snapshot of a virtual server returned from [`Client.serversnapshotcreate`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct Snapshot {
+    pub version: u32,
+    /// The salt used to encrypt `data`. Empty if the snapshot is not encrypted.
+    pub salt: String,
+    /// The base64 encoded snapshot.
+    pub data: String,
+}
+
+/// An entry returned from [`Client.serversnapshotdeploy`]. The first entry contains the id
+/// and port of the deployed server, every entry contains a channel mapping if it was
+/// requested.
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct SnapshotDeployed {
+    pub sid: ServerId,
+    pub virtualserver_port: u16,
+    /// The id of the channel in the snapshot.
+    pub ochannel_id: ChannelId,
+    /// The id of the channel on the deployed server.
+    pub nchannel_id: ChannelId,
+}
+
+/// A query login returned from [`Client.queryloginlist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct QueryLogin {
+    pub cldbid: ClientDatabaseId,
+    /// The virtual server the login belongs to. `0` for logins of the server instance.
+    pub sid: ServerId,
+    pub client_login_name: String,
+    /// The total number of matching logins. Only included in the first entry if requested.
+    pub count: u64,
+}
+
+impl QueryLogin {
+    /// Returns `true` if the login belongs to the server instance instead of a virtual
+    /// server.
+    pub fn is_instance_login(&self) -> bool {
+        self.sid == ServerId(0)
+    }
+}
+
+/// A virtual server created using [`Client.servercreate`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct VirtualServerCreated {
+    pub sid: ServerId,
+    pub virtualserver_port: u16,
+    /// The privilege key for the server admin group of the new server.
+    pub token: String,
 }
 
 /// An API Key returned from [`Client.apikeyadd`].
 #[derive(Debug, Decode, Default)]
+#[non_exhaustive]
 pub struct ApiKey {
     pub apikey: String,
     pub id: ApiKeyId,
@@ -129,10 +430,537 @@ pub struct ApiKey {
     pub cldbid: ClientDatabaseId,
     pub scope: ApiKeyScope,
     pub time_left: u64,
-    _priv: (),
 }
 
+/// A ban rule returned from [`Client.banlist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct BanEntry {
+    pub banid: u64,
+    pub ip: String,
+    pub name: String,
+    pub uid: String,
+    pub mytsid: String,
+    pub lastnickname: String,
+    pub created: u64,
+    pub duration: u64,
+    pub invokername: String,
+    pub invokercldbid: ClientDatabaseId,
+    pub invokeruid: String,
+    pub reason: String,
+    pub enforcements: u64,
+}
+
+/// The properties of a channel returned from [`Client.channelinfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ChannelInfo {
+    pub pid: ChannelId,
+    pub channel_name: String,
+    pub channel_topic: String,
+    pub channel_description: String,
+    pub channel_codec: u8,
+    pub channel_codec_quality: u8,
+    /// The maximum number of clients. `-1` if unlimited.
+    pub channel_maxclients: i32,
+    /// The maximum number of clients in the channel and its subchannels. `-1` if unlimited.
+    pub channel_maxfamilyclients: i32,
+    pub channel_order: u64,
+    pub channel_flag_permanent: bool,
+    pub channel_flag_semi_permanent: bool,
+    pub channel_flag_default: bool,
+    /// `true` if the channel is protected by a password.
+    pub channel_flag_password: bool,
+    pub channel_flag_maxclients_unlimited: bool,
+    pub channel_flag_maxfamilyclients_unlimited: bool,
+    pub channel_needed_talk_power: u32,
+    pub channel_icon_id: IconId,
+    /// The number of seconds the channel is empty. `-1` if clients are in the channel.
+    pub seconds_empty: i64,
+}
+
+/// A complaint returned from [`Client.complainlist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct Complaint {
+    /// The database id of the client the complaint is about.
+    pub tcldbid: ClientDatabaseId,
+    pub tname: String,
+    /// The database id of the client who filed the complaint.
+    pub fcldbid: ClientDatabaseId,
+    pub fname: String,
+    pub message: String,
+    /// Unix timestamp of the complaint.
+    pub timestamp: u64,
+}
+
+/// A custom property of a client returned from [`Client.custominfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct CustomProperty {
+    pub ident: String,
+    pub value: String,
+}
+
+/// A custom property returned from [`Client.customsearch`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct CustomSearchEntry {
+    pub cldbid: ClientDatabaseId,
+    pub ident: String,
+    pub value: String,
+}
+
+/// A client returned from [`Client.clientlist`]. Most fields are only included if the
+/// corresponding option was set in the [`ClientListOptions`].
+///
+/// [`ClientListOptions`]: crate::request::ClientListOptions
 #[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ClientListEntry {
+    pub clid: ClientId,
+    pub cid: ChannelId,
+    pub client_database_id: ClientDatabaseId,
+    pub client_nickname: String,
+    /// `0` for a regular client, `1` for a ServerQuery client.
+    pub client_type: u8,
+    // -uid
+    pub client_unique_identifier: String,
+    // -away
+    pub client_away: bool,
+    pub client_away_message: String,
+    // -voice
+    pub client_flag_talking: bool,
+    pub client_input_muted: bool,
+    pub client_output_muted: bool,
+    pub client_input_hardware: bool,
+    pub client_output_hardware: bool,
+    pub client_talk_power: i64,
+    pub client_is_talker: bool,
+    pub client_is_priority_speaker: bool,
+    pub client_is_recording: bool,
+    pub client_is_channel_commander: bool,
+    // -times
+    /// The idle time in milliseconds.
+    pub client_idle_time: u64,
+    pub client_created: u64,
+    pub client_lastconnected: u64,
+    // -groups
+    pub client_servergroups: List<ServerGroupId, Comma>,
+    pub client_channel_group_id: ChannelGroupId,
+    pub client_channel_group_inherited_channel_id: ChannelId,
+    // -info
+    pub client_version: String,
+    pub client_platform: String,
+    // -icon
+    pub client_icon_id: IconId,
+    // -country
+    pub client_country: String,
+    // -ip
+    pub client_connection_client_ip: String,
+    // -badges
+    pub client_badges: String,
+}
+
+impl ClientListEntry {
+    /// Returns `true` if the client is a ServerQuery client.
+    pub fn is_query(&self) -> bool {
+        self.client_type == 1
+    }
+
+    /// Returns the regular clients in `clients` whose nickname contains `nick_fragment`,
+    /// ignoring case. The best matches come first: exact matches, then nicknames starting
+    /// with the fragment, then nicknames containing it at the start of a word, then all
+    /// others. Ties are ordered by the length of the nickname.
+    ///
+    /// Returns no clients if `nick_fragment` is empty.
+    pub fn find<'a>(clients: &'a [Self], nick_fragment: &str) -> Vec<&'a Self> {
+        if nick_fragment.is_empty() {
+            return Vec::new();
+        }
+
+        let fragment = nick_fragment.to_lowercase();
+        let mut candidates: Vec<_> = clients
+            .iter()
+            .filter(|client| !client.is_query())
+            .filter_map(|client| Some((nickname_rank(&client.client_nickname, &fragment)?, client)))
+            .collect();
+
+        candidates.sort_by_key(|(rank, _)| *rank);
+        candidates.into_iter().map(|(_, client)| client).collect()
+    }
+
+    /// Returns the time since the last activity of the client. Only included with the
+    /// `times` option of [`ClientListOptions`].
+    ///
+    /// [`ClientListOptions`]: crate::request::ClientListOptions
+    pub fn idle_duration(&self) -> Duration {
+        Duration::from_millis(self.client_idle_time)
+    }
+
+    /// Returns the country of the client. Only included with the `country` option of
+    /// [`ClientListOptions`].
+    ///
+    /// [`ClientListOptions`]: crate::request::ClientListOptions
+    pub fn country(&self) -> Option<CountryCode> {
+        CountryCode::new(&self.client_country)
+    }
+
+    /// Returns the regular clients in `clients` which are in the channel `cid`. ServerQuery
+    /// clients are never returned.
+    pub fn in_channel(clients: &[Self], cid: ChannelId) -> Vec<&Self> {
+        clients
+            .iter()
+            .filter(|client| client.cid == cid && !client.is_query())
+            .collect()
+    }
+
+    /// Returns the number of regular clients in `clients` per country. Clients without a
+    /// known country are not counted.
+    pub fn count_by_country(clients: &[Self]) -> HashMap<CountryCode, usize> {
+        let mut counts = HashMap::new();
+        for country in clients
+            .iter()
+            .filter(|client| !client.is_query())
+            .filter_map(Self::country)
+        {
+            *counts.entry(country).or_default() += 1;
+        }
+
+        counts
+    }
+}
+
+/// Ranks how well `nickname` matches the lowercase `fragment`. Lower is better.
+fn nickname_rank(nickname: &str, fragment: &str) -> Option<(u8, usize, usize)> {
+    let nickname = nickname.to_lowercase();
+    let pos = nickname.find(fragment)?;
+
+    let word_start = |pos: usize| {
+        nickname[..pos]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric())
+    };
+
+    let kind = if nickname == fragment {
+        0
+    } else if pos == 0 {
+        1
+    } else if nickname
+        .match_indices(fragment)
+        .any(|(pos, _)| word_start(pos))
+    {
+        2
+    } else {
+        3
+    };
+
+    Some((kind, nickname.len(), pos))
+}
+
+/// Information about an online client returned from [`Client.clientinfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ClientInfo {
+    pub cid: ChannelId,
+    /// The idle time in milliseconds.
+    pub client_idle_time: u64,
+    pub client_unique_identifier: String,
+    pub client_nickname: String,
+    pub client_version: String,
+    pub client_platform: String,
+    pub client_input_muted: bool,
+    pub client_output_muted: bool,
+    pub client_database_id: ClientDatabaseId,
+    pub client_channel_group_id: ChannelGroupId,
+    pub client_servergroups: List<ServerGroupId, Comma>,
+    /// Unix timestamp of the first connection of the client.
+    pub client_created: u64,
+    /// Unix timestamp of the last connection of the client.
+    pub client_lastconnected: u64,
+    pub client_totalconnections: u64,
+    pub client_away: bool,
+    pub client_away_message: String,
+    /// `0` for a regular client, `1` for a ServerQuery client.
+    pub client_type: u8,
+    pub client_talk_power: i64,
+    pub client_description: String,
+    pub client_is_talker: bool,
+    pub client_icon_id: IconId,
+    /// The two letter country code of the client. Empty if unknown.
+    pub client_country: String,
+    /// The time since the client connected in milliseconds.
+    pub connection_connected_time: u64,
+    pub connection_client_ip: String,
+}
+
+impl ClientInfo {
+    /// Returns the time since the last activity of the client.
+    pub fn idle_duration(&self) -> Duration {
+        Duration::from_millis(self.client_idle_time)
+    }
+
+    /// Returns the time since the client connected.
+    pub fn connected_duration(&self) -> Duration {
+        Duration::from_millis(self.connection_connected_time)
+    }
+
+    /// Returns the country of the client.
+    pub fn country(&self) -> Option<CountryCode> {
+        CountryCode::new(&self.client_country)
+    }
+}
+
+/// Database information about a client returned from [`Client.clientdbinfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ClientDbInfo {
+    pub client_unique_identifier: String,
+    pub client_nickname: String,
+    pub client_database_id: ClientDatabaseId,
+    /// Unix timestamp of the first connection of the client.
+    pub client_created: u64,
+    /// Unix timestamp of the last connection of the client.
+    pub client_lastconnected: u64,
+    pub client_totalconnections: u64,
+    pub client_description: String,
+    pub client_month_bytes_uploaded: u64,
+    pub client_month_bytes_downloaded: u64,
+    pub client_total_bytes_uploaded: u64,
+    pub client_total_bytes_downloaded: u64,
+    pub client_icon_id: IconId,
+    /// The last IP address of the client. Only included with sufficient permissions.
+    pub client_lastip: String,
+}
+
+/// An offline message returned from [`Client.messagelist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct MessageEntry {
+    pub msgid: u64,
+    pub cluid: String,
+    pub subject: String,
+    pub timestamp: u64,
+    pub flag_read: bool,
+}
+
+/// An offline message returned from [`Client.messageget`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct Message {
+    pub msgid: u64,
+    pub cluid: String,
+    pub subject: String,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+/// A single permission value returned from [`Client.permoverview`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct PermissionOverview {
+    /// The type of the origin, see [`origin`](Self::origin).
+    pub t: u8,
+    pub id1: u64,
+    pub id2: u64,
+    /// The permission.
+    pub p: String,
+    /// The value of the permission.
+    pub v: i64,
+    /// The negated flag.
+    pub n: bool,
+    /// The skip flag.
+    pub s: bool,
+}
+
+impl PermissionOverview {
+    /// Returns the origin of the permission value. Returns `None` if the origin type is
+    /// unknown.
+    pub fn origin(&self) -> Option<PermissionOrigin> {
+        match self.t {
+            0 => Some(PermissionOrigin::ServerGroup(ServerGroupId(self.id1))),
+            1 => Some(PermissionOrigin::Client(ClientDatabaseId(self.id1))),
+            2 => Some(PermissionOrigin::Channel(ChannelId(self.id1))),
+            3 => Some(PermissionOrigin::ChannelGroup(
+                ChannelId(self.id1),
+                ChannelGroupId(self.id2),
+            )),
+            4 => Some(PermissionOrigin::ChannelClient(
+                ChannelId(self.id1),
+                ClientDatabaseId(self.id2),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The origin of a [`PermissionOverview`] value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PermissionOrigin {
+    ServerGroup(ServerGroupId),
+    Client(ClientDatabaseId),
+    Channel(ChannelId),
+    ChannelGroup(ChannelId, ChannelGroupId),
+    ChannelClient(ChannelId, ClientDatabaseId),
+}
+
+/// A server group of a client returned from [`Client.servergroupsbyclientid`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ServerGroupMembership {
+    pub name: String,
+    pub sgid: ServerGroupId,
+    pub cldbid: ClientDatabaseId,
+}
+
+/// The outcome of [`Client.servergroupaddclient_verified`] and
+/// [`Client.servergroupdelclient_verified`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MembershipChange {
+    /// The client was added to the group.
+    Added,
+    /// The client was removed from the group.
+    Removed,
+    /// The client already was a member of the group.
+    AlreadyMember,
+    /// The client was not a member of the group.
+    NotAMember,
+}
+
+/// The outcome of a messaging command like [`Client.sendtextmessage`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MessageOutcome {
+    /// The message was sent.
+    Sent,
+    /// The message was dropped by the flood protection of the server. `retry_after` is the
+    /// time to wait before sending again, if the server included it.
+    RateLimited { retry_after: Option<Duration> },
+}
+
+/// Information about the selected virtual server returned from [`Client.serverinfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ServerInfo {
+    pub virtualserver_id: ServerId,
+    pub virtualserver_unique_identifier: String,
+    pub virtualserver_name: String,
+    pub virtualserver_welcomemessage: String,
+    pub virtualserver_platform: String,
+    pub virtualserver_version: String,
+    pub virtualserver_port: u16,
+    pub virtualserver_status: VirtualServerStatus,
+    pub virtualserver_maxclients: u32,
+    pub virtualserver_reserved_slots: u32,
+    pub virtualserver_clientsonline: u32,
+    pub virtualserver_queryclientsonline: u32,
+    pub virtualserver_channelsonline: u32,
+    /// Unix timestamp of the creation of the server.
+    pub virtualserver_created: u64,
+    /// The uptime of the server in seconds.
+    pub virtualserver_uptime: u64,
+    pub virtualserver_hostmessage: String,
+    pub virtualserver_hostmessage_mode: u8,
+    pub virtualserver_default_server_group: ServerGroupId,
+    pub virtualserver_default_channel_group: ChannelGroupId,
+    pub virtualserver_default_channel_admin_group: ChannelGroupId,
+    pub virtualserver_flag_password: bool,
+    pub virtualserver_codec_encryption_mode: u8,
+    pub virtualserver_icon_id: IconId,
+    pub virtualserver_total_packetloss_speech: f64,
+    pub virtualserver_total_packetloss_keepalive: f64,
+    pub virtualserver_total_packetloss_control: f64,
+    pub virtualserver_total_packetloss_total: f64,
+    pub virtualserver_total_ping: f64,
+    pub virtualserver_month_bytes_downloaded: u64,
+    pub virtualserver_month_bytes_uploaded: u64,
+    pub virtualserver_total_bytes_downloaded: u64,
+    pub virtualserver_total_bytes_uploaded: u64,
+    /// The total download bandwidth of all file transfers in bytes per second.
+    pub virtualserver_max_download_total_bandwidth: u64,
+    /// The total upload bandwidth of all file transfers in bytes per second.
+    pub virtualserver_max_upload_total_bandwidth: u64,
+    pub connection_bandwidth_sent_last_second_total: u64,
+    pub connection_bandwidth_received_last_second_total: u64,
+    pub connection_bandwidth_sent_last_minute_total: u64,
+    pub connection_bandwidth_received_last_minute_total: u64,
+    pub connection_packets_sent_total: u64,
+    pub connection_packets_received_total: u64,
+    pub connection_bytes_sent_total: u64,
+    pub connection_bytes_received_total: u64,
+}
+
+impl ServerInfo {
+    /// Returns the time since the virtual server was started.
+    pub fn uptime(&self) -> Duration {
+        Duration::from_secs(self.virtualserver_uptime)
+    }
+}
+
+/// Connection information of the selected virtual server returned from
+/// [`Client.serverrequestconnectioninfo`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ConnectionInfo {
+    pub connection_filetransfer_bandwidth_sent: u64,
+    pub connection_filetransfer_bandwidth_received: u64,
+    pub connection_filetransfer_bytes_sent_total: u64,
+    pub connection_filetransfer_bytes_received_total: u64,
+    pub connection_packets_sent_total: u64,
+    pub connection_bytes_sent_total: u64,
+    pub connection_packets_received_total: u64,
+    pub connection_bytes_received_total: u64,
+    pub connection_bandwidth_sent_last_second_total: u64,
+    pub connection_bandwidth_sent_last_minute_total: u64,
+    pub connection_bandwidth_received_last_second_total: u64,
+    pub connection_bandwidth_received_last_minute_total: u64,
+    /// The connected time in seconds.
+    pub connection_connected_time: u64,
+    pub connection_packetloss_total: f64,
+    pub connection_ping: f64,
+}
+
+/// A server group returned from [`Client.servergrouplist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ServerGroup {
+    pub sgid: ServerGroupId,
+    pub name: String,
+    pub r#type: GroupType,
+    pub iconid: IconId,
+    pub savedb: bool,
+    pub sortid: u64,
+    pub namemode: u8,
+    pub n_modifyp: u64,
+    pub n_member_addp: u64,
+    pub n_member_removep: u64,
+}
+
+/// A channel group returned from [`Client.channelgrouplist`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct ChannelGroup {
+    pub cgid: ChannelGroupId,
+    pub name: String,
+    pub r#type: GroupType,
+    pub iconid: IconId,
+    pub savedb: bool,
+    pub sortid: u64,
+    pub namemode: u8,
+    pub n_modifyp: u64,
+    pub n_member_addp: u64,
+    pub n_member_removep: u64,
+}
+
+/// A privilege key returned from [`Client.danger_permreset`].
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
+pub struct Token {
+    pub token: String,
+}
+
+#[derive(Clone, Debug, Default, Decode)]
+#[non_exhaustive]
 pub struct Whoami {
     pub virtualserver_status: VirtualServerStatus,
     pub virtualserver_unique_identifier: String,
@@ -145,26 +973,222 @@ pub struct Whoami {
     pub client_login_name: String,
     pub client_unique_identifier: String,
     pub client_origin_server_id: ServerId,
-    _priv: (),
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum VirtualServerStatus {
+    /// A status not known to this crate.
     #[default]
     Unknown,
     Online,
     Offline,
+    /// The server is stopped, but selected in virtual mode (see [`UseOptions`]).
+    ///
+    /// [`UseOptions`]: crate::request::UseOptions
+    VirtualOnline,
+    BootingUp,
+    ShuttingDown,
+    /// A snapshot is being deployed to the server.
+    DeployRunning,
 }
 
 impl Decode for VirtualServerStatus {
     type Error = Error;
 
     fn decode(buf: &[u8]) -> Result<Self, Self::Error> {
-        match buf {
-            b"unknown" => Ok(Self::Unknown),
-            b"online" => Ok(Self::Online),
-            b"offline" => Ok(Self::Offline),
-            _ => Err(Error(DecodeError::UnexpectedEof.into())),
-        }
+        // Newer servers may report other statuses, these must not fail the whole response.
+        Ok(match buf {
+            b"online" => Self::Online,
+            b"offline" => Self::Offline,
+            b"virtual\\sonline" => Self::VirtualOnline,
+            b"booting\\sup" => Self::BootingUp,
+            b"shutting\\sdown" => Self::ShuttingDown,
+            b"deploy\\srunning" => Self::DeployRunning,
+            _ => Self::Unknown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        ClientInfo, ClientListEntry, Entry, Response, ServerGroup, ServerListEntry, Version,
+        VirtualServerStatus,
+    };
+    use crate::shared::list::Pipe;
+    use crate::shared::{ChannelId, ClientId, CountryCode, GroupType, List};
+    use crate::Decode;
+
+    #[test]
+    fn test_servergroup_decode() {
+        let buf = b"sgid=1 name=Guest\\sServer\\sQuery type=2 iconid=0|sgid=2 name=Admin type=0";
+        let groups = List::<ServerGroup, Pipe>::decode(buf).unwrap();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "Guest Server Query");
+        assert_eq!(groups[0].r#type, GroupType::Query);
+        assert!(groups[1].r#type.is_template());
+    }
+
+    #[test]
+    fn test_response_decode() {
+        let resp = Response::decode(b"a=1 b c=x\\sy|a=2 a=3").unwrap();
+
+        assert_eq!(resp.len(), 2);
+        assert_eq!(resp[0].get::<u64>("a").unwrap(), 1);
+        assert!(resp[0].contains("b"));
+        assert!(resp[0].get::<String>("b").is_err());
+        assert_eq!(resp[0].get::<String>("c").unwrap(), "x y");
+        assert!(!resp[0].contains("d"));
+        assert_eq!(resp[1].get::<u64>("a").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_entry_get_or() {
+        let entry = Entry::decode(b"a=1 b c=x").unwrap();
+
+        assert_eq!(entry.get_or::<u64>("a", 5).unwrap(), 1);
+        assert_eq!(entry.get_or::<u64>("b", 5).unwrap(), 5);
+        assert_eq!(entry.get_or::<u64>("d", 5).unwrap(), 5);
+        assert!(entry.get_or::<u64>("c", 5).is_err());
+        assert_eq!(entry.get_or_default::<String>("d").unwrap(), "");
+
+        assert_eq!(entry.get_parsed_or_none::<u64>("a"), Some(1));
+        assert_eq!(entry.get_parsed_or_none::<u64>("c"), None);
+        assert_eq!(entry.get_parsed_or_none::<u64>("d"), None);
+    }
+
+    #[test]
+    fn test_entry_to() {
+        let resp = Response::decode(b"version=3.13.7 build=1|version=3.13.6 build=2").unwrap();
+
+        let version = resp[1].to::<Version>().unwrap();
+        assert_eq!(version.version, "3.13.6");
+        assert_eq!(version.build, 2);
+    }
+
+    #[test]
+    fn test_response_into_single() {
+        let entry = Response::decode(b"a=1").unwrap().into_single().unwrap();
+        assert_eq!(entry.get::<u64>("a").unwrap(), 1);
+
+        let err = Response::decode(b"a=1|a=2|a=3")
+            .unwrap()
+            .into_single()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "failed to decode stream: expected a single entry, got 3"
+        );
+
+        assert!(Version::decode(b"version=3.13.7 build=1|version=3.13.6").is_err());
+        assert!(Entry::decode(b"a=1|a=2").is_err());
+    }
+
+    #[test]
+    fn test_client_info_durations() {
+        let buf =
+            b"cid=1 client_idle_time=1500 client_nickname=Bob connection_connected_time=3600000";
+        let info = ClientInfo::decode(buf).unwrap();
+
+        assert_eq!(info.idle_duration(), Duration::from_millis(1500));
+        assert_eq!(info.connected_duration(), Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_client_list_entry_count_by_country() {
+        let clients: Vec<ClientListEntry> = [("DE", 0), ("de", 0), ("US", 0), ("", 0), ("FR", 1)]
+            .into_iter()
+            .map(|(country, client_type)| ClientListEntry {
+                client_country: country.to_owned(),
+                client_type,
+                ..Default::default()
+            })
+            .collect();
+
+        let counts = ClientListEntry::count_by_country(&clients);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&CountryCode::new("DE").unwrap()], 2);
+        assert_eq!(counts[&CountryCode::new("US").unwrap()], 1);
+    }
+
+    #[test]
+    fn test_client_list_entry_find() {
+        let clients: Vec<ClientListEntry> = [
+            ("Xbobx", 0),
+            ("Bobby", 0),
+            ("serveradmin bob", 1),
+            ("The Bob", 0),
+            ("bob", 0),
+            ("Alice", 0),
+            ("Bobbington", 0),
+        ]
+        .into_iter()
+        .map(|(nickname, client_type)| ClientListEntry {
+            client_nickname: nickname.to_owned(),
+            client_type,
+            ..Default::default()
+        })
+        .collect();
+
+        let found: Vec<&str> = ClientListEntry::find(&clients, "BOB")
+            .iter()
+            .map(|client| client.client_nickname.as_str())
+            .collect();
+        assert_eq!(found, ["bob", "Bobby", "Bobbington", "The Bob", "Xbobx"]);
+
+        assert!(ClientListEntry::find(&clients, "").is_empty());
+        assert!(ClientListEntry::find(&clients, "carol").is_empty());
+    }
+
+    #[test]
+    fn test_server_list_entry_status() {
+        let buf = b"virtualserver_id=1 virtualserver_status=online\
+            |virtualserver_id=2 virtualserver_status=offline\
+            |virtualserver_id=3 virtualserver_status=virtual\\sonline\
+            |virtualserver_id=4 virtualserver_status=booting\\sup\
+            |virtualserver_id=5 virtualserver_status=shutting\\sdown\
+            |virtualserver_id=6 virtualserver_status=deploy\\srunning\
+            |virtualserver_id=7 virtualserver_status=something\\snew";
+
+        let servers = List::<ServerListEntry, Pipe>::decode(buf).unwrap();
+        let statuses: Vec<VirtualServerStatus> = servers
+            .iter()
+            .map(|server| server.virtualserver_status)
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                VirtualServerStatus::Online,
+                VirtualServerStatus::Offline,
+                VirtualServerStatus::VirtualOnline,
+                VirtualServerStatus::BootingUp,
+                VirtualServerStatus::ShuttingDown,
+                VirtualServerStatus::DeployRunning,
+                VirtualServerStatus::Unknown,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_client_list_entry_in_channel() {
+        let clients: Vec<ClientListEntry> = [(1, 1, 0), (2, 5, 0), (3, 5, 1), (4, 5, 0)]
+            .into_iter()
+            .map(|(clid, cid, client_type)| ClientListEntry {
+                clid: ClientId(clid),
+                cid: ChannelId(cid),
+                client_type,
+                ..Default::default()
+            })
+            .collect();
+
+        let clids: Vec<ClientId> = ClientListEntry::in_channel(&clients, ChannelId(5))
+            .iter()
+            .map(|client| client.clid)
+            .collect();
+        assert_eq!(clids, [ClientId(2), ClientId(4)]);
+        assert!(ClientListEntry::in_channel(&clients, ChannelId(2)).is_empty());
     }
 }
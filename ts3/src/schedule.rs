@@ -0,0 +1,213 @@
+//! Delayed and recurring actions tied to a [`Client`].
+//!
+//! Actions are scheduled using [`Client::schedule`] and run on the runtime of the client.
+//! They stop once the connection of the client is closed or [`Client::disconnect`] is
+//! called, an action never starts after that. Errors returned by an action are reported to
+//! [`EventHandler::error`].
+//!
+//! Actions are paused while the client reconnects, see [`ClientBuilder::reconnect`]. An
+//! action that is due while reconnecting runs once the setup hook for the new connection
+//! completed, runs missed in the meantime are skipped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::request::TextMessageTarget;
+//! use ts3::schedule::Schedule;
+//! use ts3::Client;
+//!
+//! # fn run(client: Client) {
+//! let handle = client.schedule(Schedule::every(Duration::from_secs(3600)), |client| async move {
+//!     client
+//!         .sendtextmessage(TextMessageTarget::Server, "Visit our website!")
+//!         .await?;
+//!     Ok(())
+//! });
+//!
+//! // Stop the announcements.
+//! handle.cancel();
+//! # }
+//! ```
+//!
+//! [`Client::schedule`]: crate::Client::schedule
+//! [`Client::disconnect`]: crate::Client::disconnect
+//! [`ClientBuilder::reconnect`]: crate::ClientBuilder::reconnect
+//! [`EventHandler::error`]: crate::event::EventHandler::error
+
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{self, sleep_until};
+
+use crate::client::Result;
+use crate::Client;
+
+/// When a scheduled action runs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    start: Instant,
+    period: Option<Duration>,
+}
+
+impl Schedule {
+    /// Runs the action once at `instant`. If `instant` is in the past the action runs right
+    /// away.
+    pub fn at(instant: Instant) -> Self {
+        Self {
+            start: instant,
+            period: None,
+        }
+    }
+
+    /// Runs the action once after `delay`.
+    pub fn after(delay: Duration) -> Self {
+        Self::at(Instant::now() + delay)
+    }
+
+    /// Runs the action every `period`, starting after the first period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn every(period: Duration) -> Self {
+        Self::after(period).repeat(period)
+    }
+
+    /// Repeats the action every `period` after the first run.
+    ///
+    /// If a run takes longer than `period` the next run starts right after it, runs never
+    /// overlap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn repeat(mut self, period: Duration) -> Self {
+        assert!(!period.is_zero(), "period must not be zero");
+        self.period = Some(period);
+        self
+    }
+}
+
+/// A handle to a scheduled action. Returned by [`Client::schedule`].
+///
+/// Dropping the handle does not cancel the action.
+///
+/// [`Client::schedule`]: crate::Client::schedule
+#[derive(Debug)]
+pub struct ScheduleHandle {
+    cancel: Arc<Cancel>,
+    task: JoinHandle<()>,
+}
+
+impl ScheduleHandle {
+    /// Cancels the action. A run that already started is completed, but the action does not
+    /// run again.
+    pub fn cancel(&self) {
+        self.cancel.flag.store(true, Ordering::Release);
+        self.cancel.notify.notify_one();
+    }
+
+    /// Returns `true` if the action will not run again, because it was cancelled, it was
+    /// scheduled to run once and did or the connection was closed.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Cancel {
+    flag: AtomicBool,
+    notify: Notify,
+}
+
+impl Cancel {
+    fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::Acquire)
+    }
+}
+
+pub(crate) fn spawn<F, Fut>(client: Client, schedule: Schedule, action: F) -> ScheduleHandle
+where
+    F: Fn(Client) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let cancel = Arc::new(Cancel::default());
+
+    // Deadlines are tracked using the clock of the runtime.
+    let delay = schedule.start.saturating_duration_since(Instant::now());
+    let mut next = time::Instant::now() + delay;
+
+    let cancel2 = cancel.clone();
+    let task = tokio::spawn(async move {
+        while wait(&client, next, &cancel2).await {
+            if let Err(err) = action(client.clone()).await {
+                client.handle_error(err);
+            }
+
+            match schedule.period {
+                // Skip runs that were missed while the action was running.
+                Some(period) => next = time::Instant::max(next + period, time::Instant::now()),
+                None => return,
+            }
+        }
+    });
+
+    ScheduleHandle { cancel, task }
+}
+
+/// Waits until `deadline` and until `client` is not reconnecting. Returns `false` if the
+/// action was cancelled or the connection of `client` was closed before.
+async fn wait(client: &Client, deadline: time::Instant, cancel: &Cancel) -> bool {
+    let mut sleep = pin!(async {
+        sleep_until(deadline).await;
+        client.connected().await;
+    });
+    let mut closed = pin!(client.closed());
+    let mut notified = pin!(cancel.notify.notified());
+
+    poll_fn(|cx| {
+        if cancel.is_cancelled() || notified.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(false);
+        }
+        if closed.as_mut().poll(cx).is_ready() {
+            return Poll::Ready(false);
+        }
+
+        sleep
+            .as_mut()
+            .poll(cx)
+            .map(|()| !cancel.is_cancelled() && !client.is_closed())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Schedule;
+
+    #[test]
+    fn test_schedule() {
+        let now = Instant::now();
+        assert_eq!(Schedule::at(now).period, None);
+
+        let schedule = Schedule::every(Duration::from_secs(5));
+        assert_eq!(schedule.period, Some(Duration::from_secs(5)));
+        assert!(schedule.start >= now + Duration::from_secs(5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_schedule_zero_period() {
+        Schedule::every(Duration::ZERO);
+    }
+}
@@ -0,0 +1,231 @@
+//! Commands grouped by the scope they operate on.
+//!
+//! Commands of the TS3 query interface either operate on the server instance or on the
+//! selected virtual server. Calling a virtual server command before selecting a server
+//! fails with error `1024` at runtime. [`Instance`] only exposes instance commands, while a
+//! [`VirtualServer`] can only be obtained by selecting a server first.
+//!
+//! The scopes only group the commands. All commands are still available on [`Client`]
+//! directly, where nothing prevents calling a virtual server command without a selected
+//! server.
+
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::event::ReasonId;
+use crate::request::{
+    ClientListOptions, ClientProperties, QueryLoginListOptions, ServerNotifyRegister, TempPassword,
+    TextMessageTarget, VirtualServerProperties,
+};
+use crate::response::{
+    BanEntry, ChannelGroup, ChannelInfo, ClientDbInfo, ClientInfo, ClientListEntry, Complaint,
+    ConnectionInfo, CustomProperty, CustomSearchEntry, FileEntry, FileTransferInit, HostInfo,
+    MembershipChange, Message, MessageEntry, MessageOutcome, PermissionOverview, QueryLogin,
+    ServerGroup, ServerGroupMembership, ServerInfo, ServerListEntry, Snapshot, SnapshotDeployed,
+    TempPasswordEntry, Version, VirtualServerCreated, Whoami,
+};
+use crate::shared::list::Pipe;
+use crate::shared::{
+    ChannelId, ClientDatabaseId, ClientId, GroupType, List, ServerGroupId, ServerId,
+};
+use crate::voice::VoiceState;
+use crate::{Client, Error};
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Generates methods forwarding to the method of the same name on [`Client`].
+macro_rules! forward {
+    ($($name:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!("See [`Client::", stringify!($name), "`].")]
+            pub async fn $name(&self, $($arg: $ty),*) -> Result<$ret> {
+                self.client.$name($($arg),*).await
+            }
+        )*
+    };
+}
+
+/// Commands operating on the server instance. Returned from [`Client::instance`].
+#[derive(Copy, Clone)]
+pub struct Instance<'a> {
+    client: &'a Client,
+}
+
+impl<'a> Instance<'a> {
+    pub(crate) fn new(client: &'a Client) -> Self {
+        Self { client }
+    }
+
+    forward! {
+        create_and_use(name: &str, properties: VirtualServerProperties<'_>) -> VirtualServerCreated;
+        gm(msg: &str) -> ();
+        gm_outcome(msg: &str) -> MessageOutcome;
+        hostinfo() -> HostInfo;
+        instance_uptime() -> Duration;
+        queryloginlist(options: QueryLoginListOptions<'_>) -> List<QueryLogin, Pipe>;
+        serverprocessstop() -> ();
+        serverprocessstop_with_reason(reasonmsg: &str) -> ();
+        servercreate(name: &str) -> VirtualServerCreated;
+        serverlist() -> List<ServerListEntry, Pipe>;
+        serversnapshotdeploy(
+            snapshot: &Snapshot,
+            keepfiles: bool,
+            mapping: bool
+        ) -> List<SnapshotDeployed, Pipe>;
+        version() -> Version;
+    }
+
+    /// See [`Client::serverstart`].
+    pub async fn serverstart<T>(&self, sid: T) -> Result<()>
+    where
+        T: Into<ServerId>,
+    {
+        self.client.serverstart(sid).await
+    }
+
+    /// See [`Client::serverstop`].
+    pub async fn serverstop<T>(&self, sid: T) -> Result<()>
+    where
+        T: Into<ServerId>,
+    {
+        self.client.serverstop(sid).await
+    }
+
+    /// See [`Client::serverstop_with_reason`].
+    pub async fn serverstop_with_reason<T>(&self, sid: T, reasonmsg: &str) -> Result<()>
+    where
+        T: Into<ServerId>,
+    {
+        self.client.serverstop_with_reason(sid, reasonmsg).await
+    }
+}
+
+/// Commands operating on a selected virtual server. Returned from [`Client::server`] and
+/// [`Client::server_by_port`].
+///
+/// The `VirtualServer` does not select the server again before every command. If the same
+/// [`Client`] selects another server, the commands operate on that server instead.
+#[derive(Copy, Clone)]
+pub struct VirtualServer<'a> {
+    client: &'a Client,
+    sid: ServerId,
+}
+
+impl<'a> VirtualServer<'a> {
+    pub(crate) fn new(client: &'a Client, sid: ServerId) -> Self {
+        Self { client, sid }
+    }
+
+    /// Returns the id of the selected virtual server.
+    pub fn id(&self) -> ServerId {
+        self.sid
+    }
+
+    /// Returns the [`Client`] the server was selected on.
+    pub fn client(&self) -> &'a Client {
+        self.client
+    }
+
+    forward! {
+        banclient(clid: ClientId, time: Option<u64>, banreason: Option<&str>) -> ();
+        banlist() -> List<BanEntry, Pipe>;
+        channelgrouplist() -> List<ChannelGroup, Pipe>;
+        channelgrouplist_by_type(group_type: GroupType) -> List<ChannelGroup, Pipe>;
+        channelinfo(cid: ChannelId) -> ChannelInfo;
+        clientdbedit(cldbid: ClientDatabaseId, properties: ClientProperties<'_>) -> ();
+        clientdbinfo(cldbid: ClientDatabaseId) -> ClientDbInfo;
+        clientinfo(clid: ClientId) -> ClientInfo;
+        clientedit(clid: ClientId, properties: ClientProperties<'_>) -> ();
+        clientkick(clids: &[ClientId], reasonid: ReasonId, reasonmsg: Option<&str>) -> ();
+        clientlist(options: ClientListOptions) -> List<ClientListEntry, Pipe>;
+        clientmove(clids: &[ClientId], cid: ChannelId, cpw: Option<&str>) -> ();
+        clientpoke(clid: ClientId, msg: &str) -> ();
+        clientpoke_outcome(clid: ClientId, msg: &str) -> MessageOutcome;
+        clients_in_channel(cid: ChannelId) -> Vec<ClientListEntry>;
+        complaindelall(tcldbid: ClientDatabaseId) -> ();
+        complainlist(tcldbid: Option<ClientDatabaseId>) -> List<Complaint, Pipe>;
+        customdelete(cldbid: ClientDatabaseId, ident: &str) -> ();
+        custominfo(cldbid: ClientDatabaseId) -> List<CustomProperty, Pipe>;
+        customsearch(ident: &str, pattern: &str) -> List<CustomSearchEntry, Pipe>;
+        ftgetfilelist(cid: ChannelId, cpw: Option<&str>, path: &str) -> List<FileEntry, Pipe>;
+        ftinitdownload(
+            clientftfid: u16,
+            name: &str,
+            cid: ChannelId,
+            cpw: Option<&str>,
+            seekpos: u64
+        ) -> FileTransferInit;
+        customset(cldbid: ClientDatabaseId, ident: &str, value: &str) -> ();
+        find_client(nick_fragment: &str) -> Vec<ClientListEntry>;
+        messageadd(cluid: &str, subject: &str, message: &str) -> ();
+        messagedel(msgid: u64) -> ();
+        messageget(msgid: u64) -> Message;
+        messagelist() -> List<MessageEntry, Pipe>;
+        messageupdateflag(msgid: u64, read: bool) -> ();
+        permoverview(
+            cid: ChannelId,
+            cldbid: ClientDatabaseId,
+            permsid: Option<&str>
+        ) -> List<PermissionOverview, Pipe>;
+        queryloginlist(options: QueryLoginListOptions<'_>) -> List<QueryLogin, Pipe>;
+        sendtextmessage(target: TextMessageTarget, msg: &str) -> ();
+        sendtextmessage_outcome(target: TextMessageTarget, msg: &str) -> MessageOutcome;
+        servergroupaddclient(sgid: ServerGroupId, cldbid: ClientDatabaseId) -> ();
+        servergroupaddclient_verified(
+            sgid: ServerGroupId,
+            cldbid: ClientDatabaseId
+        ) -> MembershipChange;
+        servergroupdelclient(sgid: ServerGroupId, cldbid: ClientDatabaseId) -> ();
+        servergroupdelclient_verified(
+            sgid: ServerGroupId,
+            cldbid: ClientDatabaseId
+        ) -> MembershipChange;
+        servergrouplist() -> List<ServerGroup, Pipe>;
+        servergrouplist_by_type(group_type: GroupType) -> List<ServerGroup, Pipe>;
+        serveredit(properties: VirtualServerProperties<'_>) -> ();
+        servergroupsbyclientid(cldbid: ClientDatabaseId) -> List<ServerGroupMembership, Pipe>;
+        serverinfo() -> ServerInfo;
+        server_uptime() -> Duration;
+        servernotifyregister(event: ServerNotifyRegister) -> ();
+        serversnapshotcreate() -> Snapshot;
+        serversnapshotdeploy(
+            snapshot: &Snapshot,
+            keepfiles: bool,
+            mapping: bool
+        ) -> List<SnapshotDeployed, Pipe>;
+        servertemppasswordadd(password: TempPassword<'_>) -> ();
+        servertemppassworddel(pw: &str) -> ();
+        servertemppasswordlist() -> List<TempPasswordEntry, Pipe>;
+        serverrequestconnectioninfo() -> ConnectionInfo;
+        voice_states() -> Vec<VoiceState>;
+        whoami() -> Whoami;
+    }
+
+    /// See [`Client::ftinitupload`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn ftinitupload(
+        &self,
+        clientftfid: u16,
+        name: &str,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        size: u64,
+        overwrite: bool,
+        resume: bool,
+    ) -> Result<FileTransferInit> {
+        self.client
+            .ftinitupload(clientftfid, name, cid, cpw, size, overwrite, resume)
+            .await
+    }
+
+    /// See [`Client::walk_files`].
+    pub fn walk_files(
+        &self,
+        cid: ChannelId,
+        cpw: Option<&str>,
+        path: &str,
+    ) -> mpsc::Receiver<Result<FileEntry>> {
+        self.client.walk_files(cid, cpw, path)
+    }
+}
@@ -0,0 +1,35 @@
+//! Integration with [`tower`](https://docs.rs/tower).
+//!
+//! With the `tower` feature enabled [`Client`] implements [`Service<Request>`], allowing
+//! raw commands to be wrapped in tower middleware such as timeouts, retries or rate limits.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tower_service::Service;
+
+use crate::request::Request;
+use crate::response::Response;
+use crate::{Client, Error};
+
+/// Sends raw [`Request`]s using [`Client::send`].
+///
+/// The client queues commands itself, so the service is always ready. A full queue is not
+/// reported by [`poll_ready`] and the returned future waits for a free slot instead.
+///
+/// [`poll_ready`]: Service::poll_ready
+impl Service<Request> for Client {
+    type Response = Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move { client.send(req).await })
+    }
+}
@@ -2,16 +2,20 @@
 
 pub mod list;
 
+use std::fmt::{self, Display, Formatter};
+
 use crate::{Decode, DecodeError, Encode, Error, ErrorKind};
 
 pub use crate::types::{
-    ApiKeyId, ChannelGroupId, ChannelId, ClientDatabaseId, ClientId, ServerGroupId, ServerId,
+    ApiKeyId, ChannelGroupId, ChannelId, ClientDatabaseId, ClientId, IconId, ServerGroupId,
+    ServerId,
 };
 
 pub use list::List;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub enum ApiKeyScope {
+    #[default]
     Manage,
     Write,
     Read,
@@ -23,12 +27,6 @@ impl ApiKeyScope {
     const READ: &str = "read";
 }
 
-impl Default for ApiKeyScope {
-    fn default() -> Self {
-        Self::Manage
-    }
-}
-
 impl Encode for ApiKeyScope {
     fn encode(&self, buf: &mut String) {
         match self {
@@ -53,3 +51,99 @@ impl Decode for ApiKeyScope {
         }
     }
 }
+
+/// The type of a server or channel group.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GroupType {
+    /// A template group. Clients cannot be added to template groups.
+    Template,
+    /// A regular group.
+    #[default]
+    Regular,
+    /// A group for ServerQuery clients.
+    Query,
+}
+
+impl GroupType {
+    /// Returns `true` if this is a [`Template`] group.
+    ///
+    /// [`Template`]: Self::Template
+    #[inline]
+    pub fn is_template(&self) -> bool {
+        matches!(self, Self::Template)
+    }
+}
+
+impl Encode for GroupType {
+    fn encode(&self, buf: &mut String) {
+        match self {
+            Self::Template => *buf += "0",
+            Self::Regular => *buf += "1",
+            Self::Query => *buf += "2",
+        }
+    }
+}
+
+impl Decode for GroupType {
+    type Error = Error;
+
+    fn decode(buf: &[u8]) -> Result<Self, Self::Error> {
+        match u8::decode(buf)? {
+            0 => Ok(Self::Template),
+            1 => Ok(Self::Regular),
+            2 => Ok(Self::Query),
+            b => Err(Error(ErrorKind::Decode(DecodeError::InvalidGroupType(b)))),
+        }
+    }
+}
+
+/// A two letter ISO 3166-1 alpha-2 country code, e.g. `DE`. The code is stored in
+/// uppercase.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CountryCode([u8; 2]);
+
+impl CountryCode {
+    /// Creates a new `CountryCode` from `code`, ignoring case. Returns `None` if `code` is not
+    /// made of two ASCII letters.
+    ///
+    /// Note that the code is only checked for its format, not whether the country exists.
+    pub fn new(code: &str) -> Option<Self> {
+        match code.as_bytes() {
+            [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+                Some(Self([a.to_ascii_uppercase(), b.to_ascii_uppercase()]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the code as an uppercase string.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        // Both bytes are ASCII letters.
+        std::str::from_utf8(&self.0).unwrap()
+    }
+}
+
+impl Display for CountryCode {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CountryCode;
+
+    #[test]
+    fn test_country_code() {
+        assert_eq!(CountryCode::new("de").unwrap().as_str(), "DE");
+        assert_eq!(CountryCode::new("US").unwrap().to_string(), "US");
+        assert_eq!(CountryCode::new("de"), CountryCode::new("DE"));
+        assert!(CountryCode::new("").is_none());
+        assert!(CountryCode::new("D").is_none());
+        assert!(CountryCode::new("DEU").is_none());
+        assert!(CountryCode::new("1A").is_none());
+        assert!(CountryCode::new("ü").is_none());
+    }
+}
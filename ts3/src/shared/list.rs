@@ -92,7 +92,7 @@ where
     S: Separator,
 {
     fn encode(&self, buf: &mut String) {
-        if let Some(elem) = self.vec.get(0) {
+        if let Some(elem) = self.vec.first() {
             elem.encode(buf);
         }
 
@@ -146,33 +146,50 @@ impl Separator for Comma {
     const PATTERN: &'static str = ",";
 }
 
-fn bytes_split<'a>(mut buf: &'a [u8], pat: &[u8]) -> Vec<&'a [u8]> {
-    let mut cursor = 0;
+/// Splits `buf` at every occurrence of `pat`. Behaves like [`str::split`].
+///
+/// # Panics
+///
+/// Panics if `pat` is empty.
+pub(crate) fn bytes_split<'a>(buf: &'a [u8], pat: &'a [u8]) -> BytesSplit<'a> {
+    assert!(!pat.is_empty(), "pattern must not be empty");
+
+    BytesSplit {
+        rest: Some(buf),
+        pat,
+    }
+}
 
-    let mut segs = Vec::new();
-    while buf.len() - cursor >= pat.len() {
-        // Peek current position
-        let slice = &buf[cursor..cursor + pat.len()];
+/// An iterator over the segments of a byte slice. Created by [`bytes_split`].
+#[derive(Clone, Debug)]
+pub(crate) struct BytesSplit<'a> {
+    /// The remaining buffer. `None` if the iterator is exhausted.
+    rest: Option<&'a [u8]>,
+    pat: &'a [u8],
+}
 
-        if slice == pat {
-            segs.push(&buf[0..cursor]);
+impl<'a> Iterator for BytesSplit<'a> {
+    type Item = &'a [u8];
 
-            // End of buffer
-            if buf.len() <= pat.len() {
-                return segs;
-            }
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest?;
 
-            buf = &buf[cursor + pat.len()..];
-            cursor = 0;
-        } else {
-            cursor += 1;
+        let index = match self.pat {
+            [b] => memchr::memchr(*b, rest),
+            pat => memchr::memmem::find(rest, pat),
+        };
+
+        match index {
+            Some(index) => {
+                self.rest = Some(&rest[index + self.pat.len()..]);
+                Some(&rest[..index])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
         }
     }
-
-    // Remainder
-    segs.push(buf);
-
-    segs
 }
 
 #[cfg(test)]
@@ -181,22 +198,25 @@ mod tests {
     use crate::shared::list::bytes_split;
     use crate::Decode;
 
+    fn split<'a>(buf: &'a [u8], pat: &'a [u8]) -> Vec<&'a [u8]> {
+        bytes_split(buf, pat).collect()
+    }
+
     #[test]
     fn test_bytes_split() {
-        assert_eq!(bytes_split(b"a|b|c", b"|"), [b"a", b"b", b"c"]);
-        assert_eq!(bytes_split(b"abc", b"|"), [b"abc"]);
-        assert_eq!(
-            bytes_split(b"a|bc", b"|"),
-            [b"a".as_slice(), b"bc".as_slice()]
-        );
+        assert_eq!(split(b"a|b|c", b"|"), [b"a", b"b", b"c"]);
+        assert_eq!(split(b"abc", b"|"), [b"abc"]);
+        assert_eq!(split(b"a|bc", b"|"), [b"a".as_slice(), b"bc".as_slice()]);
         assert_eq!(
-            bytes_split(b"a|bc|", b"|"),
+            split(b"a|bc|", b"|"),
             [b"a".as_slice(), b"bc".as_slice(), b"".as_slice()]
         );
-        assert_eq!(bytes_split(b"ABCabcABC", b"abc"), [b"ABC", b"ABC"]);
+        assert_eq!(split(b"ABCabcABC", b"abc"), [b"ABC", b"ABC"]);
+        assert_eq!(split(b"", b"|"), [b"".as_slice()]);
+        assert_eq!(split(b"|", b"|"), [b"".as_slice(), b"".as_slice()]);
 
         assert_eq!(
-            bytes_split(b"00abcd0e0f00g000", b"0"),
+            split(b"00abcd0e0f00g000", b"0"),
             [
                 b"".as_slice(),
                 b"".as_slice(),
@@ -206,9 +226,16 @@ mod tests {
                 b"".as_slice(),
                 b"g".as_slice(),
                 b"".as_slice(),
+                b"".as_slice(),
                 b"".as_slice()
             ]
         );
+
+        // Matches str::split.
+        for (s, pat) in [("a||b|", "|"), ("abab", "ab"), ("aaa", "aa")] {
+            let expected: Vec<&[u8]> = s.split(pat).map(str::as_bytes).collect();
+            assert_eq!(split(s.as_bytes(), pat.as_bytes()), expected);
+        }
     }
 
     #[test]
@@ -0,0 +1,158 @@
+//! Periodic sampling of server statistics.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::stats::Sampler;
+//! use ts3::Client;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let (_sampler, mut samples) = Sampler::new(Duration::from_secs(10)).spawn(client);
+//!
+//!     while let Some(sample) = samples.recv().await {
+//!         match sample {
+//!             Ok(sample) => println!("{} clients online", sample.clients_online),
+//!             Err(err) => println!("failed to sample: {}", err),
+//!         }
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::{Client, Error};
+
+/// A single sample of server statistics.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Sample {
+    /// The time the sample was taken.
+    pub time: SystemTime,
+    /// The number of clients online, excluding ServerQuery clients.
+    pub clients_online: u32,
+    /// The number of ServerQuery clients online.
+    pub queryclients_online: u32,
+    /// The number of channels.
+    pub channels_online: u32,
+    /// The bandwidth sent in the last second in bytes.
+    pub bandwidth_sent: u64,
+    /// The bandwidth received in the last second in bytes.
+    pub bandwidth_received: u64,
+    /// The packet loss in the range `0.0..=1.0`.
+    pub packetloss: f64,
+    /// The average ping in milliseconds.
+    pub ping: f64,
+}
+
+/// Periodically samples statistics of the selected virtual server using `serverinfo` and
+/// `serverrequestconnectioninfo`.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    interval: Duration,
+    history: usize,
+}
+
+impl Sampler {
+    /// Creates a new `Sampler` taking a sample every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            history: 60,
+        }
+    }
+
+    /// Sets the number of samples kept in the [`SamplerHandle`]. Defaults to `60`.
+    pub fn history(mut self, history: usize) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Takes a single sample.
+    pub async fn sample(client: &Client) -> Result<Sample, Error> {
+        let info = client.serverinfo().await?;
+        let conn = client.serverrequestconnectioninfo().await?;
+
+        Ok(Sample {
+            time: SystemTime::now(),
+            clients_online: info
+                .virtualserver_clientsonline
+                .saturating_sub(info.virtualserver_queryclientsonline),
+            queryclients_online: info.virtualserver_queryclientsonline,
+            channels_online: info.virtualserver_channelsonline,
+            bandwidth_sent: conn.connection_bandwidth_sent_last_second_total,
+            bandwidth_received: conn.connection_bandwidth_received_last_second_total,
+            packetloss: info.virtualserver_total_packetloss_total,
+            ping: info.virtualserver_total_ping,
+        })
+    }
+
+    /// Spawns a new task running the `Sampler` using `client`. Returns a handle giving access
+    /// to the recent samples and a receiver for all new samples. Samples are dropped if the
+    /// receiver is not drained.
+    pub fn spawn(self, client: Client) -> (SamplerHandle, mpsc::Receiver<Result<Sample, Error>>) {
+        let (tx, rx) = mpsc::channel(32);
+
+        let history = Arc::new(Mutex::new(VecDeque::with_capacity(self.history)));
+
+        let history2 = history.clone();
+        let handle = tokio::task::spawn(async move {
+            loop {
+                let sample = Self::sample(&client).await;
+
+                if let Ok(sample) = &sample {
+                    let mut history = history2.lock().unwrap();
+                    if history.len() >= self.history {
+                        history.pop_front();
+                    }
+                    if self.history != 0 {
+                        history.push_back(sample.clone());
+                    }
+                }
+
+                let _ = tx.try_send(sample);
+
+                sleep(self.interval).await;
+            }
+        });
+
+        (SamplerHandle { handle, history }, rx)
+    }
+}
+
+/// A handle to a running [`Sampler`]. The `Sampler` is stopped when the handle is dropped.
+#[derive(Debug)]
+pub struct SamplerHandle {
+    handle: JoinHandle<()>,
+    history: Arc<Mutex<VecDeque<Sample>>>,
+}
+
+impl SamplerHandle {
+    /// Returns the most recent samples, oldest first.
+    pub fn history(&self) -> Vec<Sample> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Stops the `Sampler`.
+    #[inline]
+    pub fn stop(self) {}
+}
+
+impl Drop for SamplerHandle {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::{self, Display, Formatter};
 
-use crate::{Decode, Encode};
+use crate::{Decode, Encode, Error};
 
 #[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct ServerId(pub u64);
@@ -74,3 +74,44 @@ id_impls! {
     ChannelGroupId,
     ApiKeyId,
 }
+
+/// The id of an icon. Icons are stored in the file `/icon_<id>` of channel `0`.
+///
+/// Icon ids are 32 bit unsigned integers, but some servers return them sign extended as
+/// negative numbers. Both forms are decoded into the same id.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct IconId(pub u32);
+
+impl Display for IconId {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Encode for IconId {
+    #[inline]
+    fn encode(&self, buf: &mut String) {
+        self.0.encode(buf)
+    }
+}
+
+impl Decode for IconId {
+    type Error = Error;
+
+    fn decode(buf: &[u8]) -> Result<Self, Self::Error> {
+        if buf.starts_with(b"-") {
+            i32::decode(buf).map(|id| Self(id as u32))
+        } else {
+            u32::decode(buf).map(Self)
+        }
+    }
+}
+
+impl From<u32> for IconId {
+    #[inline]
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
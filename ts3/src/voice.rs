@@ -0,0 +1,282 @@
+//! Voice states of the clients on a virtual server.
+//!
+//! The query interface receives no events when a client starts or stops talking. The voice
+//! state is only included in the client list, [`Client::voice_states`] returns it for all
+//! clients and a [`VoiceMonitor`] polls it to emit an event on every change.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! use ts3::voice::{VoiceEvent, VoiceMonitor};
+//! use ts3::Client;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+//!     let client = Client::connect("localhost:10011").await?;
+//!     client.use_sid(1).await?;
+//!
+//!     let (_monitor, mut events) = VoiceMonitor::new()
+//!         .interval(Duration::from_millis(500))
+//!         .spawn(client);
+//!
+//!     while let Some(event) = events.recv().await {
+//!         match event {
+//!             VoiceEvent::StartedTalking(state) => println!("{} is talking", state.nickname),
+//!             VoiceEvent::StoppedTalking(state) => println!("{} stopped talking", state.nickname),
+//!             _ => (),
+//!         }
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Client::voice_states`]: crate::Client::voice_states
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::response::ClientListEntry;
+use crate::shared::{ChannelId, ClientId};
+use crate::{Client, Error};
+
+/// The voice state of a client. Returned by [`Client::voice_states`].
+///
+/// [`Client::voice_states`]: crate::Client::voice_states
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VoiceState {
+    pub clid: ClientId,
+    pub cid: ChannelId,
+    pub nickname: String,
+    /// The client is currently talking.
+    pub talking: bool,
+    /// The client muted its microphone.
+    pub input_muted: bool,
+    /// The client muted its speakers.
+    pub output_muted: bool,
+    /// The client has a microphone enabled.
+    pub input_hardware: bool,
+    /// The client has speakers enabled.
+    pub output_hardware: bool,
+}
+
+impl VoiceState {
+    /// Returns `true` if the client cannot be heard, because its microphone is muted or
+    /// disabled.
+    pub fn is_muted(&self) -> bool {
+        self.input_muted || !self.input_hardware
+    }
+
+    /// Returns `true` if the client cannot hear other clients, because its speakers are
+    /// muted or disabled.
+    pub fn is_deafened(&self) -> bool {
+        self.output_muted || !self.output_hardware
+    }
+
+    /// Returns the clients in `states` that are currently talking.
+    pub fn talking(states: &[Self]) -> Vec<&Self> {
+        states.iter().filter(|state| state.talking).collect()
+    }
+}
+
+impl From<&ClientListEntry> for VoiceState {
+    fn from(client: &ClientListEntry) -> Self {
+        Self {
+            clid: client.clid,
+            cid: client.cid,
+            nickname: client.client_nickname.clone(),
+            talking: client.client_flag_talking,
+            input_muted: client.client_input_muted,
+            output_muted: client.client_output_muted,
+            input_hardware: client.client_input_hardware,
+            output_hardware: client.client_output_hardware,
+        }
+    }
+}
+
+/// An event emitted by a [`VoiceMonitor`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum VoiceEvent {
+    /// A client started talking.
+    StartedTalking(VoiceState),
+    /// A client stopped talking or left the server while talking.
+    StoppedTalking(VoiceState),
+    /// A client muted or unmuted its microphone or speakers.
+    MuteChanged(VoiceState),
+    /// An error occured while polling the voice states.
+    Error(Error),
+}
+
+/// Periodically polls the voice states of all clients and emits a [`VoiceEvent`] for every
+/// change.
+///
+/// Short utterances between two polls are not noticed. The client list is requested on
+/// every poll, very short intervals may trigger the flood protection of the server unless
+/// the client is whitelisted.
+#[derive(Clone, Debug)]
+pub struct VoiceMonitor {
+    interval: Duration,
+}
+
+impl VoiceMonitor {
+    /// Creates a new `VoiceMonitor`.
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets the interval in which the voice states are polled. Defaults to 1 second.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Spawns a new task running the `VoiceMonitor` using `client`. Returns a handle to stop
+    /// the task and a receiver for all emitted [`VoiceEvent`]s. Events are dropped if the
+    /// receiver is not drained. The task stops once the receiver is dropped.
+    pub fn spawn(self, client: Client) -> (VoiceMonitorHandle, mpsc::Receiver<VoiceEvent>) {
+        let (tx, rx) = mpsc::channel(32);
+
+        let handle = tokio::task::spawn(async move {
+            let mut states = HashMap::new();
+
+            // Polling is useless once nobody receives the events.
+            while !tx.is_closed() {
+                match client.voice_states().await {
+                    Ok(new) => {
+                        for event in diff(&mut states, new) {
+                            let _ = tx.try_send(event);
+                        }
+                    }
+                    Err(err) => {
+                        let _ = tx.try_send(VoiceEvent::Error(err));
+                    }
+                }
+
+                sleep(self.interval).await;
+            }
+        });
+
+        (VoiceMonitorHandle { handle }, rx)
+    }
+}
+
+impl Default for VoiceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces `states` with `new` and returns the events for all changes.
+fn diff(states: &mut HashMap<ClientId, VoiceState>, new: Vec<VoiceState>) -> Vec<VoiceEvent> {
+    let mut events = Vec::new();
+    let mut old = std::mem::take(states);
+
+    for state in new {
+        match old.remove(&state.clid) {
+            Some(prev) => {
+                if prev.is_muted() != state.is_muted() || prev.is_deafened() != state.is_deafened()
+                {
+                    events.push(VoiceEvent::MuteChanged(state.clone()));
+                }
+
+                match (prev.talking, state.talking) {
+                    (false, true) => events.push(VoiceEvent::StartedTalking(state.clone())),
+                    (true, false) => events.push(VoiceEvent::StoppedTalking(state.clone())),
+                    _ => (),
+                }
+            }
+            None if state.talking => events.push(VoiceEvent::StartedTalking(state.clone())),
+            None => (),
+        }
+
+        states.insert(state.clid, state);
+    }
+
+    // Clients that left while talking.
+    for (_, mut state) in old {
+        if state.talking {
+            state.talking = false;
+            events.push(VoiceEvent::StoppedTalking(state));
+        }
+    }
+
+    events
+}
+
+/// A handle to a running [`VoiceMonitor`]. The `VoiceMonitor` is stopped when the handle is
+/// dropped.
+#[derive(Debug)]
+pub struct VoiceMonitorHandle {
+    handle: JoinHandle<()>,
+}
+
+impl VoiceMonitorHandle {
+    /// Stops the `VoiceMonitor`.
+    #[inline]
+    pub fn stop(self) {}
+}
+
+impl Drop for VoiceMonitorHandle {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{diff, VoiceEvent, VoiceState};
+    use crate::shared::{ChannelId, ClientId};
+
+    fn state(clid: u64, talking: bool, input_muted: bool) -> VoiceState {
+        VoiceState {
+            clid: ClientId(clid),
+            cid: ChannelId(1),
+            nickname: format!("client{}", clid),
+            talking,
+            input_muted,
+            output_muted: false,
+            input_hardware: true,
+            output_hardware: true,
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut states = HashMap::new();
+
+        let events = diff(
+            &mut states,
+            vec![state(1, false, false), state(2, true, false)],
+        );
+        assert!(matches!(&events[..], [VoiceEvent::StartedTalking(s)] if s.clid == ClientId(2)));
+
+        let events = diff(
+            &mut states,
+            vec![state(1, true, false), state(2, true, true)],
+        );
+        assert!(matches!(
+            &events[..],
+            [VoiceEvent::StartedTalking(a), VoiceEvent::MuteChanged(b)]
+                if a.clid == ClientId(1) && b.clid == ClientId(2)
+        ));
+
+        // Client 1 left while talking.
+        let events = diff(&mut states, vec![state(2, false, true)]);
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, VoiceEvent::StoppedTalking(_))));
+        assert_eq!(states.len(), 1);
+    }
+}
@@ -0,0 +1,2559 @@
+#![cfg(feature = "client")]
+
+mod mock;
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use ts3::admin::{self, CloneOptions};
+use ts3::ban::{self, BanRule, BanSet};
+use ts3::broadcast::Broadcast;
+use ts3::event::{ChannelDeleted, Event, EventHandler};
+use ts3::ft::{DownloadOptions, FileTransfer, TransferOutcome};
+use ts3::membership::{MembershipStore, MemoryStore, TemporaryMembership};
+use ts3::perm::{self, BulkEdit, PermissionAssignment, PermissionTarget, Verdict};
+use ts3::protect::MoveProtection;
+use ts3::provision::{self, ProvisionError};
+use ts3::proxy::Proxy;
+use ts3::request::{
+    QueryLoginListOptions, RequestBuilder, ServerNotifyRegister, TextMessageTarget, TypedCommand,
+    VirtualServerProperties,
+};
+use ts3::response::MessageOutcome;
+use ts3::schedule::Schedule;
+use ts3::shared::list::Pipe;
+use ts3::shared::{
+    ChannelGroupId, ChannelId, ClientDatabaseId, ClientId, IconId, List, ServerGroupId, ServerId,
+};
+use ts3::voice::{VoiceEvent, VoiceMonitor};
+use ts3::{
+    async_trait, Client, ClientBuilder, Decode, Error, PanicPolicy, Reconnect, ServerFlavor,
+    ServerSelection, Shutdown, SlowCommand,
+};
+
+use mock::MockServer;
+
+#[tokio::test]
+async fn message_rate_limited() {
+    let (addr, _) = MockServer::new()
+        .ok("sendtextmessage")
+        .reply(
+            "sendtextmessage",
+            &["error id=524 msg=client\\sis\\sflooding extra_msg=please\\swait\\s2\\sseconds"],
+        )
+        .reply(
+            "gm",
+            &["error id=2568 msg=insufficient\\sclient\\spermissions"],
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let target = TextMessageTarget::Client(ClientId(1));
+    assert_eq!(
+        client
+            .sendtextmessage_outcome(target, "Hello")
+            .await
+            .unwrap(),
+        MessageOutcome::Sent
+    );
+    assert_eq!(
+        client
+            .sendtextmessage_outcome(target, "Hello")
+            .await
+            .unwrap(),
+        MessageOutcome::RateLimited {
+            retry_after: Some(Duration::from_secs(2))
+        }
+    );
+    // Without the outcome the rejected message is an error.
+    let err = client.sendtextmessage(target, "Hello").await.unwrap_err();
+    assert_eq!(err.retry_after(), Some(Duration::from_secs(2)));
+    assert!(client.gm_outcome("Hello").await.is_err());
+}
+
+#[tokio::test]
+async fn dead_letter_queue() {
+    let (addr, _) = MockServer::new()
+        .reply(
+            "servernotifyregister",
+            &[
+                "notifychanneldeleted invokerid=0 invokername=Server cid=1",
+                "error id=0 msg=ok",
+            ],
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    let mut rx = client.dead_letter_queue(8);
+
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+    match rx.recv().await.unwrap() {
+        Event::ChannelDeleted(event) => assert_eq!(event.cid, ChannelId(1)),
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    client.set_event_handler_fn(|_, _| async {});
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+    assert!(rx.try_recv().is_err());
+}
+
+/// Sets the flag after handling a `channeldeleted` event for 100ms.
+struct Slow(Arc<AtomicBool>);
+
+#[async_trait]
+impl EventHandler for Slow {
+    async fn channeldeleted(&self, _client: Client, _event: ChannelDeleted) {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Returns a mock server sending a `channeldeleted` event on `servernotifyregister`.
+fn event_server() -> MockServer {
+    MockServer::new()
+        .reply(
+            "servernotifyregister",
+            &[
+                "notifychanneldeleted invokerid=0 invokername=Server cid=1",
+                "error id=0 msg=ok",
+            ],
+        )
+        .ok("quit")
+}
+
+#[tokio::test]
+async fn replace_event_handler() {
+    struct Noop;
+
+    impl EventHandler for Noop {}
+
+    let (addr, _) = event_server().spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let handled = Arc::new(AtomicBool::new(false));
+    client.set_event_handler(Slow(handled.clone()));
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+
+    client.replace_event_handler(Noop).await;
+    assert!(handled.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn disconnect() {
+    for (shutdown, completed) in [(Shutdown::Wait, true), (Shutdown::Abort, false)] {
+        let (addr, log) = event_server().spawn().await;
+
+        let client = ClientBuilder::new()
+            .no_keepalive()
+            .connect(addr)
+            .await
+            .unwrap();
+
+        let handled = Arc::new(AtomicBool::new(false));
+        client.set_event_handler(Slow(handled.clone()));
+        client
+            .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+            .await
+            .unwrap();
+
+        client.disconnect(shutdown).await.unwrap();
+        assert_eq!(handled.load(Ordering::SeqCst), completed);
+        assert_eq!(log.lock().unwrap().last().unwrap(), "quit");
+    }
+}
+
+#[tokio::test]
+async fn disconnect_no_error() {
+    struct Errors(Arc<AtomicUsize>);
+
+    impl EventHandler for Errors {
+        fn error(&self, _client: Client, _error: Error) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let (addr, _) = event_server().spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let errors = Arc::new(AtomicUsize::new(0));
+    client.set_event_handler(Errors(errors.clone()));
+    client.disconnect(Shutdown::Wait).await.unwrap();
+
+    // The server closing the connection after `quit` is not an error.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(errors.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn handler_panic() {
+    struct Panics(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl EventHandler for Panics {
+        async fn channeldeleted(&self, _client: Client, event: ChannelDeleted) {
+            panic!("channel {} deleted", event.cid.0);
+        }
+
+        fn error(&self, _client: Client, error: Error) {
+            let msg = error.as_handler_panic().unwrap().to_owned();
+            self.0.lock().unwrap().push(msg);
+        }
+    }
+
+    for policy in [PanicPolicy::Report, PanicPolicy::Disconnect] {
+        let (addr, log) = event_server().spawn().await;
+
+        let client = ClientBuilder::new()
+            .no_keepalive()
+            .panic_policy(policy)
+            .connect(addr)
+            .await
+            .unwrap();
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        client.set_event_handler(Panics(errors.clone()));
+        client
+            .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(*errors.lock().unwrap(), ["channel 1 deleted"]);
+        assert_eq!(
+            log.lock().unwrap().last().unwrap() == "quit",
+            policy == PanicPolicy::Disconnect
+        );
+    }
+}
+
+#[tokio::test]
+async fn handler_fn_panic() {
+    for policy in [PanicPolicy::Report, PanicPolicy::Disconnect] {
+        let (addr, log) = event_server().spawn().await;
+
+        let client = ClientBuilder::new()
+            .no_keepalive()
+            .panic_policy(policy)
+            .connect(addr)
+            .await
+            .unwrap();
+
+        // The function panics before returning a future.
+        client.set_event_handler_fn(|_client, event| match event {
+            Event::ChannelDeleted(event) => panic!("channel {} deleted", event.cid.0),
+            _ => std::future::ready(()),
+        });
+        client
+            .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        if policy == PanicPolicy::Report {
+            // The connection is still read.
+            client
+                .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+                .await
+                .unwrap();
+        } else {
+            assert_eq!(log.lock().unwrap().last().unwrap(), "quit");
+        }
+    }
+}
+
+#[tokio::test]
+async fn max_concurrent_handlers() {
+    #[derive(Default)]
+    struct Counter {
+        running: AtomicUsize,
+        max: AtomicUsize,
+        handled: AtomicUsize,
+    }
+
+    struct Limited(Arc<Counter>);
+
+    #[async_trait]
+    impl EventHandler for Limited {
+        async fn channeldeleted(&self, _client: Client, _event: ChannelDeleted) {
+            let running = self.0.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.0.max.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.0.running.fetch_sub(1, Ordering::SeqCst);
+            self.0.handled.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let (addr, _) = MockServer::new()
+        .reply(
+            "servernotifyregister",
+            &[
+                "notifychanneldeleted cid=1",
+                "notifychanneldeleted cid=2",
+                "notifychanneldeleted cid=3",
+                "error id=0 msg=ok",
+            ],
+        )
+        .ok("quit")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .max_concurrent_handlers(2)
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let counter = Arc::new(Counter::default());
+    client.set_event_handler(Limited(counter.clone()));
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+    client.disconnect(Shutdown::Wait).await.unwrap();
+
+    assert_eq!(counter.handled.load(Ordering::SeqCst), 3);
+    assert_eq!(counter.max.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn create_and_use() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "servercreate",
+            "sid=7 token=AdminToken+ virtualserver_port=9988",
+        )
+        .ok("use")
+        .ok("serveredit")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let properties = VirtualServerProperties::new()
+        .maxclients(32)
+        .welcomemessage("Hello World");
+    let server = client.create_and_use("Test", properties).await.unwrap();
+    assert_eq!(server.sid, ServerId(7));
+    assert_eq!(server.token, "AdminToken+");
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "servercreate virtualserver_name=Test",
+            "use sid=7",
+            "whoami",
+            "serveredit virtualserver_welcomemessage=Hello\\sWorld virtualserver_maxclients=32",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn walk_files() {
+    let (addr, log) = MockServer::new()
+        .reply(
+            "ftgetfilelist",
+            &["error id=524 msg=client\\sis\\sflooding extra_msg=please\\swait\\s0\\sseconds"],
+        )
+        .data(
+            "ftgetfilelist",
+            "cid=1 path=\\/ name=docs size=0 datetime=1 type=0|name=a.txt size=3 datetime=2 type=1",
+        )
+        .data(
+            "ftgetfilelist",
+            "cid=1 path=\\/docs name=b.txt size=5 datetime=3 type=1",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let mut files = Vec::new();
+    let mut rx = client.walk_files(ChannelId(1), None, "/");
+    while let Some(file) = rx.recv().await {
+        let file = file.unwrap();
+        assert_eq!(file.cid, ChannelId(1));
+        files.push(format!("{} {}", file.path, file.name));
+    }
+
+    assert_eq!(files, ["/ docs", "/ a.txt", "/docs b.txt"]);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "ftgetfilelist cid=1 cpw= path=\\/",
+            "ftgetfilelist cid=1 cpw= path=\\/",
+            "ftgetfilelist cid=1 cpw= path=\\/docs",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn connection_closed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream
+            .write_all(b"TS3\n\rWelcome to the TeamSpeak 3 ServerQuery interface.\n\r")
+            .await
+            .unwrap();
+
+        // Close the connection after the first command without answering it.
+        let _ = stream.read(&mut [0; 64]).await;
+    });
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let timeout = Duration::from_secs(5);
+    let err = tokio::time::timeout(timeout, client.version())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(err.is_retryable());
+    assert!(err.to_string().contains("connection closed"), "{}", err);
+
+    // Later commands fail instead of waiting for a response.
+    let err = tokio::time::timeout(timeout, client.version())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn watchdog_idle() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream
+            .write_all(b"TS3\n\rWelcome to the TeamSpeak 3 ServerQuery interface.\n\r")
+            .await
+            .unwrap();
+
+        // Keep the connection open without ever answering.
+        let mut buf = [0; 64];
+        while let Ok(n @ 1..) = stream.read(&mut buf).await {
+            let _ = tx.send(String::from_utf8_lossy(&buf[..n]).into_owned());
+        }
+    });
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .watchdog(Duration::from_millis(100))
+        .connect(addr)
+        .await
+        .unwrap();
+
+    // The idle connection is probed without any command being sent.
+    let probe = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(probe, "version\n");
+
+    // The unanswered probe closes the connection and later commands fail immediately.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let err = tokio::time::timeout(Duration::from_millis(50), client.whoami())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn watchdog_responsive() {
+    let (addr, log) = MockServer::new()
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .watchdog(Duration::from_millis(50))
+        .connect(addr)
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(log.lock().unwrap().iter().all(|line| line == "version"));
+    assert!(log.lock().unwrap().len() >= 2);
+
+    // Answered probes keep the connection open.
+    client.version().await.unwrap();
+}
+
+#[tokio::test]
+async fn reconnect() {
+    let (addr, log) = MockServer::new()
+        .ok("login")
+        .data("whoami", "virtualserver_id=0 client_id=3")
+        .close("version")
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .reconnect(Reconnect::new().delay(Duration::from_millis(10)))
+        .connect(addr)
+        .await
+        .unwrap();
+
+    client.login("serveradmin", "password").await.unwrap();
+    assert!(client.me().is_some());
+
+    // The command sent when the connection is lost fails, later commands are sent over the
+    // new connection.
+    assert!(client.version().await.is_err());
+    assert_eq!(client.version().await.unwrap().build, 1655727713);
+    assert!(client.me().is_none());
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "login client_login_name=serveradmin client_login_password=password",
+            "whoami",
+            "version",
+            "version",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reconnect_give_up() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        // Close the only connection the server ever accepts right after the banner.
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream
+            .write_all(b"TS3\n\rWelcome to the TeamSpeak 3 ServerQuery interface.\n\r")
+            .await
+            .unwrap();
+    });
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .reconnect(
+            Reconnect::new()
+                .delay(Duration::from_millis(10))
+                .max_attempts(2),
+        )
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let err = tokio::time::timeout(Duration::from_secs(5), client.version())
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn watchdog_reconnect() {
+    let (addr, _) = MockServer::new()
+        .reply("version", &[])
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .watchdog(Duration::from_millis(100))
+        .reconnect(Reconnect::new().delay(Duration::from_millis(10)))
+        .connect(addr)
+        .await
+        .unwrap();
+
+    // The unanswered command times out and the client reconnects.
+    let err = client.version().await.unwrap_err();
+    assert!(err.is_retryable());
+    client.version().await.unwrap();
+}
+
+/// Serves `data` on every file transfer connection. Returns the port and the highest number
+/// of connections open at the same time.
+async fn ft_server(data: &'static [u8]) -> (u16, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let active = Arc::new(AtomicUsize::new(0));
+    let max = Arc::new(AtomicUsize::new(0));
+    let max2 = max.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let n = active.fetch_add(1, Ordering::SeqCst) + 1;
+            max2.fetch_max(n, Ordering::SeqCst);
+
+            let active = active.clone();
+            tokio::spawn(async move {
+                let mut key = [0; 3];
+                stream.read_exact(&mut key).await.unwrap();
+                assert_eq!(&key, b"key");
+
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                stream.write_all(data).await.unwrap();
+                stream.shutdown().await.unwrap();
+
+                // Wait for the client to close the connection.
+                let _ = stream.read(&mut [0]).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+
+    (port, max)
+}
+
+#[tokio::test]
+async fn file_transfer_max_parallel() {
+    let (port, max) = ft_server(b"Hello World").await;
+    let (addr, log) = MockServer::new()
+        .data(
+            "ftinitdownload",
+            &format!(
+                "clientftfid=0 serverftfid=1 ftkey=key port={} size=11",
+                port
+            ),
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let transfer = FileTransfer::new(client, "127.0.0.1").max_parallel(1);
+    let (mut a, mut b) = (Vec::new(), Vec::new());
+    let (res_a, res_b) = tokio::join!(
+        transfer.download(ChannelId(1), None, "/a", &mut a),
+        transfer.download(ChannelId(1), None, "/b", &mut b),
+    );
+
+    assert_eq!(res_a.unwrap(), TransferOutcome::Complete { bytes: 11 });
+    assert_eq!(res_b.unwrap(), TransferOutcome::Complete { bytes: 11 });
+    assert_eq!(a, b"Hello World");
+    assert_eq!(b, b"Hello World");
+    assert_eq!(max.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "ftinitdownload clientftfid=0 name=\\/a cid=1 cpw= seekpos=0",
+            "ftinitdownload clientftfid=1 name=\\/b cid=1 cpw= seekpos=0",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn file_transfer_proxy() {
+    let (port, _) = ft_server(b"Hello World").await;
+    let (addr, _) = MockServer::new()
+        .data(
+            "ftinitdownload",
+            &format!(
+                "clientftfid=0 serverftfid=1 ftkey=key port={} size=11",
+                port
+            ),
+        )
+        .spawn()
+        .await;
+
+    // An HTTP proxy recording the targets of all tunnels.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy_addr = listener.local_addr().unwrap();
+    let targets = Arc::new(Mutex::new(Vec::new()));
+    let targets2 = targets.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let targets = targets2.clone();
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                while !buf.ends_with(b"\r\n\r\n") {
+                    buf.push(stream.read_u8().await.unwrap());
+                }
+                let target = String::from_utf8(buf).unwrap()[8..]
+                    .split(' ')
+                    .next()
+                    .unwrap()
+                    .to_owned();
+                targets.lock().unwrap().push(target.clone());
+
+                let mut upstream = TcpStream::connect(target).await.unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await
+                    .unwrap();
+                let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+            });
+        }
+    });
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .proxy(Proxy::http(&proxy_addr.to_string()))
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let transfer = FileTransfer::new(client, "127.0.0.1");
+    let mut buf = Vec::new();
+    let outcome = transfer
+        .download(ChannelId(1), None, "/a", &mut buf)
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, TransferOutcome::Complete { bytes: 11 });
+    assert_eq!(buf, b"Hello World");
+    assert_eq!(
+        *targets.lock().unwrap(),
+        [addr.to_string(), format!("127.0.0.1:{}", port)]
+    );
+}
+
+#[tokio::test]
+async fn file_transfer_resume() {
+    let (port, _) = ft_server(b"World").await;
+    let (addr, log) = MockServer::new()
+        .data(
+            "ftinitdownload",
+            &format!(
+                "clientftfid=0 serverftfid=1 ftkey=key port={} size=11",
+                port
+            ),
+        )
+        .data(
+            "ftinitdownload",
+            &format!(
+                "clientftfid=1 serverftfid=2 ftkey=key port={} size=12",
+                port
+            ),
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let transfer = FileTransfer::new(client, "127.0.0.1");
+    let mut buf = b"Hello ".to_vec();
+    let options = DownloadOptions::new().offset(6).crc32(0x1234);
+    let outcome = transfer
+        .download_with(ChannelId(1), None, "/a", &mut buf, options)
+        .await
+        .unwrap();
+    assert_eq!(buf, b"Hello World");
+    assert!(matches!(
+        outcome,
+        TransferOutcome::ChecksumMismatch {
+            expected: 0x1234,
+            ..
+        }
+    ));
+
+    let options = DownloadOptions::new().offset(6);
+    let outcome = transfer
+        .download_with(ChannelId(1), None, "/a", &mut Vec::new(), options)
+        .await
+        .unwrap();
+    assert_eq!(
+        outcome,
+        TransferOutcome::Incomplete {
+            received: 5,
+            expected: 6
+        }
+    );
+
+    assert_eq!(
+        log.lock().unwrap()[0],
+        "ftinitdownload clientftfid=0 name=\\/a cid=1 cpw= seekpos=6"
+    );
+}
+
+#[tokio::test]
+async fn file_transfer_rejected() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "ftinitdownload",
+            "clientftfid=0 status=2051 msg=invalid\\sfile\\spath",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let err = FileTransfer::new(client, "127.0.0.1")
+        .download(ChannelId(1), None, "/a", &mut Vec::new())
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "TS3 error 2051: invalid file path");
+}
+
+#[tokio::test]
+async fn clone_server() {
+    let (src_addr, src_log) = MockServer::new()
+        .data("serversnapshotcreate", "version=3 data=AAAA")
+        .spawn()
+        .await;
+    let (dst_addr, dst_log) = MockServer::new()
+        .data(
+            "serversnapshotdeploy",
+            "sid=4 virtualserver_port=9990 ochannel_id=1 nchannel_id=11|ochannel_id=2 nchannel_id=12",
+        )
+        .ok("use")
+        .ok("serveredit")
+        .spawn()
+        .await;
+
+    let src = ClientBuilder::new()
+        .no_keepalive()
+        .connect(src_addr)
+        .await
+        .unwrap();
+    let dst = ClientBuilder::new()
+        .no_keepalive()
+        .connect(dst_addr)
+        .await
+        .unwrap();
+
+    let options = CloneOptions::new().keepfiles(true).port(9988);
+    let server = admin::clone_server(&src, &dst, options).await.unwrap();
+    assert_eq!(server.sid, ServerId(4));
+    assert_eq!(server.port, 9988);
+    assert_eq!(
+        server.channels,
+        [(ChannelId(1), ChannelId(11)), (ChannelId(2), ChannelId(12))]
+    );
+
+    assert_eq!(*src_log.lock().unwrap(), ["serversnapshotcreate"]);
+    assert_eq!(
+        *dst_log.lock().unwrap(),
+        [
+            "serversnapshotdeploy -keepfiles -mapping version=3 data=AAAA",
+            "use sid=4",
+            "whoami",
+            "serveredit virtualserver_port=9988",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn clone_server_invalid_target() {
+    let (src_addr, _) = MockServer::new()
+        .data("serversnapshotcreate", "version=3 data=AAAA")
+        .spawn()
+        .await;
+    let (dst_addr, dst_log) = MockServer::new()
+        .ok("use")
+        .ok("serversnapshotdeploy")
+        .spawn()
+        .await;
+
+    let src = ClientBuilder::new()
+        .no_keepalive()
+        .connect(src_addr)
+        .await
+        .unwrap();
+    let dst = ClientBuilder::new()
+        .no_keepalive()
+        .connect(dst_addr)
+        .await
+        .unwrap();
+
+    // No server was deployed.
+    admin::clone_server(&src, &dst, CloneOptions::new())
+        .await
+        .unwrap_err();
+
+    // The selected server would be replaced.
+    dst.use_sid(1).await.unwrap();
+    admin::clone_server(&src, &dst, CloneOptions::new())
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        *dst_log.lock().unwrap(),
+        [
+            "serversnapshotdeploy -mapping version=3 data=AAAA",
+            "use sid=1",
+            "whoami"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn login_with_apikey() {
+    let (addr, log) = MockServer::new()
+        .reply("auth", &["error id=0 msg=ok"])
+        .reply("auth", &["error id=520 msg=invalid\\sapikey"])
+        .data(
+            "whoami",
+            "virtualserver_id=0 client_id=3 client_database_id=7",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    client
+        .login_with_apikey("BAByFoiEXZfnSJyE6dbXFiW")
+        .await
+        .unwrap();
+    assert_eq!(client.me().unwrap().client_database_id, ClientDatabaseId(7));
+
+    let err = client.login_with_apikey("wrong").await.unwrap_err();
+    assert_eq!(err.id(), Some(520));
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "auth apikey=BAByFoiEXZfnSJyE6dbXFiW",
+            "whoami",
+            "auth apikey=wrong",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn queryloginlist() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "queryloginlist",
+            "count=2 cldbid=3 sid=0 client_login_name=admin|cldbid=7 sid=1 client_login_name=bot",
+        )
+        .reply(
+            "queryloginlist",
+            &["error id=1281 msg=database\\sempty\\sresult\\sset"],
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let options = QueryLoginListOptions {
+        count: true,
+        ..Default::default()
+    };
+    let logins = client.queryloginlist(options).await.unwrap();
+    assert_eq!(logins.len(), 2);
+    assert_eq!(logins[0].count, 2);
+    assert!(logins[0].is_instance_login());
+    assert_eq!(logins[1].client_login_name, "bot");
+    assert_eq!(logins[1].sid, ServerId(1));
+
+    let options = QueryLoginListOptions {
+        pattern: Some("none%"),
+        ..Default::default()
+    };
+    assert!(client.queryloginlist(options).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn broadcast() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "serverlist",
+            "virtualserver_id=1 virtualserver_status=online|virtualserver_id=2 virtualserver_status=offline",
+        )
+        .ok("use")
+        .data(
+            "serverinfo",
+            "virtualserver_id=1 virtualserver_name=Alpha virtualserver_clientsonline=3",
+        )
+        .data(
+            "serverinfo",
+            "virtualserver_id=1 virtualserver_name=Alpha virtualserver_clientsonline=5",
+        )
+        .ok("sendtextmessage")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let broadcast = Broadcast::new();
+    for _ in 0..2 {
+        let outcomes = broadcast
+            .send(&client, "%server_name% restarts for %clients_online%")
+            .await
+            .unwrap();
+        assert_eq!(outcomes, [(ServerId(1), MessageOutcome::Sent)]);
+    }
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "serverlist",
+            "use sid=1",
+            "whoami",
+            "serverinfo",
+            "sendtextmessage targetmode=3 msg=Alpha\\srestarts\\sfor\\s3",
+            "serverlist",
+            "use sid=1",
+            "whoami",
+            "serverinfo",
+            "sendtextmessage targetmode=3 msg=Alpha\\srestarts\\sfor\\s5",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn gm_with() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "serverlist",
+            "virtualserver_id=1 virtualserver_status=online|virtualserver_id=2 virtualserver_status=online",
+        )
+        .ok("use")
+        .data("serverinfo", "virtualserver_id=1 virtualserver_maxclients=lots")
+        .data(
+            "serverinfo",
+            "virtualserver_id=2 virtualserver_name=Beta virtualserver_clientsonline=5",
+        )
+        .ok("sendtextmessage")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    // The server with the invalid info is skipped.
+    let outcomes = client
+        .gm_with("%server_name%: %clients_online%", &Broadcast::new())
+        .await
+        .unwrap();
+    assert_eq!(outcomes, [(ServerId(2), MessageOutcome::Sent)]);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "serverlist",
+            "use sid=1",
+            "whoami",
+            "serverinfo",
+            "use sid=2",
+            "whoami",
+            "serverinfo",
+            "sendtextmessage targetmode=3 msg=Beta:\\s5",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn bulk_edit() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "servergrouppermlist",
+            "permsid=b_a permvalue=1 permnegated=0 permskip=0\
+             |permsid=i_b permvalue=50 permnegated=0 permskip=1\
+             |permsid=i_c permvalue=5 permnegated=1 permskip=0",
+        )
+        .ok("servergroupaddperm")
+        .ok("servergroupdelperm")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let target = PermissionTarget::ServerGroup(ServerGroupId(4));
+    let edit = BulkEdit::new(
+        target,
+        vec![
+            PermissionAssignment::new("b_a", 1),
+            PermissionAssignment::new("i_b", 50).skip(true),
+            PermissionAssignment::new("i_d", 10),
+            PermissionAssignment::new("i_e", 20).negated(true),
+        ],
+    )
+    .remove_unlisted(true)
+    .batch_size(1)
+    .delay(Duration::ZERO);
+
+    // Unchanged permissions, including their flags, are not part of the diff.
+    let changes = edit.clone().dry_run(true).execute(&client).await.unwrap();
+    let diff: Vec<_> = changes
+        .iter()
+        .map(|change| {
+            (
+                change.old.as_ref().map(|perm| perm.permsid.as_str()),
+                change.new.as_ref().map(|perm| perm.permsid.as_str()),
+            )
+        })
+        .collect();
+    assert_eq!(
+        diff,
+        [
+            (None, Some("i_d")),
+            (None, Some("i_e")),
+            (Some("i_c"), None)
+        ]
+    );
+    assert!(changes[2].old.as_ref().unwrap().permnegated);
+
+    assert_eq!(edit.execute(&client).await.unwrap(), changes);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "servergrouppermlist sgid=4 -permsid",
+            "servergrouppermlist sgid=4 -permsid",
+            "servergroupaddperm sgid=4 permsid=i_d permvalue=10 permnegated=0 permskip=0",
+            "servergroupaddperm sgid=4 permsid=i_e permvalue=20 permnegated=1 permskip=0",
+            "servergroupdelperm sgid=4 permsid=i_c",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn perm_export_apply() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "servergrouppermlist",
+            "permsid=b_a permvalue=1 permnegated=0 permskip=0\
+             |permsid=i_b permvalue=50 permnegated=1 permskip=1",
+        )
+        .data(
+            "channelgrouppermlist",
+            "permsid=i_b permvalue=50 permnegated=0 permskip=0\
+             |permsid=i_c permvalue=5 permnegated=0 permskip=0",
+        )
+        .reply(
+            "channelgrouppermlist",
+            &["error id=1281 msg=database\\sempty\\sresult\\sset"],
+        )
+        .ok("channelgroupaddperm")
+        .ok("channelgroupdelperm")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let set = perm::export(&client, PermissionTarget::ServerGroup(ServerGroupId(4)))
+        .await
+        .unwrap();
+    assert_eq!(
+        set.permissions(),
+        [
+            PermissionAssignment::new("b_a", 1),
+            PermissionAssignment::new("i_b", 50)
+                .negated(true)
+                .skip(true),
+        ]
+    );
+
+    // The target ends up with exactly the exported permissions. Channel groups do not
+    // support the negated and skip flags, so they are dropped.
+    let target = PermissionTarget::ChannelGroup(ChannelGroupId(9));
+    let changes = perm::apply(&client, target, set.clone()).await.unwrap();
+    assert_eq!(changes.len(), 2);
+
+    // A target without any permissions gets all of them.
+    let changes = perm::apply(&client, target, set).await.unwrap();
+    assert_eq!(changes.len(), 2);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "servergrouppermlist sgid=4 -permsid",
+            "channelgrouppermlist cgid=9 -permsid",
+            "channelgroupaddperm cgid=9 permsid=b_a permvalue=1",
+            "channelgroupdelperm cgid=9 permsid=i_c",
+            "channelgrouppermlist cgid=9 -permsid",
+            "channelgroupaddperm cgid=9 permsid=b_a permvalue=1|permsid=i_b permvalue=50",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn ban_export_apply() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "banlist",
+            "banid=1 ip=10.0.0.1 name= uid= mytsid= lastnickname= created=1 duration=0 \
+             invokername=admin invokercldbid=2 invokeruid=abc reason=spam enforcements=3\
+             |banid=2 ip= name=Bot.* uid= mytsid= lastnickname=Bot created=1 duration=3600 \
+             invokername=admin invokercldbid=2 invokeruid=abc reason= enforcements=0",
+        )
+        .data(
+            "banlist",
+            "banid=7 ip=10.0.0.1 name= uid= mytsid= lastnickname= created=1 duration=60 \
+             invokername=admin invokercldbid=2 invokeruid=abc reason= enforcements=0",
+        )
+        .reply(
+            "banlist",
+            &["error id=1281 msg=database\\sempty\\sresult\\sset"],
+        )
+        .ok("banadd")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let set = ban::export(&client).await.unwrap();
+    assert_eq!(
+        set.rules(),
+        [
+            BanRule {
+                ip: Some(String::from("10.0.0.1")),
+                duration: 0,
+                reason: Some(String::from("spam")),
+                ..Default::default()
+            },
+            BanRule {
+                name: Some(String::from("Bot.*")),
+                duration: 3600,
+                lastnickname: Some(String::from("Bot")),
+                ..Default::default()
+            },
+        ]
+    );
+
+    // Rules for a target that is already banned and duplicates are skipped.
+    let mut rules = set.into_inner();
+    rules.push(rules[1].clone());
+    let added = ban::apply(&client, BanSet::new(rules), Duration::ZERO)
+        .await
+        .unwrap();
+    assert_eq!(added.len(), 1);
+    assert_eq!(added[0].name.as_deref(), Some("Bot.*"));
+
+    // An empty ban list exports an empty set.
+    assert!(ban::export(&client).await.unwrap().rules().is_empty());
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "banlist",
+            "banlist",
+            "banadd name=Bot.* time=3600 lastnickname=Bot",
+            "banlist",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn check_slots() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "serverlist",
+            "virtualserver_id=1 virtualserver_port=9987 virtualserver_status=online \
+             virtualserver_maxclients=32\
+             |virtualserver_id=2 virtualserver_port=9988 virtualserver_status=offline \
+             virtualserver_maxclients=64",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    // The slots of the stopped server are used as well.
+    provision::check_slots(&client, 32, 128).await.unwrap();
+    let err = provision::check_slots(&client, 33, 128).await.unwrap_err();
+    assert_eq!(
+        err.as_provision(),
+        Some(&ProvisionError::NotEnoughSlots {
+            requested: 33,
+            available: 32
+        })
+    );
+    assert_eq!(*log.lock().unwrap(), ["serverlist", "serverlist"]);
+}
+
+#[tokio::test]
+async fn provision_transitional_status() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "serverlist",
+            "virtualserver_id=1 virtualserver_port=9987 virtualserver_status=booting\\sup \
+             virtualserver_maxclients=32\
+             |virtualserver_id=2 virtualserver_port=9988 virtualserver_status=virtual\\sonline \
+             virtualserver_maxclients=64\
+             |virtualserver_id=3 virtualserver_port=9989 virtualserver_status=shutting\\sdown \
+             virtualserver_maxclients=16",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        provision::free_port(&client, 9987..=10000).await.unwrap(),
+        9990
+    );
+    let err = provision::check_slots(&client, 17, 128).await.unwrap_err();
+    assert_eq!(
+        err.as_provision(),
+        Some(&ProvisionError::NotEnoughSlots {
+            requested: 17,
+            available: 16
+        })
+    );
+}
+
+#[tokio::test]
+async fn can_client_join() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "permoverview",
+            "t=2 id1=3 id2=0 p=i_channel_needed_join_power v=50 n=0 s=0",
+        )
+        .data(
+            "permoverview",
+            "t=0 id1=6 id2=0 p=i_channel_join_power v=75 n=0 s=0",
+        )
+        .reply(
+            "permoverview",
+            &["error id=1281 msg=database\\sempty\\sresult\\sset"],
+        )
+        .data(
+            "channelinfo",
+            "pid=0 channel_name=Private channel_flag_password=1",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let verdict = perm::can_client_join(&client, ClientDatabaseId(5), ChannelId(3))
+        .await
+        .unwrap();
+    assert_eq!(verdict, Verdict::PasswordRequired);
+
+    // The needed power is not set anymore.
+    let verdict = perm::can_client_subscribe(&client, ClientDatabaseId(5), ChannelId(3))
+        .await
+        .unwrap();
+    assert!(verdict.is_allowed());
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "permoverview cid=3 cldbid=5 permsid=i_channel_needed_join_power",
+            "permoverview cid=3 cldbid=5 permsid=i_channel_join_power",
+            "channelinfo cid=3",
+            "permoverview cid=3 cldbid=5 permsid=b_channel_join_ignore_password",
+            "permoverview cid=3 cldbid=5 permsid=i_channel_needed_subscribe_power",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn can_client_join_insufficient_power() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "permoverview",
+            "t=2 id1=3 id2=0 p=i_channel_needed_join_power v=50 n=0 s=0",
+        )
+        .data(
+            "permoverview",
+            "t=0 id1=6 id2=0 p=i_channel_join_power v=20 n=0 s=0",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let verdict = perm::can_client_join(&client, ClientDatabaseId(5), ChannelId(3))
+        .await
+        .unwrap();
+    assert_eq!(
+        verdict,
+        Verdict::InsufficientPower {
+            permsid: "i_channel_join_power",
+            needed: 50,
+            granted: 20
+        }
+    );
+}
+
+#[tokio::test]
+async fn servergroup_icons() {
+    let (port, _) = ft_server(b"PNG").await;
+    let (addr, log) = MockServer::new()
+        .data(
+            "servergrouplist",
+            "sgid=1 name=Guest type=1 iconid=0|sgid=2 name=Admin type=1 iconid=300|sgid=3 name=VIP type=1 iconid=1234|sgid=4 name=Mod type=1 iconid=1234|sgid=5 name=Bot type=1 iconid=-1234",
+        )
+        .data(
+            "ftinitdownload",
+            &format!("clientftfid=0 serverftfid=1 ftkey=key port={} size=3", port),
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let icons = FileTransfer::new(client, "127.0.0.1")
+        .servergroup_icons()
+        .await
+        .unwrap();
+    assert_eq!(icons.len(), 3);
+    assert_eq!(icons[&ServerGroupId(3)], b"PNG");
+    assert_eq!(icons[&ServerGroupId(4)], b"PNG");
+    assert_eq!(icons[&ServerGroupId(5)], b"PNG");
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "servergrouplist",
+            "ftinitdownload clientftfid=0 name=\\/icon_1234 cid=0 cpw= seekpos=0",
+            "ftinitdownload clientftfid=1 name=\\/icon_4294966062 cid=0 cpw= seekpos=0",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn server_flavor() {
+    let data = "version=1.5.6 build=beta platform=Linux";
+
+    let (addr, log) = MockServer::new()
+        .banner(&[
+            "TS3",
+            "Welcome to the TeaSpeak ServerQuery interface.",
+            "TeaSpeak 1.5.6",
+        ])
+        .data("version", data)
+        .data("whoami", "virtualserver_id=1 client_id=abc")
+        .spawn()
+        .await;
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .flavor(ServerFlavor::TeaSpeak)
+        .connect(addr)
+        .await
+        .unwrap();
+    assert_eq!(client.flavor(), ServerFlavor::TeaSpeak);
+
+    // The invalid build is skipped.
+    let version = client.version().await.unwrap();
+    assert_eq!((version.version.as_str(), version.build), ("1.5.6", 0));
+    // Other invalid fields are not.
+    assert!(client.whoami().await.is_err());
+
+    assert!(client.apikeylist(None, None, None, false).await.is_err());
+    assert_eq!(*log.lock().unwrap(), ["version", "whoami"]);
+
+    let (addr, _) = MockServer::new().data("version", data).spawn().await;
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    assert!(client.version().await.is_err());
+}
+
+#[tokio::test]
+async fn blank_lines() {
+    let (addr, _) = MockServer::new()
+        .banner(&[
+            "TS3",
+            "",
+            "Welcome to the TeamSpeak 3 ServerQuery interface.",
+        ])
+        .reply(
+            "version",
+            &[
+                "",
+                "version=3.13.7 build=1655727713 platform=Linux",
+                "",
+                "error id=0 msg=ok",
+            ],
+        )
+        .reply(
+            "whoami",
+            &["", "virtualserver_id=1 client_id=3", "error id=0 msg=ok"],
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    // The blank lines do not shift the pairing of responses and errors.
+    let version = client.version().await.unwrap();
+    assert_eq!(version.build, 1655727713);
+    let whoami = client.whoami().await.unwrap();
+    assert_eq!(whoami.client_id, ClientId(3));
+}
+
+#[tokio::test]
+async fn temporary_membership() {
+    let empty = "error id=1281 msg=database\\sempty\\sresult\\sset";
+    let member = "name=Trial sgid=9 cldbid=5";
+
+    let (addr, log) = MockServer::new()
+        .data("whoami", "virtualserver_id=1 client_id=3")
+        .reply("servergroupsbyclientid", &[empty])
+        .data("servergroupsbyclientid", member)
+        .ok("servergroupaddclient")
+        .ok("servergroupdelclient")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let store = Arc::new(MemoryStore::new());
+    client.set_membership_store(store.clone());
+
+    let membership = client
+        .add_to_group_temporarily(
+            ClientDatabaseId(5),
+            ServerGroupId(9),
+            Duration::from_millis(50),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(membership.sid, ServerId(1));
+    assert_eq!(store.load().await.unwrap(), [membership]);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert!(store.load().await.unwrap().is_empty());
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "whoami",
+            "servergroupsbyclientid cldbid=5",
+            "servergroupaddclient sgid=9 cldbid=5",
+            "servergroupsbyclientid cldbid=5",
+            "servergroupdelclient sgid=9 cldbid=5",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn temporary_membership_already_member() {
+    let (addr, log) = MockServer::new()
+        .data("whoami", "virtualserver_id=1 client_id=3")
+        .data("servergroupsbyclientid", "name=Member sgid=9 cldbid=5")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let store = Arc::new(MemoryStore::new());
+    client.set_membership_store(store.clone());
+
+    // A permanent membership is never removed.
+    let membership = client
+        .add_to_group_temporarily(
+            ClientDatabaseId(5),
+            ServerGroupId(9),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+    assert!(membership.is_none());
+    assert!(store.load().await.unwrap().is_empty());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["whoami", "servergroupsbyclientid cldbid=5"]
+    );
+}
+
+#[tokio::test]
+async fn resume_temporary_memberships() {
+    let (addr, log) = MockServer::new()
+        .data("whoami", "virtualserver_id=1 client_id=3")
+        .data("servergroupsbyclientid", "name=Trial sgid=9 cldbid=5")
+        .ok("servergroupdelclient")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    client.whoami().await.unwrap();
+
+    // Expired while the application was not running.
+    let store = Arc::new(MemoryStore::new());
+    store
+        .save(&TemporaryMembership {
+            sid: ServerId(1),
+            sgid: ServerGroupId(9),
+            cldbid: ClientDatabaseId(5),
+            expires: std::time::SystemTime::now() - Duration::from_secs(60),
+        })
+        .await
+        .unwrap();
+    // Belongs to another virtual server.
+    store
+        .save(&TemporaryMembership {
+            sid: ServerId(2),
+            sgid: ServerGroupId(9),
+            cldbid: ClientDatabaseId(5),
+            expires: std::time::SystemTime::now() - Duration::from_secs(60),
+        })
+        .await
+        .unwrap();
+    client.set_membership_store(store.clone());
+
+    assert_eq!(client.resume_temporary_memberships().await.unwrap(), 1);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let remaining = store.load().await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].sid, ServerId(2));
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "whoami",
+            "servergroupsbyclientid cldbid=5",
+            "servergroupdelclient sgid=9 cldbid=5",
+        ]
+    );
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn tower_service() {
+    use std::future::poll_fn;
+
+    use tower_service::Service;
+    use ts3::request::RequestBuilder;
+
+    let (addr, log) = MockServer::new()
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .ok("clientupdate")
+        .spawn()
+        .await;
+
+    let mut client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    poll_fn(|cx| client.poll_ready(cx)).await.unwrap();
+    let resp = client
+        .call(RequestBuilder::new("version").build())
+        .await
+        .unwrap();
+    assert_eq!(resp.len(), 1);
+    assert_eq!(resp[0].get::<String>("platform").unwrap(), "Linux");
+
+    poll_fn(|cx| client.poll_ready(cx)).await.unwrap();
+    client
+        .call(
+            RequestBuilder::new("clientupdate")
+                .arg("client_nickname", "bot")
+                .build(),
+        )
+        .await
+        .unwrap();
+
+    assert!(log
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|line| line == "clientupdate client_nickname=bot"));
+}
+
+#[tokio::test]
+async fn schedule() {
+    let (addr, log) = MockServer::new().ok("gm").spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    tokio::time::pause();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = client.schedule(Schedule::every(Duration::from_millis(100)), move |client| {
+        let tx = tx.clone();
+        async move {
+            client.gm("hello").await?;
+            let _ = tx.send(());
+            Ok(())
+        }
+    });
+
+    for _ in 0..2 {
+        tokio::time::advance(Duration::from_millis(100)).await;
+        rx.recv().await.unwrap();
+    }
+
+    handle.cancel();
+    tokio::time::advance(Duration::from_millis(100)).await;
+    tokio::task::yield_now().await;
+
+    assert!(handle.is_finished());
+    assert!(rx.try_recv().is_err());
+    assert_eq!(*log.lock().unwrap(), ["gm msg=hello"; 2]);
+}
+
+#[tokio::test]
+async fn schedule_after_disconnect() {
+    let (addr, log) = MockServer::new().ok("quit").spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    tokio::time::pause();
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let runs2 = runs.clone();
+    let handle = client.schedule(Schedule::every(Duration::from_millis(100)), move |client| {
+        runs2.fetch_add(1, Ordering::SeqCst);
+        async move {
+            client.gm("hello").await?;
+            Ok(())
+        }
+    });
+
+    client.disconnect(Shutdown::Wait).await.unwrap();
+    tokio::time::advance(Duration::from_secs(1)).await;
+    tokio::task::yield_now().await;
+
+    assert!(handle.is_finished());
+    assert_eq!(runs.load(Ordering::SeqCst), 0);
+    assert_eq!(*log.lock().unwrap(), ["quit"]);
+}
+
+#[tokio::test]
+async fn schedule_reconnect() {
+    let (addr, log) = MockServer::new().close("gm").ok("gm").spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .reconnect(Reconnect::new().delay(Duration::from_millis(300)))
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let runs2 = runs.clone();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = client.schedule(Schedule::every(Duration::from_millis(50)), move |client| {
+        runs2.fetch_add(1, Ordering::SeqCst);
+        let tx = tx.clone();
+        async move {
+            let _ = tx.send(client.gm("hello").await.is_ok());
+            Ok(())
+        }
+    });
+
+    assert!(!rx.recv().await.unwrap());
+    // The action is not run while reconnecting.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    assert!(rx.recv().await.unwrap());
+    handle.cancel();
+
+    assert_eq!(log.lock().unwrap()[..2], ["gm msg=hello"; 2]);
+}
+
+#[tokio::test]
+async fn typed_command() {
+    #[derive(Debug, Decode, Default)]
+    struct MusicBot {
+        client_id: u64,
+        client_nickname: String,
+    }
+
+    struct MusicBotList {
+        owner: Option<u64>,
+    }
+
+    impl TypedCommand for MusicBotList {
+        type Response = List<MusicBot, Pipe>;
+
+        const NAME: &'static str = "musicbotlist";
+
+        fn apply(&self, req: RequestBuilder) -> RequestBuilder {
+            match self.owner {
+                Some(owner) => req.arg("owner", owner),
+                None => req,
+            }
+        }
+    }
+
+    let (addr, log) = MockServer::new()
+        .data(
+            "musicbotlist",
+            "client_id=4 client_nickname=Radio|client_id=5 client_nickname=DJ",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let bots = client
+        .execute(MusicBotList { owner: Some(2) })
+        .await
+        .unwrap();
+    assert_eq!(bots.len(), 2);
+    assert_eq!(
+        (bots[1].client_id, bots[1].client_nickname.as_str()),
+        (5, "DJ")
+    );
+    assert_eq!(*log.lock().unwrap(), ["musicbotlist owner=2"]);
+}
+
+#[tokio::test]
+async fn me_refresh_failed() {
+    let (addr, _) = MockServer::new()
+        .ok("login")
+        .ok("use")
+        .data("whoami", "virtualserver_id=0 client_id=3")
+        .reply(
+            "whoami",
+            &["error id=2568 msg=insufficient\\sclient\\spermissions"],
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    client.login("serveradmin", "password").await.unwrap();
+    assert_eq!(client.me().unwrap().client_id, ClientId(3));
+
+    // The identity on the old server is not kept when the refresh fails.
+    client.use_sid(1).await.unwrap();
+    assert!(client.me().is_none());
+}
+
+#[tokio::test]
+async fn setup() {
+    let (addr, log) = MockServer::new()
+        .ok("login")
+        .data("whoami", "virtualserver_id=0 client_id=3")
+        .ok("servernotifyregister")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .setup(|client| async move {
+            client.login("serveradmin", "password").await?;
+            client
+                .servernotifyregister(ServerNotifyRegister::Server)
+                .await
+        })
+        .connect(addr)
+        .await
+        .unwrap();
+    assert_eq!(client.me().unwrap().client_id, ClientId(3));
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "login client_login_name=serveradmin client_login_password=password",
+            "whoami",
+            "servernotifyregister event=server",
+        ]
+    );
+
+    // The client is disconnected if the hook fails.
+    let (addr, log) = MockServer::new().ok("quit").spawn().await;
+    let res = ClientBuilder::new()
+        .no_keepalive()
+        .setup(|client| async move { client.login("serveradmin", "wrong").await })
+        .connect(addr)
+        .await;
+    assert_eq!(res.err().and_then(|err| err.id()), Some(256));
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "login client_login_name=serveradmin client_login_password=wrong",
+            "quit",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn setup_reconnect() {
+    let (addr, log) = MockServer::new()
+        .ok("login")
+        .data("whoami", "virtualserver_id=0 client_id=3")
+        .ok("servernotifyregister")
+        .close("version")
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .reconnect(Reconnect::new().delay(Duration::from_millis(10)))
+        .setup(|client| async move {
+            client.login("serveradmin", "password").await?;
+            client
+                .servernotifyregister(ServerNotifyRegister::Server)
+                .await
+        })
+        .connect(addr)
+        .await
+        .unwrap();
+
+    assert!(client.version().await.is_err());
+    // The hook runs for the new connection before the queued command is sent.
+    client.version().await.unwrap();
+    assert_eq!(client.me().unwrap().client_id, ClientId(3));
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "login client_login_name=serveradmin client_login_password=password",
+            "whoami",
+            "servernotifyregister event=server",
+            "version",
+            "login client_login_name=serveradmin client_login_password=password",
+            "whoami",
+            "servernotifyregister event=server",
+            "version",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn reselect_after_reconnect() {
+    let (addr, log) = MockServer::new()
+        .ok("login")
+        .ok("use")
+        .data("whoami", "virtualserver_id=1 client_id=3")
+        .close("version")
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .reconnect(Reconnect::new().delay(Duration::from_millis(10)))
+        .setup(|client| async move { client.login("serveradmin", "password").await })
+        .connect(addr)
+        .await
+        .unwrap();
+    client.use_sid(1).await.unwrap();
+
+    assert!(client.version().await.is_err());
+    // The server is selected again after the hook logged in, before the queued command.
+    client.version().await.unwrap();
+    assert_eq!(
+        client.selected_server(),
+        Some(ServerSelection::Id(ServerId(1)))
+    );
+    assert!(client.me().is_some());
+
+    assert_eq!(
+        log.lock().unwrap()[4..],
+        [
+            "version",
+            "login client_login_name=serveradmin client_login_password=password",
+            "whoami",
+            "use sid=1",
+            "whoami",
+            "version",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn voice_monitor() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "clientlist",
+            "clid=1 cid=1 client_nickname=Alice client_type=0 client_flag_talking=0 \
+             client_input_hardware=1 client_output_hardware=1|clid=2 cid=1 \
+             client_nickname=Bob client_type=0 client_flag_talking=1 client_input_hardware=1 \
+             client_output_hardware=1|clid=3 cid=1 client_nickname=serveradmin client_type=1",
+        )
+        .data(
+            "clientlist",
+            "clid=1 cid=1 client_nickname=Alice client_type=0 client_flag_talking=1 \
+             client_input_hardware=1 client_output_hardware=1",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let (_monitor, mut events) = VoiceMonitor::new()
+        .interval(Duration::from_millis(10))
+        .spawn(client);
+
+    let mut talking = Vec::new();
+    for _ in 0..3 {
+        match events.recv().await.unwrap() {
+            VoiceEvent::StartedTalking(state) => talking.push((state.nickname, true)),
+            VoiceEvent::StoppedTalking(state) => talking.push((state.nickname, false)),
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+    assert_eq!(
+        talking,
+        [
+            ("Bob".to_owned(), true),
+            ("Alice".to_owned(), true),
+            ("Bob".to_owned(), false),
+        ]
+    );
+    assert_eq!(log.lock().unwrap()[0], "clientlist -voice");
+
+    // The monitor stops polling once the receiver is dropped.
+    drop(events);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let polls = log.lock().unwrap().len();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(log.lock().unwrap().len(), polls);
+}
+
+#[tokio::test]
+async fn track_occupancy() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "clientlist",
+            "clid=1 cid=1 client_type=0|clid=2 cid=1 client_type=0|clid=3 cid=1 client_type=1",
+        )
+        .reply(
+            "servernotifyregister",
+            &[
+                "notifyclientmoved ctid=2 reasonid=0 clid=1",
+                "notifyclientleftview cfid=1 ctid=0 reasonid=8 clid=2",
+                "error id=0 msg=ok",
+            ],
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    let mut rx = client.dead_letter_queue(8);
+    client.track_occupancy().await.unwrap();
+
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    for _ in 0..4 {
+        events.push(match rx.recv().await.unwrap() {
+            Event::ClientMoved(_) => "moved".to_owned(),
+            Event::ClientLeftView(_) => "left".to_owned(),
+            Event::ChannelBecameOccupied(event) => format!("occupied {}", event.cid.0),
+            Event::ChannelBecameEmpty(event) => format!("empty {}", event.cid.0),
+            event => panic!("unexpected event: {:?}", event),
+        });
+    }
+    assert_eq!(events, ["moved", "occupied 2", "left", "empty 1"]);
+}
+
+#[tokio::test]
+async fn track_nicknames() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "clientlist",
+            "clid=1 client_nickname=Alice|clid=2 client_nickname=Bob",
+        )
+        .data(
+            "clientlist",
+            "clid=1 client_nickname=Alice|clid=2 client_nickname=Bobby",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    let mut rx = client.dead_letter_queue(8);
+
+    let handle = client
+        .track_nicknames(Duration::from_millis(10))
+        .await
+        .unwrap();
+    match rx.recv().await.unwrap() {
+        Event::ClientNicknameChanged(event) => {
+            assert_eq!(event.clid, ClientId(2));
+            assert_eq!((event.old.as_str(), event.new.as_str()), ("Bob", "Bobby"));
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    handle.cancel();
+}
+
+#[tokio::test]
+async fn move_protection() {
+    struct Noop;
+
+    impl EventHandler for Noop {}
+
+    let (addr, log) = MockServer::new()
+        .data(
+            "clientlist",
+            "clid=5 cid=1 client_unique_identifier=abc= client_type=0|clid=6 cid=1 \
+             client_unique_identifier=def= client_type=0",
+        )
+        .reply(
+            "servernotifyregister",
+            &[
+                // Moved out of the protected channel by another client.
+                "notifyclientmoved ctid=2 reasonid=1 invokerid=9 invokername=Mod \
+                 invokeruid=mod= clid=5",
+                // Moved by a whitelisted client.
+                "notifyclientmoved ctid=3 reasonid=1 invokerid=8 invokername=Admin \
+                 invokeruid=admin= clid=6",
+                "error id=0 msg=ok",
+            ],
+        )
+        .ok("clientmove")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let protection = MoveProtection::new(Noop)
+        .protect_channel(ChannelId(1))
+        .whitelist("admin=");
+    protection.sync(&client).await.unwrap();
+    client.set_event_handler(protection);
+
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+    // Wait for the handler to finish.
+    client.replace_event_handler(Noop).await;
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "clientlist -uid",
+            "servernotifyregister event=channel id=0",
+            "clientmove clid=5 cid=1",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn server_uptime() {
+    let (addr, log) = MockServer::new()
+        .data("serverinfo", "virtualserver_uptime=3600")
+        .data("hostinfo", "instance_uptime=7200")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let uptime = client.server_uptime().await.unwrap();
+    assert!(uptime >= Duration::from_secs(3600));
+    // The second call is served from the cache.
+    assert!(client.server_uptime().await.unwrap() >= uptime);
+    assert!(client.instance_uptime().await.unwrap() >= Duration::from_secs(7200));
+
+    assert_eq!(*log.lock().unwrap(), ["serverinfo", "hostinfo"]);
+}
+
+#[tokio::test]
+async fn server_uptime_virtual() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "serverinfo",
+            "virtualserver_status=virtual\\sonline virtualserver_uptime=0",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    // A stopped server selected in virtual mode has no uptime.
+    assert_eq!(client.server_uptime().await.unwrap(), Duration::ZERO);
+}
+
+#[tokio::test]
+async fn on_slow_command() {
+    let (addr, _) = MockServer::new()
+        .ok("login")
+        .data("version", "version=3.13.7 build=1655727713 platform=Linux")
+        .spawn()
+        .await;
+
+    let slow = Arc::new(Mutex::new(Vec::<SlowCommand>::new()));
+    let slow2 = slow.clone();
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        // Every command exceeds a threshold of zero.
+        .on_slow_command(Duration::ZERO, move |cmd| {
+            slow2.lock().unwrap().push(cmd.clone())
+        })
+        .connect(addr)
+        .await
+        .unwrap();
+
+    client.login("serveradmin", "secret").await.unwrap();
+    client.version().await.unwrap();
+
+    let slow = slow.lock().unwrap();
+    let commands: Vec<_> = slow.iter().map(|cmd| cmd.command.as_str()).collect();
+    // Logging in refreshes the client's own info using whoami.
+    assert_eq!(commands, ["login", "whoami", "version"]);
+}
+
+#[tokio::test]
+async fn inbox() {
+    let list = "msgid=1 cluid=a= subject=Hi timestamp=1 flag_read=0|msgid=2 cluid=b= \
+                subject=Re timestamp=2 flag_read=1|msgid=3 cluid=c= subject=Yo timestamp=3 flag_read=0";
+
+    let (addr, log) = MockServer::new()
+        .data("messagelist", list)
+        .data(
+            "messageget",
+            "msgid=1 cluid=a= subject=Hi message=Hello timestamp=1",
+        )
+        .data(
+            "messageget",
+            "msgid=3 cluid=c= subject=Yo message=World timestamp=3",
+        )
+        .ok("messageupdateflag")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let messages = client.inbox().await.unwrap();
+    let bodies: Vec<_> = messages.iter().map(|m| m.message.as_str()).collect();
+    assert_eq!(bodies, ["Hello", "World"]);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "messagelist",
+            "messageget msgid=1",
+            "messageget msgid=3",
+            "messageupdateflag msgid=1 flag=1",
+            "messageupdateflag msgid=3 flag=1",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn inbox_keeps_messages_unread_on_error() {
+    let (addr, log) = MockServer::new()
+        .data(
+            "messagelist",
+            "msgid=1 cluid=a= subject=Hi timestamp=1 flag_read=0|msgid=2 cluid=b= subject=Re \
+             timestamp=2 flag_read=0",
+        )
+        .data(
+            "messageget",
+            "msgid=1 cluid=a= subject=Hi message=Hello timestamp=1",
+        )
+        .reply("messageget", &["error id=512 msg=invalid\\sclientID"])
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    client.inbox().await.unwrap_err();
+    assert!(!log
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|cmd| cmd.starts_with("messageupdateflag")));
+}
+
+#[tokio::test]
+async fn commands_match_table() {
+    use ts3::event::ReasonId;
+    use ts3::protocol::commands;
+    use ts3::request::{ClientListOptions, ClientProperties, TempPassword};
+    use ts3::shared::{ApiKeyScope, GroupType};
+
+    // Every command fails, only the sent requests are checked.
+    let (addr, log) = MockServer::new().spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let cid = ChannelId(1);
+    let clid = ClientId(2);
+    let cldbid = ClientDatabaseId(3);
+    let sgid = ServerGroupId(4);
+    let all = ClientListOptions {
+        uid: true,
+        away: true,
+        voice: true,
+        times: true,
+        groups: true,
+        info: true,
+        icon: true,
+        country: true,
+        ip: true,
+        badges: true,
+    };
+    let options = QueryLoginListOptions {
+        pattern: Some("bot%"),
+        start: Some(0),
+        duration: Some(10),
+        count: true,
+    };
+    let client_properties = ClientProperties::new()
+        .description("desc")
+        .icon_id(IconId(1))
+        .is_talker(true);
+    let server_properties = VirtualServerProperties::new()
+        .name("Test")
+        .welcomemessage("Hi")
+        .maxclients(32)
+        .password("pw")
+        .hostmessage("Hello")
+        .port(9987);
+    let temp_password = TempPassword::new("pw", "desc", Duration::from_secs(60)).channel(cid, None);
+
+    let _ = client
+        .apikeyadd(ApiKeyScope::Read, Some(60), Some(cldbid))
+        .await;
+    let _ = client.apikeydel(1).await;
+    let _ = client
+        .apikeylist(Some((cldbid, false)), Some(0), Some(10), true)
+        .await;
+    let _ = client.queryloginlist(options).await;
+    let _ = client
+        .banadd(
+            Some("1.2.3.4"),
+            Some("name"),
+            Some("uid"),
+            Some("mytsid"),
+            Some(60),
+            Some("reason"),
+            Some("nick"),
+        )
+        .await;
+    let _ = client.banclient(clid, Some(60), Some("reason")).await;
+    let _ = client.banlist().await;
+    let _ = client.clientdbedit(cldbid, client_properties).await;
+    let _ = client.clientinfo(clid).await;
+    let _ = client.clientdbinfo(cldbid).await;
+    let _ = client.clientlist(all).await;
+    let _ = client.clientedit(clid, client_properties).await;
+    let _ = client
+        .clientkick(&[clid, clid], ReasonId::ServerKick, Some("bye"))
+        .await;
+    let _ = client.clientmove(&[clid], cid, Some("pw")).await;
+    let _ = client.clientpoke(clid, "Hi").await;
+    let _ = client.channelinfo(cid).await;
+    let _ = client.channelgrouplist().await;
+    let _ = client.complaindelall(cldbid).await;
+    let _ = client.complainlist(Some(cldbid)).await;
+    let _ = client.customdelete(cldbid, "ident").await;
+    let _ = client.custominfo(cldbid).await;
+    let _ = client.customsearch("ident", "%").await;
+    let _ = client.customset(cldbid, "ident", "value").await;
+    let _ = client.gm("Hi").await;
+    let _ = client.hostinfo().await;
+    let _ = client.login("user", "pw").await;
+    let _ = client.login_with_apikey("key").await;
+    let _ = client.logout().await;
+    let _ = client.messageadd("uid", "subject", "message").await;
+    let _ = client.messagedel(1).await;
+    let _ = client.messageget(1).await;
+    let _ = client.messagelist().await;
+    let _ = client.messageupdateflag(1, true).await;
+    let _ = client.permoverview(cid, cldbid, Some("i_icon_id")).await;
+    let _ = client.danger_permreset().await;
+    let _ = client
+        .sendtextmessage(TextMessageTarget::Client(clid), "Hi")
+        .await;
+    let _ = client.servercreate("Test").await;
+    let _ = client.serversnapshotcreate().await;
+    let _ = client.ftinitdownload(1, "/file", cid, None, 0).await;
+    let _ = client
+        .ftinitupload(1, "/file", cid, Some("pw"), 10, true, false)
+        .await;
+    let _ = client.ftgetfilelist(cid, None, "/").await;
+    let _ = client.servergroupaddclient(sgid, cldbid).await;
+    let _ = client.servergroupdelclient(sgid, cldbid).await;
+    let _ = client.servergrouplist().await;
+    let _ = client.servergroupsbyclientid(cldbid).await;
+    let _ = client
+        .servernotifyregister(ServerNotifyRegister::Channel(cid))
+        .await;
+    let _ = client.servertemppasswordadd(temp_password).await;
+    let _ = client.servertemppassworddel("pw").await;
+    let _ = client.servertemppasswordlist().await;
+    let _ = client.serveredit(server_properties).await;
+    let _ = client.serverlist().await;
+    let _ = client.serverinfo().await;
+    let _ = client.serverrequestconnectioninfo().await;
+    let _ = client.serverstart(1).await;
+    let _ = client.serverstop(1).await;
+    let _ = client.serverstop_with_reason(1, "reason").await;
+    let _ = client.version().await;
+    let _ = client.whoami().await;
+    let _ = client.channelgrouplist_by_type(GroupType::Query).await;
+    let _ = client.serverprocessstop().await;
+    let _ = client.serverprocessstop_with_reason("reason").await;
+    let _ = client.quit().await;
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.last().unwrap(), "quit");
+    let invalid: Vec<_> = log
+        .iter()
+        .filter_map(|line| commands::validate(&RequestBuilder::new(line).build()).err())
+        .map(|err| err.to_string())
+        .collect();
+    assert_eq!(invalid, Vec::<String>::new());
+}
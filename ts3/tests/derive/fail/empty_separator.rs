@@ -0,0 +1,9 @@
+use ts3::Decode;
+
+#[derive(Default, Decode)]
+struct Entry {
+    #[ts3(separator = "")]
+    permids: Vec<u64>,
+}
+
+fn main() {}
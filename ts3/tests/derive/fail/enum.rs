@@ -0,0 +1,9 @@
+use ts3::Decode;
+
+#[derive(Decode)]
+enum Status {
+    Online,
+    Offline,
+}
+
+fn main() {}
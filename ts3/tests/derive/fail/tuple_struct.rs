@@ -0,0 +1,6 @@
+use ts3::Decode;
+
+#[derive(Default, Decode)]
+struct Id(u64);
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ts3::Decode;
+
+#[derive(Default, Decode)]
+struct Whoami {
+    #[ts3(skip)]
+    client_id: u64,
+}
+
+fn main() {}
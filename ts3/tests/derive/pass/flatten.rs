@@ -0,0 +1,25 @@
+use ts3::Decode;
+
+#[derive(Debug, Default, Decode)]
+struct Invoker {
+    invokerid: u64,
+    invokername: String,
+}
+
+#[derive(Debug, Default, Decode)]
+struct Moved {
+    ctid: u64,
+    #[ts3(flatten)]
+    invoker: Invoker,
+    clid: u64,
+}
+
+fn main() {
+    let buf = b"ctid=2 invokerid=3 invokername=Admin clid=4";
+
+    let moved = Moved::decode(buf).unwrap();
+    assert_eq!(moved.ctid, 2);
+    assert_eq!(moved.invoker.invokerid, 3);
+    assert_eq!(moved.invoker.invokername, "Admin");
+    assert_eq!(moved.clid, 4);
+}
@@ -0,0 +1,18 @@
+use ts3::response::Entry;
+use ts3::Decode;
+
+#[derive(Debug, Default, Decode)]
+struct Group {
+    sgid: u64,
+    #[ts3(raw)]
+    raw: Entry,
+}
+
+fn main() {
+    let buf = b"sgid=6 name=Server\\sAdmin";
+
+    let group = Group::decode(buf).unwrap();
+    assert_eq!(group.sgid, 6);
+    assert_eq!(group.raw.get::<u64>("sgid").unwrap(), 6);
+    assert_eq!(group.raw.get::<String>("name").unwrap(), "Server Admin");
+}
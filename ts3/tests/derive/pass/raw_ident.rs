@@ -0,0 +1,20 @@
+use ts3::Decode;
+
+#[derive(Debug, Default, Decode)]
+struct Group {
+    sgid: u64,
+    name: String,
+    r#type: u8,
+    // Keys without a value are left at their default.
+    iconid: u64,
+}
+
+fn main() {
+    let buf = b"sgid=6 name=Server\\sAdmin type=1 iconid savedb=1";
+
+    let group = Group::decode(buf).unwrap();
+    assert_eq!(group.sgid, 6);
+    assert_eq!(group.name, "Server Admin");
+    assert_eq!(group.r#type, 1);
+    assert_eq!(group.iconid, 0);
+}
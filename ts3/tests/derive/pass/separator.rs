@@ -0,0 +1,20 @@
+use ts3::Decode;
+
+#[derive(Debug, Default, Decode)]
+struct Entry {
+    #[ts3(separator = "|")]
+    permids: Vec<u64>,
+    #[ts3(separator = ",")]
+    names: Vec<String>,
+    #[ts3(separator = ",")]
+    empty: Vec<u64>,
+}
+
+fn main() {
+    let buf = b"permids=1\\p2\\p3 names=a\\sb,c empty=";
+
+    let entry = Entry::decode(buf).unwrap();
+    assert_eq!(entry.permids, [1, 2, 3]);
+    assert_eq!(entry.names, ["a b", "c"]);
+    assert!(entry.empty.is_empty());
+}
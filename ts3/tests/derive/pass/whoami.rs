@@ -0,0 +1,28 @@
+use ts3::shared::{ClientId, ServerId};
+use ts3::Decode;
+
+#[derive(Debug, Default, Decode)]
+struct Whoami {
+    virtualserver_status: String,
+    virtualserver_id: ServerId,
+    virtualserver_port: u16,
+    client_id: ClientId,
+    client_nickname: String,
+    client_login_name: String,
+}
+
+fn main() {
+    let buf = b"virtualserver_status=online virtualserver_id=1 \
+        virtualserver_unique_identifier=9zFRpQ8ABKFRx5Z0k7N4Rf0OpGU= virtualserver_port=9987 \
+        client_id=5 client_channel_id=1 client_nickname=serveradmin\\sfrom\\s127.0.0.1:47356 \
+        client_database_id=1 client_login_name=serveradmin \
+        client_unique_identifier=serveradmin client_origin_server_id=0";
+
+    let whoami = Whoami::decode(buf).unwrap();
+    assert_eq!(whoami.virtualserver_status, "online");
+    assert_eq!(whoami.virtualserver_id, ServerId(1));
+    assert_eq!(whoami.virtualserver_port, 9987);
+    assert_eq!(whoami.client_id, ClientId(5));
+    assert_eq!(whoami.client_nickname, "serveradmin from 127.0.0.1:47356");
+    assert_eq!(whoami.client_login_name, "serveradmin");
+}
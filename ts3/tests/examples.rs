@@ -0,0 +1,175 @@
+//! Runs the flows used by the examples against a mock server.
+
+#![cfg(feature = "client")]
+
+mod mock;
+
+use ts3::event::EventHandler;
+use ts3::greeter::Greeter;
+use ts3::request::ServerNotifyRegister;
+use ts3::shared::{ClientDatabaseId, ClientId, ServerId};
+use ts3::stats::Sampler;
+use ts3::{Client, ClientBuilder, Credentials};
+
+use mock::MockServer;
+
+const WHOAMI: &str = "virtualserver_status=online virtualserver_id=1 \
+    virtualserver_unique_identifier=abc virtualserver_port=9987 client_id=5 \
+    client_channel_id=1 client_nickname=serveradmin client_database_id=1 \
+    client_login_name=serveradmin client_unique_identifier=serveradmin \
+    client_origin_server_id=0";
+
+fn connect() -> ClientBuilder {
+    ClientBuilder::new().no_keepalive()
+}
+
+#[tokio::test]
+async fn login_and_use() {
+    let (addr, log) = MockServer::new()
+        .ok("login")
+        .ok("use")
+        .data("whoami", WHOAMI)
+        .spawn()
+        .await;
+
+    let client = connect().connect(addr).await.unwrap();
+    client.login("serveradmin", "secret").await.unwrap();
+    client.use_sid(1).await.unwrap();
+
+    let me = client.me().unwrap();
+    assert_eq!(me.client_id, ClientId(5));
+    assert_eq!(me.client_database_id, ClientDatabaseId(1));
+    assert_eq!(me.virtualserver_id, ServerId(1));
+
+    let log = log.lock().unwrap();
+    assert_eq!(
+        log[0],
+        "login client_login_name=serveradmin client_login_password=secret"
+    );
+    assert_eq!(log[2], "use sid=1");
+}
+
+#[tokio::test]
+async fn connect_with() {
+    let (addr, log) = MockServer::new()
+        .ok("login")
+        .ok("use")
+        .ok("clientupdate")
+        .ok("servernotifyregister")
+        .data("whoami", WHOAMI)
+        .spawn()
+        .await;
+
+    let credentials = Credentials::new("serveradmin", "secret")
+        .port(9987)
+        .nickname("bot")
+        .notify(ServerNotifyRegister::Server)
+        .notify(ServerNotifyRegister::TextPrivate);
+    Client::connect_with(addr, credentials).await.unwrap();
+
+    let log: Vec<String> = log
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|cmd| *cmd != "whoami")
+        .cloned()
+        .collect();
+    assert_eq!(
+        log,
+        [
+            "login client_login_name=serveradmin client_login_password=secret",
+            "use port=9987",
+            "clientupdate client_nickname=bot",
+            "servernotifyregister event=server",
+            "servernotifyregister event=textprivate",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stats_sample() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "serverinfo",
+            "virtualserver_clientsonline=4 virtualserver_queryclientsonline=1 \
+             virtualserver_channelsonline=3 virtualserver_total_packetloss_total=0.5 \
+             virtualserver_total_ping=12.5",
+        )
+        .data(
+            "serverrequestconnectioninfo",
+            "connection_bandwidth_sent_last_second_total=100 \
+             connection_bandwidth_received_last_second_total=200",
+        )
+        .spawn()
+        .await;
+
+    let client = connect().connect(addr).await.unwrap();
+    let sample = Sampler::sample(&client).await.unwrap();
+
+    assert_eq!(sample.clients_online, 3);
+    assert_eq!(sample.queryclients_online, 1);
+    assert_eq!(sample.channels_online, 3);
+    assert_eq!(sample.bandwidth_sent, 100);
+    assert_eq!(sample.bandwidth_received, 200);
+    assert_eq!(sample.packetloss, 0.5);
+    assert_eq!(sample.ping, 12.5);
+}
+
+#[tokio::test]
+async fn complainlist_empty() {
+    let (addr, _) = MockServer::new()
+        .reply(
+            "complainlist",
+            &["error id=1281 msg=database\\sempty\\sresult\\sset"],
+        )
+        .spawn()
+        .await;
+
+    let client = connect().connect(addr).await.unwrap();
+    assert!(client.complainlist(None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn unknown_command() {
+    let (addr, _) = MockServer::new().spawn().await;
+
+    let client = connect().connect(addr).await.unwrap();
+    assert!(client.serverinfo().await.is_err());
+}
+
+#[tokio::test]
+async fn greet() {
+    struct Handler;
+
+    impl EventHandler for Handler {}
+
+    let (addr, log) = MockServer::new()
+        .reply(
+            "servernotifyregister",
+            &[
+                "notifycliententerview ctid=1 clid=7 client_unique_identifier=abc= \
+                 client_nickname=Alice client_type=0",
+                // Rejoining within the cooldown is not greeted again.
+                "notifycliententerview ctid=1 clid=8 client_unique_identifier=abc= \
+                 client_nickname=Alice client_type=0",
+                "error id=0 msg=ok",
+            ],
+        )
+        .ok("sendtextmessage")
+        .spawn()
+        .await;
+
+    let client = connect().connect(addr).await.unwrap();
+    client.set_event_handler(Greeter::new(Handler).template("Hello World, %nickname%!"));
+    client
+        .servernotifyregister(ServerNotifyRegister::Server)
+        .await
+        .unwrap();
+
+    // Wait for the greeter to finish.
+    client.replace_event_handler(Handler).await;
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        ["sendtextmessage targetmode=1 target=7 msg=Hello\\sWorld,\\sAlice!"]
+    );
+}
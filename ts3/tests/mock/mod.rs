@@ -0,0 +1,154 @@
+//! A scripted ServerQuery server for integration tests.
+
+// Not every test uses every helper.
+#![allow(dead_code)]
+
+pub mod transcript;
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+const OK: &str = "error id=0 msg=ok";
+
+/// A reply to a command.
+#[derive(Clone, Debug)]
+enum Reply {
+    Lines(Vec<String>),
+    /// Closes the connection without answering.
+    Close,
+}
+
+/// A mock server answering commands with scripted replies. Commands without a reply are
+/// answered with `error id=256`. If multiple replies are set for a command they are used in
+/// order, the last reply is repeated.
+///
+/// The server accepts any number of connections, which share the replies and the log.
+#[derive(Debug, Default)]
+pub struct MockServer {
+    replies: HashMap<String, VecDeque<Reply>>,
+    banner: Option<Vec<String>>,
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `cmd` with the `error id=0` line only.
+    pub fn ok(self, cmd: &str) -> Self {
+        self.reply(cmd, &[OK])
+    }
+
+    /// Answers `cmd` with a data line followed by the `error id=0` line.
+    pub fn data(self, cmd: &str, data: &str) -> Self {
+        self.reply(cmd, &[data, OK])
+    }
+
+    /// Answers `cmd` with `lines`.
+    pub fn reply(self, cmd: &str, lines: &[&str]) -> Self {
+        let lines = lines.iter().map(|line| line.to_string()).collect();
+        self.push(cmd, Reply::Lines(lines))
+    }
+
+    /// Closes the connection when `cmd` is received, without answering it.
+    pub fn close(self, cmd: &str) -> Self {
+        self.push(cmd, Reply::Close)
+    }
+
+    fn push(mut self, cmd: &str, reply: Reply) -> Self {
+        self.replies
+            .entry(cmd.to_owned())
+            .or_default()
+            .push_back(reply);
+        self
+    }
+
+    /// Sends `lines` as the welcome banner instead of the banner of the TeamSpeak 3 server.
+    pub fn banner(mut self, lines: &[&str]) -> Self {
+        self.banner = Some(lines.iter().map(|line| line.to_string()).collect());
+        self
+    }
+
+    /// Starts the server on a random local port. Returns the address of the server and a
+    /// log of all received commands.
+    pub async fn spawn(self) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let log2 = log.clone();
+        let replies = Arc::new(Mutex::new(self.replies));
+        let banner = match self.banner {
+            Some(lines) => lines.iter().map(|line| format!("{}\n\r", line)).collect(),
+            None => String::from("TS3\n\rWelcome to the TeamSpeak 3 ServerQuery interface.\n\r"),
+        };
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(serve(stream, banner.clone(), replies.clone(), log2.clone()));
+            }
+        });
+
+        (addr, log)
+    }
+}
+
+/// Answers the commands received over `stream`.
+async fn serve(
+    stream: TcpStream,
+    banner: String,
+    replies: Arc<Mutex<HashMap<String, VecDeque<Reply>>>>,
+    log: Arc<Mutex<Vec<String>>>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    writer.write_all(banner.as_bytes()).await.unwrap();
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+
+        let cmd = line.trim_end().to_owned();
+        let name = cmd.split(' ').next().unwrap_or_default();
+
+        let reply = replies
+            .lock()
+            .unwrap()
+            .get_mut(name)
+            .and_then(|replies| match replies.len() {
+                1 => replies.front().cloned(),
+                _ => replies.pop_front(),
+            });
+
+        let mut buf = String::new();
+        match reply {
+            Some(Reply::Lines(lines)) => {
+                for line in lines {
+                    buf.push_str(&line);
+                    buf.push_str("\n\r");
+                }
+            }
+            Some(Reply::Close) => {
+                log.lock().unwrap().push(cmd);
+                return;
+            }
+            None => buf.push_str("error id=256 msg=command\\snot\\sfound\n\r"),
+        }
+
+        let quit = name == "quit";
+        log.lock().unwrap().push(cmd);
+
+        // The server closes the connection after answering `quit`.
+        if writer.write_all(buf.as_bytes()).await.is_err() || quit {
+            return;
+        }
+    }
+}
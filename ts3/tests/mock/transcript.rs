@@ -0,0 +1,90 @@
+//! ServerQuery sessions replayed by the [`MockServer`].
+//!
+//! A transcript is a text file in `tests/transcripts/`. Lines sent by the client start with
+//! `> `, lines sent by the server with `< `. Server lines before the first client line are
+//! the welcome banner, all other server lines are sent in reply to the preceding client
+//! line, including events. Blank lines and lines starting with `#` are ignored.
+//!
+//! Transcripts starting with `# synthetic` were written by hand instead of being captured
+//! from a real server, see `tests/transcripts/README.md`.
+//!
+//! ```text
+//! # TeamSpeak 3 server 3.13.7
+//! < TS3
+//! < Welcome to the TeamSpeak 3 ServerQuery interface.
+//! > version
+//! < version=3.13.7 build=1655727713 platform=Linux
+//! < error id=0 msg=ok
+//! ```
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::MockServer;
+
+/// A parsed transcript. See the module documentation for the format.
+#[derive(Debug)]
+pub struct Transcript {
+    server: MockServer,
+    commands: Vec<String>,
+}
+
+impl Transcript {
+    /// Loads the transcript `name` from `tests/transcripts/`.
+    pub fn load(name: &str) -> Self {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/transcripts")
+            .join(name);
+        let text = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("failed to read {}: {}", path.display(), err));
+        Self::parse(&text)
+    }
+
+    /// Parses a transcript.
+    pub fn parse(text: &str) -> Self {
+        let mut banner = Vec::new();
+        let mut replies: Vec<(String, Vec<&str>)> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(cmd) = line.strip_prefix("> ") {
+                replies.push((cmd.to_owned(), Vec::new()));
+            } else if let Some(line) = line.strip_prefix("< ") {
+                match replies.last_mut() {
+                    Some((_, lines)) => lines.push(line),
+                    None => banner.push(line),
+                }
+            } else {
+                panic!("invalid transcript line {}: {:?}", index + 1, line);
+            }
+        }
+
+        let mut server = MockServer::new();
+        if !banner.is_empty() {
+            server = server.banner(&banner);
+        }
+
+        let mut commands = Vec::new();
+        for (cmd, lines) in replies {
+            let name = cmd.split(' ').next().unwrap_or_default();
+            server = server.reply(name, &lines);
+            commands.push(cmd);
+        }
+
+        Self { server, commands }
+    }
+
+    /// Returns the lines sent by the client, in order.
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+
+    /// Starts the server replaying the transcript. See [`MockServer::spawn`].
+    pub async fn spawn(self) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+        self.server.spawn().await
+    }
+}
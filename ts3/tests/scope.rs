@@ -0,0 +1,157 @@
+#![cfg(feature = "client")]
+
+mod mock;
+
+use ts3::request::UseOptions;
+use ts3::response::VirtualServerStatus;
+use ts3::shared::ServerId;
+use ts3::{ClientBuilder, ServerSelection};
+
+use mock::MockServer;
+
+#[tokio::test]
+async fn instance_serverlist() {
+    let (addr, _) = MockServer::new()
+        .data(
+            "serverlist",
+            "virtualserver_id=1 virtualserver_port=9987 virtualserver_status=online \
+             virtualserver_clientsonline=2 virtualserver_name=Server\\s1\
+             |virtualserver_id=2 virtualserver_port=9988 virtualserver_status=offline \
+             virtualserver_name=Server\\s2",
+        )
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    let servers = client.instance().serverlist().await.unwrap();
+
+    assert_eq!(servers.len(), 2);
+    assert_eq!(servers[0].virtualserver_id, ServerId(1));
+    assert_eq!(servers[0].virtualserver_port, 9987);
+    assert!(matches!(
+        servers[0].virtualserver_status,
+        VirtualServerStatus::Online
+    ));
+    assert_eq!(servers[0].virtualserver_clientsonline, 2);
+    assert_eq!(servers[1].virtualserver_name, "Server 2");
+}
+
+#[tokio::test]
+async fn server_by_port() {
+    let (addr, log) = MockServer::new()
+        .ok("use")
+        .data("whoami", "virtualserver_id=3 client_id=1")
+        .data("serverinfo", "virtualserver_id=3 virtualserver_name=Test")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    let server = client.server_by_port(9987).await.unwrap();
+    assert_eq!(server.id(), ServerId(3));
+
+    let info = server.serverinfo().await.unwrap();
+    assert_eq!(info.virtualserver_name, "Test");
+    assert_eq!(log.lock().unwrap()[0], "use port=9987");
+}
+
+#[tokio::test]
+async fn reselect_after_restart() {
+    let (addr, log) = MockServer::new()
+        .ok("use")
+        .data("whoami", "virtualserver_id=3 client_id=1")
+        .reply("serverinfo", &["error id=1024 msg=invalid\\sserverID"])
+        .data("serverinfo", "virtualserver_id=3 virtualserver_name=Test")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    assert_eq!(client.selected_server(), None);
+
+    client.use_sid(3).await.unwrap();
+    assert_eq!(
+        client.selected_server(),
+        Some(ServerSelection::Id(ServerId(3)))
+    );
+
+    let info = client.serverinfo().await.unwrap();
+    assert_eq!(info.virtualserver_name, "Test");
+
+    let log = log.lock().unwrap();
+    assert_eq!(log[2..], ["serverinfo", "use sid=3", "serverinfo"]);
+}
+
+#[tokio::test]
+async fn serverstop_reason() {
+    let (addr, log) = MockServer::new()
+        .ok("serverstop")
+        .ok("serverprocessstop")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+    let instance = client.instance();
+    instance.serverstop(1).await.unwrap();
+    instance
+        .serverstop_with_reason(2, "Be right back")
+        .await
+        .unwrap();
+    instance.serverprocessstop().await.unwrap();
+    instance
+        .serverprocessstop_with_reason("Update")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "serverstop sid=1",
+            "serverstop sid=2 reasonmsg=Be\\sright\\sback",
+            "serverprocessstop",
+            "serverprocessstop reasonmsg=Update",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn use_options() {
+    let (addr, log) = MockServer::new()
+        .ok("use")
+        .data("whoami", "virtualserver_id=1 client_id=1")
+        .reply("serverinfo", &["error id=1024 msg=invalid\\sserverID"])
+        .data("serverinfo", "virtualserver_id=1")
+        .spawn()
+        .await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    let options = UseOptions {
+        r#virtual: true,
+        client_nickname: Some("Bot 1"),
+    };
+    client.use_sid_with(1, options).await.unwrap();
+    client.serverinfo().await.unwrap();
+
+    let log = log.lock().unwrap();
+    assert_eq!(log[0], "use sid=1 client_nickname=Bot\\s1 -virtual");
+    assert_eq!(log[3], log[0]);
+}
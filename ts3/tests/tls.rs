@@ -0,0 +1,66 @@
+#![cfg(feature = "rustls")]
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::pki_types::PrivateKeyDer;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use ts3::Client;
+
+#[tokio::test]
+async fn connect_tls() {
+    let _ = tokio_rustls::rustls::crypto::ring::default_provider().install_default();
+
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_owned()]).unwrap();
+    let key = PrivateKeyDer::Pkcs8(cert.signing_key.serialize_der().into());
+
+    let server_config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.cert.der().clone()], key)
+        .unwrap();
+
+    let mut roots = RootCertStore::empty();
+    roots.add(cert.cert.der().clone()).unwrap();
+    let client_config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let stream = TlsAcceptor::from(Arc::new(server_config))
+            .accept(stream)
+            .await
+            .unwrap();
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+
+        writer
+            .write_all(b"TS3\n\rWelcome to the TeamSpeak 3 ServerQuery interface.\n\r")
+            .await
+            .unwrap();
+
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "version\n");
+
+        writer
+            .write_all(b"version=3.13.7 build=1655727713 platform=Linux\n\rerror id=0 msg=ok\n\r")
+            .await
+            .unwrap();
+    });
+
+    let client = Client::builder()
+        .no_keepalive()
+        .tls_config(Arc::new(client_config))
+        .connect_tls(addr, "localhost".try_into().unwrap())
+        .await
+        .unwrap();
+
+    let version = client.version().await.unwrap();
+    assert_eq!(version.version, "3.13.7");
+}
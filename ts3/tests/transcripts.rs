@@ -0,0 +1,114 @@
+#![cfg(feature = "client")]
+
+mod mock;
+
+use std::sync::{Arc, Mutex};
+
+use ts3::event::{Event, ReasonId};
+use ts3::request::{ClientListOptions, ServerNotifyRegister};
+use ts3::shared::{ChannelId, ClientDatabaseId, ClientId, ServerId};
+use ts3::{Client, ClientBuilder};
+
+use mock::transcript::Transcript;
+
+/// Connects to a server replaying the transcript `name`. The returned guard checks that the
+/// client sent the lines of the transcript in order.
+async fn replay(name: &str) -> (Client, Sent) {
+    let transcript = Transcript::load(name);
+    let expected = transcript.commands().to_vec();
+    let (addr, log) = transcript.spawn().await;
+
+    let client = ClientBuilder::new()
+        .no_keepalive()
+        .connect(addr)
+        .await
+        .unwrap();
+
+    (client, Sent { expected, log })
+}
+
+/// The lines the client is expected to send and the lines it sent.
+struct Sent {
+    expected: Vec<String>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Sent {
+    fn assert(self) {
+        assert_eq!(*self.log.lock().unwrap(), self.expected);
+    }
+}
+
+#[tokio::test]
+async fn login() {
+    let (client, sent) = replay("login.txt").await;
+
+    client.login("serveradmin", "secret").await.unwrap();
+    client.use_sid(1).await.unwrap();
+
+    let me = client.me().unwrap();
+    assert_eq!(me.virtualserver_id, ServerId(1));
+    assert_eq!(me.virtualserver_port, 9987);
+    assert_eq!(me.client_id, ClientId(4));
+    assert_eq!(me.client_nickname, "serveradmin from 127.0.0.1:51234");
+    assert_eq!(me.client_database_id, ClientDatabaseId(1));
+
+    let version = client.version().await.unwrap();
+    assert_eq!(version.version, "3.13.7");
+    assert_eq!(version.build, 1655727713);
+    assert_eq!(version.platform, "Linux");
+
+    sent.assert();
+}
+
+#[tokio::test]
+async fn clientlist() {
+    let (client, sent) = replay("clientlist.txt").await;
+
+    let options = ClientListOptions {
+        uid: true,
+        ..Default::default()
+    };
+    let clients = client.clientlist(options).await.unwrap();
+    assert_eq!(clients.len(), 2);
+    assert!(clients[0].is_query());
+    assert_eq!(clients[1].clid, ClientId(7));
+    assert_eq!(clients[1].cid, ChannelId(5));
+    assert_eq!(clients[1].client_database_id, ClientDatabaseId(12));
+    assert_eq!(clients[1].client_nickname, "Alice | AFK");
+    assert_eq!(
+        clients[1].client_unique_identifier,
+        "pT3bZ8Xq1fKd9mLw0YcVnR2sHgE="
+    );
+
+    sent.assert();
+}
+
+#[tokio::test]
+async fn client_moved() {
+    let (client, sent) = replay("client_moved.txt").await;
+    let mut rx = client.dead_letter_queue(8);
+
+    client
+        .servernotifyregister(ServerNotifyRegister::Channel(ChannelId(0)))
+        .await
+        .unwrap();
+
+    let Event::ClientMoved(event) = rx.recv().await.unwrap() else {
+        panic!("expected a clientmoved event");
+    };
+    assert_eq!(event.clid, ClientId(7));
+    assert_eq!(event.ctid, ChannelId(5));
+    assert_eq!(event.reasonid, ReasonId::SwitchChannel);
+
+    let Event::ClientMoved(event) = rx.recv().await.unwrap() else {
+        panic!("expected a clientmoved event");
+    };
+    assert_eq!(event.clid, ClientId(7));
+    assert_eq!(event.ctid, ChannelId(1));
+    assert_eq!(event.reasonid, ReasonId::Moved);
+    assert_eq!(event.invoker.invokerid, ClientId(9));
+    assert_eq!(event.invoker.invokername, "Admin");
+
+    sent.assert();
+}
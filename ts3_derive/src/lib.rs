@@ -1,28 +1,50 @@
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Data, DeriveInput, Fields};
+use syn::{
+    parse_macro_input, Data, DeriveInput, Field, Fields, Lit, LitByteStr, LitStr, Meta, NestedMeta,
+};
 
-#[proc_macro_derive(Decode)]
+#[proc_macro_derive(Decode, attributes(ts3))]
 pub fn decode_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let name = input.ident;
-    let expr = gen_expr(&input.data);
+    let (expr, raw) = match gen_expr(&input.data) {
+        Ok(expr) => expr,
+        Err(err) => return err.to_compile_error().into(),
+    };
 
     let expanded = quote! {
+        impl ::ts3::DecodeField for #name {
+            fn decode_field(
+                &mut self,
+                key: &[u8],
+                val: &[u8],
+            ) -> ::std::result::Result<bool, ::ts3::Error> {
+                #expr
+            }
+        }
+
         impl ::ts3::Decode for #name {
             type Error = ::ts3::Error;
 
             fn decode(buf: &[u8]) -> ::std::result::Result<Self, Self::Error> {
+                ::ts3::single_entry(buf)?;
+
                 let mut st = #name::default();
+                #raw
 
                 for s in buf.split(|c| *c == b' ') {
-                    let parts: ::std::vec::Vec<&[u8]> = s.splitn(2, |c| *c == b'=').collect();
+                    // Keys without a value are skipped.
+                    let (key, val) = match s.iter().position(|c| *c == b'=') {
+                        Some(index) => (&s[..index], &s[index + 1..]),
+                        None => continue,
+                    };
 
-                    match *parts.get(0).unwrap() {
-                        #expr
-                        _ => (),
+                    if let Err(err) = ::ts3::DecodeField::decode_field(&mut st, key, val) {
+                        return Err(err.with_decode_context(buf, key, val));
                     }
                 }
 
@@ -34,44 +56,171 @@ pub fn decode_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     proc_macro::TokenStream::from(expanded)
 }
 
-fn gen_expr(data: &Data) -> TokenStream {
-    match *data {
-        Data::Struct(ref data) => match data.fields {
-            Fields::Named(ref fields) => {
-                let recurse = fields.named.iter().map(|f| {
-                    let name = &f.ident;
-                    let ty = &f.ty;
-
-                    let bytes = name.clone().unwrap().to_string().as_bytes().to_owned();
-                    let bytes_fmt = bin_to_tokens(&bytes);
-
-                    quote_spanned! {f.span()=>
-                        #bytes_fmt => {
-                            st.#name = match <#ty>::decode(match parts.get(1) {
-                                Some(val) => val,
-                                None => continue,
-                            }) {
-                            Ok(val) => val,
-                            Err(err) => return Err(err.into()),
-                        }
-                    },
+/// Returns the body of `decode_field` and the statements decoding fields marked with
+/// `#[ts3(raw)]`.
+fn gen_expr(data: &Data) -> syn::Result<(TokenStream, TokenStream)> {
+    let fields = match data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields,
+            fields => {
+                return Err(syn::Error::new(
+                    fields.span(),
+                    "Decode can only be derived for structs with named fields",
+                ))
+            }
+        },
+        Data::Enum(data) => {
+            return Err(syn::Error::new(
+                data.enum_token.span,
+                "Decode can only be derived for structs with named fields",
+            ))
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new(
+                data.union_token.span,
+                "Decode can only be derived for structs with named fields",
+            ))
+        }
+    };
+
+    let mut recurse = Vec::new();
+    let mut flatten = Vec::new();
+    let mut raw = Vec::new();
+    for f in &fields.named {
+        let name = &f.ident;
+        let ty = &f.ty;
+        let attr = field_attr(f)?;
+
+        match attr {
+            Some(FieldAttr::Flatten) => {
+                // Flattened fields are offered every key not matching a field of this struct.
+                flatten.push(quote_spanned! {f.span()=>
+                    if <#ty as ::ts3::DecodeField>::decode_field(&mut self.#name, key, val)? {
+                        return Ok(true);
                     }
                 });
-
-                quote! {
-                    #(#recurse)*
-                }
+                continue;
             }
-            _ => unimplemented!(),
-        },
-        _ => unimplemented!(),
+            Some(FieldAttr::Raw) => {
+                // Raw fields are decoded from the whole buffer.
+                let key = LitByteStr::new(
+                    name.as_ref().unwrap().unraw().to_string().as_bytes(),
+                    f.span(),
+                );
+                let decode = quote_spanned! {f.span()=>
+                    <#ty as ::ts3::Decode>::decode(buf)
+                };
+                raw.push(quote! {
+                    st.#name = #decode.map_err(|err| {
+                        ::ts3::Error::from(err).with_decode_context(buf, #key, buf)
+                    })?;
+                });
+                continue;
+            }
+            Some(FieldAttr::Separator(_) | FieldAttr::Tolerant) | None => (),
+        }
+
+        // Match the key against a byte string literal, which matches slices of any length.
+        let key = name.as_ref().unwrap().unraw().to_string();
+        let key = LitByteStr::new(key.as_bytes(), f.span());
+
+        let decode = match &attr {
+            // Nested lists are decoded into a `Vec` of the element type.
+            Some(FieldAttr::Separator(separator)) => quote_spanned! {f.span()=>
+                ::ts3::decode_separated(val, #separator)?
+            },
+            _ => quote_spanned! {f.span()=>
+                <#ty as ::ts3::Decode>::decode(val)?
+            },
+        };
+
+        match attr {
+            // The field keeps its default value if it fails to decode and the client
+            // tolerates deviations of the server.
+            Some(FieldAttr::Tolerant) => recurse.push(quote_spanned! {f.span()=>
+                #key => {
+                    match <#ty as ::ts3::Decode>::decode(val) {
+                        Err(_) if ::ts3::skip_invalid_fields() => (),
+                        res => self.#name = res?,
+                    }
+                },
+            }),
+            _ => recurse.push(quote_spanned! {f.span()=>
+                #key => {
+                    self.#name = #decode;
+                },
+            }),
+        }
     }
+
+    let expr = quote! {
+        match key {
+            #(#recurse)*
+            _ => {
+                #(#flatten)*
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    };
+
+    Ok((expr, quote! { #(#raw)* }))
 }
 
-fn bin_to_tokens(slice: &[u8]) -> TokenStream {
-    let recurse = slice.iter().map(|b| quote!(#b));
+enum FieldAttr {
+    /// `#[ts3(flatten)]`: The keys of the field type are part of this struct.
+    Flatten,
+    /// `#[ts3(raw)]`: The field is decoded from the whole buffer.
+    Raw,
+    /// `#[ts3(separator = "...")]`: The value is a list of elements separated by the given
+    /// pattern.
+    Separator(LitStr),
+    /// `#[ts3(tolerant)]`: The field keeps its default value if it fails to decode from a
+    /// server other than the TeamSpeak 3 server.
+    Tolerant,
+}
+
+/// Returns the attribute the field is marked with, if any.
+fn field_attr(field: &Field) -> syn::Result<Option<FieldAttr>> {
+    let mut attr_kind = None;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("ts3")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new(meta.span(), "expected `ts3(...)`")),
+        };
 
-    quote! {
-        [#(#recurse),*]
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("flatten") => {
+                    attr_kind = Some(FieldAttr::Flatten);
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("raw") => {
+                    attr_kind = Some(FieldAttr::Raw);
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("tolerant") => {
+                    attr_kind = Some(FieldAttr::Tolerant);
+                }
+                NestedMeta::Meta(Meta::NameValue(meta)) if meta.path.is_ident("separator") => {
+                    match meta.lit {
+                        Lit::Str(separator) if !separator.value().is_empty() => {
+                            attr_kind = Some(FieldAttr::Separator(separator));
+                        }
+                        lit => {
+                            return Err(syn::Error::new(
+                                lit.span(),
+                                "expected a non-empty string literal",
+                            ));
+                        }
+                    }
+                }
+                nested => {
+                    return Err(syn::Error::new(nested.span(), "unknown ts3 attribute"));
+                }
+            }
+        }
     }
+
+    Ok(attr_kind)
 }